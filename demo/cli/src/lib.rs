@@ -32,6 +32,7 @@ extern crate substrate_rpc_servers as rpc;
 extern crate substrate_runtime_io as runtime_io;
 extern crate substrate_state_machine as state_machine;
 extern crate substrate_extrinsic_pool as extrinsic_pool;
+extern crate substrate_network as network;
 extern crate demo_executor;
 extern crate demo_primitives;
 extern crate demo_runtime;
@@ -64,18 +65,47 @@ impl extrinsic_pool::api::ExtrinsicPool<UncheckedExtrinsic, Hash> for DummyPool
 	{
 		Err("unimplemented".into())
 	}
+
+	fn submit_and_watch(&self, _: UncheckedExtrinsic)
+		-> Result<extrinsic_pool::Watcher<Hash>, Self::Error>
+	{
+		Err("unimplemented".into())
+	}
+
+	fn pending(&self) -> Vec<UncheckedExtrinsic> {
+		Vec::new()
+	}
+
+	fn remove_extrinsics(&self, _: &[Hash]) -> Vec<Hash> {
+		Vec::new()
+	}
 }
 
-struct DummySystem;
-impl substrate_rpc::system::SystemApi for DummySystem {
-	fn system_name(&self) -> substrate_rpc::system::error::Result<String> {
-		Ok("substrate-demo".into())
+struct DummySync;
+impl network::SyncProvider<Block> for DummySync {
+	fn status(&self) -> network::ProtocolStatus<Block> {
+		network::ProtocolStatus {
+			sync: network::SyncStatus {
+				state: network::SyncState::Idle,
+				best_seen_block: None,
+				best_queued_number: 0,
+				best_queued_hash: Default::default(),
+			},
+			num_peers: 0,
+			num_active_peers: 0,
+		}
+	}
+
+	fn peers(&self) -> Vec<network::PeerInfo<Block>> {
+		Vec::new()
 	}
-	fn system_version(&self) -> substrate_rpc::system::error::Result<String> {
-		Ok(crate_version!().into())
+
+	fn node_id(&self) -> Option<String> {
+		None
 	}
-	fn system_chain(&self) -> substrate_rpc::system::error::Result<String> {
-		Ok("default".into())
+
+	fn roles(&self) -> network::Role {
+		network::Role::FULL
 	}
 }
 
@@ -160,15 +190,22 @@ pub fn run<I, T>(args: I) -> error::Result<()> where
 
 	let _rpc_servers = {
 		let handler = || {
+			let state = rpc::apis::state::State::new(client.clone(), core.remote());
 			let chain = rpc::apis::chain::Chain::new(client.clone(), core.remote());
-			rpc::rpc_handler::<Block, _, _, _, _>(client.clone(), chain, Arc::new(DummyPool), DummySystem)
+			let author = rpc::apis::author::Author::new(client.clone(), Arc::new(DummyPool), core.remote());
+			let system = rpc::apis::system::System::new(rpc::apis::system::SystemInfo {
+				impl_name: "substrate-demo".into(),
+				impl_version: crate_version!().into(),
+				chain_name: "default".into(),
+			}, client.clone(), Arc::new(DummySync));
+			rpc::rpc_handler::<Block, _, _, _, _>(state, chain, author, system, rpc::RpcMiddleware::new(false, None))
 		};
 		let http_address = "127.0.0.1:9933".parse().unwrap();
 		let ws_address = "127.0.0.1:9944".parse().unwrap();
 
 		(
-			rpc::start_http(&http_address, handler())?,
-			rpc::start_ws(&ws_address, handler())?
+			rpc::start_http(&http_address, None, None, handler())?,
+			rpc::start_ws(&ws_address, None, None, handler())?
 		)
 	};
 
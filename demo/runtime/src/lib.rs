@@ -35,6 +35,7 @@ extern crate serde_derive;
 extern crate serde;
 
 extern crate substrate_runtime_std as rstd;
+extern crate substrate_codec as codec;
 extern crate substrate_runtime_consensus as consensus;
 extern crate substrate_runtime_council as council;
 extern crate substrate_runtime_democracy as democracy;
@@ -49,10 +50,20 @@ use rstd::prelude::*;
 use demo_primitives::{AccountId, Balance, BlockNumber, Hash, Index, SessionKey, Signature};
 use runtime_primitives::generic;
 use runtime_primitives::traits::{Convert, HasPublicAux, BlakeTwo256};
+use runtime_primitives::version::RuntimeVersion;
 
 #[cfg(any(feature = "std", test))]
 pub use runtime_primitives::BuildExternalities;
 
+/// Runtime version.
+pub const VERSION: RuntimeVersion = RuntimeVersion {
+	spec_name: ver_str!("demo"),
+	spec_version: 2,
+	impl_name: ver_str!("parity-demo"),
+	impl_version: 0,
+	apis: ver_apis!(("core", 1), ("metadata", 1), ("session", 1), ("payment", 1)),
+};
+
 /// Concrete runtime type used to parameterize the various modules.
 pub struct Concrete;
 
@@ -175,14 +186,35 @@ impl_outer_config! {
 	}
 }
 
+impl_json_metadata! {
+	for Concrete with modules {
+		system: System [storage],
+		consensus: Consensus,
+		timestamp: Timestamp [storage],
+		session: Session [storage],
+		staking: Staking [storage],
+		democracy: Democracy [storage],
+		council: Council [storage],
+		council_voting: CouncilVoting [storage],
+	}
+}
+
+/// The fee to be paid for including the given extrinsic.
+pub fn transaction_fee(uxt: UncheckedExtrinsic) -> Balance {
+	Staking::transaction_fee(::codec::Slicable::encode(&uxt).len())
+}
+
 pub mod api {
 	impl_stubs!(
+		version => |()| super::VERSION,
 		authorities => |()| super::Consensus::authorities(),
 		initialise_block => |header| super::Executive::initialise_block(&header),
 		apply_extrinsic => |extrinsic| super::Executive::apply_extrinsic(extrinsic),
 		execute_block => |block| super::Executive::execute_block(block),
 		finalise_block => |()| super::Executive::finalise_block(),
 		validator_count => |()| super::Session::validator_count(),
-		validators => |()| super::Session::validators()
+		validators => |()| super::Session::validators(),
+		json_metadata => |()| super::Concrete::json_metadata(),
+		payment_query_info => |uxt| super::transaction_fee(uxt)
 	);
 }
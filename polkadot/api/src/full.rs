@@ -69,7 +69,7 @@ impl<B: LocalBackend<Block>> BlockBuilder for ClientBlockBuilder<B, LocalCallExe
 	where ::client::error::Error: From<<<B as Backend<Block>>::State as state_machine::backend::Backend>::Error>
 {
 	fn push_extrinsic(&mut self, extrinsic: UncheckedExtrinsic) -> Result<()> {
-		self.push(extrinsic).map_err(Into::into)
+		self.push(extrinsic).map(|_| ()).map_err(Into::into)
 	}
 
 	/// Bake the block with provided extrinsics.
@@ -171,6 +171,23 @@ impl<B: LocalBackend<Block>> PolkadotApi for Client<B, LocalCallExecutor<B, Nati
 				.collect()
 		})
 	}
+
+	fn included_candidates(&self, at: &CheckedId) -> Result<Vec<CandidateReceipt>> {
+		use codec::Slicable;
+		use runtime::{Block as RuntimeBlock, CheckedBlock};
+
+		let id = at.block_id();
+		let block = match (self.header(id)?, self.body(id)?) {
+			(Some(header), Some(extrinsics)) => Block { header, extrinsics },
+			_ => return Err(ErrorKind::UnknownBlock(format!("{:?}", id)).into()),
+		};
+
+		let encoded = block.encode();
+		match RuntimeBlock::decode(&mut &encoded[..]).and_then(|block| CheckedBlock::new(block).ok()) {
+			Some(block) => Ok(block.parachain_heads().to_vec()),
+			None => bail!("Block {:?} is not a valid Polkadot block", id),
+		}
+	}
 }
 
 impl<B: LocalBackend<Block>> LocalPolkadotApi for Client<B, LocalCallExecutor<B, NativeExecutor<LocalDispatch>>, Block>
@@ -238,6 +255,23 @@ mod tests {
 		assert_eq!(client.validators(&id).unwrap(), validators());
 	}
 
+	#[test]
+	fn included_candidates_are_read_from_block_body() {
+		use client::backend::{Backend as BackendT, BlockImportOperation};
+
+		let client = client();
+		let id = client.check_id(BlockId::number(0)).unwrap();
+		let block = client.build_block(&id, 1_000_000, Vec::new()).unwrap().bake().unwrap();
+
+		let mut op = client.backend().begin_operation(BlockId::number(0)).unwrap();
+		op.set_block_data(block.header, Some(block.extrinsics), None, true).unwrap();
+		client.backend().commit_operation(op).unwrap();
+
+		assert_eq!(client.included_candidates(&CheckedId(BlockId::number(1))).unwrap(), Vec::<CandidateReceipt>::new());
+		// genesis has no inherent extrinsics.
+		assert!(client.included_candidates(&id).is_err());
+	}
+
 	#[test]
 	fn build_block_implicit_succeeds() {
 		let client = client();
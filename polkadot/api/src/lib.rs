@@ -141,6 +141,9 @@ pub trait PolkadotApi {
 	/// Attempt to produce the (encoded) inherent extrinsics for a block being built upon the given.
 	/// This may vary by runtime and will fail if a runtime doesn't follow the same API.
 	fn inherent_extrinsics(&self, at: &Self::CheckedBlockId, timestamp: Timestamp, new_heads: Vec<CandidateReceipt>) -> Result<Vec<UncheckedExtrinsic>>;
+
+	/// Get the parachain candidates included in the given block.
+	fn included_candidates(&self, at: &Self::CheckedBlockId) -> Result<Vec<CandidateReceipt>>;
 }
 
 /// Mark for all Polkadot API implementations, that are making use of state data, stored locally.
@@ -0,0 +1,379 @@
+// Copyright 2017 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Persistent database for parachain data: full block data of candidates
+//! we have validated or collated, and erasure-coded chunks of candidates
+//! we are meant to keep available.
+//!
+//! Data is indexed by the relay-chain parent it was produced for, and is
+//! pruned once the candidates building on that parent are finalized.
+
+extern crate polkadot_erasure_coding as erasure;
+extern crate polkadot_primitives;
+extern crate substrate_codec as codec;
+extern crate kvdb;
+extern crate kvdb_rocksdb;
+extern crate kvdb_memorydb;
+
+#[macro_use]
+extern crate log;
+
+use codec::Slicable;
+use kvdb::{KeyValueDB, DBTransaction};
+use kvdb_rocksdb::{Database, DatabaseConfig};
+use polkadot_primitives::Hash;
+use polkadot_primitives::parachain::{BlockData, ErasureChunk};
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::io;
+
+mod columns {
+	pub const DATA: Option<u32> = Some(0);
+	pub const META: Option<u32> = Some(1);
+	pub const CHUNKS: Option<u32> = Some(2);
+	pub const NUM_COLUMNS: u32 = 3;
+}
+
+/// Configuration for the availability store.
+pub struct Config {
+	/// Cache size in bytes. If `None` default is used.
+	pub cache_size: Option<usize>,
+	/// Path to the database.
+	pub path: PathBuf,
+}
+
+/// Some data to keep available.
+pub struct Data {
+	/// The relay chain parent hash this should be localized to.
+	pub relay_parent: Hash,
+	/// Unique candidate receipt hash.
+	pub candidate_hash: Hash,
+	/// Block data.
+	pub block_data: BlockData,
+}
+
+fn block_data_key(relay_parent: &Hash, candidate_hash: &Hash) -> Vec<u8> {
+	let mut v = relay_parent.to_vec();
+	v.extend(candidate_hash.iter());
+	v
+}
+
+// chunks of a candidate share the block data key as a prefix.
+fn chunk_key(relay_parent: &Hash, candidate_hash: &Hash, index: u32) -> Vec<u8> {
+	let mut v = block_data_key(relay_parent, candidate_hash);
+	index.using_encoded(|s| v.extend(s));
+	v
+}
+
+fn db_err(err: kvdb::Error) -> io::Error {
+	match *err.kind() {
+		kvdb::ErrorKind::Io(ref err) => io::Error::new(err.kind(), format!("{}", err)),
+		_ => io::Error::new(io::ErrorKind::Other, format!("{}", err)),
+	}
+}
+
+/// Handle to the availability store.
+#[derive(Clone)]
+pub struct Store {
+	inner: Arc<KeyValueDB>,
+}
+
+impl Store {
+	/// Create a new `Store` with given config on disk.
+	pub fn new(config: Config) -> io::Result<Self> {
+		let mut db_config = DatabaseConfig::with_columns(Some(columns::NUM_COLUMNS));
+		db_config.memory_budget = config.cache_size;
+		db_config.wal = true;
+
+		let path = config.path.to_str().ok_or_else(|| io::Error::new(
+			io::ErrorKind::Other,
+			format!("Bad database path: {:?}", config.path),
+		))?;
+
+		let db = Database::open(&db_config, &path).map_err(db_err)?;
+
+		Ok(Store {
+			inner: Arc::new(db),
+		})
+	}
+
+	/// Create a new `Store` in-memory. Useful for tests.
+	pub fn new_in_memory() -> Self {
+		Store {
+			inner: Arc::new(::kvdb_memorydb::create(columns::NUM_COLUMNS)),
+		}
+	}
+
+	/// Make some data available provisionally.
+	pub fn make_available(&self, data: Data) -> io::Result<()> {
+		let mut tx = DBTransaction::new();
+
+		self.note_candidate(&mut tx, &data.relay_parent, &data.candidate_hash)?;
+		tx.put_vec(
+			columns::DATA,
+			&block_data_key(&data.relay_parent, &data.candidate_hash),
+			data.block_data.0.encode(),
+		);
+
+		self.inner.write(tx).map_err(db_err)
+	}
+
+	/// Store an erasure chunk of the given candidate.
+	///
+	/// The chunk is expected to have been checked against the candidate's erasure root.
+	pub fn add_erasure_chunk(&self, relay_parent: Hash, candidate_hash: Hash, chunk: &ErasureChunk) -> io::Result<()> {
+		let mut tx = DBTransaction::new();
+
+		self.note_candidate(&mut tx, &relay_parent, &candidate_hash)?;
+		tx.put_vec(
+			columns::CHUNKS,
+			&chunk_key(&relay_parent, &candidate_hash, chunk.index),
+			chunk.encode(),
+		);
+
+		self.inner.write(tx).map_err(db_err)
+	}
+
+	/// Query an erasure chunk by the index of the validator it belongs to.
+	pub fn get_erasure_chunk(&self, relay_parent: Hash, candidate_hash: Hash, index: u32) -> Option<ErasureChunk> {
+		let key = chunk_key(&relay_parent, &candidate_hash, index);
+		match self.inner.get(columns::CHUNKS, &key) {
+			Ok(Some(raw)) => ErasureChunk::decode(&mut &raw[..]),
+			Ok(None) => None,
+			Err(e) => {
+				warn!(target: "availability", "Error reading from availability store: {:?}", e);
+				None
+			}
+		}
+	}
+
+	/// All erasure chunks of the given candidate which are stored locally.
+	pub fn erasure_chunks(&self, relay_parent: Hash, candidate_hash: Hash) -> Vec<ErasureChunk> {
+		let prefix = block_data_key(&relay_parent, &candidate_hash);
+		self.inner.iter_from_prefix(columns::CHUNKS, &prefix)
+			.take_while(|&(ref key, _)| key.starts_with(&prefix))
+			.filter_map(|(_, raw)| ErasureChunk::decode(&mut &raw[..]))
+			.collect()
+	}
+
+	/// Query block data.
+	pub fn block_data(&self, relay_parent: Hash, candidate_hash: Hash) -> Option<BlockData> {
+		let key = block_data_key(&relay_parent, &candidate_hash);
+		match self.inner.get(columns::DATA, &key) {
+			Ok(Some(raw)) => Vec::<u8>::decode(&mut &raw[..]).map(BlockData),
+			Ok(None) => None,
+			Err(e) => {
+				warn!(target: "availability", "Error reading from availability store: {:?}", e);
+				None
+			}
+		}
+	}
+
+	/// Query block data, reconstructing it from the locally stored erasure chunks
+	/// if the full data is not known.
+	///
+	/// Reconstructed data is stored for later queries.
+	pub fn block_data_or_reconstruct(&self, relay_parent: Hash, candidate_hash: Hash, n_validators: usize)
+		-> Result<BlockData, erasure::Error>
+	{
+		if let Some(block_data) = self.block_data(relay_parent, candidate_hash) {
+			return Ok(block_data);
+		}
+
+		let chunks = self.erasure_chunks(relay_parent, candidate_hash);
+		let block_data = erasure::reconstruct(
+			n_validators,
+			chunks.iter().map(|c| (&c.chunk[..], c.index as usize)),
+		)?;
+
+		let data = Data {
+			relay_parent,
+			candidate_hash,
+			block_data: block_data.clone(),
+		};
+
+		if let Err(e) = self.make_available(data) {
+			warn!(target: "availability", "Failed to store reconstructed block data: {:?}", e);
+		}
+
+		Ok(block_data)
+	}
+
+	/// Note that a set of candidates have been included in a finalized block with given hash and parent hash.
+	///
+	/// All data and chunks for other candidates built on the same relay parent are pruned.
+	pub fn candidates_finalized(&self, parent: Hash, finalized_candidates: HashSet<Hash>) -> io::Result<()> {
+		let mut tx = DBTransaction::new();
+
+		let v = self.candidates_at(&parent)?;
+		tx.delete(columns::META, parent.as_ref());
+
+		for candidate_hash in v {
+			if !finalized_candidates.contains(&candidate_hash) {
+				tx.delete(columns::DATA, &block_data_key(&parent, &candidate_hash));
+
+				let prefix = block_data_key(&parent, &candidate_hash);
+				// the iterator starts at the prefix, but does not stop after it.
+				let keys = self.inner.iter_from_prefix(columns::CHUNKS, &prefix)
+					.take_while(|&(ref key, _)| key.starts_with(&prefix));
+				for (key, _) in keys {
+					tx.delete(columns::CHUNKS, &key);
+				}
+			}
+		}
+
+		self.inner.write(tx).map_err(db_err)
+	}
+
+	fn candidates_at(&self, relay_parent: &Hash) -> io::Result<Vec<Hash>> {
+		Ok(self.inner.get(columns::META, relay_parent.as_ref()).map_err(db_err)?
+			.and_then(|raw| Slicable::decode(&mut &raw[..]))
+			.unwrap_or_default())
+	}
+
+	// record the candidate under its relay parent, for pruning.
+	fn note_candidate(&self, tx: &mut DBTransaction, relay_parent: &Hash, candidate_hash: &Hash) -> io::Result<()> {
+		let mut v = self.candidates_at(relay_parent)?;
+		if !v.contains(candidate_hash) {
+			v.push(*candidate_hash);
+			tx.put_vec(columns::META, relay_parent.as_ref(), v.encode());
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn chunks_for(n_validators: usize, block_data: &BlockData) -> Vec<ErasureChunk> {
+		let chunks = erasure::obtain_chunks(n_validators, block_data).unwrap();
+		erasure::branches(&chunks).enumerate().map(|(index, (proof, chunk))| ErasureChunk {
+			chunk: chunk.to_vec(),
+			index: index as u32,
+			proof,
+		}).collect()
+	}
+
+	#[test]
+	fn finalization_removes_unneeded() {
+		let relay_parent = [1; 32].into();
+
+		let candidate_1 = [2; 32].into();
+		let candidate_2 = [3; 32].into();
+
+		let block_data_1 = BlockData(vec![1, 2, 3]);
+		let block_data_2 = BlockData(vec![4, 5, 6]);
+
+		let store = Store::new_in_memory();
+		store.make_available(Data {
+			relay_parent,
+			candidate_hash: candidate_1,
+			block_data: block_data_1.clone(),
+		}).unwrap();
+
+		store.make_available(Data {
+			relay_parent,
+			candidate_hash: candidate_2,
+			block_data: block_data_2.clone(),
+		}).unwrap();
+
+		assert_eq!(store.block_data(relay_parent, candidate_1).unwrap(), block_data_1);
+		assert_eq!(store.block_data(relay_parent, candidate_2).unwrap(), block_data_2);
+
+		store.candidates_finalized(relay_parent, [candidate_1].iter().cloned().collect()).unwrap();
+
+		assert_eq!(store.block_data(relay_parent, candidate_1).unwrap(), block_data_1);
+		assert!(store.block_data(relay_parent, candidate_2).is_none());
+	}
+
+	#[test]
+	fn erasure_chunks_are_stored_and_pruned() {
+		let relay_parent = [1; 32].into();
+		let candidate = [2; 32].into();
+		let block_data = BlockData(vec![42; 100]);
+
+		let chunks = chunks_for(10, &block_data);
+
+		let store = Store::new_in_memory();
+		for chunk in &chunks[..3] {
+			store.add_erasure_chunk(relay_parent, candidate, chunk).unwrap();
+		}
+
+		assert_eq!(store.get_erasure_chunk(relay_parent, candidate, 1).as_ref(), Some(&chunks[1]));
+		assert!(store.get_erasure_chunk(relay_parent, candidate, 5).is_none());
+		assert_eq!(store.erasure_chunks(relay_parent, candidate).len(), 3);
+
+		store.candidates_finalized(relay_parent, HashSet::new()).unwrap();
+
+		assert!(store.get_erasure_chunk(relay_parent, candidate, 1).is_none());
+		assert!(store.erasure_chunks(relay_parent, candidate).is_empty());
+	}
+
+	#[test]
+	fn erasure_chunks_of_other_candidates_are_kept() {
+		let relay_parent = [1; 32].into();
+		let pruned = [2; 32].into();
+		let kept = [3; 32].into();
+		let chunks = chunks_for(10, &BlockData(vec![42; 100]));
+
+		let store = Store::new_in_memory();
+		for chunk in &chunks[..3] {
+			store.add_erasure_chunk(relay_parent, pruned, chunk).unwrap();
+			store.add_erasure_chunk(relay_parent, kept, chunk).unwrap();
+		}
+
+		assert_eq!(store.erasure_chunks(relay_parent, pruned).len(), 3);
+		assert_eq!(store.erasure_chunks(relay_parent, kept).len(), 3);
+
+		store.candidates_finalized(relay_parent, vec![kept].into_iter().collect()).unwrap();
+
+		assert!(store.erasure_chunks(relay_parent, pruned).is_empty());
+		assert_eq!(store.erasure_chunks(relay_parent, kept), chunks[..3].to_vec());
+	}
+
+	#[test]
+	fn reconstructs_block_data_from_chunks() {
+		let relay_parent = [1; 32].into();
+		let candidate = [2; 32].into();
+		let block_data = BlockData((0..200).collect());
+
+		let chunks = chunks_for(10, &block_data);
+
+		let store = Store::new_in_memory();
+
+		// threshold for 10 validators is 4.
+		for chunk in &chunks[5..8] {
+			store.add_erasure_chunk(relay_parent, candidate, chunk).unwrap();
+		}
+
+		assert_eq!(
+			store.block_data_or_reconstruct(relay_parent, candidate, 10),
+			Err(erasure::Error::NotEnoughChunks),
+		);
+
+		store.add_erasure_chunk(relay_parent, candidate, &chunks[9]).unwrap();
+
+		assert_eq!(
+			store.block_data_or_reconstruct(relay_parent, candidate, 10).unwrap(),
+			block_data,
+		);
+		assert_eq!(store.block_data(relay_parent, candidate).unwrap(), block_data);
+	}
+}
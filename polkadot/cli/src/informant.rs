@@ -24,6 +24,7 @@ use network::{SyncState, SyncProvider};
 use polkadot_primitives::Block;
 use state_machine;
 use client::{self, BlockchainEvents};
+use client::backend::Backend;
 
 const TIMER_INTERVAL_MS: u64 = 5000;
 
@@ -50,7 +51,11 @@ pub fn start<B, E>(service: &Service<B, E>, handle: reactor::Handle)
 				(SyncState::Downloading, None) => "Syncing".into(),
 				(SyncState::Downloading, Some(n)) => format!("Syncing, target=#{}", n),
 			};
-			info!(target: "polkadot", "{} ({} peers), best: #{} ({})", status, sync_status.num_peers, best_block.number, hash)
+			let cache = match client.backend().state_cache_stats() {
+				Some(stats) => format!(", state cache: {} hits, {} misses", stats.hits, stats.misses),
+				None => String::new(),
+			};
+			info!(target: "polkadot", "{} ({} peers), best: #{} ({}){}", status, sync_status.num_peers, best_block.number, hash, cache)
 		} else {
 			warn!("Error getting best block information");
 		}
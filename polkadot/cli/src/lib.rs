@@ -37,7 +37,9 @@ extern crate substrate_client as client;
 extern crate substrate_network as network;
 extern crate substrate_rpc;
 extern crate substrate_rpc_servers as rpc;
+extern crate polkadot_collator as collator;
 extern crate polkadot_primitives;
+extern crate polkadot_rpc;
 extern crate polkadot_service as service;
 extern crate polkadot_transaction_pool as txpool;
 
@@ -53,35 +55,19 @@ extern crate log;
 pub mod error;
 mod informant;
 
+use std::fs;
 use std::io;
 use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use polkadot_primitives::Block;
 
 use futures::sync::mpsc;
 use futures::{Sink, Future, Stream};
 use tokio_core::reactor;
 use service::ChainSpec;
-
-struct Configuration(service::Configuration);
-
-impl substrate_rpc::system::SystemApi for Configuration {
-	fn system_name(&self) -> substrate_rpc::system::error::Result<String> {
-		Ok("parity-polkadot".into())
-	}
-
-	fn system_version(&self) -> substrate_rpc::system::error::Result<String> {
-		Ok(crate_version!().into())
-	}
-
-	fn system_chain(&self) -> substrate_rpc::system::error::Result<String> {
-		Ok(match self.0.chain_spec {
-			ChainSpec::Development => "dev",
-			ChainSpec::LocalTestnet => "local",
-			ChainSpec::PoC2Testnet => "poc-2",
-		}.into())
-	}
-}
+use client::ExecutionStrategy;
+use polkadot_rpc::parachain::ParachainApi;
 
 /// Parse command line arguments and start the node.
 ///
@@ -127,6 +113,21 @@ pub fn run<I, T>(args: I) -> error::Result<()> where
 
 	config.database_path = db_path(&base_path).to_string_lossy().into();
 
+	if let Some(matches) = matches.subcommand_matches("revert") {
+		return revert_chain(matches, config);
+	}
+
+	if let Some(matches) = matches.subcommand_matches("purge-chain") {
+		return purge_chain(matches, &config.database_path);
+	}
+
+	let ipc_path = match matches.is_present("no-ipc") {
+		true => None,
+		false => Some(matches.value_of("ipc-path")
+			.map(|x| Path::new(x).to_owned())
+			.unwrap_or_else(|| ipc_path(&base_path))),
+	};
+
 	let mut role = service::Role::FULL;
 	if matches.is_present("collator") {
 		info!("Starting collator.");
@@ -176,14 +177,93 @@ pub fn run<I, T>(args: I) -> error::Result<()> where
 
 	config.keys = matches.values_of("key").unwrap_or_default().map(str::to_owned).collect();
 
+	{
+		let default = parse_execution_strategy(matches.value_of("execution"))
+			.unwrap_or(ExecutionStrategy::NativeWhenPossible);
+		let strategy_for = |param: &str| parse_execution_strategy(matches.value_of(param)).unwrap_or(default);
+		config.execution_strategies = client::ExecutionStrategies {
+			importing: strategy_for("import-execution"),
+			block_construction: strategy_for("block-construction-execution"),
+			other: strategy_for("other-execution"),
+		};
+	}
+
 	match role == service::Role::LIGHT {
-		true => run_until_exit(core, service::new_light(config.clone())?, &matches, config),
-		false => run_until_exit(core, service::new_full(config.clone())?, &matches, config),
+		// light clients can't serve parachain state.
+		true => run_until_exit(core, service::new_light(config.clone())?, &matches, config, ipc_path, |_, _| ()),
+		false => {
+			let service = service::new_full(config.clone())?;
+			if role == service::Role::COLLATOR {
+				// the collator's key is only used to attribute its candidates.
+				let key = Arc::new(ed25519::Pair::generate());
+				let network: Arc<network::ConsensusService<Block>> = service.network();
+				info!("Collating on parachain {} with key {}", service::TEST_PARACHAIN_ID, key.public());
+
+				core.handle().spawn(collator::collate_on_new_heads(
+					service::TEST_PARACHAIN_ID.into(),
+					service.client(),
+					service.client(),
+					network,
+					collator::basic_add::BasicAdd::new(key.clone()),
+					key,
+				));
+			}
+
+			let client = service.client();
+			run_until_exit(core, service, &matches, config, ipc_path, move |io, remote| {
+				let parachain = polkadot_rpc::parachain::Parachain::new(client.clone(), client.clone(), remote);
+				io.extend_with(parachain.to_delegate());
+			})
+		}
 	}
 }
 
-fn run_until_exit<B, E>(mut core: reactor::Core, service: service::Service<B, E>, matches: &clap::ArgMatches, config: service::Configuration) -> error::Result<()>
+fn revert_chain(matches: &clap::ArgMatches, config: service::Configuration) -> error::Result<()> {
+	let num = matches.value_of("NUM").unwrap_or("256");
+	let blocks = num.parse().map_err(|_| format!("Invalid number of blocks to revert: {}", num))?;
+	let reverted = service::revert_chain(config, blocks)?;
+	info!("Reverted {} blocks", reverted);
+	Ok(())
+}
+
+fn purge_chain(matches: &clap::ArgMatches, database_path: &str) -> error::Result<()> {
+	use std::io::Write;
+
+	let path = Path::new(database_path);
+	if !path.exists() {
+		info!("No chain data found at {}", path.display());
+		return Ok(());
+	}
+
+	if !matches.is_present("yes") {
+		print!("Are you sure to remove {}? (y/N) ", path.display());
+		io::stdout().flush()?;
+		let mut input = String::new();
+		io::stdin().read_line(&mut input)?;
+		match input.trim() {
+			"y" | "Y" | "yes" => {},
+			_ => {
+				info!("Aborted");
+				return Ok(());
+			},
+		}
+	}
+
+	fs::remove_dir_all(path)?;
+	info!("Removed chain data at {}", path.display());
+	Ok(())
+}
+
+fn run_until_exit<B, E, R>(
+	mut core: reactor::Core,
+	service: service::Service<B, E>,
+	matches: &clap::ArgMatches,
+	config: service::Configuration,
+	ipc_path: Option<PathBuf>,
+	extend_rpc: R,
+) -> error::Result<()>
 	where
+		R: Fn(&mut rpc::RpcHandler, reactor::Remote),
 		B: client::backend::Backend<Block> + Send + Sync + 'static,
 		E: client::CallExecutor<Block> + Send + Sync + 'static,
 		client::error::Error: From<<<B as client::backend::Backend<Block>>::State as state_machine::backend::Backend>::Error>
@@ -201,21 +281,65 @@ fn run_until_exit<B, E>(mut core: reactor::Core, service: service::Service<B, E>
 	informant::start(&service, core.handle());
 
 	let _rpc_servers = {
-		let http_address = parse_address("127.0.0.1:9933", "rpc-port", matches)?;
-		let ws_address = parse_address("127.0.0.1:9944", "ws-port", matches)?;
+		let rpc_external = matches.is_present("rpc-external");
+		let rpc_interface = if rpc_external { "0.0.0.0" } else { "127.0.0.1" };
+		let http_address = parse_address(&format!("{}:9933", rpc_interface), "rpc-port", matches)?;
+		let ws_address = parse_address(&format!("{}:9944", rpc_interface), "ws-port", matches)?;
+		let cors = parse_domains(matches.value_of("rpc-cors"), Some(vec![
+			"http://localhost:*".into(),
+			"http://127.0.0.1:*".into(),
+			"https://localhost:*".into(),
+			"https://127.0.0.1:*".into(),
+		]));
+		let hosts = parse_domains(matches.value_of("rpc-hosts"), match rpc_external {
+			true => None,
+			false => Some(vec!["localhost:*".into(), "127.0.0.1:*".into()]),
+		});
+		let deny_unsafe = match matches.value_of("rpc-methods") {
+			Some("safe") => true,
+			Some("unsafe") => false,
+			_ => rpc_external,
+		};
+		let rate_limit = match matches.value_of("rpc-rate-limit") {
+			Some(limit) => Some(limit.parse::<u32>().ok().ok_or("Invalid --rpc-rate-limit specified.")?),
+			None => None,
+		};
+		let system_info = rpc::apis::system::SystemInfo {
+			impl_name: "parity-polkadot".into(),
+			impl_version: crate_version!().into(),
+			chain_name: match config.chain_spec {
+				ChainSpec::Development => "dev",
+				ChainSpec::LocalTestnet => "local",
+				ChainSpec::PoC2Testnet => "poc-2",
+			}.into(),
+		};
 
-		let handler = || {
+		let public_middleware = || rpc::RpcMiddleware::new(deny_unsafe, rate_limit);
+		let handler = |middleware: rpc::RpcMiddleware| {
+			let state = rpc::apis::state::State::new(service.client(), core.remote());
 			let chain = rpc::apis::chain::Chain::new(service.client(), core.remote());
-			rpc::rpc_handler::<Block, _, _, _, _>(
-				service.client(),
+			let author = rpc::apis::author::Author::new(service.client(), service.transaction_pool(), core.remote());
+			let system = rpc::apis::system::System::new(system_info.clone(), service.client(), service.network());
+			let mut io = rpc::rpc_handler::<Block, _, _, _, _>(
+				state,
 				chain,
-				service.transaction_pool(),
-				Configuration(config.clone()),
-			)
+				author,
+				system,
+				middleware,
+			);
+			extend_rpc(&mut io, core.remote());
+			io
+		};
+		let ipc_server = match ipc_path {
+			// the socket is only accessible to the node's user, so nothing is denied or limited.
+			Some(path) => Some(rpc::start_ipc(&path, handler(rpc::RpcMiddleware::new(false, None)))
+				.map_err(|e| format!("Unable to start IPC server at {}: {}", path.display(), e))?),
+			None => None,
 		};
 		(
-			start_server(http_address, |address| rpc::start_http(address, handler())),
-			start_server(ws_address, |address| rpc::start_ws(address, handler())),
+			start_server(http_address, |address| rpc::start_http(address, cors.as_ref(), hosts.as_ref(), handler(public_middleware()))),
+			start_server(ws_address, |address| rpc::start_ws(address, cors.as_ref(), hosts.as_ref(), handler(public_middleware()))),
+			ipc_server,
 		)
 	};
 
@@ -248,6 +372,29 @@ fn parse_address(default: &str, port_param: &str, matches: &clap::ArgMatches) ->
 	Ok(address)
 }
 
+fn parse_domains(domains: Option<&str>, default: Option<Vec<String>>) -> Option<Vec<String>> {
+	match domains {
+		Some("all") => None,
+		Some(domains) => Some(domains.split(',').map(|domain| domain.trim().to_owned()).collect()),
+		None => default,
+	}
+}
+
+fn parse_execution_strategy(strategy: Option<&str>) -> Option<ExecutionStrategy> {
+	match strategy {
+		Some("native") => Some(ExecutionStrategy::NativeWhenPossible),
+		Some("wasm") => Some(ExecutionStrategy::AlwaysWasm),
+		Some("both") => Some(ExecutionStrategy::Both),
+		_ => None,
+	}
+}
+
+fn ipc_path(base_path: &Path) -> PathBuf {
+	let mut path = base_path.to_owned();
+	path.push("jsonrpc.ipc");
+	path
+}
+
 fn keystore_path(base_path: &Path) -> PathBuf {
 	let mut path = base_path.to_owned();
 	path.push("keystore");
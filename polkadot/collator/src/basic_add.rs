@@ -0,0 +1,185 @@
+// Copyright 2017 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Collation logic for the `basic_add` test parachain.
+//!
+//! The head and block data encodings mirror those of
+//! `parachain/test-chains/basic_add`.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use codec::{Slicable, Input};
+use ed25519;
+use polkadot_primitives::AccountId;
+use polkadot_primitives::parachain::{self, CandidateSignature, Id as ParaId, Message};
+
+use super::ParachainContext;
+
+/// Amount added to the state in every block.
+const ADD: u64 = 1;
+
+/// Head data of `basic_add`.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct HeadData {
+	/// Block number
+	pub number: u64,
+	/// parent block keccak256
+	pub parent_hash: [u8; 32],
+	/// hash of post-execution state.
+	pub post_state: [u8; 32],
+}
+
+impl Slicable for HeadData {
+	fn encode(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+
+		self.number.using_encoded(|s| v.extend(s));
+		self.parent_hash.using_encoded(|s| v.extend(s));
+		self.post_state.using_encoded(|s| v.extend(s));
+
+		v
+	}
+
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Some(HeadData {
+			number: Slicable::decode(input)?,
+			parent_hash: Slicable::decode(input)?,
+			post_state: Slicable::decode(input)?,
+		})
+	}
+}
+
+/// Block data of `basic_add`.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct BlockData {
+	/// State to begin from.
+	pub state: u64,
+	/// Amount to add (overflowing)
+	pub add: u64,
+}
+
+impl Slicable for BlockData {
+	fn encode(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+
+		self.state.using_encoded(|s| v.extend(s));
+		self.add.using_encoded(|s| v.extend(s));
+
+		v
+	}
+
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Some(BlockData {
+			state: Slicable::decode(input)?,
+			add: Slicable::decode(input)?,
+		})
+	}
+}
+
+/// Hash of a `basic_add` state, as committed to in the head data.
+pub fn hash_state(state: u64) -> [u8; 32] {
+	::tiny_keccak::keccak256(state.encode().as_slice())
+}
+
+/// The head data `basic_add` starts from.
+pub fn genesis_head() -> HeadData {
+	HeadData {
+		number: 0,
+		parent_hash: [0; 32],
+		post_state: hash_state(0),
+	}
+}
+
+/// Parachain context producing `basic_add` blocks.
+///
+/// Only the hash of the state is committed to in the head data, so the full
+/// states built upon are tracked locally.
+#[derive(Clone)]
+pub struct BasicAdd {
+	key: Arc<ed25519::Pair>,
+	states: Arc<Mutex<HashMap<[u8; 32], u64>>>,
+}
+
+impl BasicAdd {
+	/// Create a new context, signing candidates with the given key.
+	pub fn new(key: Arc<ed25519::Pair>) -> Self {
+		let mut states = HashMap::new();
+		states.insert(hash_state(0), 0);
+
+		BasicAdd {
+			key,
+			states: Arc::new(Mutex::new(states)),
+		}
+	}
+}
+
+impl ParachainContext for BasicAdd {
+	fn produce_candidate<I: IntoIterator<Item=(ParaId, Message)>>(
+		&self,
+		last_head: parachain::HeadData,
+		_ingress: I,
+	) -> (parachain::BlockData, AccountId, CandidateSignature) {
+		let mut states = self.states.lock().expect("state lock is never poisoned; qed");
+
+		let state = HeadData::decode(&mut &last_head.0[..])
+			.and_then(|head| states.get(&head.post_state).cloned())
+			.unwrap_or_else(|| {
+				// we have not seen this state: collations on it will be invalid.
+				warn!("Unknown basic_add parent head: {:?}", last_head.0);
+				0
+			});
+
+		let block_data = BlockData { state, add: ADD };
+		let new_state = state.wrapping_add(ADD);
+		states.insert(hash_state(new_state), new_state);
+
+		let encoded = block_data.encode();
+		let signature = self.key.sign(&encoded).into();
+
+		(parachain::BlockData(encoded), self.key.public().0.into(), signature)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn builds_on_known_states() {
+		let context = BasicAdd::new(Arc::new(ed25519::Pair::from_seed(b"12345678901234567890123456789012")));
+
+		let (block_data, _, _) = context.produce_candidate(
+			parachain::HeadData(genesis_head().encode()),
+			Vec::new(),
+		);
+
+		assert_eq!(BlockData::decode(&mut &block_data.0[..]), Some(BlockData { state: 0, add: ADD }));
+
+		let next_head = HeadData {
+			number: 1,
+			parent_hash: ::tiny_keccak::keccak256(&genesis_head().encode()),
+			post_state: hash_state(ADD),
+		};
+
+		let (block_data, _, _) = context.produce_candidate(
+			parachain::HeadData(next_head.encode()),
+			Vec::new(),
+		);
+
+		assert_eq!(BlockData::decode(&mut &block_data.0[..]), Some(BlockData { state: ADD, add: ADD }));
+	}
+}
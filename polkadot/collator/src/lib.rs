@@ -44,24 +44,42 @@
 //! This crate defines traits which provide context necessary for collation logic
 //! to be performed, as the collation logic itself.
 
+extern crate ed25519;
 extern crate futures;
+extern crate substrate_client as client;
 extern crate substrate_codec as codec;
+extern crate substrate_network as network;
 extern crate substrate_primitives as primitives;
+extern crate polkadot_api;
+extern crate polkadot_erasure_coding as erasure;
+extern crate polkadot_parachain;
 extern crate polkadot_runtime;
 extern crate polkadot_primitives;
+extern crate tiny_keccak;
+
+#[macro_use]
+extern crate log;
 
 use std::collections::{BTreeSet, BTreeMap};
+use std::sync::Arc;
 
-use futures::{stream, Stream, Future, IntoFuture};
+use client::BlockchainEvents;
+use codec::Slicable;
+use futures::{future, stream, Stream, Future, IntoFuture};
+use polkadot_api::PolkadotApi;
+use polkadot_primitives::{Block, BlockId, Hash};
 use polkadot_primitives::parachain::{self, CandidateSignature, ConsolidatedIngress, Message, Id as ParaId};
 
+pub mod basic_add;
+
 /// Parachain context needed for collation.
 ///
 /// This can be implemented through an externally attached service or a stub.
 pub trait ParachainContext {
-	/// Produce a candidate, given the latest ingress queue information.
+	/// Produce a candidate, given the latest parachain head and ingress queue information.
 	fn produce_candidate<I: IntoIterator<Item=(ParaId, Message)>>(
 		&self,
+		last_head: parachain::HeadData,
 		ingress: I,
 	) -> (parachain::BlockData, polkadot_primitives::AccountId, CandidateSignature);
 }
@@ -120,8 +138,8 @@ pub fn collate_ingress<'a, R>(relay_context: R)
 		.map(ConsolidatedIngress))
 }
 
-/// Produce a candidate for the parachain.
-pub fn collate<'a, R, P>(local_id: ParaId, relay_context: R, para_context: P)
+/// Produce a candidate for the parachain, building on the given parachain head.
+pub fn collate<'a, R, P>(local_id: ParaId, last_head: parachain::HeadData, relay_context: R, para_context: P)
 	-> Box<Future<Item=parachain::Candidate, Error=R::Error> + 'a>
 	where
 		R: RelayChainContext,
@@ -131,6 +149,7 @@ pub fn collate<'a, R, P>(local_id: ParaId, relay_context: R, para_context: P)
 {
 	Box::new(collate_ingress(relay_context).map(move |ingress| {
 		let (block_data, _, signature) = para_context.produce_candidate(
+			last_head,
 			ingress.0.iter().flat_map(|&(id, ref msgs)| msgs.iter().cloned().map(move |msg| (id, msg)))
 		);
 
@@ -143,6 +162,142 @@ pub fn collate<'a, R, P>(local_id: ParaId, relay_context: R, para_context: P)
 	}))
 }
 
+/// Relay chain context which routes no messages to the local parachain.
+// TODO: gather un-routed egress queues from the relay chain state.
+#[derive(Clone, Copy)]
+pub struct NoIngress;
+
+impl RelayChainContext for NoIngress {
+	type Error = ();
+	type FutureEgress = Result<Vec<Vec<Message>>, ()>;
+
+	fn routing_parachains(&self) -> BTreeSet<ParaId> {
+		BTreeSet::new()
+	}
+
+	fn unrouted_egress(&self, _id: ParaId) -> Self::FutureEgress {
+		Ok(Vec::new())
+	}
+}
+
+/// Turn a candidate into a full collation, building the candidate receipt.
+///
+/// The block data is executed against the current validation code of the parachain
+/// to obtain the new head data, and erasure-coded for the current set of authorities.
+pub fn produce_collation<P: PolkadotApi>(
+	client: &P,
+	relay_parent: &P::CheckedBlockId,
+	candidate: parachain::Candidate,
+	collator: polkadot_primitives::AccountId,
+) -> Result<parachain::Collation, String> {
+	use polkadot_parachain::{wasm, ValidationParams};
+
+	let para_id = candidate.parachain_index;
+	let validation_code = client.parachain_code(relay_parent, para_id)
+		.map_err(|e| format!("{}", e))?
+		.ok_or_else(|| format!("Parachain {:?} is not active", para_id))?;
+
+	let parent_head = client.parachain_head(relay_parent, para_id)
+		.map_err(|e| format!("{}", e))?
+		.ok_or_else(|| format!("Parachain {:?} is not active", para_id))?;
+
+	let params = ValidationParams {
+		parent_head,
+		block_data: candidate.block.0.clone(),
+	};
+
+	let result = wasm::validate_candidate(&validation_code, params)
+		.map_err(|e| format!("Produced invalid candidate: {}", e))?;
+
+	let n_validators = client.session_keys(relay_parent).map_err(|e| format!("{}", e))?.len();
+	let chunks = erasure::obtain_chunks(n_validators, &candidate.block)
+		.map_err(|e| format!("Failed to erasure-code candidate: {:?}", e))?;
+
+	let receipt = parachain::CandidateReceipt {
+		parachain_index: para_id,
+		collator,
+		head_data: parachain::HeadData(result.head_data),
+		balance_uploads: Vec::new(),
+		egress_queue_roots: Vec::new(),
+		fees: 0,
+		erasure_root: erasure::branches(&chunks).root(),
+	};
+
+	Ok(parachain::Collation {
+		block_data: candidate.block,
+		receipt,
+	})
+}
+
+/// Collate on the given parachain whenever a new best relay chain block is
+/// imported, submitting the collations to validators over the network.
+///
+/// The returned future runs until the client's import notification stream ends.
+pub fn collate_on_new_heads<C, A, P>(
+	para_id: ParaId,
+	client: Arc<C>,
+	api: Arc<A>,
+	network: Arc<network::ConsensusService<Block>>,
+	parachain_context: P,
+	key: Arc<ed25519::Pair>,
+) -> Box<Future<Item=(), Error=()>>
+	where
+		C: BlockchainEvents<Block> + 'static,
+		A: PolkadotApi + 'static,
+		P: ParachainContext + Clone + 'static,
+{
+	let collator: polkadot_primitives::AccountId = key.public().0.into();
+
+	// collations are produced one after another, without blocking the reactor while waiting
+	// for ingress.
+	Box::new(client.import_notification_stream().for_each(move |notification| -> Box<Future<Item=(), Error=()>> {
+		if !notification.is_new_best { return Box::new(future::ok(())) }
+
+		let relay_parent: Hash = notification.hash;
+		let checked_id = match api.check_id(BlockId::hash(relay_parent)) {
+			Ok(id) => id,
+			Err(e) => {
+				warn!("Unable to collate on {}: {}", relay_parent, e);
+				return Box::new(future::ok(()));
+			}
+		};
+
+		let last_head = match api.parachain_head(&checked_id, para_id) {
+			Ok(Some(head)) => parachain::HeadData(head),
+			Ok(None) => {
+				debug!("Parachain {:?} is not active at {}", para_id, relay_parent);
+				return Box::new(future::ok(()));
+			}
+			Err(e) => {
+				warn!("Unable to collate on {}: {}", relay_parent, e);
+				return Box::new(future::ok(()));
+			}
+		};
+
+		let api = api.clone();
+		let network = network.clone();
+		Box::new(collate(para_id, last_head, NoIngress, parachain_context.clone()).then(move |candidate| -> Result<(), ()> {
+			let candidate = match candidate {
+				Ok(candidate) => candidate,
+				Err(()) => return Ok(()),
+			};
+
+			match produce_collation(&*api, &checked_id, candidate, collator) {
+				Ok(collation) => {
+					info!("Submitting collation for parachain {:?} on top of {}", para_id, relay_parent);
+					network.send_collation(network::CollationMessage {
+						relay_parent,
+						collation: collation.encode(),
+					});
+				}
+				Err(e) => warn!("Failed to produce collation on {}: {}", relay_parent, e),
+			}
+
+			Ok(())
+		}))
+	}))
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -22,18 +22,12 @@
 use std::sync::Arc;
 
 use polkadot_api::PolkadotApi;
-use polkadot_primitives::{Hash, AccountId};
-use polkadot_primitives::parachain::{Id as ParaId, Chain, BlockData, Extrinsic, CandidateReceipt};
+use polkadot_primitives::Hash;
+use polkadot_primitives::parachain::{Id as ParaId, Chain, Extrinsic};
 
 use futures::prelude::*;
 
-/// A full collation.
-pub struct Collation {
-	/// Block data.
-	pub block_data: BlockData,
-	/// The candidate receipt itself.
-	pub receipt: CandidateReceipt,
-}
+pub use polkadot_primitives::parachain::Collation;
 
 /// Encapsulates connections to collators and allows collation on any parachain.
 ///
@@ -47,8 +41,9 @@ pub trait Collators: Clone {
 	/// Collate on a specific parachain, building on a given relay chain parent hash.
 	fn collate(&self, parachain: ParaId, relay_parent: Hash) -> Self::Collation;
 
-	/// Note a bad collator. TODO: take proof
-	fn note_bad_collator(&self, collator: AccountId);
+	/// Note a bad collation, built on the given relay chain parent hash. The collator
+	/// which submitted it should no longer be collated from.
+	fn note_bad_collation(&self, relay_parent: Hash, collation: &Collation);
 }
 
 /// A future which resolves when a collation is available.
@@ -113,7 +108,7 @@ impl<C: Collators, P: PolkadotApi> Future for CollationFetch<C, P> {
 
 					// just continue if we got a bad collation or failed to validate
 					self.live_fetch = None;
-					self.collators.note_bad_collator(x.receipt.collator)
+					self.collators.note_bad_collation(self.relay_parent_hash, &x)
 				}
 			}
 		}
@@ -137,6 +132,14 @@ error_chain! {
 			description("Parachain validation produced wrong head data."),
 			display("Parachain validation produced wrong head data (expected: {:?}, got {:?}", expected, got),
 		}
+		ErasureRootMismatch(expected: Hash, got: Hash) {
+			description("Erasure root of candidate does not match its block data."),
+			display("Erasure root of candidate does not match its block data (expected: {:?}, got {:?})", expected, got),
+		}
+		Erasure(err: ::erasure::Error) {
+			description("Failed to erasure-code block data."),
+			display("Failed to erasure-code block data: {:?}", err),
+		}
 	}
 
 	links {
@@ -155,6 +158,15 @@ pub fn validate_collation<P: PolkadotApi>(client: &P, relay_parent: &P::CheckedB
 	let chain_head = client.parachain_head(relay_parent, para_id)?
 		.ok_or_else(|| ErrorKind::InactiveParachain(para_id))?;
 
+	// the block data must be recoverable from the chunks committed to in the receipt.
+	let n_validators = client.session_keys(relay_parent)?.len();
+	let chunks = ::erasure::obtain_chunks(n_validators, &collation.block_data)
+		.map_err(ErrorKind::Erasure)?;
+	let erasure_root = ::erasure::branches(&chunks).root();
+	if erasure_root != collation.receipt.erasure_root {
+		bail!(ErrorKind::ErasureRootMismatch(collation.receipt.erasure_root, erasure_root));
+	}
+
 	let params = ValidationParams {
 		parent_head: chain_head,
 		block_data: collation.block_data.0.clone(),
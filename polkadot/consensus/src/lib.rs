@@ -32,9 +32,10 @@
 extern crate ed25519;
 extern crate parking_lot;
 extern crate polkadot_api;
-extern crate polkadot_collator as collator;
+extern crate polkadot_availability_store as av_store;
 extern crate polkadot_statement_table as table;
 extern crate polkadot_parachain as parachain;
+extern crate polkadot_erasure_coding as erasure;
 extern crate polkadot_transaction_pool as transaction_pool;
 extern crate polkadot_runtime;
 extern crate polkadot_primitives;
@@ -123,8 +124,9 @@ pub trait Network {
 	/// routing statements to peers, and driving completion of any `StatementProducers`.
 	type TableRouter: TableRouter;
 
-	/// Instantiate a table router using the given shared table.
-	fn table_router(&self, table: Arc<SharedTable>) -> Self::TableRouter;
+	/// Instantiate a table router using the given shared table and the set of
+	/// authorities among which candidate data is to be kept available.
+	fn table_router(&self, table: Arc<SharedTable>, authorities: &[AuthorityId]) -> Self::TableRouter;
 }
 
 /// Information about a specific group.
@@ -266,7 +268,7 @@ impl<C, N, P> bft::ProposerFactory<Block> for ProposerFactory<C, N, P>
 
 		let n_parachains = active_parachains.len();
 		let table = Arc::new(SharedTable::new(group_info, sign_with.clone(), parent_hash));
-		let router = self.network.table_router(table.clone());
+		let router = self.network.table_router(table.clone(), authorities);
 		let dynamic_inclusion = DynamicInclusion::new(
 			n_parachains,
 			Instant::now(),
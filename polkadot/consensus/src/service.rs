@@ -19,19 +19,23 @@
 /// Consensus service. A long runnung service that manages BFT agreement and parachain
 /// candidate agreement over the network.
 
+use std::collections::{HashMap, HashSet};
 use std::thread;
 use std::time::{Duration, Instant};
 use std::sync::Arc;
 
+use av_store;
 use bft::{self, BftService};
 use client::{BlockchainEvents, ChainHead};
 use ed25519;
 use futures::prelude::*;
 use futures::{future, Canceled};
+use parking_lot::Mutex;
 use polkadot_api::LocalPolkadotApi;
 use polkadot_primitives::{BlockId, Block, Header, Hash, AccountId};
-use polkadot_primitives::parachain::{Id as ParaId, BlockData, Extrinsic, CandidateReceipt};
+use polkadot_primitives::parachain::{Id as ParaId, BlockData, Extrinsic, CandidateReceipt, ErasureChunk};
 use primitives::AuthorityId;
+use codec::Slicable;
 use runtime_support::Hashable;
 use substrate_network as net;
 use tokio_core::reactor;
@@ -43,6 +47,11 @@ use error;
 const TIMER_DELAY_MS: u64 = 5000;
 const TIMER_INTERVAL_MS: u64 = 500;
 
+// maximum number of unknown candidates to keep erasure chunks for.
+const MAX_PENDING_CANDIDATES: usize = 64;
+// maximum number of chunks to keep for a single unknown candidate. only one of them can be valid.
+const MAX_PENDING_CHUNKS_PER_CANDIDATE: usize = 4;
+
 struct BftSink<E> {
 	network: Arc<net::ConsensusService<Block>>,
 	parent_hash: Hash,
@@ -174,17 +183,121 @@ impl<E> Sink for BftSink<E> {
 	}
 }
 
-struct Network(Arc<net::ConsensusService<Block>>);
+struct Network {
+	network: Arc<net::ConsensusService<Block>>,
+	availability_store: av_store::Store,
+	handle: reactor::Handle,
+}
 
 impl super::Network for Network {
 	type TableRouter = Router;
-	fn table_router(&self, _table: Arc<SharedTable>) -> Self::TableRouter {
+	fn table_router(&self, table: Arc<SharedTable>, authorities: &[AuthorityId]) -> Self::TableRouter {
+		let parent_hash = *table.consensus_parent_hash();
+		let local_index = authorities.iter().position(|a| a == &table.session_key());
+
+		if let Some(local_index) = local_index {
+			self.handle.spawn(import_erasure_chunks(
+				self.network.erasure_chunks(parent_hash),
+				table,
+				self.availability_store.clone(),
+				local_index as u32,
+				parent_hash,
+			));
+		}
+
 		Router {
-			network: self.0.clone()
+			network: self.network.clone(),
+			availability_store: self.availability_store.clone(),
+			parent_hash,
+			n_validators: authorities.len(),
+		}
+	}
+}
+
+// Erasure chunks for candidates which are not yet known, so can't be checked.
+#[derive(Default)]
+struct PendingChunks {
+	by_candidate: HashMap<Hash, Vec<net::ErasureChunkMessage<Block>>>,
+}
+
+impl PendingChunks {
+	// Keep a chunk until its candidate is known. Chunks beyond the limits are dropped.
+	fn insert(&mut self, msg: net::ErasureChunkMessage<Block>) {
+		if !self.by_candidate.contains_key(&msg.candidate_hash) && self.by_candidate.len() >= MAX_PENDING_CANDIDATES {
+			debug!("Dropping erasure chunk for candidate {}: too many unknown candidates", msg.candidate_hash);
+			return;
+		}
+
+		let chunks = self.by_candidate.entry(msg.candidate_hash).or_insert_with(Vec::new);
+		if chunks.len() >= MAX_PENDING_CHUNKS_PER_CANDIDATE {
+			debug!("Dropping erasure chunk for candidate {}: too many pending chunks", msg.candidate_hash);
+			return;
+		}
+
+		chunks.push(msg);
+	}
+
+	// Take the chunks of all candidates for which `erasure_root` yields the root.
+	fn take_known<F>(&mut self, mut erasure_root: F) -> Vec<(Hash, net::ErasureChunkMessage<Block>)>
+		where F: FnMut(&Hash) -> Option<Hash>
+	{
+		let known: Vec<(Hash, Hash)> = self.by_candidate.keys()
+			.filter_map(|candidate| erasure_root(candidate).map(|root| (*candidate, root)))
+			.collect();
+
+		let mut chunks = Vec::new();
+		for (candidate, root) in known {
+			if let Some(pending) = self.by_candidate.remove(&candidate) {
+				chunks.extend(pending.into_iter().map(|msg| (root, msg)));
+			}
 		}
+
+		chunks
 	}
 }
 
+// Keep the chunks gossiped for the local validator, once they can be checked
+// against the erasure root of a known candidate.
+//
+// The stream concludes when chunks for a newer parent hash are requested, dropping
+// the chunks of candidates which never became known.
+fn import_erasure_chunks(
+	chunks: net::ErasureChunkStream<Block>,
+	table: Arc<SharedTable>,
+	availability_store: av_store::Store,
+	local_index: u32,
+	parent_hash: Hash,
+) -> Box<Future<Item=(),Error=()>> {
+	let mut pending = PendingChunks::default();
+
+	Box::new(chunks.filter(move |msg| msg.index == local_index).for_each(move |msg| {
+		pending.insert(msg);
+
+		let known = pending.take_known(|candidate_hash| {
+			table.with_candidate(candidate_hash, |c| c.map(|c| c.erasure_root))
+		});
+
+		for (erasure_root, msg) in known {
+			match ::erasure::check_chunk(&erasure_root, &msg.chunk, &msg.proof, msg.index as usize) {
+				Ok(()) => {
+					let chunk = ErasureChunk {
+						chunk: msg.chunk,
+						index: msg.index,
+						proof: msg.proof,
+					};
+
+					if let Err(e) = availability_store.add_erasure_chunk(parent_hash, msg.candidate_hash, &chunk) {
+						warn!("Failed to store erasure chunk for candidate {}: {:?}", msg.candidate_hash, e);
+					}
+				}
+				Err(e) => debug!("Bad erasure chunk for candidate {}: {:?}", msg.candidate_hash, e),
+			}
+		}
+
+		Ok(())
+	}))
+}
+
 fn start_bft<F, C>(
 	header: &Header,
 	handle: reactor::Handle,
@@ -223,6 +336,23 @@ fn start_bft<F, C>(
 	}
 }
 
+// Prune the availability data of candidates built on the parent of a finalized block,
+// except for those the block included.
+fn prune_availability<A: LocalPolkadotApi>(api: &A, availability_store: &av_store::Store, header: &Header) {
+	let included = api.check_id(BlockId::hash(header.hash()))
+		.and_then(|id| api.included_candidates(&id));
+
+	match included {
+		Ok(candidates) => {
+			let included = candidates.iter().map(|c| c.hash()).collect();
+			if let Err(e) = availability_store.candidates_finalized(header.parent_hash, included) {
+				warn!("Failed to prune availability store: {:?}", e);
+			}
+		}
+		Err(e) => debug!("Unable to read candidates included in block {}: {:?}", header.hash(), e),
+	}
+}
+
 /// Consensus service. Starts working when created.
 pub struct Service {
 	thread: Option<thread::JoinHandle<()>>,
@@ -236,6 +366,7 @@ impl Service {
 		api: Arc<A>,
 		network: Arc<net::ConsensusService<Block>>,
 		transaction_pool: Arc<TransactionPool>,
+		availability_store: av_store::Store,
 		parachain_empty_duration: Duration,
 		key: ed25519::Pair,
 	) -> Service
@@ -251,8 +382,12 @@ impl Service {
 			let factory = ProposerFactory {
 				client: api.clone(),
 				transaction_pool: transaction_pool.clone(),
-				network: Network(network.clone()),
-				collators: NoCollators,
+				network: Network {
+					network: network.clone(),
+					availability_store: availability_store.clone(),
+					handle: core.handle(),
+				},
+				collators: NetworkCollators::new(network.clone()),
 				parachain_empty_duration,
 				handle: core.handle(),
 			};
@@ -262,9 +397,12 @@ impl Service {
 				let handle = core.handle();
 				let network = network.clone();
 				let client = client.clone();
+				let api = api.clone();
 				let bft_service = bft_service.clone();
 
 				client.import_notification_stream().for_each(move |notification| {
+					// imported blocks are finalized by their justification.
+					prune_availability(&*api, &availability_store, &notification.header);
 					if notification.is_new_best {
 						start_bft(&notification.header, handle.clone(), &*client, network.clone(), &*bft_service);
 					}
@@ -329,41 +467,163 @@ impl Drop for Service {
 	}
 }
 
-// Collators implementation which never collates anything.
-// TODO: do a real implementation.
-#[derive(Clone, Copy)]
-struct NoCollators;
+// Collators implementation which receives collations submitted over the network.
+#[derive(Clone)]
+struct NetworkCollators {
+	network: Arc<net::ConsensusService<Block>>,
+	bad_collators: Arc<Mutex<HashSet<AccountId>>>,
+}
+
+impl NetworkCollators {
+	fn new(network: Arc<net::ConsensusService<Block>>) -> Self {
+		NetworkCollators {
+			network,
+			bad_collators: Default::default(),
+		}
+	}
+}
 
-impl ::collation::Collators for NoCollators {
+impl ::collation::Collators for NetworkCollators {
 	type Error = ();
-	type Collation = future::Empty<::collation::Collation, ()>;
+	type Collation = Box<Future<Item=::collation::Collation, Error=()> + Send>;
+
+	fn collate(&self, parachain: ParaId, relay_parent: Hash) -> Self::Collation {
+		let bad_collators = self.bad_collators.clone();
+		let collations = self.network.collations(relay_parent)
+			.filter_map(move |msg| {
+				let collation = ::collation::Collation::decode(&mut &msg.collation[..]);
+				if collation.is_none() {
+					debug!("Received undecodable collation on {}", msg.relay_parent);
+				}
+
+				collation.and_then(|c| if c.receipt.parachain_index == parachain && !bad_collators.lock().contains(&c.receipt.collator) {
+					Some(c)
+				} else {
+					None
+				})
+			});
 
-	fn collate(&self, _parachain: ParaId, _relay_parent: Hash) -> Self::Collation {
-		future::empty()
+		Box::new(collations.into_future()
+			.map_err(|_| ())
+			.and_then(|(collation, _)| collation.ok_or(())))
 	}
 
-	fn note_bad_collator(&self, _collator: AccountId) { }
+	fn note_bad_collation(&self, relay_parent: Hash, collation: &::collation::Collation) {
+		debug!("Bad collation from collator {}", collation.receipt.collator);
+		self.bad_collators.lock().insert(collation.receipt.collator);
+		self.network.report_bad_collation(net::CollationMessage {
+			relay_parent,
+			collation: collation.encode(),
+		});
+	}
 }
 
 #[derive(Clone)]
 struct Router {
 	network: Arc<net::ConsensusService<Block>>,
+	availability_store: av_store::Store,
+	parent_hash: Hash,
+	n_validators: usize,
 }
 
 impl TableRouter for Router {
 	type Error = Canceled;
-	type FetchCandidate =  future::Empty<BlockData, Self::Error>;
+	type FetchCandidate = future::Either<
+		future::FutureResult<BlockData, Self::Error>,
+		future::Empty<BlockData, Self::Error>,
+	>;
 	type FetchExtrinsic = future::FutureResult<Extrinsic, Self::Error>;
 
-	fn local_candidate_data(&self, _hash: Hash, _block_data: BlockData, _extrinsic: Extrinsic) {
-		// TODO
+	fn local_candidate_data(&self, hash: Hash, block_data: BlockData, _extrinsic: Extrinsic) {
+		let chunks = match ::erasure::obtain_chunks(self.n_validators, &block_data) {
+			Ok(chunks) => chunks,
+			Err(e) => {
+				warn!("Failed to erasure-code candidate {}: {:?}", hash, e);
+				return;
+			}
+		};
+
+		let data = av_store::Data {
+			relay_parent: self.parent_hash,
+			candidate_hash: hash,
+			block_data,
+		};
+
+		if let Err(e) = self.availability_store.make_available(data) {
+			warn!("Failed to make candidate {} available: {:?}", hash, e);
+		}
+
+		for (index, (proof, chunk)) in ::erasure::branches(&chunks).enumerate() {
+			self.network.send_erasure_chunk(net::ErasureChunkMessage {
+				parent_hash: self.parent_hash,
+				candidate_hash: hash,
+				index: index as u32,
+				chunk: chunk.to_vec(),
+				proof,
+			});
+		}
 	}
 
-	fn fetch_block_data(&self, _candidate: &CandidateReceipt) -> Self::FetchCandidate {
-		future::empty()
+	fn fetch_block_data(&self, candidate: &CandidateReceipt) -> Self::FetchCandidate {
+		let data = self.availability_store.block_data_or_reconstruct(
+			self.parent_hash,
+			candidate.hash(),
+			self.n_validators,
+		);
+
+		match data {
+			Ok(block_data) => future::Either::A(future::ok(block_data)),
+			Err(_) => future::Either::B(future::empty()),
+		}
 	}
 
 	fn fetch_extrinsic_data(&self, _candidate: &CandidateReceipt) -> Self::FetchExtrinsic {
 		future::ok(Extrinsic)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn chunk(candidate_hash: Hash, index: u32) -> net::ErasureChunkMessage<Block> {
+		net::ErasureChunkMessage {
+			parent_hash: Default::default(),
+			candidate_hash,
+			index,
+			chunk: vec![index as u8],
+			proof: Vec::new(),
+		}
+	}
+
+	#[test]
+	fn pending_chunks_are_bounded() {
+		let mut pending = PendingChunks::default();
+		let first: Hash = [1; 32].into();
+		for i in 0..(MAX_PENDING_CHUNKS_PER_CANDIDATE + 1) {
+			pending.insert(chunk(first, i as u32));
+		}
+		for i in 0..(MAX_PENDING_CANDIDATES + 1) {
+			pending.insert(chunk([i as u8 + 2; 32].into(), 0));
+		}
+
+		assert_eq!(pending.by_candidate.len(), MAX_PENDING_CANDIDATES);
+		assert_eq!(pending.by_candidate[&first].len(), MAX_PENDING_CHUNKS_PER_CANDIDATE);
+	}
+
+	#[test]
+	fn chunks_of_known_candidates_are_taken() {
+		let mut pending = PendingChunks::default();
+		let known: Hash = [1; 32].into();
+		let unknown: Hash = [2; 32].into();
+		let root: Hash = [3; 32].into();
+		pending.insert(chunk(known, 0));
+		pending.insert(chunk(known, 1));
+		pending.insert(chunk(unknown, 0));
+
+		let taken = pending.take_known(|candidate| if candidate == &known { Some(root) } else { None });
+		assert_eq!(taken.len(), 2);
+		assert!(taken.iter().all(|&(r, ref msg)| r == root && msg.candidate_hash == known));
+		assert_eq!(pending.by_candidate.keys().collect::<Vec<_>>(), vec![&unknown]);
+	}
+}
@@ -319,6 +319,16 @@ impl SharedTable {
 		&self.context.groups
 	}
 
+	/// Get the parent hash this table is instantiated for.
+	pub fn consensus_parent_hash(&self) -> &Hash {
+		&self.context.parent_hash
+	}
+
+	/// Get the local session key the table signs with.
+	pub fn session_key(&self) -> AuthorityId {
+		self.context.key.public().0
+	}
+
 	/// Import a single statement. Provide a handle to a table router
 	/// for dispatching any other requests which come up.
 	pub fn import_statement<R: TableRouter, C: FnMut(Collation) -> bool>(
@@ -491,6 +501,7 @@ mod tests {
 			balance_uploads: Vec::new(),
 			egress_queue_roots: Vec::new(),
 			fees: 1_000_000,
+			erasure_root: [1; 32].into(),
 		};
 
 		let candidate_statement = GenericStatement::Candidate(candidate);
@@ -541,6 +552,7 @@ mod tests {
 			balance_uploads: Vec::new(),
 			egress_queue_roots: Vec::new(),
 			fees: 1_000_000,
+			erasure_root: [1; 32].into(),
 		};
 
 		let candidate_statement = GenericStatement::Candidate(candidate);
@@ -0,0 +1,179 @@
+// Copyright 2017 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Reed-Solomon coding over GF(2^8).
+//!
+//! Data is split into `k` equally-sized pieces which are treated, byte-column by
+//! byte-column, as the coefficients of a polynomial of degree less than `k`.
+//! Shard `i` holds the evaluations of those polynomials at the field element `i`,
+//! so any `k` distinct shards determine the data.
+
+/// Number of distinct elements in the field, and so the maximum number of shards.
+pub const FIELD_SIZE: usize = 256;
+
+// x^8 + x^4 + x^3 + x^2 + 1
+const GENERATOR_POLYNOMIAL: usize = 0x11d;
+
+/// Log and exponent tables for GF(2^8).
+pub struct Field {
+	exp: [u8; 2 * FIELD_SIZE],
+	log: [u8; FIELD_SIZE],
+}
+
+impl Field {
+	/// Build the lookup tables.
+	pub fn new() -> Self {
+		let mut exp = [0u8; 2 * FIELD_SIZE];
+		let mut log = [0u8; FIELD_SIZE];
+
+		let mut x = 1usize;
+		for i in 0..FIELD_SIZE - 1 {
+			exp[i] = x as u8;
+			log[x] = i as u8;
+			x <<= 1;
+			if x & FIELD_SIZE != 0 {
+				x ^= GENERATOR_POLYNOMIAL;
+			}
+		}
+
+		for i in FIELD_SIZE - 1..2 * FIELD_SIZE {
+			exp[i] = exp[i - (FIELD_SIZE - 1)];
+		}
+
+		Field { exp, log }
+	}
+
+	/// Multiply two field elements.
+	pub fn mul(&self, a: u8, b: u8) -> u8 {
+		if a == 0 || b == 0 {
+			0
+		} else {
+			self.exp[self.log[a as usize] as usize + self.log[b as usize] as usize]
+		}
+	}
+
+	/// Multiplicative inverse of a non-zero field element.
+	pub fn inv(&self, a: u8) -> u8 {
+		assert!(a != 0, "zero has no multiplicative inverse");
+		self.exp[FIELD_SIZE - 1 - self.log[a as usize] as usize]
+	}
+
+	/// Raise `a` to the power `n`.
+	pub fn pow(&self, a: u8, n: usize) -> u8 {
+		match (a, n) {
+			(_, 0) => 1,
+			(0, _) => 0,
+			_ => self.exp[(self.log[a as usize] as usize * n) % (FIELD_SIZE - 1)],
+		}
+	}
+}
+
+/// Encode `k` pieces of equal length into `n` shards.
+///
+/// Panics if the pieces differ in length or `n` exceeds the field size.
+pub fn encode(field: &Field, pieces: &[Vec<u8>], n: usize) -> Vec<Vec<u8>> {
+	assert!(n <= FIELD_SIZE, "at most {} shards can be produced", FIELD_SIZE);
+	let piece_len = pieces.first().map_or(0, |p| p.len());
+	assert!(pieces.iter().all(|p| p.len() == piece_len), "pieces must be of equal length");
+
+	(0..n).map(|i| {
+		let powers: Vec<u8> = (0..pieces.len()).map(|s| field.pow(i as u8, s)).collect();
+		(0..piece_len).map(|j| {
+			pieces.iter().zip(&powers).fold(0u8, |acc, (piece, &power)| acc ^ field.mul(piece[j], power))
+		}).collect()
+	}).collect()
+}
+
+/// Recover the `k` pieces from exactly `k` shards with distinct indices.
+///
+/// Returns `None` if the indices are not distinct or the shards differ in length.
+pub fn decode(field: &Field, shards: &[(usize, &[u8])]) -> Option<Vec<Vec<u8>>> {
+	let k = shards.len();
+	let shard_len = shards.first().map_or(0, |s| s.1.len());
+	if shards.iter().any(|s| s.1.len() != shard_len || s.0 >= FIELD_SIZE) {
+		return None;
+	}
+
+	// invert the Vandermonde matrix of the evaluation points using Gauss-Jordan elimination.
+	let mut matrix: Vec<Vec<u8>> = shards.iter()
+		.map(|&(i, _)| (0..k).map(|s| field.pow(i as u8, s)).collect())
+		.collect();
+	let mut inverse: Vec<Vec<u8>> = (0..k)
+		.map(|r| (0..k).map(|c| if r == c { 1 } else { 0 }).collect())
+		.collect();
+
+	for col in 0..k {
+		let pivot = (col..k).find(|&r| matrix[r][col] != 0)?;
+		matrix.swap(col, pivot);
+		inverse.swap(col, pivot);
+
+		let scale = field.inv(matrix[col][col]);
+		for c in 0..k {
+			matrix[col][c] = field.mul(matrix[col][c], scale);
+			inverse[col][c] = field.mul(inverse[col][c], scale);
+		}
+
+		for r in 0..k {
+			let factor = matrix[r][col];
+			if r == col || factor == 0 { continue }
+			for c in 0..k {
+				let (m, i) = (field.mul(factor, matrix[col][c]), field.mul(factor, inverse[col][c]));
+				matrix[r][c] ^= m;
+				inverse[r][c] ^= i;
+			}
+		}
+	}
+
+	Some(inverse.iter().map(|row| {
+		(0..shard_len).map(|j| {
+			row.iter().zip(shards).fold(0u8, |acc, (&coeff, shard)| acc ^ field.mul(coeff, shard.1[j]))
+		}).collect()
+	}).collect())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn field_inverse() {
+		let field = Field::new();
+		for a in 1..FIELD_SIZE {
+			assert_eq!(field.mul(a as u8, field.inv(a as u8)), 1);
+		}
+	}
+
+	#[test]
+	fn decode_from_any_shards() {
+		let field = Field::new();
+		let pieces = vec![vec![1, 2, 3], vec![4, 5, 6], vec![7, 8, 9]];
+		let shards = encode(&field, &pieces, 10);
+
+		for &(a, b, c) in &[(0, 1, 2), (7, 8, 9), (9, 0, 5), (3, 6, 2)] {
+			let chosen = [(a, &shards[a][..]), (b, &shards[b][..]), (c, &shards[c][..])];
+			assert_eq!(decode(&field, &chosen).unwrap(), pieces);
+		}
+	}
+
+	#[test]
+	fn decode_fails_on_duplicate_shards() {
+		let field = Field::new();
+		let pieces = vec![vec![1, 2], vec![3, 4]];
+		let shards = encode(&field, &pieces, 4);
+
+		assert!(decode(&field, &[(1, &shards[1][..]), (1, &shards[1][..])]).is_none());
+	}
+}
@@ -0,0 +1,344 @@
+// Copyright 2017 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! As part of Polkadot's availability system, certain pieces of data
+//! for each block are required to be kept available.
+//!
+//! The way we accomplish this is by erasure coding the data into n pieces
+//! and constructing a merkle root of the data.
+//!
+//! Each of n validators stores their piece of data. We assume n=3f+k, k < 3.
+//! f is the maximum number of faulty validators in the system.
+//! The data is coded so any f+1 chunks can be used to reconstruct the full data.
+
+extern crate polkadot_primitives as primitives;
+extern crate substrate_codec as codec;
+extern crate substrate_primitives;
+
+use codec::Slicable;
+use primitives::Hash;
+use primitives::parachain::BlockData;
+use substrate_primitives::hashing::blake2_256;
+
+mod galois;
+
+/// The maximum number of validators the block data can be split between.
+pub const MAX_VALIDATORS: usize = galois::FIELD_SIZE;
+
+/// Errors in erasure coding.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+	/// Returned when there are too many validators.
+	TooManyValidators,
+	/// Cannot encode something for no validators
+	EmptyValidators,
+	/// Cannot reconstruct: wrong number of validators.
+	WrongValidatorCount,
+	/// Not enough chunks present.
+	NotEnoughChunks,
+	/// Chunks not of uniform length or the chunks are empty.
+	NonUniformChunks,
+	/// Chunk index out of bounds.
+	ChunkIndexOutOfBounds(usize, usize),
+	/// Bad payload in reconstructed bytes.
+	BadPayload,
+	/// Invalid branch proof.
+	InvalidBranchProof,
+	/// Branch out of bounds.
+	BranchOutOfBounds,
+}
+
+/// The number of chunks needed to reconstruct the data, given the number of validators.
+///
+/// This is `f + 1` where `f` is the maximum number of faulty validators.
+pub fn recovery_threshold(n_validators: usize) -> Result<usize, Error> {
+	if n_validators > MAX_VALIDATORS { return Err(Error::TooManyValidators) }
+	if n_validators == 0 { return Err(Error::EmptyValidators) }
+
+	let needed = n_validators.saturating_sub(1) / 3;
+	Ok(needed + 1)
+}
+
+/// Obtain erasure-coded chunks, one for each validator.
+///
+/// Works only up to `MAX_VALIDATORS` validators.
+pub fn obtain_chunks(n_validators: usize, block_data: &BlockData) -> Result<Vec<Vec<u8>>, Error> {
+	let threshold = recovery_threshold(n_validators)?;
+
+	// length-prefixed, so padding can be told apart from the data on reconstruction.
+	let encoded = block_data.0.encode();
+	let piece_len = (encoded.len() + threshold - 1) / threshold;
+
+	let pieces: Vec<Vec<u8>> = (0..threshold).map(|i| {
+		let mut piece: Vec<u8> = encoded.iter().skip(i * piece_len).take(piece_len).cloned().collect();
+		piece.resize(piece_len, 0);
+		piece
+	}).collect();
+
+	Ok(galois::encode(&galois::Field::new(), &pieces, n_validators))
+}
+
+/// Reconstruct the block data from a set of chunks.
+///
+/// Provide an iterator containing chunk data and the corresponding index.
+/// The indices of the present chunks must be indicated. If too few chunks
+/// are provided, recovery is not possible.
+///
+/// Works only up to `MAX_VALIDATORS` validators.
+pub fn reconstruct<'a, I: 'a>(n_validators: usize, chunks: I) -> Result<BlockData, Error>
+	where I: IntoIterator<Item=(&'a [u8], usize)>
+{
+	let threshold = recovery_threshold(n_validators)?;
+
+	let mut seen = vec![false; n_validators];
+	let mut shards = Vec::with_capacity(threshold);
+	for (chunk, index) in chunks {
+		if index >= n_validators {
+			return Err(Error::ChunkIndexOutOfBounds(index, n_validators));
+		}
+
+		// duplicates carry no extra information.
+		if seen[index] { continue }
+		seen[index] = true;
+
+		if shards.len() < threshold {
+			shards.push((index, chunk));
+		}
+	}
+
+	if shards.len() < threshold { return Err(Error::NotEnoughChunks) }
+
+	let chunk_len = shards[0].1.len();
+	if chunk_len == 0 || shards.iter().any(|s| s.1.len() != chunk_len) {
+		return Err(Error::NonUniformChunks);
+	}
+
+	let pieces = galois::decode(&galois::Field::new(), &shards).ok_or(Error::NonUniformChunks)?;
+	let encoded: Vec<u8> = pieces.into_iter().flat_map(|p| p).collect();
+
+	Vec::<u8>::decode(&mut &encoded[..]).map(BlockData).ok_or(Error::BadPayload)
+}
+
+fn hash_pair(left: &Hash, right: &Hash) -> Hash {
+	let mut concat = [0u8; 64];
+	concat[..32].copy_from_slice(&left.0);
+	concat[32..].copy_from_slice(&right.0);
+	blake2_256(&concat).into()
+}
+
+/// Merkle branches over a set of erasure chunks.
+///
+/// The leaves are the blake2-256 hashes of the chunks, padded with zero hashes up
+/// to the next power of two.
+pub struct Branches<'a> {
+	chunks: &'a [Vec<u8>],
+	// all tree nodes, leaves first, root last.
+	levels: Vec<Vec<Hash>>,
+	current_pos: usize,
+}
+
+impl<'a> Branches<'a> {
+	/// Get the merkle root of the chunks.
+	pub fn root(&self) -> Hash {
+		self.levels.last().and_then(|l| l.first()).cloned().unwrap_or_default()
+	}
+
+	/// The merkle proof of the chunk at the given index, bottom-up.
+	pub fn proof(&self, index: usize) -> Option<Vec<Hash>> {
+		if index >= self.chunks.len() { return None }
+
+		let mut pos = index;
+		let proof = self.levels[..self.levels.len() - 1].iter().map(|level| {
+			let sibling = level[pos ^ 1];
+			pos >>= 1;
+			sibling
+		}).collect();
+
+		Some(proof)
+	}
+}
+
+impl<'a> Iterator for Branches<'a> {
+	type Item = (Vec<Hash>, &'a [u8]);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let index = self.current_pos;
+		let proof = self.proof(index)?;
+		self.current_pos += 1;
+
+		Some((proof, &self.chunks[index][..]))
+	}
+}
+
+/// Construct a merkle tree over the given chunks, yielding the root and a
+/// proof for each chunk.
+pub fn branches<'a>(chunks: &'a [Vec<u8>]) -> Branches<'a> {
+	let width = chunks.len().next_power_of_two();
+	let mut leaves: Vec<Hash> = chunks.iter().map(|c| blake2_256(c).into()).collect();
+	leaves.resize(width, Hash::default());
+
+	let mut levels = vec![leaves];
+	while levels.last().map_or(false, |l| l.len() > 1) {
+		let next = levels.last().expect("checked in loop condition; qed")
+			.chunks(2)
+			.map(|pair| hash_pair(&pair[0], &pair[1]))
+			.collect();
+		levels.push(next);
+	}
+
+	Branches {
+		chunks,
+		levels,
+		current_pos: 0,
+	}
+}
+
+/// Verify that the given chunk is present at `index` under `root`.
+pub fn check_chunk(root: &Hash, chunk: &[u8], branch: &[Hash], index: usize) -> Result<(), Error> {
+	// no tree has more leaves than there can be validators. this also keeps the shift
+	// below in range for branches of arbitrary length.
+	let max_branch_len = MAX_VALIDATORS.next_power_of_two().trailing_zeros() as usize;
+	if branch.len() > max_branch_len || index >= 1 << branch.len() { return Err(Error::BranchOutOfBounds) }
+
+	let mut pos = index;
+	let leaf: Hash = blake2_256(chunk).into();
+	let computed = branch.iter().fold(leaf, |node, sibling| {
+		let parent = if pos & 1 == 0 { hash_pair(&node, sibling) } else { hash_pair(sibling, &node) };
+		pos >>= 1;
+		parent
+	});
+
+	if &computed == root {
+		Ok(())
+	} else {
+		Err(Error::InvalidBranchProof)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn recovery_thresholds() {
+		assert_eq!(recovery_threshold(0), Err(Error::EmptyValidators));
+		assert_eq!(recovery_threshold(1), Ok(1));
+		assert_eq!(recovery_threshold(3), Ok(1));
+		assert_eq!(recovery_threshold(4), Ok(2));
+		assert_eq!(recovery_threshold(10), Ok(4));
+		assert_eq!(recovery_threshold(MAX_VALIDATORS + 1), Err(Error::TooManyValidators));
+	}
+
+	#[test]
+	fn round_trip_works() {
+		let block_data = BlockData((0..255).collect());
+		let chunks = obtain_chunks(10, &block_data).unwrap();
+
+		assert_eq!(chunks.len(), 10);
+
+		// any 4 chunks should work.
+		let reconstructed = reconstruct(
+			10,
+			[
+				(&*chunks[1], 1),
+				(&*chunks[4], 4),
+				(&*chunks[6], 6),
+				(&*chunks[9], 9),
+			].iter().cloned(),
+		).unwrap();
+
+		assert_eq!(reconstructed, block_data);
+	}
+
+	#[test]
+	fn recovers_from_every_threshold_subset() {
+		let block_data = BlockData(b"some parachain block data".to_vec());
+		let chunks = obtain_chunks(7, &block_data).unwrap();
+
+		for a in 0..7 {
+			for b in (a + 1)..7 {
+				for c in (b + 1)..7 {
+					let reconstructed = reconstruct(
+						7,
+						vec![(&chunks[a][..], a), (&chunks[b][..], b), (&chunks[c][..], c)],
+					).unwrap();
+
+					assert_eq!(reconstructed, block_data);
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn reconstruct_does_not_work_with_too_few_chunks() {
+		let block_data = BlockData((0..255).collect());
+		let chunks = obtain_chunks(10, &block_data).unwrap();
+
+		assert_eq!(
+			reconstruct(10, vec![(&chunks[0][..], 0), (&chunks[1][..], 1), (&chunks[2][..], 2)]),
+			Err(Error::NotEnoughChunks),
+		);
+
+		// duplicates don't count towards the threshold.
+		assert_eq!(
+			reconstruct(10, vec![(&chunks[0][..], 0), (&chunks[0][..], 0), (&chunks[1][..], 1), (&chunks[2][..], 2)]),
+			Err(Error::NotEnoughChunks),
+		);
+
+		assert_eq!(
+			reconstruct(10, vec![(&chunks[0][..], 10)]),
+			Err(Error::ChunkIndexOutOfBounds(10, 10)),
+		);
+	}
+
+	#[test]
+	fn empty_block_data_round_trips() {
+		let block_data = BlockData(Vec::new());
+		let chunks = obtain_chunks(4, &block_data).unwrap();
+
+		assert_eq!(reconstruct(4, vec![(&chunks[3][..], 3), (&chunks[0][..], 0)]).unwrap(), block_data);
+	}
+
+	#[test]
+	fn construct_valid_branches() {
+		let block_data = BlockData(vec![2; 256]);
+		let chunks = obtain_chunks(10, &block_data).unwrap();
+
+		assert_eq!(chunks.len(), 10);
+
+		let branches = branches(&chunks);
+		let root = branches.root();
+
+		let proofs: Vec<_> = branches.map(|(proof, _)| proof).collect();
+		assert_eq!(proofs.len(), 10);
+
+		for (i, proof) in proofs.into_iter().enumerate() {
+			assert_eq!(check_chunk(&root, &chunks[i], &proof, i), Ok(()));
+			assert_eq!(check_chunk(&root, &chunks[i], &proof, (i + 1) % 10), Err(Error::InvalidBranchProof));
+			assert_eq!(check_chunk(&root, &chunks[i], &proof, 16), Err(Error::BranchOutOfBounds));
+		}
+	}
+
+	#[test]
+	fn overlong_branches_are_rejected() {
+		let chunks = obtain_chunks(10, &BlockData(vec![2; 256])).unwrap();
+		let root = branches(&chunks).root();
+
+		let branch = vec![Hash::default(); 64];
+		assert_eq!(check_chunk(&root, &chunks[0], &branch, 0), Err(Error::BranchOutOfBounds));
+		assert_eq!(check_chunk(&root, &chunks[0], &branch, usize::max_value()), Err(Error::BranchOutOfBounds));
+	}
+}
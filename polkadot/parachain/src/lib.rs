@@ -55,10 +55,26 @@ extern crate core;
 #[cfg(feature = "std")]
 extern crate wasmi;
 
+#[cfg(feature = "std")]
+extern crate parity_wasm;
+
+#[cfg(feature = "std")]
+extern crate pwasm_utils;
+
+#[cfg(feature = "std")]
+extern crate parking_lot;
+
+#[cfg(feature = "std")]
+extern crate tiny_keccak;
+
 #[cfg(feature = "std")]
 #[macro_use]
 extern crate error_chain;
 
+#[cfg(feature = "std")]
+#[macro_use]
+extern crate lazy_static;
+
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 use codec::Slicable;
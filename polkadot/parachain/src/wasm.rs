@@ -19,16 +19,33 @@
 //! steps to ensure that the provided input parameters are correct.
 //! Assuming the parameters are correct, this module provides a wrapper around
 //! a WASM VM for re-execution of a parachain candidate.
+//!
+//! Validation code is instrumented before execution so that memory usage, stack height
+//! and the number of executed instructions are bounded by `ExecutionLimits`. Prepared
+//! modules are cached by the hash of their original code.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::Arc;
 
 use codec::Slicable;
+use parking_lot::Mutex;
+use parity_wasm::elements;
+use pwasm_utils::{self, rules};
 
-use wasmi::{self, Module, ModuleInstance,  MemoryInstance, MemoryDescriptor, MemoryRef, ModuleImportResolver};
-use wasmi::{memory_units, RuntimeValue};
+use wasmi::{self, Module, ModuleInstance, MemoryInstance, MemoryDescriptor, MemoryRef, ModuleImportResolver};
+use wasmi::{memory_units, RuntimeValue, RuntimeArgs, Externals, FuncRef, FuncInstance, Signature};
+use wasmi::{Trap, TrapKind, HostError, ValueType};
 use wasmi::Error as WasmError;
 
 use super::{ValidationParams, ValidationResult};
 
-use std::cell::RefCell;
+/// Index of the injected gas-metering host function.
+const GAS_FUNC_INDEX: usize = 0;
+
+/// Maximum number of prepared modules kept in a cache.
+const MAX_CACHED_MODULES: usize = 64;
 
 error_chain! {
 	types { Error, ErrorKind, ResultExt; }
@@ -46,6 +63,82 @@ error_chain! {
 			description("Validation function returned invalid data."),
 			display("Validation function returned invalid data."),
 		}
+		/// Validation code could not be instrumented for execution.
+		Instrumentation(reason: &'static str) {
+			description("Validation code could not be instrumented."),
+			display("Validation code could not be instrumented: {}", reason),
+		}
+		/// More memory was needed than allowed.
+		MemoryLimitExceeded(requested: u32, max: u32) {
+			description("Validation required more memory than allowed."),
+			display("Validation required {} memory pages, max allowed is {}", requested, max),
+		}
+		/// The stack height limit was hit.
+		StackExhausted(max: u32) {
+			description("Validation exceeded the stack height limit."),
+			display("Validation exceeded the stack height limit of {}", max),
+		}
+		/// The execution step limit was hit.
+		StepsExhausted(max: u64) {
+			description("Validation exceeded the execution step limit."),
+			display("Validation exceeded the execution step limit of {}", max),
+		}
+	}
+}
+
+/// Resource limits for executing validation code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutionLimits {
+	/// Maximum number of linear memory pages (64 KiB each).
+	pub max_memory_pages: u32,
+	/// Maximum stack height, as computed by `pwasm_utils::stack_height`.
+	pub max_stack_height: u32,
+	/// Maximum number of execution steps. Every executed instruction costs one step.
+	pub max_steps: u64,
+}
+
+impl Default for ExecutionLimits {
+	fn default() -> Self {
+		ExecutionLimits {
+			max_memory_pages: 1024, // 64 MiB
+			max_stack_height: 64 * 1024,
+			max_steps: 1_000_000_000,
+		}
+	}
+}
+
+#[derive(Debug)]
+struct StepsExhausted;
+
+impl fmt::Display for StepsExhausted {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "StepsExhausted")
+	}
+}
+
+impl HostError for StepsExhausted {}
+
+/// Externals metering the steps taken by instrumented validation code.
+struct StepMeter {
+	left: u64,
+	exhausted: bool,
+}
+
+impl Externals for StepMeter {
+	fn invoke_index(&mut self, index: usize, args: RuntimeArgs) -> Result<Option<RuntimeValue>, Trap> {
+		match index {
+			GAS_FUNC_INDEX => {
+				let amount: u32 = args.nth_checked(0)?;
+				if u64::from(amount) > self.left {
+					self.left = 0;
+					self.exhausted = true;
+					return Err(TrapKind::Host(Box::new(StepsExhausted)).into());
+				}
+				self.left -= u64::from(amount);
+				Ok(None)
+			}
+			_ => Err(TrapKind::UnexpectedSignature.into()),
+		}
 	}
 }
 
@@ -55,19 +148,32 @@ struct Resolver {
 }
 
 impl ModuleImportResolver for Resolver {
+	fn resolve_func(
+		&self,
+		field_name: &str,
+		signature: &Signature,
+	) -> Result<FuncRef, WasmError> {
+		if field_name == "gas" && signature.params() == &[ValueType::I32][..] && signature.return_type().is_none() {
+			Ok(FuncInstance::alloc_host(signature.clone(), GAS_FUNC_INDEX))
+		} else {
+			Err(WasmError::Instantiation(format!("Function {} imported under unknown name or signature", field_name)))
+		}
+	}
+
 	fn resolve_memory(
 		&self,
 		field_name: &str,
 		descriptor: &MemoryDescriptor,
 	) -> Result<MemoryRef, WasmError> {
 		if field_name == "memory" {
-			let effective_max = descriptor.maximum().unwrap_or(self.max_memory);
-			if descriptor.initial() > self.max_memory || effective_max > self.max_memory {
+			// growth is capped at the limit whatever maximum the module declares.
+			let effective_max = ::std::cmp::min(descriptor.maximum().unwrap_or(self.max_memory), self.max_memory);
+			if descriptor.initial() > self.max_memory {
 				Err(WasmError::Instantiation("Module requested too much memory".to_owned()))
 			} else {
 				let mem = MemoryInstance::alloc(
 					memory_units::Pages(descriptor.initial() as usize),
-					descriptor.maximum().map(|x| memory_units::Pages(x as usize)),
+					Some(memory_units::Pages(effective_max as usize)),
 				)?;
 				*self.memory.borrow_mut() = Some(mem.clone());
 				Ok(mem)
@@ -78,89 +184,234 @@ impl ModuleImportResolver for Resolver {
 	}
 }
 
-/// Validate a candidate under the given validation code.
-///
-/// This will fail if the validation code is not a proper parachain validation module.
-pub fn validate_candidate(validation_code: &[u8], params: ValidationParams) -> Result<ValidationResult, Error> {
-	use wasmi::LINEAR_MEMORY_PAGE_SIZE;
+/// Check the initial size of the memory imported by the validation code against the memory
+/// limit. The declared maximum is not checked, as growth is capped at the limit.
+fn check_memory_import(module: &elements::Module, max_memory: u32) -> Result<(), Error> {
+	let memory_type = module.import_section().and_then(|section| section.entries().iter()
+		.filter_map(|import| match (import.module(), import.field(), import.external()) {
+			("env", "memory", &elements::External::Memory(ref memory_type)) => Some(memory_type),
+			_ => None,
+		})
+		.next()
+	);
+
+	if let Some(memory_type) = memory_type {
+		let initial = memory_type.limits().initial();
+		if initial > max_memory {
+			bail!(ErrorKind::MemoryLimitExceeded(initial, max_memory));
+		}
+	}
 
-	// maximum memory in bytes
-	const MAX_MEM: u32 = 1024 * 1024 * 1024; // 1 GiB
+	Ok(())
+}
 
-	// instantiate the module.
-	let (module, memory) = {
-		let module = Module::from_buffer(validation_code)?;
+/// Instrument validation code with step metering and a stack height limiter.
+fn prepare_module(validation_code: &[u8], limits: &ExecutionLimits) -> Result<Module, Error> {
+	let module: elements::Module = elements::deserialize_buffer(validation_code)
+		.map_err(|_| ErrorKind::Instrumentation("deserialization failed"))?;
 
-		let module_resolver = Resolver {
-			max_memory: MAX_MEM / LINEAR_MEMORY_PAGE_SIZE.0 as u32,
-			memory: RefCell::new(None),
-		};
+	if module.memory_section().map_or(false, |ms| ms.entries().len() > 0) {
+		bail!(ErrorKind::Instrumentation("internal memory declared"));
+	}
 
-		let module = ModuleInstance::new(
-			&module,
-			&wasmi::ImportsBuilder::new().with_resolver("env", &module_resolver),
-		)?.run_start(&mut wasmi::NopExternals).map_err(WasmError::Trap)?;
+	check_memory_import(&module, limits.max_memory_pages)?;
 
-		let memory = module_resolver.memory.borrow_mut()
-			.as_ref()
-			.ok_or_else(|| WasmError::Instantiation("No imported memory instance".to_owned()))?
-			.clone();
+	let gas_rules = rules::Set::new(1, Default::default()).with_forbidden_floats();
+	let module = pwasm_utils::inject_gas_counter(module, &gas_rules)
+		.map_err(|_| ErrorKind::Instrumentation("step metering injection failed"))?;
 
-		(module, memory)
-	};
+	let module = pwasm_utils::stack_height::inject_limiter(module, limits.max_stack_height)
+		.map_err(|_| ErrorKind::Instrumentation("stack height limiter injection failed"))?;
 
-	// allocate call data in memory.
-	let (offset, len) = {
-		let encoded_call_data = params.encode();
+	let instrumented = elements::serialize(module)
+		.map_err(|_| ErrorKind::Instrumentation("serialization failed"))?;
 
-		// hard limit from WASM.
-		if encoded_call_data.len() > i32::max_value() as usize {
-			bail!(ErrorKind::ParamsTooLarge(encoded_call_data.len()));
-		}
+	Module::from_buffer(instrumented).map_err(Into::into)
+}
 
-		let call_data_pages = (encoded_call_data.len() / LINEAR_MEMORY_PAGE_SIZE.0) +
-			(encoded_call_data.len() % LINEAR_MEMORY_PAGE_SIZE.0);
+/// Executes parachain validation code under resource limits, caching prepared modules
+/// by the hash of their code.
+pub struct ValidationExecutor {
+	limits: ExecutionLimits,
+	cache: Mutex<ModuleCache>,
+}
 
-		let call_data_pages = wasmi::memory_units::Pages(call_data_pages);
+#[derive(Default)]
+struct ModuleCache {
+	modules: HashMap<[u8; 32], Arc<Module>>,
+	order: VecDeque<[u8; 32]>,
+}
 
-		if memory.current_size() < call_data_pages {
-			memory.grow(call_data_pages - memory.current_size())?;
+impl ValidationExecutor {
+	/// Create a new executor enforcing the given limits.
+	pub fn new(limits: ExecutionLimits) -> Self {
+		ValidationExecutor {
+			limits,
+			cache: Mutex::new(ModuleCache::default()),
 		}
+	}
 
-		memory.set(0, &encoded_call_data).expect("enough memory allocated just before this; \
-			copying never fails if memory is large enough; qed");
+	/// The limits enforced by this executor.
+	pub fn limits(&self) -> &ExecutionLimits {
+		&self.limits
+	}
 
-		(0, encoded_call_data.len() as i32)
-	};
+	/// The number of prepared modules currently cached.
+	pub fn cached_modules(&self) -> usize {
+		self.cache.lock().modules.len()
+	}
 
-	let output = module.invoke_export(
-		"validate",
-		&[RuntimeValue::I32(offset), RuntimeValue::I32(len)],
-		&mut wasmi::NopExternals,
-	)?;
+	/// Fetch the prepared module for the given code, preparing and caching it if necessary.
+	fn prepared_module(&self, validation_code: &[u8]) -> Result<Arc<Module>, Error> {
+		let code_hash = ::tiny_keccak::keccak256(validation_code);
 
-	match output {
-		Some(RuntimeValue::I32(len_offset)) => {
-			let len_offset = len_offset as u32;
+		if let Some(module) = self.cache.lock().modules.get(&code_hash) {
+			return Ok(module.clone());
+		}
+
+		// prepare outside of the lock; preparing the same code twice concurrently is harmless.
+		let module = Arc::new(prepare_module(validation_code, &self.limits)?);
 
-			let mut len_bytes = [0u8; 4];
-			memory.get_into(len_offset, &mut len_bytes)?;
+		let mut cache = self.cache.lock();
+		if !cache.modules.contains_key(&code_hash) {
+			if cache.order.len() >= MAX_CACHED_MODULES {
+				if let Some(evicted) = cache.order.pop_front() {
+					cache.modules.remove(&evicted);
+				}
+			}
+			cache.order.push_back(code_hash);
+			cache.modules.insert(code_hash, module.clone());
+		}
 
-			let len = u32::decode(&mut &len_bytes[..])
-				.ok_or_else(|| ErrorKind::BadReturn)?;
+		Ok(module)
+	}
 
-			let return_offset = if len > len_offset {
-				bail!(ErrorKind::BadReturn);
-			} else {
-				len_offset - len
+	/// Validate a candidate under the given validation code.
+	///
+	/// This will fail if the validation code is not a proper parachain validation module
+	/// or if its execution exceeds any of the limits.
+	pub fn validate_candidate(&self, validation_code: &[u8], params: ValidationParams) -> Result<ValidationResult, Error> {
+		use wasmi::LINEAR_MEMORY_PAGE_SIZE;
+
+		let max_steps = self.limits.max_steps;
+		let max_stack_height = self.limits.max_stack_height;
+		let module = self.prepared_module(validation_code)?;
+		let mut meter = StepMeter { left: max_steps, exhausted: false };
+
+		// instantiate the module.
+		let (module, memory) = {
+			let module_resolver = Resolver {
+				max_memory: self.limits.max_memory_pages,
+				memory: RefCell::new(None),
 			};
 
-			// TODO: optimize when `wasmi` lets you inspect memory with a closure.
-			let raw_return = memory.get(return_offset, len as usize)?;
-			ValidationResult::decode(&mut &raw_return[..])
-				.ok_or_else(|| ErrorKind::BadReturn)
-				.map_err(Into::into)
+			let module = ModuleInstance::new(
+				&module,
+				&wasmi::ImportsBuilder::new().with_resolver("env", &module_resolver),
+			)?.run_start(&mut meter).map_err(|trap| trap_error(trap, meter.exhausted, max_steps, max_stack_height))?;
+
+			let memory = module_resolver.memory.borrow_mut()
+				.as_ref()
+				.ok_or_else(|| WasmError::Instantiation("No imported memory instance".to_owned()))?
+				.clone();
+
+			(module, memory)
+		};
+
+		// allocate call data in memory.
+		let (offset, len) = {
+			let encoded_call_data = params.encode();
+
+			// hard limit from WASM.
+			if encoded_call_data.len() > i32::max_value() as usize {
+				bail!(ErrorKind::ParamsTooLarge(encoded_call_data.len()));
+			}
+
+			let call_data_pages = (encoded_call_data.len() + LINEAR_MEMORY_PAGE_SIZE.0 - 1) /
+				LINEAR_MEMORY_PAGE_SIZE.0;
+
+			if call_data_pages > self.limits.max_memory_pages as usize {
+				bail!(ErrorKind::MemoryLimitExceeded(call_data_pages as u32, self.limits.max_memory_pages));
+			}
+
+			let call_data_pages = wasmi::memory_units::Pages(call_data_pages);
+
+			if memory.current_size() < call_data_pages {
+				memory.grow(call_data_pages - memory.current_size())?;
+			}
+
+			memory.set(0, &encoded_call_data).expect("enough memory allocated just before this; \
+				copying never fails if memory is large enough; qed");
+
+			(0, encoded_call_data.len() as i32)
+		};
+
+		let output = match module.invoke_export(
+			"validate",
+			&[RuntimeValue::I32(offset), RuntimeValue::I32(len)],
+			&mut meter,
+		) {
+			Ok(output) => output,
+			Err(WasmError::Trap(trap)) => return Err(trap_error(trap, meter.exhausted, max_steps, max_stack_height)),
+			Err(e) => return Err(e.into()),
+		};
+
+		match output {
+			Some(RuntimeValue::I32(len_offset)) => {
+				let len_offset = len_offset as u32;
+
+				let mut len_bytes = [0u8; 4];
+				memory.get_into(len_offset, &mut len_bytes)?;
+
+				let len = u32::decode(&mut &len_bytes[..])
+					.ok_or_else(|| ErrorKind::BadReturn)?;
+
+				let return_offset = if len > len_offset {
+					bail!(ErrorKind::BadReturn);
+				} else {
+					len_offset - len
+				};
+
+				// TODO: optimize when `wasmi` lets you inspect memory with a closure.
+				let raw_return = memory.get(return_offset, len as usize)?;
+				ValidationResult::decode(&mut &raw_return[..])
+					.ok_or_else(|| ErrorKind::BadReturn)
+					.map_err(Into::into)
+			}
+			_ => bail!(ErrorKind::BadReturn),
 		}
-		_ => bail!(ErrorKind::BadReturn),
 	}
 }
+
+impl Default for ValidationExecutor {
+	fn default() -> Self {
+		ValidationExecutor::new(ExecutionLimits::default())
+	}
+}
+
+/// Map a trap of instrumented validation code to the limit it hit, if any.
+///
+/// The stack height limiter traps with `unreachable`, which can't be told apart from an
+/// `unreachable` in the validation code itself; both are reported as stack exhaustion.
+fn trap_error(trap: Trap, steps_exhausted: bool, max_steps: u64, max_stack_height: u32) -> Error {
+	if steps_exhausted {
+		return ErrorKind::StepsExhausted(max_steps).into();
+	}
+
+	match *trap.kind() {
+		TrapKind::StackOverflow | TrapKind::Unreachable => ErrorKind::StackExhausted(max_stack_height).into(),
+		_ => WasmError::Trap(trap).into(),
+	}
+}
+
+lazy_static! {
+	static ref DEFAULT_EXECUTOR: ValidationExecutor = ValidationExecutor::default();
+}
+
+/// Validate a candidate under the given validation code, using the default execution limits
+/// and a process-wide module cache.
+///
+/// This will fail if the validation code is not a proper parachain validation module.
+pub fn validate_candidate(validation_code: &[u8], params: ValidationParams) -> Result<ValidationResult, Error> {
+	DEFAULT_EXECUTOR.validate_candidate(validation_code, params)
+}
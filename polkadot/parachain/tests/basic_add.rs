@@ -18,8 +18,10 @@
 
 extern crate polkadot_parachain as parachain;
 extern crate tiny_keccak;
+extern crate parity_wasm;
 
 use parachain::ValidationParams;
+use parachain::wasm::{ValidationExecutor, ExecutionLimits, Error, ErrorKind};
 use parachain::codec::{Slicable, Input};
 
 // Head data for this parachain.
@@ -168,3 +170,89 @@ fn execute_bad_on_parent() {
 		block_data: block_data.encode(),
 	}).unwrap_err();
 }
+
+fn good_params() -> ValidationParams {
+	let parent_head = HeadData {
+		number: 0,
+		parent_hash: [0; 32],
+		post_state: hash_state(0),
+	};
+
+	let block_data = BlockData {
+		state: 0,
+		add: 512,
+	};
+
+	ValidationParams {
+		parent_head: parent_head.encode(),
+		block_data: block_data.encode(),
+	}
+}
+
+#[test]
+fn prepared_module_is_cached() {
+	let executor = ValidationExecutor::default();
+	assert_eq!(executor.cached_modules(), 0);
+
+	executor.validate_candidate(TEST_CODE, good_params()).unwrap();
+	executor.validate_candidate(TEST_CODE, good_params()).unwrap();
+
+	assert_eq!(executor.cached_modules(), 1);
+}
+
+#[test]
+fn execution_step_limit() {
+	let executor = ValidationExecutor::new(ExecutionLimits {
+		max_steps: 10,
+		..Default::default()
+	});
+
+	match executor.validate_candidate(TEST_CODE, good_params()) {
+		Err(Error(ErrorKind::StepsExhausted(10), _)) => {},
+		other => panic!("expected step limit to be hit, got {:?}", other.map(|_| ())),
+	}
+}
+
+#[test]
+fn memory_limit() {
+	let executor = ValidationExecutor::new(ExecutionLimits {
+		max_memory_pages: 1,
+		..Default::default()
+	});
+
+	match executor.validate_candidate(TEST_CODE, good_params()) {
+		Err(Error(ErrorKind::MemoryLimitExceeded(_, 1), _)) => {},
+		other => panic!("expected memory limit to be hit, got {:?}", other.map(|_| ())),
+	}
+}
+
+#[test]
+fn memory_maximum_above_limit() {
+	use parity_wasm::elements::{self, External, ImportEntry, MemoryType};
+
+	// declare a maximum above the limit; the initial size still fits.
+	let mut module = elements::deserialize_buffer(TEST_CODE).unwrap();
+	for entry in module.import_section_mut().unwrap().entries_mut() {
+		if entry.field() == "memory" {
+			*entry = ImportEntry::new("env".into(), "memory".into(), External::Memory(MemoryType::new(17, Some(1 << 16))));
+		}
+	}
+	let code = elements::serialize(module).unwrap();
+
+	let executor = ValidationExecutor::default();
+	assert!(executor.limits().max_memory_pages < 1 << 16);
+	executor.validate_candidate(&code, good_params()).unwrap();
+}
+
+#[test]
+fn stack_limit() {
+	let executor = ValidationExecutor::new(ExecutionLimits {
+		max_stack_height: 1,
+		..Default::default()
+	});
+
+	match executor.validate_candidate(TEST_CODE, good_params()) {
+		Err(Error(ErrorKind::StackExhausted(1), _)) => {},
+		other => panic!("expected stack limit to be hit, got {:?}", other.map(|_| ())),
+	}
+}
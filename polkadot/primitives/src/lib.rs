@@ -40,7 +40,7 @@ extern crate serde;
 use primitives::bytes;
 
 use rstd::prelude::*;
-use runtime_primitives::traits::BlakeTwo256;
+use runtime_primitives::traits::{BlakeTwo256, DigestItem};
 use runtime_primitives::generic;
 use codec::{Input, Slicable};
 
@@ -114,3 +114,13 @@ impl Slicable for Log {
 		self.0.using_encoded(f)
 	}
 }
+
+impl DigestItem for Log {
+	fn changes_trie_root(root: [u8; 32]) -> Option<Self> {
+		Vec::<u8>::changes_trie_root(root).map(Log)
+	}
+
+	fn as_changes_trie_root(&self) -> Option<[u8; 32]> {
+		self.0.as_changes_trie_root()
+	}
+}
@@ -59,7 +59,8 @@ impl Slicable for Id {
 
 /// Identifier for a chain, either one of a number of parachains or the relay chain.
 #[derive(Copy, Clone, PartialEq)]
-#[cfg_attr(feature = "std", derive(Debug))]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
 pub enum Chain {
 	/// The relay chain.
 	Relay,
@@ -96,7 +97,8 @@ impl Slicable for Chain {
 
 /// The duty roster specifying what jobs each validator must do.
 #[derive(Clone, PartialEq)]
-#[cfg_attr(feature = "std", derive(Default, Debug))]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Default, Debug))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
 pub struct DutyRoster {
 	/// Lookup from validator index to chain on which that validator has a duty to validate.
 	pub validator_duty: Vec<Chain>,
@@ -172,6 +174,8 @@ pub struct CandidateReceipt {
 	pub egress_queue_roots: Vec<(Id, Hash)>,
 	/// Fees paid from the chain to the relay chain validators
 	pub fees: u64,
+	/// Merkle root of the erasure-coded chunks of the block data.
+	pub erasure_root: Hash,
 }
 
 impl Slicable for CandidateReceipt {
@@ -184,6 +188,7 @@ impl Slicable for CandidateReceipt {
 		self.balance_uploads.using_encoded(|s| v.extend(s));
 		self.egress_queue_roots.using_encoded(|s| v.extend(s));
 		self.fees.using_encoded(|s| v.extend(s));
+		self.erasure_root.using_encoded(|s| v.extend(s));
 
 		v
 	}
@@ -196,6 +201,7 @@ impl Slicable for CandidateReceipt {
 			balance_uploads: Slicable::decode(input)?,
 			egress_queue_roots: Slicable::decode(input)?,
 			fees: Slicable::decode(input)?,
+			erasure_root: Slicable::decode(input)?,
 		})
 	}
 }
@@ -223,6 +229,37 @@ impl Ord for CandidateReceipt {
 	}
 }
 
+/// A full collation: a candidate receipt along with the block data it was
+/// produced from.
+#[derive(PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "std", serde(deny_unknown_fields))]
+pub struct Collation {
+	/// Block data.
+	pub block_data: BlockData,
+	/// The candidate receipt itself.
+	pub receipt: CandidateReceipt,
+}
+
+impl Slicable for Collation {
+	fn encode(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+
+		self.block_data.0.using_encoded(|s| v.extend(s));
+		self.receipt.using_encoded(|s| v.extend(s));
+
+		v
+	}
+
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Some(Collation {
+			block_data: Slicable::decode(input).map(BlockData)?,
+			receipt: Slicable::decode(input)?,
+		})
+	}
+}
+
 /// Parachain ingress queue message.
 #[derive(PartialEq, Eq, Clone)]
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
@@ -243,6 +280,41 @@ pub struct ConsolidatedIngress(pub Vec<(Id, Vec<Message>)>);
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
 pub struct BlockData(#[cfg_attr(feature = "std", serde(with="bytes"))] pub Vec<u8>);
 
+/// A chunk of erasure-coded block data, as held by a single validator.
+#[derive(PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "std", serde(deny_unknown_fields))]
+pub struct ErasureChunk {
+	/// The erasure-encoded chunk of data belonging to the candidate block.
+	#[cfg_attr(feature = "std", serde(with="bytes"))]
+	pub chunk: Vec<u8>,
+	/// The index of this erasure-encoded chunk of data.
+	pub index: u32,
+	/// Proof for this chunk's branch in the Merkle tree.
+	pub proof: Vec<Hash>,
+}
+
+impl Slicable for ErasureChunk {
+	fn encode(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+
+		self.chunk.using_encoded(|s| v.extend(s));
+		self.index.using_encoded(|s| v.extend(s));
+		self.proof.using_encoded(|s| v.extend(s));
+
+		v
+	}
+
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Some(ErasureChunk {
+			chunk: Slicable::decode(input)?,
+			index: Slicable::decode(input)?,
+			proof: Slicable::decode(input)?,
+		})
+	}
+}
+
 /// Parachain header raw bytes wrapper type.
 #[derive(PartialEq, Eq)]
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
@@ -0,0 +1,53 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Polkadot-specific RPC interfaces.
+
+#![warn(missing_docs)]
+
+extern crate jsonrpc_core as rpc;
+extern crate jsonrpc_pubsub;
+extern crate polkadot_api;
+extern crate polkadot_primitives;
+extern crate substrate_client as client;
+extern crate substrate_rpc;
+extern crate substrate_state_machine as state_machine;
+extern crate tokio_core;
+
+#[macro_use]
+extern crate error_chain;
+#[macro_use]
+extern crate jsonrpc_macros;
+#[macro_use]
+extern crate log;
+
+#[cfg(test)]
+#[macro_use]
+extern crate assert_matches;
+#[cfg(test)]
+extern crate polkadot_executor;
+#[cfg(test)]
+extern crate polkadot_runtime;
+#[cfg(test)]
+extern crate substrate_bft as bft;
+#[cfg(test)]
+extern crate substrate_executor;
+#[cfg(test)]
+extern crate substrate_keyring as keyring;
+#[cfg(test)]
+extern crate substrate_runtime_primitives as runtime_primitives;
+
+pub mod parachain;
@@ -0,0 +1,44 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+use client;
+use polkadot_api;
+use rpc;
+
+error_chain! {
+	links {
+		Client(client::error::Error, client::error::ErrorKind) #[doc = "Client error"];
+		Api(polkadot_api::Error, polkadot_api::ErrorKind) #[doc = "Polkadot API error"];
+	}
+}
+
+impl From<Error> for rpc::Error {
+	fn from(e: Error) -> Self {
+		match e {
+			Error(ErrorKind::Api(polkadot_api::ErrorKind::UnknownBlock(b)), _) => rpc::Error {
+				code: rpc::ErrorCode::ServerError(-1),
+				message: format!("Unknown block {}", b),
+				data: None,
+			},
+			Error(ErrorKind::Api(polkadot_api::ErrorKind::UnknownRuntime), _) => rpc::Error {
+				code: rpc::ErrorCode::ServerError(-2),
+				message: "Parachain state is not available from this node".into(),
+				data: None,
+			},
+			_ => rpc::Error::internal_error(),
+		}
+	}
+}
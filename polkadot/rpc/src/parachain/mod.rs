@@ -0,0 +1,172 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Polkadot parachain API.
+
+use std::sync::Arc;
+
+use client::{self, Client, BlockchainEvents};
+use polkadot_api::PolkadotApi;
+use polkadot_primitives::{Block, BlockId, Hash};
+use polkadot_primitives::parachain::{DutyRoster, HeadData, Id as ParaId, ValidationCode};
+use state_machine;
+
+use jsonrpc_macros::{pubsub, Trailing};
+use jsonrpc_pubsub::SubscriptionId;
+use rpc::Result as RpcResult;
+use rpc::futures::{Future, Sink, Stream};
+use substrate_rpc::Subscriptions;
+use tokio_core::reactor::Remote;
+
+pub mod error;
+#[cfg(test)]
+mod tests;
+
+use self::error::Result;
+
+build_rpc_trait! {
+	/// Polkadot parachain API
+	pub trait ParachainApi {
+		type Metadata;
+
+		/// Get the active parachains at a block, or at the best block if none is given.
+		#[rpc(name = "parachain_getActive")]
+		fn active_parachains(&self, Trailing<Hash>) -> Result<Vec<ParaId>>;
+
+		/// Get the head data of a parachain at a block, or at the best block if none is given.
+		#[rpc(name = "parachain_getHead")]
+		fn parachain_head(&self, ParaId, Trailing<Hash>) -> Result<Option<HeadData>>;
+
+		/// Get the validation code of a parachain at a block, or at the best block if none is given.
+		#[rpc(name = "parachain_getCode")]
+		fn parachain_code(&self, ParaId, Trailing<Hash>) -> Result<Option<ValidationCode>>;
+
+		/// Get the duty roster at a block, or at the best block if none is given.
+		#[rpc(name = "parachain_getDutyRoster")]
+		fn duty_roster(&self, Trailing<Hash>) -> Result<DutyRoster>;
+
+		#[pubsub(name = "parachain_head")] {
+			/// Subscribe to head data changes of a parachain.
+			#[rpc(name = "parachain_subscribeHead")]
+			fn subscribe_head(&self, Self::Metadata, pubsub::Subscriber<Option<HeadData>>, ParaId);
+
+			/// Unsubscribe from head data changes of a parachain.
+			#[rpc(name = "parachain_unsubscribeHead")]
+			fn unsubscribe_head(&self, SubscriptionId) -> RpcResult<bool>;
+		}
+	}
+}
+
+/// Parachain API with subscriptions support.
+pub struct Parachain<B, E, A> {
+	/// Substrate client.
+	client: Arc<Client<B, E, Block>>,
+	/// Polkadot API used to query the runtime.
+	api: Arc<A>,
+	/// Current subscriptions.
+	subscriptions: Subscriptions,
+}
+
+impl<B, E, A> Parachain<B, E, A> {
+	/// Create new Parachain API RPC handler.
+	pub fn new(client: Arc<Client<B, E, Block>>, api: Arc<A>, remote: Remote) -> Self {
+		Parachain {
+			client,
+			api,
+			subscriptions: Subscriptions::new(remote),
+		}
+	}
+}
+
+impl<B, E, A> Parachain<B, E, A> where
+	B: client::backend::Backend<Block> + Send + Sync + 'static,
+	E: client::CallExecutor<Block> + Send + Sync + 'static,
+	A: PolkadotApi,
+	client::error::Error: From<<<B as client::backend::Backend<Block>>::State as state_machine::backend::Backend>::Error>,
+{
+	fn check_id(&self, at: Trailing<Hash>) -> Result<A::CheckedBlockId> {
+		let hash = match at.into() {
+			Some(hash) => hash,
+			None => self.client.info()?.chain.best_hash,
+		};
+
+		Ok(self.api.check_id(BlockId::hash(hash))?)
+	}
+}
+
+impl<B, E, A> ParachainApi for Parachain<B, E, A> where
+	B: client::backend::Backend<Block> + Send + Sync + 'static,
+	E: client::CallExecutor<Block> + Send + Sync + 'static,
+	A: PolkadotApi + Send + Sync + 'static,
+	client::error::Error: From<<<B as client::backend::Backend<Block>>::State as state_machine::backend::Backend>::Error>,
+{
+	type Metadata = ::substrate_rpc::metadata::Metadata;
+
+	fn active_parachains(&self, at: Trailing<Hash>) -> Result<Vec<ParaId>> {
+		let id = self.check_id(at)?;
+		Ok(self.api.active_parachains(&id)?)
+	}
+
+	fn parachain_head(&self, parachain: ParaId, at: Trailing<Hash>) -> Result<Option<HeadData>> {
+		let id = self.check_id(at)?;
+		Ok(self.api.parachain_head(&id, parachain)?.map(HeadData))
+	}
+
+	fn parachain_code(&self, parachain: ParaId, at: Trailing<Hash>) -> Result<Option<ValidationCode>> {
+		let id = self.check_id(at)?;
+		Ok(self.api.parachain_code(&id, parachain)?.map(ValidationCode))
+	}
+
+	fn duty_roster(&self, at: Trailing<Hash>) -> Result<DutyRoster> {
+		let id = self.check_id(at)?;
+		Ok(self.api.duty_roster(&id)?)
+	}
+
+	fn subscribe_head(&self, _metadata: Self::Metadata, subscriber: pubsub::Subscriber<Option<HeadData>>, parachain: ParaId) {
+		let api = self.api.clone();
+		// the head seen at the previous best block; `None` until the first notification.
+		let mut last_head = None;
+
+		self.subscriptions.add(subscriber, |sink| {
+			let stream = self.client.import_notification_stream()
+				.filter(|notification| notification.is_new_best)
+				.filter_map(move |notification| {
+					let head = api.check_id(BlockId::hash(notification.hash))
+						.and_then(|id| api.parachain_head(&id, parachain))
+						.map_err(|e| warn!("Unable to fetch head of parachain {:?}: {:?}", parachain, e))
+						.ok()?
+						.map(HeadData);
+
+					if last_head.as_ref() == Some(&head) {
+						return None;
+					}
+
+					last_head = Some(head.clone());
+					Some(Ok(head))
+				})
+				.map_err(|e| warn!("Block notification stream error: {:?}", e));
+			sink
+				.sink_map_err(|e| warn!("Error sending notifications: {:?}", e))
+				.send_all(stream)
+				// we ignore the resulting Stream (if the first stream is over we are unsubscribed)
+				.map(|_| ())
+		});
+	}
+
+	fn unsubscribe_head(&self, id: SubscriptionId) -> RpcResult<bool> {
+		Ok(self.subscriptions.cancel(id))
+	}
+}
@@ -0,0 +1,171 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+use client::LocalCallExecutor;
+use client::in_mem::Backend as InMemory;
+use jsonrpc_macros::pubsub;
+use jsonrpc_pubsub::SubscriptionId;
+use keyring::Keyring;
+use polkadot_executor::Executor as LocalDispatch;
+use polkadot_primitives::parachain::{CandidateReceipt, Chain};
+use polkadot_runtime::{GenesisConfig, ConsensusConfig, SessionConfig, ParachainsConfig, BuildExternalities};
+use runtime_primitives::traits::Header as HeaderT;
+use substrate_executor::{NativeExecutionDispatch, NativeExecutor};
+
+type TestClient = Client<InMemory<Block>, LocalCallExecutor<InMemory<Block>, NativeExecutor<LocalDispatch>>, Block>;
+
+fn client() -> TestClient {
+	struct GenesisBuilder;
+
+	impl client::GenesisBuilder<Block> for GenesisBuilder {
+		fn build(self) -> (::polkadot_primitives::Header, Vec<(Vec<u8>, Vec<u8>)>) {
+			let genesis_config = GenesisConfig {
+				consensus: Some(ConsensusConfig {
+					code: LocalDispatch::native_equivalent().to_vec(),
+					authorities: vec![Keyring::One.to_raw_public(), Keyring::Two.to_raw_public()],
+				}),
+				system: None,
+				session: Some(SessionConfig {
+					validators: vec![Keyring::One.to_raw_public().into(), Keyring::Two.to_raw_public().into()],
+					session_length: 100,
+				}),
+				council: Some(Default::default()),
+				democracy: Some(Default::default()),
+				parachains: Some(ParachainsConfig {
+					parachains: vec![(5.into(), vec![1, 2, 3], vec![4, 5, 6])],
+					phantom: Default::default(),
+				}),
+				staking: Some(Default::default()),
+			};
+
+			let storage = genesis_config.build_externalities();
+			let block = client::genesis::construct_genesis_block::<Block>(&storage);
+			(block.header, storage.into_iter().collect())
+		}
+	}
+
+	client::new_in_mem(LocalDispatch::new(), GenesisBuilder).unwrap()
+}
+
+// imports a block justified by both genesis authorities.
+fn justify_and_import(client: &TestClient, block: Block) {
+	let hash = block.header.hash();
+	let justification = bft::UncheckedJustification {
+		digest: hash,
+		signatures: [Keyring::One, Keyring::Two].iter().map(|key| {
+			let msg = bft::sign_message::<Block>(
+				bft::generic::Vote::Commit(1, hash).into(),
+				&key.pair(),
+				block.header.parent_hash,
+			);
+
+			match msg {
+				bft::generic::LocalizedMessage::Vote(vote) => vote.signature,
+				_ => panic!("signing vote leads to signed vote"),
+			}
+		}).collect(),
+		round_number: 1,
+	};
+
+	let justified = client.check_justification(block.header, justification).unwrap();
+	client.import_block(client::BlockOrigin::Own, justified, Some(block.extrinsics)).unwrap();
+}
+
+fn parachain(remote: Remote) -> Parachain<InMemory<Block>, LocalCallExecutor<InMemory<Block>, NativeExecutor<LocalDispatch>>, TestClient> {
+	let client = Arc::new(client());
+	Parachain::new(client.clone(), client, remote)
+}
+
+#[test]
+fn should_return_active_parachains() {
+	let core = ::tokio_core::reactor::Core::new().unwrap();
+	let api = parachain(core.remote());
+	let genesis_hash = api.client.block_hash(0).unwrap().unwrap();
+
+	assert_matches!(api.active_parachains(None.into()), Ok(ref x) if x == &vec![5.into()]);
+	assert_matches!(api.active_parachains(Some(genesis_hash).into()), Ok(ref x) if x == &vec![5.into()]);
+	assert_matches!(api.active_parachains(Some(5.into()).into()), Err(_));
+}
+
+#[test]
+fn should_return_head_and_code() {
+	let core = ::tokio_core::reactor::Core::new().unwrap();
+	let api = parachain(core.remote());
+
+	assert_matches!(
+		api.parachain_head(5.into(), None.into()),
+		Ok(Some(ref x)) if x == &HeadData(vec![4, 5, 6])
+	);
+	assert_matches!(
+		api.parachain_code(5.into(), None.into()),
+		Ok(Some(ref x)) if x == &ValidationCode(vec![1, 2, 3])
+	);
+	assert_matches!(api.parachain_head(6.into(), None.into()), Ok(None));
+	assert_matches!(api.parachain_code(6.into(), None.into()), Ok(None));
+}
+
+#[test]
+fn should_return_duty_roster() {
+	let core = ::tokio_core::reactor::Core::new().unwrap();
+	let api = parachain(core.remote());
+
+	let roster = api.duty_roster(None.into()).unwrap();
+	assert_eq!(roster.validator_duty.len(), 2);
+	assert_eq!(roster.guarantor_duty.len(), 2);
+	assert!(roster.validator_duty.iter().all(|c| *c == Chain::Relay || *c == Chain::Parachain(5.into())));
+}
+
+#[test]
+fn should_notify_about_head_changes() {
+	let mut core = ::tokio_core::reactor::Core::new().unwrap();
+	let (subscriber, id, transport) = pubsub::Subscriber::new_test("test");
+
+	{
+		let api = parachain(core.remote());
+		api.subscribe_head(Default::default(), subscriber, 5.into());
+
+		// assert id assigned
+		assert_eq!(core.run(id), Ok(Ok(SubscriptionId::Number(0))));
+
+		let receipt = CandidateReceipt {
+			parachain_index: 5.into(),
+			collator: Default::default(),
+			head_data: HeadData(vec![7, 8, 9]),
+			balance_uploads: Vec::new(),
+			egress_queue_roots: Vec::new(),
+			fees: 0,
+			erasure_root: Default::default(),
+		};
+
+		let id = api.client.check_id(BlockId::number(0)).unwrap();
+		let block = api.client.build_block(&id, 1_000_000, vec![receipt]).unwrap().bake().unwrap();
+		justify_and_import(&api.client, block);
+
+		// the head is unchanged by the next block.
+		let id = api.client.check_id(BlockId::number(1)).unwrap();
+		let block = api.client.build_block(&id, 1_000_010, Vec::new()).unwrap().bake().unwrap();
+		justify_and_import(&api.client, block);
+	}
+
+	// assert notification send to transport
+	let (notification, next) = core.run(transport.into_future()).unwrap();
+	assert_eq!(notification, Some(
+		r#"{"jsonrpc":"2.0","method":"test","params":{"result":"0x070809","subscription":0}}"#.to_owned()
+	));
+	// no more notifications on this channel
+	assert_eq!(core.run(next.into_future()).unwrap().0, None);
+}
@@ -64,6 +64,7 @@ use rstd::prelude::*;
 use primitives::{AccountId, Balance, BlockNumber, Hash, Index, Log, SessionKey, Signature};
 use primitives::parachain::CandidateReceipt;
 use runtime_primitives::{generic, traits::{HasPublicAux, BlakeTwo256, Convert}};
+use runtime_primitives::version::RuntimeVersion;
 
 #[cfg(feature = "std")]
 pub use runtime_primitives::BuildExternalities;
@@ -78,6 +79,15 @@ pub const TIMESTAMP_SET_POSITION: u32 = 0;
 /// The position of the parachains set extrinsic.
 pub const PARACHAINS_SET_POSITION: u32 = 1;
 
+/// Runtime version.
+pub const VERSION: RuntimeVersion = RuntimeVersion {
+	spec_name: ver_str!("polkadot"),
+	spec_version: 2,
+	impl_name: ver_str!("parity-polkadot"),
+	impl_version: 0,
+	apis: ver_apis!(("core", 1), ("metadata", 1), ("session", 1), ("payment", 1), ("inherents", 1)),
+};
+
 /// Block Id type for this block.
 pub type BlockId = generic::BlockId<Block>;
 /// Unchecked extrinsic type as expected by this runtime.
@@ -276,6 +286,7 @@ impl_outer_dispatch! {
 		Democracy = 5,
 		Council = 6,
 		CouncilVoting = 7,
+		Parachains = 8,
 	}
 }
 
@@ -295,6 +306,25 @@ impl_outer_config! {
 	}
 }
 
+impl_json_metadata! {
+	for Concrete with modules {
+		system: System [storage],
+		consensus: Consensus,
+		timestamp: Timestamp [storage],
+		session: Session [storage],
+		staking: Staking [storage],
+		democracy: Democracy [storage],
+		council: Council [storage],
+		council_voting: CouncilVoting [storage],
+		parachains: Parachains [storage],
+	}
+}
+
+/// The fee to be paid for including the given extrinsic.
+pub fn transaction_fee(uxt: UncheckedExtrinsic) -> Balance {
+	Staking::transaction_fee(::codec::Slicable::encode(&uxt).len())
+}
+
 /// Produces the list of inherent extrinsics.
 pub fn inherent_extrinsics(timestamp: ::primitives::Timestamp, parachain_heads: Vec<CandidateReceipt>) -> Vec<UncheckedExtrinsic> {
 	vec![
@@ -326,6 +356,7 @@ pub fn check_extrinsic(xt: UncheckedExtrinsic) -> bool {
 
 pub mod api {
 	impl_stubs!(
+		version => |()| super::VERSION,
 		authorities => |()| super::Consensus::authorities(),
 		initialise_block => |header| super::Executive::initialise_block(&header),
 		apply_extrinsic => |extrinsic| super::Executive::apply_extrinsic(extrinsic),
@@ -333,7 +364,9 @@ pub mod api {
 		finalise_block => |()| super::Executive::finalise_block(),
 		inherent_extrinsics => |(timestamp, heads)| super::inherent_extrinsics(timestamp, heads),
 		validator_count => |()| super::Session::validator_count(),
-		validators => |()| super::Session::validators()
+		validators => |()| super::Session::validators(),
+		json_metadata => |()| super::Concrete::json_metadata(),
+		payment_query_info => |uxt| super::transaction_fee(uxt)
 	);
 }
 
@@ -483,4 +516,40 @@ mod tests {
 
 		assert_eq!(Extrinsic::decode(&mut &v[..]).unwrap(), xt);
 	}
+
+	#[test]
+	fn json_metadata_describes_all_modules() {
+		#[derive(Deserialize)]
+		struct Named {
+			name: String,
+		}
+
+		#[derive(Deserialize)]
+		struct Module {
+			name: String,
+			storage: Vec<Named>,
+			calls: Vec<Named>,
+		}
+
+		#[derive(Deserialize)]
+		struct Metadata {
+			modules: Vec<Module>,
+		}
+
+		let metadata: Metadata = ser::from_slice(&Concrete::json_metadata()).unwrap();
+
+		let names: Vec<_> = metadata.modules.iter().map(|m| &m.name[..]).collect();
+		assert_eq!(names, vec![
+			"system", "consensus", "timestamp", "session", "staking",
+			"democracy", "council", "council_voting", "parachains",
+		]);
+
+		let consensus = &metadata.modules[1];
+		assert!(consensus.storage.is_empty());
+		assert!(consensus.calls.iter().any(|c| c.name == "PrivCall"));
+
+		let parachains = &metadata.modules[8];
+		assert!(parachains.storage.iter().any(|s| s.name == "Heads"));
+		assert!(parachains.calls.iter().any(|c| c.name == "Call"));
+	}
 }
@@ -14,13 +14,14 @@
 // You should have received a copy of the GNU General Public License
 // along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
 
-//! Main parachains logic. For now this is just the determination of which validators do what.
+//! Main parachains logic. For now this is just the determination of which validators do what,
+//! along with privileged registration, deregistration and code upgrades of parachains.
 
 use primitives;
 use rstd::prelude::*;
 use codec::{Slicable, Joiner};
 
-use runtime_primitives::traits::{Executable, RefInto, MaybeEmpty};
+use runtime_primitives::traits::{Executable, RefInto, MaybeEmpty, One};
 use primitives::parachain::{Id, Chain, DutyRoster, CandidateReceipt};
 use {system, session};
 
@@ -50,18 +51,33 @@ decl_module! {
 		// provide candidate receipts for parachains, in ascending order by id.
 		fn set_heads(aux, heads: Vec<CandidateReceipt>) -> Result = 0;
 	}
+
+	/// Private calls for parachains.
+	#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+	pub enum PrivCall {
+		fn register_parachain(id: Id, code: Vec<u8>, initial_head_data: Vec<u8>) -> Result = 0;
+		fn deregister_parachain(id: Id) -> Result = 1;
+		fn schedule_code_upgrade(id: Id, code: Vec<u8>, at: T::BlockNumber) -> Result = 2;
+	}
 }
 
 decl_storage! {
 	trait Store for Module<T: Trait>;
-	// Vector of all parachain IDs.
+	/// Vector of all parachain IDs.
 	pub Parachains get(active_parachains): b"para:chains" => default Vec<Id>;
-	// The parachains registered at present.
+	/// The parachains registered at present.
 	pub Code get(parachain_code): b"para:code" => map [ Id => Vec<u8> ];
-	// The heads of the parachains registered at present. these are kept sorted.
+	/// The heads of the parachains registered at present. these are kept sorted.
 	pub Heads get(parachain_head): b"para:head" => map [ Id => Vec<u8> ];
 
-	// Did the parachain heads get updated in this block?
+	/// Validation code scheduled to replace the current code of a parachain.
+	pub FutureCode get(parachain_future_code): b"para:fcode" => map [ Id => Vec<u8> ];
+	/// The relay block from which the future code is used to validate candidates.
+	pub FutureCodeAt get(parachain_future_code_at): b"para:fcat" => map [ Id => T::BlockNumber ];
+	/// The parachains with a code upgrade scheduled. These are kept sorted.
+	PendingUpgrades: b"para:pend" => default Vec<Id>;
+
+	/// Did the parachain heads get updated in this block?
 	DidUpdate: b"para:did" => default bool;
 }
 
@@ -116,29 +132,87 @@ impl<T: Trait> Module<T> {
 
 	/// Register a parachain with given code.
 	/// Fails if given ID is already used.
-	pub fn register_parachain(id: Id, code: Vec<u8>, initial_head_data: Vec<u8>) {
+	pub fn register_parachain(id: Id, code: Vec<u8>, initial_head_data: Vec<u8>) -> Result {
 		let mut parachains = Self::active_parachains();
 		match parachains.binary_search(&id) {
-			Ok(_) => panic!("Parachain with id {} already exists", id.into_inner()),
+			Ok(_) => fail!("Parachain already exists"),
 			Err(idx) => parachains.insert(idx, id),
 		}
 
 		<Code<T>>::insert(id, code);
 		<Parachains<T>>::put(parachains);
 		<Heads<T>>::insert(id, initial_head_data);
+
+		Ok(())
 	}
 
-	/// Deregister a parachain with given id
-	pub fn deregister_parachain(id: Id) {
+	/// Deregister a parachain with given id, dropping any scheduled code upgrade.
+	/// Fails if no parachain with given ID is registered.
+	pub fn deregister_parachain(id: Id) -> Result {
 		let mut parachains = Self::active_parachains();
 		match parachains.binary_search(&id) {
 			Ok(idx) => { parachains.remove(idx); }
-			Err(_) => {}
+			Err(_) => fail!("Parachain is not registered"),
 		}
 
 		<Code<T>>::remove(id);
 		<Heads<T>>::remove(id);
 		<Parachains<T>>::put(parachains);
+		Self::cancel_code_upgrade(id);
+
+		Ok(())
+	}
+
+	/// Schedule the validation code of a registered parachain to be replaced by `code`.
+	///
+	/// Candidates included from relay block `at` onwards are validated against the new code;
+	/// those already in flight keep being checked against the old one. Scheduling again before
+	/// `at` replaces the pending upgrade.
+	pub fn schedule_code_upgrade(id: Id, code: Vec<u8>, at: T::BlockNumber) -> Result {
+		ensure!(Self::active_parachains().binary_search(&id).is_ok(), "Parachain is not registered");
+		ensure!(at > <system::Module<T>>::block_number() + One::one(), "Code upgrade must be scheduled for a future block");
+
+		let mut pending = <PendingUpgrades<T>>::get();
+		if let Err(idx) = pending.binary_search(&id) {
+			pending.insert(idx, id);
+		}
+
+		<FutureCode<T>>::insert(id, code);
+		<FutureCodeAt<T>>::insert(id, at);
+		<PendingUpgrades<T>>::put(pending);
+
+		Ok(())
+	}
+
+	/// Drop any scheduled code upgrade for the given parachain.
+	fn cancel_code_upgrade(id: Id) {
+		let mut pending = <PendingUpgrades<T>>::get();
+		if let Ok(idx) = pending.binary_search(&id) {
+			pending.remove(idx);
+			<PendingUpgrades<T>>::put(pending);
+		}
+
+		<FutureCode<T>>::remove(id);
+		<FutureCodeAt<T>>::remove(id);
+	}
+
+	/// Enact the code upgrades which take effect in the next block, so that its candidates
+	/// are validated against the code found in this block's state.
+	fn enact_code_upgrades() {
+		let next = <system::Module<T>>::block_number() + One::one();
+		let (due, rest): (Vec<Id>, Vec<Id>) = <PendingUpgrades<T>>::get().into_iter()
+			.partition(|id| Self::parachain_future_code_at(id).map_or(true, |at| at <= next));
+
+		if due.is_empty() { return }
+
+		for id in due {
+			if let Some(code) = <FutureCode<T>>::take(id) {
+				<Code<T>>::insert(id, code);
+			}
+			<FutureCodeAt<T>>::remove(id);
+		}
+
+		<PendingUpgrades<T>>::put(rest);
 	}
 
 	fn set_heads(aux: &<T as Trait>::PublicAux, heads: Vec<CandidateReceipt>) -> Result {
@@ -176,14 +250,15 @@ impl<T: Trait> Module<T> {
 impl<T: Trait> Executable for Module<T> {
 	fn execute() {
 		assert!(<Self as Store>::DidUpdate::take(), "Parachain heads must be updated once in the block");
+		Self::enact_code_upgrades();
 	}
 }
 
 /// Parachains module genesis configuration.
 #[cfg(any(feature = "std", test))]
 pub struct GenesisConfig<T: Trait> {
-	/// The initial parachains, mapped to code.
-	pub parachains: Vec<(Id, Vec<u8>)>,
+	/// The initial parachains, mapped to code and initial head data.
+	pub parachains: Vec<(Id, Vec<u8>, Vec<u8>)>,
 	/// Phantom data.
 	pub phantom: PhantomData<T>,
 }
@@ -201,23 +276,26 @@ impl<T: Trait> Default for GenesisConfig<T> {
 #[cfg(any(feature = "std", test))]
 impl<T: Trait> runtime_primitives::BuildExternalities for GenesisConfig<T>
 {
-	fn build_externalities(mut self) -> runtime_io::TestExternalities {
+	fn build_externalities(mut self) -> runtime_primitives::BuiltExternalities {
 		use std::collections::HashMap;
 		use runtime_io::twox_128;
 		use codec::Slicable;
 
-		self.parachains.sort_unstable_by_key(|&(ref id, _)| id.clone());
-		self.parachains.dedup_by_key(|&mut (ref id, _)| id.clone());
+		self.parachains.sort_unstable_by_key(|&(ref id, _, _)| id.clone());
+		self.parachains.dedup_by_key(|&mut (ref id, _, _)| id.clone());
 
-		let only_ids: Vec<_> = self.parachains.iter().map(|&(ref id, _)| id).cloned().collect();
+		let only_ids: Vec<_> = self.parachains.iter().map(|&(ref id, _, _)| id).cloned().collect();
 
 		let mut map: HashMap<_, _> = map![
 			twox_128(<Parachains<T>>::key()).to_vec() => only_ids.encode()
 		];
 
-		for (id, code) in self.parachains {
-			let key = twox_128(&<Code<T>>::key_for(&id)).to_vec();
-			map.insert(key, code.encode());
+		for (id, code, genesis) in self.parachains {
+			let code_key = twox_128(&<Code<T>>::key_for(&id)).to_vec();
+			let head_key = twox_128(&<Heads<T>>::key_for(&id)).to_vec();
+
+			map.insert(code_key, code.encode());
+			map.insert(head_key, genesis.encode());
 		}
 
 		map.into()
@@ -262,8 +340,8 @@ mod tests {
 
 	type Parachains = Module<Test>;
 
-	fn new_test_ext(parachains: Vec<(Id, Vec<u8>)>) -> runtime_io::TestExternalities {
-		let mut t = system::GenesisConfig::<Test>::default().build_externalities();
+	fn new_test_ext(parachains: Vec<(Id, Vec<u8>, Vec<u8>)>) -> runtime_io::TestExternalities {
+		let mut t: runtime_io::TestExternalities = system::GenesisConfig::<Test>::default().build_externalities().into();
 		t.extend(consensus::GenesisConfig::<Test>{
 			code: vec![],
 			authorities: vec![1, 2, 3],
@@ -282,8 +360,8 @@ mod tests {
 	#[test]
 	fn active_parachains_should_work() {
 		let parachains = vec![
-			(5u32.into(), vec![1,2,3]),
-			(100u32.into(), vec![4,5,6]),
+			(5u32.into(), vec![1,2,3], vec![]),
+			(100u32.into(), vec![4,5,6], vec![]),
 		];
 
 		with_externalities(&mut new_test_ext(parachains), || {
@@ -293,11 +371,22 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn genesis_head_data_is_set() {
+		let parachains = vec![
+			(5u32.into(), vec![1,2,3], vec![7,8,9]),
+		];
+
+		with_externalities(&mut new_test_ext(parachains), || {
+			assert_eq!(Parachains::parachain_head(&5u32.into()), Some(vec![7,8,9]));
+		});
+	}
+
 	#[test]
 	fn register_deregister() {
 		let parachains = vec![
-			(5u32.into(), vec![1,2,3]),
-			(100u32.into(), vec![4,5,6]),
+			(5u32.into(), vec![1,2,3], vec![]),
+			(100u32.into(), vec![4,5,6], vec![]),
 		];
 
 		with_externalities(&mut new_test_ext(parachains), || {
@@ -306,23 +395,84 @@ mod tests {
 			assert_eq!(Parachains::parachain_code(&5u32.into()), Some(vec![1,2,3]));
 			assert_eq!(Parachains::parachain_code(&100u32.into()), Some(vec![4,5,6]));
 
-			Parachains::register_parachain(99u32.into(), vec![7,8,9], vec![1, 1, 1]);
+			assert_ok!(Parachains::register_parachain(99u32.into(), vec![7,8,9], vec![1, 1, 1]));
 
 			assert_eq!(Parachains::active_parachains(), vec![5u32.into(), 99u32.into(), 100u32.into()]);
 			assert_eq!(Parachains::parachain_code(&99u32.into()), Some(vec![7,8,9]));
+			assert_eq!(Parachains::parachain_head(&99u32.into()), Some(vec![1, 1, 1]));
 
-			Parachains::deregister_parachain(5u32.into());
+			assert_ok!(Parachains::deregister_parachain(5u32.into()));
 
 			assert_eq!(Parachains::active_parachains(), vec![99u32.into(), 100u32.into()]);
 			assert_eq!(Parachains::parachain_code(&5u32.into()), None);
 		});
 	}
 
+	#[test]
+	fn register_deregister_twice_fails() {
+		let parachains = vec![
+			(5u32.into(), vec![1,2,3], vec![]),
+		];
+
+		with_externalities(&mut new_test_ext(parachains), || {
+			assert_noop!(Parachains::register_parachain(5u32.into(), vec![7,8,9], vec![]), "Parachain already exists");
+			assert_noop!(Parachains::deregister_parachain(6u32.into()), "Parachain is not registered");
+		});
+	}
+
+	#[test]
+	fn code_upgrade_takes_effect_at_scheduled_block() {
+		let parachains = vec![
+			(5u32.into(), vec![1,2,3], vec![]),
+		];
+
+		with_externalities(&mut new_test_ext(parachains), || {
+			assert_noop!(Parachains::schedule_code_upgrade(6u32.into(), vec![4,5,6], 5), "Parachain is not registered");
+			assert_noop!(Parachains::schedule_code_upgrade(5u32.into(), vec![4,5,6], 2), "Code upgrade must be scheduled for a future block");
+
+			assert_ok!(Parachains::schedule_code_upgrade(5u32.into(), vec![4,5,6], 5));
+			assert_eq!(Parachains::parachain_future_code(&5u32.into()), Some(vec![4,5,6]));
+			assert_eq!(Parachains::parachain_future_code_at(&5u32.into()), Some(5));
+
+			for n in 1..4 {
+				system::Module::<Test>::set_block_number(n);
+				Parachains::enact_code_upgrades();
+				assert_eq!(Parachains::parachain_code(&5u32.into()), Some(vec![1,2,3]));
+			}
+
+			// finalising the block before `at` makes the new code visible to its candidates.
+			system::Module::<Test>::set_block_number(4);
+			Parachains::enact_code_upgrades();
+			assert_eq!(Parachains::parachain_code(&5u32.into()), Some(vec![4,5,6]));
+			assert_eq!(Parachains::parachain_future_code(&5u32.into()), None);
+			assert_eq!(Parachains::parachain_future_code_at(&5u32.into()), None);
+		});
+	}
+
+	#[test]
+	fn deregister_cancels_code_upgrade() {
+		let parachains = vec![
+			(5u32.into(), vec![1,2,3], vec![]),
+		];
+
+		with_externalities(&mut new_test_ext(parachains), || {
+			assert_ok!(Parachains::schedule_code_upgrade(5u32.into(), vec![4,5,6], 5));
+			assert_ok!(Parachains::deregister_parachain(5u32.into()));
+
+			assert_eq!(Parachains::parachain_future_code(&5u32.into()), None);
+			assert_eq!(Parachains::parachain_future_code_at(&5u32.into()), None);
+
+			system::Module::<Test>::set_block_number(4);
+			Parachains::enact_code_upgrades();
+			assert_eq!(Parachains::parachain_code(&5u32.into()), None);
+		});
+	}
+
 	#[test]
 	fn duty_roster_works() {
 		let parachains = vec![
-			(0u32.into(), vec![]),
-			(1u32.into(), vec![]),
+			(0u32.into(), vec![], vec![]),
+			(1u32.into(), vec![], vec![]),
 		];
 
 		with_externalities(&mut new_test_ext(parachains), || {
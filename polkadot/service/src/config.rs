@@ -17,6 +17,7 @@
 //! Service configuration.
 
 use transaction_pool;
+use client::ExecutionStrategies;
 pub use network::Role;
 pub use network::NetworkConfiguration;
 
@@ -49,6 +50,9 @@ pub struct Configuration {
 	pub keys: Vec<String>,
 	/// Chain specification.
 	pub chain_spec: ChainSpec,
+	/// Strategies for executing the runtime when importing blocks, building blocks and
+	/// serving other calls.
+	pub execution_strategies: ExecutionStrategies,
 }
 
 impl Default for Configuration {
@@ -61,6 +65,7 @@ impl Default for Configuration {
 			database_path: Default::default(),
 			keys: Default::default(),
 			chain_spec: ChainSpec::Development,
+			execution_strategies: Default::default(),
 		}
 	}
 }
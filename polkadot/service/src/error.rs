@@ -21,6 +21,10 @@ use network;
 use keystore;
 
 error_chain! {
+	foreign_links {
+		Io(::std::io::Error) #[doc="IO error"];
+	}
+
 	links {
 		Client(client::error::Error, client::error::ErrorKind) #[doc="Client error"];
 		Network(network::error::Error, network::error::ErrorKind) #[doc="Network error"];
@@ -22,6 +22,8 @@ extern crate ed25519;
 extern crate exit_future;
 extern crate parking_lot;
 extern crate tokio_timer;
+extern crate polkadot_availability_store as av_store;
+extern crate polkadot_collator as collator;
 extern crate polkadot_primitives;
 extern crate polkadot_runtime;
 extern crate polkadot_executor;
@@ -63,8 +65,9 @@ use polkadot_executor::Executor as LocalDispatch;
 use keystore::Store as Keystore;
 use polkadot_api::PolkadotApi;
 use polkadot_primitives::{Block, BlockId, Hash, Header};
+use polkadot_primitives::parachain::Id as ParaId;
 use polkadot_runtime::{GenesisConfig, ConsensusConfig, CouncilConfig, DemocracyConfig,
-	SessionConfig, StakingConfig, BuildExternalities};
+	SessionConfig, StakingConfig, ParachainsConfig, BuildExternalities};
 use client::backend::Backend;
 use client::{genesis, Client, BlockchainEvents, CallExecutor};
 use network::ManageNetwork;
@@ -212,7 +215,7 @@ fn poc_2_testnet_config() -> ChainConfig {
 	ChainConfig { genesis_config, boot_nodes }
 }
 
-fn testnet_config(initial_authorities: Vec<AuthorityId>) -> ChainConfig {
+fn testnet_config(initial_authorities: Vec<AuthorityId>, parachains: Vec<(ParaId, Vec<u8>, Vec<u8>)>) -> ChainConfig {
 	let endowed_accounts = vec![
 		ed25519::Pair::from_seed(b"Alice                           ").public().0.into(),
 		ed25519::Pair::from_seed(b"Bob                             ").public().0.into(),
@@ -261,7 +264,10 @@ fn testnet_config(initial_authorities: Vec<AuthorityId>) -> ChainConfig {
 			cooloff_period: 75,
 			voting_period: 20,
 		}),
-		parachains: Some(Default::default()),
+		parachains: Some(ParachainsConfig {
+			parachains,
+			phantom: Default::default(),
+		}),
 	};
 	let boot_nodes = Vec::new();
 	ChainConfig { genesis_config, boot_nodes }
@@ -270,16 +276,23 @@ fn testnet_config(initial_authorities: Vec<AuthorityId>) -> ChainConfig {
 fn development_config() -> ChainConfig {
 	testnet_config(vec![
 		ed25519::Pair::from_seed(b"Alice                           ").public().into(),
-	])
+	], Vec::new())
 }
 
 fn local_testnet_config() -> ChainConfig {
 	testnet_config(vec![
 		ed25519::Pair::from_seed(b"Alice                           ").public().into(),
 		ed25519::Pair::from_seed(b"Bob                             ").public().into(),
+	], vec![
+		(TEST_PARACHAIN_ID.into(), BASIC_ADD_CODE.to_vec(), collator::basic_add::genesis_head().encode()),
 	])
 }
 
+/// The ID of the `basic_add` test parachain registered on the local testnet.
+pub const TEST_PARACHAIN_ID: u32 = 100;
+
+const BASIC_ADD_CODE: &[u8] = include_bytes!("../../parachain/tests/res/basic_add.wasm");
+
 struct GenesisBuilder {
 	config: GenesisConfig,
 }
@@ -292,6 +305,26 @@ impl client::GenesisBuilder<Block> for GenesisBuilder {
 	}
 }
 
+/// Warn if the natively compiled runtime can't be used for the on-chain runtime at the given block.
+fn check_runtime_version<B, E>(client: &Client<B, E, Block>, at: &BlockId) where
+	B: Backend<Block>,
+	E: CallExecutor<Block>,
+	client::error::Error: From<<<B as Backend<Block>>::State as state_machine::backend::Backend>::Error>
+{
+	let native = polkadot_runtime::VERSION;
+	match client.runtime_version_at(at) {
+		Ok(ref on_chain) if native.can_call_with(on_chain) =>
+			info!("Native runtime: {}, on-chain runtime: {}", native, on_chain),
+		Ok(ref on_chain) if on_chain.spec_name == native.spec_name && on_chain.spec_version > native.spec_version =>
+			warn!("On-chain runtime {} is newer than the native runtime {}. This node is too old and will \
+				execute the runtime in wasm, which is slower. Please upgrade.", on_chain, native),
+		Ok(on_chain) =>
+			warn!("Native runtime {} is not compatible with on-chain runtime {}. The runtime will be executed in wasm.", native, on_chain),
+		Err(e) =>
+			warn!("Unable to determine the on-chain runtime version: {}", e),
+	}
+}
+
 /// Creates light client and register protocol with the network service
 pub fn new_light(config: Configuration)
 	-> Result<
@@ -316,8 +349,10 @@ pub fn new_light(config: Configuration)
 /// Creates full client and register protocol with the network service
 pub fn new_full(config: Configuration) -> Result<Service<client_db::Backend<Block>, client::LocalCallExecutor<client_db::Backend<Block>, CodeExecutor>>, error::Error> {
 	let is_validator = (config.roles & Role::VALIDATOR) == Role::VALIDATOR;
-	Service::new(|db_settings, executor, genesis_builder: GenesisBuilder|
-		Ok((Arc::new(client_db::new_client(db_settings, executor, genesis_builder)?), None)),
+	let availability_path = ::std::path::PathBuf::from(&config.database_path).join("availability");
+	let execution_strategies = config.execution_strategies;
+	Service::new(move |db_settings, executor, genesis_builder: GenesisBuilder|
+		Ok((Arc::new(client_db::new_client(db_settings, executor, genesis_builder, execution_strategies)?), None)),
 		|client| client,
 		|client, network, tx_pool, keystore| {
 			if !is_validator {
@@ -327,11 +362,18 @@ pub fn new_full(config: Configuration) -> Result<Service<client_db::Backend<Bloc
 			// Load the first available key. Code above makes sure it exisis.
 			let key = keystore.load(&keystore.contents()?[0], "")?;
 			info!("Using authority key {:?}", key.public());
+
+			let availability_store = av_store::Store::new(av_store::Config {
+				cache_size: None,
+				path: availability_path.clone(),
+			})?;
+
 			Ok(Some(consensus::Service::new(
 				client.clone(),
 				client.clone(),
 				network.clone(),
 				tx_pool.clone(),
+				availability_store,
 				::std::time::Duration::from_millis(4000), // TODO: dynamic
 				key,
 			)))
@@ -339,6 +381,17 @@ pub fn new_full(config: Configuration) -> Result<Service<client_db::Backend<Bloc
 		config)
 }
 
+/// Revert the chain in the full node database by up to `blocks` best blocks. Returns the
+/// number of blocks actually reverted.
+pub fn revert_chain(config: Configuration, blocks: u64) -> Result<u64, error::Error> {
+	let backend = client_db::Backend::<Block>::new(&client_db::DatabaseSettings {
+		cache_size: None,
+		state_cache_size: None,
+		path: config.database_path.into(),
+	})?;
+	Ok(backend.revert(blocks)?)
+}
+
 impl<B, E> Service<B, E>
 	where
 		B: Backend<Block> + Send + Sync + 'static,
@@ -394,6 +447,7 @@ impl<B, E> Service<B, E>
 
 		let db_settings = client_db::DatabaseSettings {
 			cache_size: None,
+			state_cache_size: None,
 			path: config.database_path.into(),
 		};
 
@@ -401,6 +455,10 @@ impl<B, E> Service<B, E>
 		let api = api_creator(client.clone());
 		let best_header = client.best_block_header()?;
 		info!("Starting Polkadot. Best block is #{}", best_header.number);
+		// light clients would have to fetch the on-chain version from the network.
+		if on_demand.is_none() {
+			check_runtime_version(&*client, &BlockId::number(best_header.number));
+		}
 		let transaction_pool = Arc::new(TransactionPool::new(config.transaction_pool));
 		let transaction_pool_adapter = Arc::new(TransactionPoolAdapter {
 			pool: transaction_pool.clone(),
@@ -501,7 +559,7 @@ pub fn prune_imported<A>(api: &A, pool: &TransactionPool, hash: Hash)
 	match api.check_id(BlockId::hash(hash)) {
 		Ok(id) => {
 			let ready = transaction_pool::Ready::create(id, api);
-			pool.cull(None, ready);
+			pool.cull_from(hash, None, ready);
 		},
 		Err(e) => warn!("Failed to check block id: {:?}", e),
 	}
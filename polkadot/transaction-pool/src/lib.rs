@@ -39,7 +39,7 @@ use std::{
 };
 
 use codec::Slicable;
-use extrinsic_pool::{Pool, txpool::{self, Readiness, scoring::{Change, Choice}}};
+use extrinsic_pool::{Pool, Watcher, txpool::{self, Readiness, scoring::{Change, Choice}}};
 use extrinsic_pool::api::ExtrinsicPool;
 use polkadot_api::PolkadotApi;
 use primitives::{AccountId, Hash, UncheckedExtrinsic as FutureProofUncheckedExtrinsic};
@@ -268,14 +268,29 @@ impl ExtrinsicPool<FutureProofUncheckedExtrinsic, Hash> for TransactionPool {
 	type Error = Error;
 
 	fn submit(&self, xts: Vec<FutureProofUncheckedExtrinsic>) -> Result<Vec<Hash>> {
-		// TODO: more general transaction pool, which can handle more kinds of vec-encoded transactions,
-		// even when runtime is out of date.
 		xts.into_iter()
-			.map(|xt| xt.encode())
-			.map(|encoded| UncheckedExtrinsic::decode(&mut &encoded[..]))
-			.map(|maybe_decoded| maybe_decoded.ok_or_else(|| ErrorKind::InvalidExtrinsicFormat.into()))
+			.map(decode_extrinsic)
 			.map(|x| x.and_then(|x| self.import_unchecked_extrinsic(x)))
 			.map(|x| x.map(|x| x.hash().clone()))
 			.collect()
 	}
+
+	fn submit_and_watch(&self, xt: FutureProofUncheckedExtrinsic) -> Result<Watcher<Hash>> {
+		self.inner.submit_and_watch(decode_extrinsic(xt)?)
+	}
+
+	fn pending(&self) -> Vec<FutureProofUncheckedExtrinsic> {
+		self.inner.all().iter().map(|xt| xt.primitive_extrinsic()).collect()
+	}
+
+	fn remove_extrinsics(&self, hashes: &[Hash]) -> Vec<Hash> {
+		self.inner.remove(hashes, false).into_iter().filter_map(|xt| xt.map(|xt| *xt.hash())).collect()
+	}
+}
+
+// TODO: more general transaction pool, which can handle more kinds of vec-encoded transactions,
+// even when runtime is out of date.
+fn decode_extrinsic(xt: FutureProofUncheckedExtrinsic) -> Result<UncheckedExtrinsic> {
+	let encoded = xt.encode();
+	UncheckedExtrinsic::decode(&mut &encoded[..]).ok_or_else(|| ErrorKind::InvalidExtrinsicFormat.into())
 }
@@ -21,6 +21,7 @@ extern crate ethereum_types;
 extern crate kvdb_rocksdb;
 extern crate kvdb;
 extern crate hashdb;
+extern crate lru_cache;
 extern crate memorydb;
 extern crate parking_lot;
 extern crate patricia_trie;
@@ -35,29 +36,40 @@ extern crate log;
 
 #[cfg(test)]
 extern crate kvdb_memorydb;
+#[cfg(test)]
+extern crate tempdir;
+#[cfg(test)]
+extern crate substrate_test_client as test_client;
+
+mod state_cache;
+mod upgrade;
 
 use std::sync::Arc;
 use std::path::PathBuf;
-use std::collections::HashMap;
 
 use codec::Slicable;
 use ethereum_types::H256 as TrieH256;
-use hashdb::{DBValue, HashDB};
-use kvdb_rocksdb::{Database, DatabaseConfig};
+use hashdb::DBValue;
 use kvdb::{KeyValueDB, DBTransaction};
 use memorydb::MemoryDB;
 use parking_lot::RwLock;
-use patricia_trie::{TrieDB, TrieDBMut, TrieError, Trie, TrieMut};
+use patricia_trie::TrieDBMut;
 use runtime_primitives::generic::BlockId;
 use runtime_primitives::bft::Justification;
-use runtime_primitives::traits::{Block as BlockT, Header as HeaderT, As, Hashing, HashingFor, Zero};
+use runtime_primitives::traits::{Block as BlockT, Header as HeaderT, As, Hashing, HashingFor, Zero, One};
 use state_machine::backend::Backend as StateBackend;
 use state_machine::CodeExecutor;
+use state_cache::{SharedCache, DEFAULT_STATE_CACHE_SIZE};
+
+pub use client::backend::CacheStats;
+pub use state_cache::CachingState;
 
 /// Database settings.
 pub struct DatabaseSettings {
 	/// Cache size in bytes. If `None` default is used.
 	pub cache_size: Option<usize>,
+	/// Size of the cache of the best block's storage values in bytes. If `None` default is used.
+	pub state_cache_size: Option<usize>,
 	/// Path to the database.
 	pub path: PathBuf,
 }
@@ -67,6 +79,7 @@ pub fn new_client<E, F, Block>(
 	settings: DatabaseSettings,
 	executor: E,
 	genesis_builder: F,
+	execution_strategies: client::ExecutionStrategies,
 ) -> Result<client::Client<Backend<Block>, client::LocalCallExecutor<Backend<Block>, E>, Block>, client::error::Error>
 	where
 		Block: BlockT,
@@ -77,7 +90,7 @@ pub fn new_client<E, F, Block>(
 {
 	let backend = Arc::new(Backend::new(&settings)?);
 	let executor = client::LocalCallExecutor::new(backend.clone(), executor);
-	Ok(client::Client::new(backend, executor, genesis_builder)?)
+	Ok(client::Client::new(backend, executor, genesis_builder, execution_strategies)?)
 }
 
 mod columns {
@@ -87,11 +100,16 @@ mod columns {
 	pub const HEADER: Option<u32> = Some(3);
 	pub const BODY: Option<u32> = Some(4);
 	pub const JUSTIFICATION: Option<u32> = Some(5);
-	pub const NUM_COLUMNS: u32 = 6;
+	pub const CHANGED_KEYS: Option<u32> = Some(6);
+	/// Changes trie nodes by hash, and the nodes first inserted by each block by block number.
+	pub const CHANGES_TRIE: Option<u32> = Some(7);
+	pub const NUM_COLUMNS: u32 = 8;
 }
 
 mod meta {
 	pub const BEST_BLOCK: &[u8; 4] = b"best";
+	pub const VERSION: &[u8; 7] = b"version";
+	pub const CHANGES_INDEXED_FROM: &[u8; 20] = b"changes_indexed_from";
 }
 
 struct PendingBlock<Block: BlockT> {
@@ -235,6 +253,16 @@ impl<Block: BlockT> client::blockchain::Backend<Block> for BlockchainDb<Block> w
 		}
 	}
 
+	fn changed_keys(&self, id: BlockId<Block>) -> Result<Option<Vec<Vec<u8>>>, client::error::Error> {
+		match self.read_db(id, columns::CHANGED_KEYS)? {
+			Some(keys) => match Slicable::decode(&mut &keys[..]) {
+				Some(keys) => Ok(Some(keys)),
+				None => return Err(client::error::ErrorKind::Backend("Error decoding changed keys".into()).into()),
+			}
+			None => Ok(None),
+		}
+	}
+
 	fn info(&self) -> Result<client::blockchain::Info<Block>, client::error::Error> {
 		let meta = self.meta.read();
 		Ok(client::blockchain::Info {
@@ -266,6 +294,8 @@ impl<Block: BlockT> client::blockchain::Backend<Block> for BlockchainDb<Block> w
 pub struct BlockImportOperation<Block: BlockT> {
 	old_state: DbState,
 	updates: MemoryDB,
+	changed_keys: Option<Vec<Vec<u8>>>,
+	changes_trie_updates: MemoryDB,
 	pending_block: Option<PendingBlock<Block>>,
 }
 
@@ -294,136 +324,48 @@ impl<Block: BlockT> client::backend::BlockImportOperation<Block> for BlockImport
 
 	fn reset_storage<I: Iterator<Item=(Vec<u8>, Vec<u8>)>>(&mut self, iter: I) -> Result<(), client::error::Error> {
 		// TODO: wipe out existing trie.
-		let (_, update) = self.old_state.storage_root(iter.into_iter().map(|(k, v)| (k, Some(v))));
+		let storage = iter.collect::<Vec<_>>();
+		let mut keys = storage.iter().map(|&(ref k, _)| k.clone()).collect::<Vec<_>>();
+		keys.sort();
+		let (_, update) = self.old_state.storage_root(storage.into_iter().map(|(k, v)| (k, Some(v))));
 		self.updates = update;
+		self.changed_keys = Some(keys);
 		Ok(())
 	}
-}
-
-struct Ephemeral<'a> {
-	backing: &'a KeyValueDB,
-	overlay: &'a mut MemoryDB,
-}
-
-impl<'a> HashDB for Ephemeral<'a> {
-	fn keys(&self) -> HashMap<TrieH256, i32> {
-		self.overlay.keys() // TODO: iterate backing
-	}
-
-	fn get(&self, key: &TrieH256) -> Option<DBValue> {
-		match self.overlay.raw(key) {
-			Some((val, i)) => {
-				if i <= 0 {
-					None
-				} else {
-					Some(val)
-				}
-			}
-			None => {
-				match self.backing.get(::columns::STATE, &key.0[..]) {
-					Ok(x) => x,
-					Err(e) => {
-						warn!("Failed to read from DB: {}", e);
-						None
-					}
-				}
-			}
-		}
-	}
-
-	fn contains(&self, key: &TrieH256) -> bool {
-		self.get(key).is_some()
-	}
-
-	fn insert(&mut self, value: &[u8]) -> TrieH256 {
-		self.overlay.insert(value)
-	}
 
-	fn emplace(&mut self, key: TrieH256, value: DBValue) {
-		self.overlay.emplace(key, value)
+	fn set_changed_keys(&mut self, keys: Vec<Vec<u8>>) -> Result<(), client::error::Error> {
+		self.changed_keys = Some(keys);
+		Ok(())
 	}
 
-	fn remove(&mut self, key: &TrieH256) {
-		self.overlay.remove(key)
+	fn update_changes_trie(&mut self, update: MemoryDB) -> Result<(), client::error::Error> {
+		self.changes_trie_updates = update;
+		Ok(())
 	}
 }
 
 /// DB-backed patricia trie state, transaction type is an overlay of changes to commit.
-#[derive(Clone)]
-pub struct DbState {
+pub type DbState = CachingState;
+
+/// Trie node storage reading from the state column of the database.
+struct StorageDb {
 	db: Arc<KeyValueDB>,
-	root: TrieH256,
 }
 
-impl state_machine::Backend for DbState {
-	type Error = client::error::Error;
-	type Transaction = MemoryDB;
-
-	fn storage(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
-		let mut read_overlay = MemoryDB::default();
-		let eph = Ephemeral {
-			backing: &*self.db,
-			overlay: &mut read_overlay,
-		};
-
-		let map_e = |e: Box<TrieError>| ::client::error::Error::from(format!("Trie lookup error: {}", e));
-
-		TrieDB::new(&eph, &self.root).map_err(map_e)?
-			.get(key).map(|x| x.map(|val| val.to_vec())).map_err(map_e)
+impl state_machine::Storage for StorageDb {
+	fn get(&self, key: &TrieH256) -> Result<Option<DBValue>, String> {
+		self.db.get(columns::STATE, &key.0[..]).map_err(|e| format!("Database backend error: {:?}", e))
 	}
+}
 
-	fn pairs(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
-		let mut read_overlay = MemoryDB::default();
-		let eph = Ephemeral {
-			backing: &*self.db,
-			overlay: &mut read_overlay,
-		};
-
-		let collect_all = || -> Result<_, Box<TrieError>> {
-			let trie = TrieDB::new(&eph, &self.root)?;
-			let mut v = Vec::new();
-			for x in trie.iter()? {
-				let (key, value) = x?;
-				v.push((key.to_vec(), value.to_vec()));
-			}
-
-			Ok(v)
-		};
-
-		match collect_all() {
-			Ok(v) => v,
-			Err(e) => {
-				debug!("Error extracting trie values: {}", e);
-				Vec::new()
-			}
-		}
-	}
-
-	fn storage_root<I>(&self, delta: I) -> ([u8; 32], MemoryDB)
-		where I: IntoIterator<Item=(Vec<u8>, Option<Vec<u8>>)>
-	{
-		let mut write_overlay = MemoryDB::default();
-		let mut root = self.root;
-		{
-			let mut eph = Ephemeral {
-				backing: &*self.db,
-				overlay: &mut write_overlay,
-			};
-
-			let mut trie = TrieDBMut::from_existing(&mut eph, &mut root).expect("prior state root to exist"); // TODO: handle gracefully
-			for (key, change) in delta {
-				let result = match change {
-					Some(val) => trie.insert(&key, &val),
-					None => trie.remove(&key), // TODO: archive mode
-				};
-
-				if let Err(e) = result {
-					warn!("Failed to write to trie: {}", e);
-				}
-			}
-		}
+/// Changes trie node storage reading from the changes trie column of the database.
+struct ChangesTrieStorageDb {
+	db: Arc<KeyValueDB>,
+}
 
-		(root.0.into(), write_overlay)
+impl state_machine::Storage for ChangesTrieStorageDb {
+	fn get(&self, key: &TrieH256) -> Result<Option<DBValue>, String> {
+		self.db.get(columns::CHANGES_TRIE, &key.0[..]).map_err(|e| format!("Database backend error: {:?}", e))
 	}
 }
 
@@ -431,6 +373,9 @@ impl state_machine::Backend for DbState {
 /// Otherwise, trie nodes are kept only from the most recent block.
 pub struct Backend<Block: BlockT> {
 	db: Arc<KeyValueDB>,
+	storage: Arc<StorageDb>,
+	state_cache: Arc<SharedCache>,
+	changes_trie_storage: Arc<ChangesTrieStorageDb>,
 	blockchain: BlockchainDb<Block>,
 	archive: bool,
 }
@@ -438,26 +383,27 @@ pub struct Backend<Block: BlockT> {
 impl<Block: BlockT> Backend<Block> where <Block::Header as HeaderT>::Number: As<u32> {
 	/// Create a new instance of database backend.
 	pub fn new(config: &DatabaseSettings) -> Result<Self, client::error::Error> {
-		let mut db_config = DatabaseConfig::with_columns(Some(columns::NUM_COLUMNS));
-		db_config.memory_budget = config.cache_size;
-		db_config.wal = true;
 		let path = config.path.to_str().ok_or_else(|| client::error::ErrorKind::Backend("Invalid database path".into()))?;
-		let db = Arc::new(Database::open(&db_config, &path).map_err(db_err)?);
+		let db = Arc::new(upgrade::open_database(config.cache_size, &path)?);
 
-		Backend::from_kvdb(db as Arc<_>, true)
+		Backend::from_kvdb(db as Arc<_>, true, config.state_cache_size.unwrap_or(DEFAULT_STATE_CACHE_SIZE))
 	}
 
 	#[cfg(test)]
 	fn new_test() -> Self {
 		let db = Arc::new(::kvdb_memorydb::create(columns::NUM_COLUMNS));
 
-		Backend::from_kvdb(db as Arc<_>, false).expect("failed to create test-db")
+		Backend::from_kvdb(db as Arc<_>, false, DEFAULT_STATE_CACHE_SIZE).expect("failed to create test-db")
 	}
 
-	fn from_kvdb(db: Arc<KeyValueDB>, archive: bool) -> Result<Self, client::error::Error> {
+	fn from_kvdb(db: Arc<KeyValueDB>, archive: bool, state_cache_size: usize) -> Result<Self, client::error::Error> {
+		upgrade::upgrade_db(&*db)?;
 		let blockchain = BlockchainDb::new(db.clone())?;
 
 		Ok(Backend {
+			storage: Arc::new(StorageDb { db: db.clone() }),
+			state_cache: Arc::new(SharedCache::new(state_cache_size)),
+			changes_trie_storage: Arc::new(ChangesTrieStorageDb { db: db.clone() }),
 			db,
 			blockchain,
 			archive
@@ -479,6 +425,8 @@ impl<Block: BlockT> client::backend::Backend<Block> for Backend<Block> where
 			pending_block: None,
 			old_state: state,
 			updates: MemoryDB::default(),
+			changed_keys: None,
+			changes_trie_updates: MemoryDB::default(),
 		})
 	}
 
@@ -495,6 +443,9 @@ impl<Block: BlockT> client::backend::Backend<Block> for Backend<Block> where
 			if let Some(justification) = pending_block.justification {
 				transaction.put(columns::JUSTIFICATION, &key, &justification.encode());
 			}
+			if let Some(ref changed_keys) = operation.changed_keys {
+				transaction.put(columns::CHANGED_KEYS, &key, &changed_keys.encode());
+			}
 			transaction.put(columns::BLOCK_INDEX, hash.as_ref(), &key);
 			if pending_block.is_best {
 				transaction.put(columns::META, meta::BEST_BLOCK, &key);
@@ -506,9 +457,31 @@ impl<Block: BlockT> client::backend::Backend<Block> for Backend<Block> where
 					transaction.delete(columns::STATE, &key.0[..]);
 				}
 			}
+			// changes tries are never pruned. The nodes first inserted by a block are listed under
+			// its number, so that they can be removed when the block is reverted.
+			let mut inserted = Vec::new();
+			for (node, (val, rc)) in operation.changes_trie_updates.drain() {
+				if rc > 0 {
+					if self.db.get(columns::CHANGES_TRIE, &node.0[..]).map_err(db_err)?.is_none() {
+						inserted.push(node.0);
+					}
+					transaction.put(columns::CHANGES_TRIE, &node.0[..], &val);
+				}
+			}
+			if !inserted.is_empty() {
+				transaction.put(columns::CHANGES_TRIE, &key, &inserted.encode());
+			}
 			debug!("DB Commit {:?} ({})", hash, number);
 			self.db.write(transaction).map_err(db_err)?;
 			self.blockchain.update_meta(hash, number, pending_block.is_best);
+			if pending_block.is_best {
+				let root: [u8; 32] = pending_block.header.state_root().clone().into();
+				self.state_cache.sync(
+					operation.old_state.root(),
+					root.into(),
+					operation.changed_keys.as_ref().map(|keys| &keys[..]),
+				);
+			}
 		}
 		Ok(())
 	}
@@ -527,22 +500,79 @@ impl<Block: BlockT> client::backend::Backend<Block> for Backend<Block> where
 				let mut db = MemoryDB::default();
 				TrieDBMut::new(&mut db, &mut root);
 
-				return Ok(DbState {
-					db: self.db.clone(),
-					root,
-				})
+				let state = state_machine::TrieBackend::with_storage(self.storage.clone(), root);
+				return Ok(DbState::new(state, self.state_cache.clone()))
 			}
 			_ => {}
 		}
 
 		self.blockchain.header(block).and_then(|maybe_hdr| maybe_hdr.map(|hdr| {
 			let root: [u8; 32] = hdr.state_root().clone().into();
-			DbState {
-				db: self.db.clone(),
-				root: root.into(),
-			}
+			let state = state_machine::TrieBackend::with_storage(self.storage.clone(), root.into());
+			DbState::new(state, self.state_cache.clone())
 		}).ok_or_else(|| client::error::ErrorKind::UnknownBlock(format!("{:?}", block)).into()))
 	}
+
+	fn changes_trie_storage(&self) -> Option<Arc<state_machine::Storage>> {
+		Some(self.changes_trie_storage.clone())
+	}
+
+	fn state_cache_stats(&self) -> Option<CacheStats> {
+		Some(self.state_cache.stats())
+	}
+
+	fn revert(&self, n: <Block::Header as HeaderT>::Number) -> Result<<Block::Header as HeaderT>::Number, client::error::Error> {
+		use client::blockchain::Backend as BcBackend;
+
+		// without the state of every block there is no way to restore the state of the new best
+		// one.
+		if !self.archive {
+			return Err(client::error::ErrorKind::Backend(
+				"Reverting blocks requires an archive database: state of earlier blocks may be pruned".into()
+			).into());
+		}
+
+		// state nodes are left in place: in archive mode the state of every remaining block is
+		// still complete.
+		//
+		// BFT justifications are the only finality there is, and every imported block has one:
+		// reverting is the way to recover from blocks the authorities agreed on by mistake, so
+		// justified blocks are reverted too. Only genesis is final.
+		let mut reverted = Zero::zero();
+		while reverted < n {
+			let best_number = self.blockchain.meta.read().best_number;
+			if best_number == Zero::zero() {
+				break;
+			}
+
+			let key = number_to_db_key(best_number);
+			let header = self.blockchain.header(BlockId::Number(best_number))?
+				.ok_or_else(|| client::error::ErrorKind::UnknownBlock(format!("{}", best_number)))?;
+			let hash = header.hash();
+			let parent_number = best_number - One::one();
+
+			let mut transaction = DBTransaction::new();
+			if let Some(nodes) = self.db.get(columns::CHANGES_TRIE, &key).map_err(db_err)? {
+				let nodes: Vec<[u8; 32]> = Slicable::decode(&mut &nodes[..])
+					.ok_or_else(|| client::error::ErrorKind::Backend("Error decoding changes trie nodes".into()))?;
+				for node in nodes {
+					transaction.delete(columns::CHANGES_TRIE, &node);
+				}
+				transaction.delete(columns::CHANGES_TRIE, &key);
+			}
+			transaction.delete(columns::HEADER, &key);
+			transaction.delete(columns::BODY, &key);
+			transaction.delete(columns::JUSTIFICATION, &key);
+			transaction.delete(columns::CHANGED_KEYS, &key);
+			transaction.delete(columns::BLOCK_INDEX, hash.as_ref());
+			transaction.put(columns::META, meta::BEST_BLOCK, &number_to_db_key(parent_number));
+			debug!("DB Revert {:?} ({})", hash, best_number);
+			self.db.write(transaction).map_err(db_err)?;
+			self.blockchain.update_meta(header.parent_hash().clone(), parent_number, true);
+			reverted = reverted + One::one();
+		}
+		Ok(reverted)
+	}
 }
 
 impl<Block: BlockT> client::backend::LocalBackend<Block> for Backend<Block> where
@@ -556,7 +586,7 @@ mod tests {
 	use client::backend::Backend as BTrait;
 	use client::backend::BlockImportOperation as Op;
 	use client::blockchain::Backend as BCTrait;
-	use runtime_primitives::testing::{Header, Block as RawBlock};
+	use runtime_primitives::testing::{Header, Block as RawBlock, H256};
 
 	type Block = RawBlock<u64>;
 
@@ -595,6 +625,91 @@ mod tests {
 		}
 	}
 
+	#[test]
+	fn enumerates_keys_with_prefix() {
+		let db = Backend::<Block>::new_test();
+		let mut op = db.begin_operation(BlockId::Hash(Default::default())).unwrap();
+		let mut header = Header {
+			number: 0,
+			parent_hash: Default::default(),
+			state_root: Default::default(),
+			digest: Default::default(),
+			extrinsics_root: Default::default(),
+		};
+
+		let storage = vec![
+			(vec![1, 3, 5], vec![2, 4, 6]),
+			(vec![1, 2, 3], vec![9, 9, 9]),
+			(vec![1, 2], vec![1]),
+			(vec![2, 2, 3], vec![0]),
+		];
+
+		header.state_root = op.old_state.storage_root(storage
+			.iter()
+			.cloned()
+			.map(|(x, y)| (x, Some(y)))
+		).0.into();
+
+		op.reset_storage(storage.iter().cloned()).unwrap();
+		op.set_block_data(header, Some(vec![]), None, true).unwrap();
+		db.commit_operation(op).unwrap();
+
+		let state = db.state_at(BlockId::Number(0)).unwrap();
+
+		assert_eq!(state.keys(&[1, 2]).unwrap(), vec![vec![1, 2], vec![1, 2, 3]]);
+		assert_eq!(state.keys(&[1]).unwrap(), vec![vec![1, 2], vec![1, 2, 3], vec![1, 3, 5]]);
+		assert_eq!(state.keys(&[3]).unwrap(), Vec::<Vec<u8>>::new());
+		assert_eq!(state.keys(&[]).unwrap().len(), 4);
+	}
+
+	#[test]
+	fn indexes_changed_keys() {
+		let db = Backend::<Block>::new_test();
+		let genesis_hash = {
+			let mut op = db.begin_operation(BlockId::Hash(Default::default())).unwrap();
+			let mut header = Header {
+				number: 0,
+				parent_hash: Default::default(),
+				state_root: Default::default(),
+				digest: Default::default(),
+				extrinsics_root: Default::default(),
+			};
+
+			let storage = vec![(vec![3], vec![1]), (vec![1, 2], vec![2])];
+
+			header.state_root = op.old_state.storage_root(storage
+				.iter()
+				.cloned()
+				.map(|(x, y)| (x, Some(y)))
+			).0.into();
+			let hash = header.hash();
+
+			op.reset_storage(storage.iter().cloned()).unwrap();
+			op.set_block_data(header, Some(vec![]), None, true).unwrap();
+			db.commit_operation(op).unwrap();
+			hash
+		};
+
+		{
+			let mut op = db.begin_operation(BlockId::Number(0)).unwrap();
+			let header = Header {
+				number: 1,
+				parent_hash: genesis_hash,
+				state_root: db.blockchain().header(BlockId::Number(0)).unwrap().unwrap().state_root,
+				digest: Default::default(),
+				extrinsics_root: Default::default(),
+			};
+
+			op.set_changed_keys(vec![vec![3]]).unwrap();
+			op.set_block_data(header, Some(vec![]), None, true).unwrap();
+			db.commit_operation(op).unwrap();
+		}
+
+		assert_eq!(db.blockchain().changed_keys(BlockId::Number(0)).unwrap(), Some(vec![vec![1, 2], vec![3]]));
+		assert_eq!(db.blockchain().changed_keys(BlockId::Number(1)).unwrap(), Some(vec![vec![3]]));
+		assert_eq!(db.blockchain().changed_keys(BlockId::Number(2)).unwrap(), None);
+	}
+
 	#[test]
 	fn set_state_data() {
 		let db = Backend::<Block>::new_test();
@@ -673,6 +788,36 @@ mod tests {
 		}
 	}
 
+	#[test]
+	fn set_child_state_data() {
+		let db = Backend::<Block>::new_test();
+		let child = b":child_storage:test".to_vec();
+		let mut op = db.begin_operation(BlockId::Hash(Default::default())).unwrap();
+		let mut header = Header {
+			number: 0,
+			parent_hash: Default::default(),
+			state_root: Default::default(),
+			digest: Default::default(),
+			extrinsics_root: Default::default(),
+		};
+
+		let (root, overlay) = op.old_state.full_storage_root(
+			vec![(vec![1, 2, 3], Some(vec![9, 9, 9]))],
+			vec![(child.clone(), vec![(vec![1, 3, 5], Some(vec![2, 4, 6]))])],
+		);
+		op.update_storage(overlay).unwrap();
+		header.state_root = root.into();
+		op.set_block_data(header, Some(vec![]), None, true).unwrap();
+		db.commit_operation(op).unwrap();
+
+		let state = db.state_at(BlockId::Number(0)).unwrap();
+
+		assert_eq!(state.storage(&[1, 2, 3]).unwrap(), Some(vec![9, 9, 9]));
+		assert_eq!(state.storage(&[1, 3, 5]).unwrap(), None);
+		assert_eq!(state.child_storage(&child, &[1, 3, 5]).unwrap(), Some(vec![2, 4, 6]));
+		assert_eq!(state.child_keys(&child, &[]).unwrap(), vec![vec![1, 3, 5]]);
+	}
+
 	#[test]
 	fn delete_only_when_negative_rc() {
 		let key;
@@ -774,4 +919,224 @@ mod tests {
 			assert!(db.db.get(::columns::STATE, &key.0[..]).unwrap().is_none());
 		}
 	}
+
+	#[test]
+	fn stores_changes_trie_nodes() {
+		use patricia_trie::TrieMut;
+
+		let db = Backend::<Block>::new_test();
+		let mut changes_trie = MemoryDB::default();
+		let mut root = TrieH256::default();
+		{
+			let mut trie = TrieDBMut::new(&mut changes_trie, &mut root);
+			trie.insert(b"key", &vec![0u32, 2].encode()).unwrap();
+		}
+
+		let mut op = db.begin_operation(BlockId::Hash(Default::default())).unwrap();
+		let header = Header {
+			number: 0,
+			parent_hash: Default::default(),
+			state_root: Default::default(),
+			digest: Default::default(),
+			extrinsics_root: Default::default(),
+		};
+		op.update_changes_trie(changes_trie).unwrap();
+		op.set_block_data(header, Some(vec![]), None, true).unwrap();
+		db.commit_operation(op).unwrap();
+
+		let storage = db.changes_trie_storage().unwrap();
+		let (extrinsics, proof) = state_machine::prove_key_changes(storage, root.0, b"key").unwrap();
+		assert_eq!(extrinsics, Some(vec![0, 2]));
+		assert_eq!(state_machine::check_key_changes_proof(root.0, proof, b"key").unwrap(), Some(vec![0, 2]));
+	}
+
+	#[test]
+	fn state_cache_follows_best_block() {
+		let db = Backend::<Block>::new_test();
+		{
+			let mut op = db.begin_operation(BlockId::Hash(Default::default())).unwrap();
+			let mut header = Header {
+				number: 0,
+				parent_hash: Default::default(),
+				state_root: Default::default(),
+				digest: Default::default(),
+				extrinsics_root: Default::default(),
+			};
+
+			let storage = vec![(vec![1], vec![1]), (vec![2], vec![2])];
+			header.state_root = op.old_state.storage_root(storage
+				.iter()
+				.cloned()
+				.map(|(x, y)| (x, Some(y)))
+			).0.into();
+
+			op.reset_storage(storage.iter().cloned()).unwrap();
+			op.set_block_data(header, Some(vec![]), None, true).unwrap();
+			db.commit_operation(op).unwrap();
+		}
+
+		let state = db.state_at(BlockId::Number(0)).unwrap();
+		assert_eq!(state.storage(&[1]).unwrap(), Some(vec![1]));
+		assert_eq!(state.storage(&[1]).unwrap(), Some(vec![1]));
+		assert_eq!(state.storage(&[2]).unwrap(), Some(vec![2]));
+		assert_eq!(db.state_cache_stats(), Some(CacheStats { hits: 1, misses: 2 }));
+
+		{
+			let mut op = db.begin_operation(BlockId::Number(0)).unwrap();
+			let mut header = Header {
+				number: 1,
+				parent_hash: Default::default(),
+				state_root: Default::default(),
+				digest: Default::default(),
+				extrinsics_root: Default::default(),
+			};
+
+			let (root, overlay) = op.old_state.storage_root(vec![(vec![1], Some(vec![3]))]);
+			op.update_storage(overlay).unwrap();
+			op.set_changed_keys(vec![vec![1]]).unwrap();
+			header.state_root = root.into();
+			op.set_block_data(header, Some(vec![]), None, true).unwrap();
+			db.commit_operation(op).unwrap();
+		}
+
+		// the changed value is read from the database, the unchanged one from the cache.
+		let state = db.state_at(BlockId::Number(1)).unwrap();
+		assert_eq!(state.storage(&[1]).unwrap(), Some(vec![3]));
+		assert_eq!(state.storage(&[2]).unwrap(), Some(vec![2]));
+		assert_eq!(db.state_cache_stats(), Some(CacheStats { hits: 2, misses: 3 }));
+	}
+
+	#[test]
+	fn reverts_best_blocks() {
+		let kvdb: Arc<KeyValueDB> = Arc::new(::kvdb_memorydb::create(columns::NUM_COLUMNS));
+		let db = Backend::<Block>::from_kvdb(kvdb, true, DEFAULT_STATE_CACHE_SIZE).unwrap();
+		let mut hashes = Vec::new();
+		for i in 0..3 {
+			let id = if i == 0 {
+				BlockId::Hash(Default::default())
+			} else {
+				BlockId::Number(i - 1)
+			};
+
+			let mut op = db.begin_operation(id).unwrap();
+			let mut header = Header {
+				number: i,
+				parent_hash: hashes.last().cloned().unwrap_or_default(),
+				state_root: Default::default(),
+				digest: Default::default(),
+				extrinsics_root: Default::default(),
+			};
+
+			let (root, overlay) = op.old_state.storage_root(vec![(vec![1], Some(vec![i as u8]))]);
+			op.update_storage(overlay).unwrap();
+			header.state_root = root.into();
+			hashes.push(header.hash());
+			op.set_block_data(header, Some(vec![]), None, true).unwrap();
+			db.commit_operation(op).unwrap();
+		}
+
+		assert_eq!(db.revert(1).unwrap(), 1);
+		assert_eq!(db.blockchain().info().unwrap().best_hash, hashes[1]);
+		assert_eq!(db.blockchain().info().unwrap().best_number, 1);
+		assert!(db.blockchain().hash(2).unwrap().is_none());
+		assert!(db.blockchain().header(BlockId::Hash(hashes[2])).unwrap().is_none());
+		assert_eq!(db.state_at(BlockId::Number(1)).unwrap().storage(&[1]).unwrap(), Some(vec![1]));
+
+		// genesis is never reverted.
+		assert_eq!(db.revert(5).unwrap(), 1);
+		assert_eq!(db.blockchain().info().unwrap().best_hash, hashes[0]);
+		assert_eq!(db.blockchain().info().unwrap().best_number, 0);
+		assert_eq!(db.state_at(BlockId::Number(0)).unwrap().storage(&[1]).unwrap(), Some(vec![0]));
+	}
+
+	fn insert_block(
+		db: &Backend<Block>,
+		number: u64,
+		parent_hash: H256,
+		changes: &[(&[u8], Vec<u32>)],
+	) -> (H256, TrieH256) {
+		use patricia_trie::TrieMut;
+
+		let mut changes_trie = MemoryDB::default();
+		let mut root = TrieH256::default();
+		{
+			let mut trie = TrieDBMut::new(&mut changes_trie, &mut root);
+			for &(key, ref extrinsics) in changes {
+				trie.insert(key, &extrinsics.encode()).unwrap();
+			}
+		}
+
+		let id = if number == 0 { BlockId::Hash(Default::default()) } else { BlockId::Number(number - 1) };
+		let mut op = db.begin_operation(id).unwrap();
+		let header = Header {
+			number,
+			parent_hash,
+			state_root: Default::default(),
+			digest: Default::default(),
+			extrinsics_root: Default::default(),
+		};
+		let hash = header.hash();
+		op.set_changed_keys(changes.iter().map(|&(key, _)| key.to_vec()).collect()).unwrap();
+		op.update_changes_trie(changes_trie).unwrap();
+		op.set_block_data(header, Some(vec![]), None, true).unwrap();
+		db.commit_operation(op).unwrap();
+		(hash, root)
+	}
+
+	#[test]
+	fn reverts_blocks_imported_through_client() {
+		use client::BlockOrigin;
+		use test_client::{self, TestClient};
+
+		let kvdb: Arc<KeyValueDB> = Arc::new(::kvdb_memorydb::create(columns::NUM_COLUMNS));
+		let backend = Backend::<test_client::runtime::Block>::from_kvdb(kvdb, true, DEFAULT_STATE_CACHE_SIZE).unwrap();
+		let client = test_client::new_with_backend(Arc::new(backend));
+		for _ in 0..2 {
+			let block = client.new_block().unwrap().bake().unwrap();
+			client.justify_and_import(BlockOrigin::Own, block).unwrap();
+		}
+		assert_eq!(client.info().unwrap().chain.best_number, 2);
+		assert!(client.justification(&BlockId::Number(2)).unwrap().is_some());
+
+		assert_eq!(client.revert(1).unwrap(), 1);
+		assert_eq!(client.info().unwrap().chain.best_number, 1);
+		assert_eq!(client.block_hash(2).unwrap(), None);
+
+		// genesis is never reverted.
+		assert_eq!(client.revert(5).unwrap(), 1);
+		assert_eq!(client.info().unwrap().chain.best_number, 0);
+		assert_eq!(client.info().unwrap().chain.best_hash, client.genesis_hash());
+	}
+
+	#[test]
+	fn refuses_to_revert_pruned_database() {
+		let db = Backend::<Block>::new_test();
+		let (genesis, _) = insert_block(&db, 0, Default::default(), &[]);
+		let (hash, _) = insert_block(&db, 1, genesis, &[]);
+
+		assert!(db.revert(1).is_err());
+		assert_eq!(db.blockchain().info().unwrap().best_hash, hash);
+	}
+
+	#[test]
+	fn reverts_changes_of_reverted_blocks() {
+		let kvdb: Arc<KeyValueDB> = Arc::new(::kvdb_memorydb::create(columns::NUM_COLUMNS));
+		let db = Backend::<Block>::from_kvdb(kvdb, true, DEFAULT_STATE_CACHE_SIZE).unwrap();
+		let (genesis, genesis_root) = insert_block(&db, 0, Default::default(), &[(&b"key"[..], vec![0])]);
+		let (hash, root) = insert_block(&db, 1, genesis, &[(&b"key"[..], vec![0]), (&b"other"[..], vec![1])]);
+		// a block with the same changes as genesis inserts no new nodes.
+		let (_, same_root) = insert_block(&db, 2, hash, &[(&b"key"[..], vec![0])]);
+		assert_eq!(same_root, genesis_root);
+
+		assert_eq!(db.revert(2).unwrap(), 2);
+		assert_eq!(db.blockchain().changed_keys(BlockId::Number(1)).unwrap(), None);
+		assert!(db.db.get(columns::CHANGES_TRIE, &root.0[..]).unwrap().is_none());
+		assert!(db.db.get(columns::CHANGES_TRIE, &number_to_db_key(1u64)).unwrap().is_none());
+
+		// nodes shared with genesis are kept.
+		let storage = db.changes_trie_storage().unwrap();
+		let (extrinsics, _) = state_machine::prove_key_changes(storage, genesis_root.0, b"key").unwrap();
+		assert_eq!(extrinsics, Some(vec![0]));
+		assert_eq!(db.blockchain().changed_keys(BlockId::Number(0)).unwrap(), Some(vec![b"key".to_vec()]));
+	}
 }
@@ -0,0 +1,275 @@
+// Copyright 2017 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Cache of the storage values of the best block's state.
+//!
+//! The cache belongs to a single state, identified by its root. Reads of any other state
+//! bypass it. When a new best block is committed on top of the cached state, the keys it
+//! changed are evicted and the cache moves on to the new state; otherwise (e.g. on a
+//! re-org) it is cleared.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use ethereum_types::H256 as TrieH256;
+use lru_cache::LruCache;
+use parking_lot::Mutex;
+use client::backend::CacheStats;
+use primitives::storage::well_known_keys::is_child_storage_key;
+use state_machine::TrieBackend;
+use state_machine::backend::Backend as StateBackend;
+
+/// Default limit of the total size of the cached keys and values, in bytes.
+pub const DEFAULT_STATE_CACHE_SIZE: usize = 16 * 1024 * 1024;
+
+struct Cache {
+	/// Root of the state the values belong to.
+	root: Option<TrieH256>,
+	values: LruCache<Vec<u8>, Option<Vec<u8>>>,
+	used_size: usize,
+	size_limit: usize,
+}
+
+impl Cache {
+	fn insert(&mut self, key: Vec<u8>, value: Option<Vec<u8>>) {
+		let size = entry_size(&key, &value);
+		if size > self.size_limit {
+			return;
+		}
+
+		self.remove(&key);
+		self.values.insert(key, value);
+		self.used_size += size;
+		while self.used_size > self.size_limit {
+			match self.values.remove_lru() {
+				Some((key, value)) => self.used_size -= entry_size(&key, &value),
+				None => break,
+			}
+		}
+	}
+
+	fn remove(&mut self, key: &[u8]) {
+		if let Some(value) = self.values.remove(key) {
+			self.used_size -= entry_size(key, &value);
+		}
+	}
+
+	fn clear(&mut self) {
+		self.values.clear();
+		self.used_size = 0;
+	}
+}
+
+fn entry_size(key: &[u8], value: &Option<Vec<u8>>) -> usize {
+	key.len() + value.as_ref().map_or(0, Vec::len)
+}
+
+/// Size-bounded cache of storage values, shared between the backend and all of its states.
+pub struct SharedCache {
+	cache: Mutex<Cache>,
+	hits: AtomicUsize,
+	misses: AtomicUsize,
+}
+
+impl SharedCache {
+	/// Create a new empty cache keeping at most `size_limit` bytes of keys and values.
+	pub fn new(size_limit: usize) -> Self {
+		SharedCache {
+			cache: Mutex::new(Cache {
+				root: None,
+				values: LruCache::new(usize::max_value()),
+				used_size: 0,
+				size_limit,
+			}),
+			hits: AtomicUsize::new(0),
+			misses: AtomicUsize::new(0),
+		}
+	}
+
+	/// Move the cache onto the state of a new best block, built on top of the state with
+	/// `parent_root`. `changed_keys` are the keys changed by the block, if known.
+	pub fn sync(&self, parent_root: &TrieH256, root: TrieH256, changed_keys: Option<&[Vec<u8>]>) {
+		let mut cache = self.cache.lock();
+		match changed_keys {
+			Some(keys) if cache.root.as_ref() == Some(parent_root) => {
+				for key in keys {
+					cache.remove(key);
+				}
+			},
+			_ => cache.clear(),
+		}
+		cache.root = Some(root);
+	}
+
+	/// Get the hit and miss counters.
+	pub fn stats(&self) -> CacheStats {
+		CacheStats {
+			hits: self.hits.load(Ordering::Relaxed),
+			misses: self.misses.load(Ordering::Relaxed),
+		}
+	}
+
+	fn get(&self, root: &TrieH256, key: &[u8]) -> Option<Option<Vec<u8>>> {
+		let mut cache = self.cache.lock();
+		if cache.root.as_ref() != Some(root) {
+			return None;
+		}
+
+		let value = cache.values.get_mut(key).map(|value| value.clone());
+		match value {
+			Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+			None => self.misses.fetch_add(1, Ordering::Relaxed),
+		};
+		value
+	}
+
+	fn insert(&self, root: &TrieH256, key: &[u8], value: &Option<Vec<u8>>) {
+		let mut cache = self.cache.lock();
+		// the cache may have moved on while the value was read from the database.
+		if cache.root.as_ref() == Some(root) {
+			cache.insert(key.to_vec(), value.clone());
+		}
+	}
+}
+
+/// Patricia trie-based state which answers reads from the shared cache, if it is the
+/// cached state.
+#[derive(Clone)]
+pub struct CachingState {
+	state: TrieBackend,
+	cache: Arc<SharedCache>,
+}
+
+impl CachingState {
+	/// Create a new caching state on top of the given trie state.
+	pub fn new(state: TrieBackend, cache: Arc<SharedCache>) -> Self {
+		CachingState { state, cache }
+	}
+
+	/// Root of the state.
+	pub fn root(&self) -> &TrieH256 {
+		self.state.root()
+	}
+}
+
+impl StateBackend for CachingState {
+	type Error = <TrieBackend as StateBackend>::Error;
+	type Transaction = <TrieBackend as StateBackend>::Transaction;
+
+	fn storage(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+		// values of child storage keys change with their child tries, which are not tracked.
+		if is_child_storage_key(key) {
+			return self.state.storage(key);
+		}
+
+		if let Some(value) = self.cache.get(self.state.root(), key) {
+			return Ok(value);
+		}
+
+		let value = self.state.storage(key)?;
+		self.cache.insert(self.state.root(), key, &value);
+		Ok(value)
+	}
+
+	fn child_storage(&self, storage_key: &[u8], key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+		self.state.child_storage(storage_key, key)
+	}
+
+	fn storage_root<I>(&self, delta: I) -> ([u8; 32], Self::Transaction)
+		where I: IntoIterator<Item=(Vec<u8>, Option<Vec<u8>>)>
+	{
+		self.state.storage_root(delta)
+	}
+
+	fn child_storage_root<I>(&self, storage_key: &[u8], delta: I) -> ([u8; 32], bool, Self::Transaction)
+		where I: IntoIterator<Item=(Vec<u8>, Option<Vec<u8>>)>
+	{
+		self.state.child_storage_root(storage_key, delta)
+	}
+
+	fn pairs(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+		self.state.pairs()
+	}
+
+	fn keys(&self, prefix: &[u8]) -> Result<Vec<Vec<u8>>, Self::Error> {
+		self.state.keys(prefix)
+	}
+
+	fn keys_paged(&self, prefix: &[u8], start_key: Option<&[u8]>, count: usize) -> Result<Vec<Vec<u8>>, Self::Error> {
+		self.state.keys_paged(prefix, start_key, count)
+	}
+
+	fn child_keys(&self, storage_key: &[u8], prefix: &[u8]) -> Result<Vec<Vec<u8>>, Self::Error> {
+		self.state.child_keys(storage_key, prefix)
+	}
+
+	fn try_into_trie_backend(self) -> Option<TrieBackend> {
+		Some(self.state)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn cache_at(root: TrieH256, size_limit: usize) -> SharedCache {
+		let cache = SharedCache::new(size_limit);
+		cache.sync(&Default::default(), root, None);
+		cache
+	}
+
+	#[test]
+	fn values_of_other_states_are_not_cached() {
+		let cache = cache_at(1.into(), 1024);
+		cache.insert(&2.into(), b"key", &Some(vec![1]));
+		assert_eq!(cache.get(&2.into(), b"key"), None);
+		assert_eq!(cache.get(&1.into(), b"key"), None);
+		assert_eq!(cache.stats(), CacheStats { hits: 0, misses: 1 });
+	}
+
+	#[test]
+	fn changed_keys_are_evicted_on_sync() {
+		let cache = cache_at(1.into(), 1024);
+		cache.insert(&1.into(), b"changed", &Some(vec![1]));
+		cache.insert(&1.into(), b"unchanged", &None);
+
+		cache.sync(&1.into(), 2.into(), Some(&[b"changed".to_vec()]));
+		assert_eq!(cache.get(&2.into(), b"changed"), None);
+		assert_eq!(cache.get(&2.into(), b"unchanged"), Some(None));
+		assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 1 });
+	}
+
+	#[test]
+	fn cache_is_cleared_when_synced_to_other_fork() {
+		let cache = cache_at(1.into(), 1024);
+		cache.insert(&1.into(), b"key", &Some(vec![1]));
+
+		cache.sync(&3.into(), 2.into(), Some(&[]));
+		assert_eq!(cache.get(&2.into(), b"key"), None);
+	}
+
+	#[test]
+	fn least_recently_used_values_are_evicted() {
+		let cache = cache_at(1.into(), 8);
+		cache.insert(&1.into(), b"a", &Some(vec![1, 2, 3]));
+		cache.insert(&1.into(), b"b", &Some(vec![1, 2, 3]));
+		assert!(cache.get(&1.into(), b"a").is_some());
+		cache.insert(&1.into(), b"c", &Some(vec![1, 2, 3]));
+
+		assert!(cache.get(&1.into(), b"a").is_some());
+		assert!(cache.get(&1.into(), b"b").is_none());
+		assert!(cache.get(&1.into(), b"c").is_some());
+	}
+}
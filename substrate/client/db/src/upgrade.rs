@@ -0,0 +1,289 @@
+// Copyright 2017 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Database layout versioning and upgrades.
+//!
+//! The version of the layout is kept in the meta column. Databases written before it was
+//! introduced have no version and are treated as version 0. On open, the columns added since
+//! the version the database was written with are created, and the database is then upgraded
+//! one version at a time, each step committed together with the version it brings the
+//! database to.
+//!
+//! Version 0 has the meta, state, block index, header, body and justification columns.
+//! Version 1 adds the changed keys and changes trie columns.
+
+use codec::Slicable;
+use kvdb::{KeyValueDB, DBTransaction};
+use kvdb_rocksdb::{Database, DatabaseConfig};
+use client;
+use {columns, meta, db_err};
+
+/// Version of the database layout written by this binary.
+pub const CURRENT_VERSION: u32 = 1;
+
+/// Number of columns beyond the current ones tried when looking for the version of a database
+/// written by a newer binary.
+const MAX_NEWER_COLUMNS: u32 = 16;
+
+/// Number of columns in the database layout of the given version.
+fn num_columns(version: u32) -> u32 {
+	match version {
+		0 => 6,
+		_ => columns::NUM_COLUMNS,
+	}
+}
+
+/// Open the RocksDB database at `path`, creating the columns added since the version it was
+/// written with. Fails with `DatabaseVersionTooNew` if the database has columns added by a newer
+/// binary.
+pub fn open_database(memory_budget: Option<usize>, path: &str) -> Result<Database, client::error::Error> {
+	let config = |num_columns| {
+		let mut config = DatabaseConfig::with_columns(Some(num_columns));
+		config.memory_budget = memory_budget;
+		config.wal = true;
+		config
+	};
+
+	// RocksDB refuses to open a database with columns it does not have, so try the column
+	// counts of older versions, newest first.
+	let error = match Database::open(&config(columns::NUM_COLUMNS), path) {
+		Ok(db) => return Ok(db),
+		Err(error) => error,
+	};
+	for version in (0..CURRENT_VERSION).rev() {
+		if num_columns(version) == columns::NUM_COLUMNS {
+			continue;
+		}
+		if let Ok(db) = Database::open(&config(num_columns(version)), path) {
+			info!("Adding database columns of version {}", CURRENT_VERSION);
+			while db.num_columns() < columns::NUM_COLUMNS {
+				db.add_column()
+					.map_err(|e| client::error::Error::from(client::error::ErrorKind::Backend(format!("{}", e))))?;
+			}
+			return Ok(db);
+		}
+	}
+
+	// the database may have the columns of a newer version; open it with those only to read its
+	// version.
+	for num_columns in columns::NUM_COLUMNS + 1..columns::NUM_COLUMNS + MAX_NEWER_COLUMNS + 1 {
+		if let Ok(db) = Database::open(&config(num_columns), path) {
+			if let Some(version) = read_version(&db)? {
+				if version > CURRENT_VERSION {
+					return Err(client::error::ErrorKind::DatabaseVersionTooNew(version, CURRENT_VERSION).into());
+				}
+			}
+			break;
+		}
+	}
+	Err(db_err(error))
+}
+
+/// Upgrade of the database by one version, by the version it upgrades from.
+type Migration = fn(&KeyValueDB, &mut DBTransaction) -> Result<(), client::error::Error>;
+
+fn migration(from: u32) -> Migration {
+	match from {
+		0 => migrate_0_to_1,
+		_ => unreachable!("there is a migration from every version below the current one; qed"),
+	}
+}
+
+/// Version 1 records the changed keys and the changes trie of every imported block. These
+/// can't be backfilled, as the state they are computed from may be pruned, so the number of
+/// the first block that has them is recorded instead.
+fn migrate_0_to_1(db: &KeyValueDB, transaction: &mut DBTransaction) -> Result<(), client::error::Error> {
+	let best = db.get(columns::META, meta::BEST_BLOCK).map_err(db_err)?
+		.ok_or_else(|| client::error::ErrorKind::Backend("Missing best block".into()))?;
+	if best.len() != 4 {
+		return Err(client::error::ErrorKind::Backend("Invalid best block key".into()).into());
+	}
+	let best = best.iter().fold(0u32, |n, byte| (n << 8) | *byte as u32);
+	transaction.put(columns::META, meta::CHANGES_INDEXED_FROM, &::number_to_db_key(best + 1));
+	Ok(())
+}
+
+/// Read the version of the database layout. `None` for a new, empty database.
+fn read_version(db: &KeyValueDB) -> Result<Option<u32>, client::error::Error> {
+	match db.get(columns::META, meta::VERSION).map_err(db_err)? {
+		Some(version) => u32::decode(&mut &version[..])
+			.map(Some)
+			.ok_or_else(|| client::error::ErrorKind::Backend("Invalid database version".into()).into()),
+		None => match db.get(columns::META, meta::BEST_BLOCK).map_err(db_err)? {
+			Some(_) => Ok(Some(0)),
+			None => Ok(None),
+		},
+	}
+}
+
+fn write_version(transaction: &mut DBTransaction, version: u32) {
+	transaction.put(columns::META, meta::VERSION, &version.encode());
+}
+
+/// Upgrade the database to the current version. Fails if the database was written by a newer
+/// binary.
+pub fn upgrade_db(db: &KeyValueDB) -> Result<(), client::error::Error> {
+	let version = match read_version(db)? {
+		Some(version) => version,
+		None => {
+			let mut transaction = DBTransaction::new();
+			write_version(&mut transaction, CURRENT_VERSION);
+			return db.write(transaction).map_err(db_err);
+		},
+	};
+
+	if version > CURRENT_VERSION {
+		return Err(client::error::ErrorKind::DatabaseVersionTooNew(version, CURRENT_VERSION).into());
+	}
+
+	for from in version..CURRENT_VERSION {
+		info!("Upgrading database from version {} to {}", from, from + 1);
+		let mut transaction = DBTransaction::new();
+		migration(from)(db, &mut transaction)?;
+		write_version(&mut transaction, from + 1);
+		db.write(transaction).map_err(db_err)?;
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+	use tempdir::TempDir;
+	use client::backend::Backend as BTrait;
+	use client::blockchain::Backend as BCTrait;
+	use runtime_primitives::generic::BlockId;
+	use runtime_primitives::testing::{Header, Block as RawBlock};
+	use runtime_primitives::traits::{BlakeTwo256, Hashing, Header as HeaderT};
+	use {Backend, DatabaseSettings, number_to_db_key};
+	use super::*;
+
+	type Block = RawBlock<u64>;
+
+	/// Write a database of version 0 with a genesis block at `path`, using the column count and
+	/// encoding of that version. Returns the genesis header.
+	fn v0_fixture(path: &str) -> Header {
+		let db = Database::open(&DatabaseConfig::with_columns(Some(6)), path).unwrap();
+
+		// parent hash, number, state root, extrinsics root, no digest items.
+		let mut header = Vec::new();
+		header.extend_from_slice(&[0; 32]);
+		header.extend_from_slice(&[0; 8]);
+		header.extend_from_slice(&[1; 32]);
+		header.extend_from_slice(&[2; 32]);
+		header.extend_from_slice(&[0; 4]);
+		let key = [0, 0, 0, 0];
+
+		let mut transaction = DBTransaction::new();
+		transaction.put(Some(3), &key, &header);
+		transaction.put(Some(4), &key, &[0, 0, 0, 0]);
+		transaction.put(Some(2), BlakeTwo256::hash(&header).as_ref(), &key);
+		transaction.put(Some(0), b"best", &key);
+		db.write(transaction).unwrap();
+
+		Header {
+			parent_hash: Default::default(),
+			number: 0,
+			state_root: [1; 32].into(),
+			extrinsics_root: [2; 32].into(),
+			digest: Default::default(),
+		}
+	}
+
+	fn open_backend(path: &TempDir) -> Result<Backend<Block>, client::error::Error> {
+		Backend::new(&DatabaseSettings {
+			cache_size: None,
+			state_cache_size: None,
+			path: path.path().to_owned(),
+		})
+	}
+
+	#[test]
+	fn new_database_is_at_current_version() {
+		let db: Arc<KeyValueDB> = Arc::new(::kvdb_memorydb::create(columns::NUM_COLUMNS));
+		Backend::<Block>::from_kvdb(db.clone(), false, 0).unwrap();
+		assert_eq!(read_version(&*db).unwrap(), Some(CURRENT_VERSION));
+		assert_eq!(db.get(columns::META, meta::CHANGES_INDEXED_FROM).unwrap(), None);
+	}
+
+	#[test]
+	fn database_of_version_0_is_upgraded() {
+		let path = TempDir::new("substrate-db-upgrade").unwrap();
+		let header = v0_fixture(path.path().to_str().unwrap());
+
+		{
+			let backend = open_backend(&path).unwrap();
+			assert_eq!(read_version(&*backend.db).unwrap(), Some(CURRENT_VERSION));
+			assert_eq!(backend.blockchain().info().unwrap().best_hash, header.hash());
+			assert_eq!(backend.blockchain().header(BlockId::Number(0)).unwrap(), Some(header.clone()));
+			assert_eq!(
+				backend.db.get(columns::META, meta::CHANGES_INDEXED_FROM).unwrap().map(|v| v.to_vec()),
+				Some(number_to_db_key(1u32).to_vec())
+			);
+			assert_eq!(backend.blockchain().changed_keys(BlockId::Number(0)).unwrap(), None);
+
+			let mut transaction = DBTransaction::new();
+			transaction.put(columns::CHANGED_KEYS, b"key", b"value");
+			transaction.put(columns::CHANGES_TRIE, b"key", b"value");
+			backend.db.write(transaction).unwrap();
+		}
+
+		// the upgraded database opens with all the columns of the current version.
+		let backend = open_backend(&path).unwrap();
+		assert_eq!(read_version(&*backend.db).unwrap(), Some(CURRENT_VERSION));
+		assert_eq!(backend.blockchain().header(BlockId::Number(0)).unwrap(), Some(header));
+		assert_eq!(backend.db.get(columns::CHANGED_KEYS, b"key").unwrap().map(|v| v.to_vec()), Some(b"value".to_vec()));
+		assert_eq!(backend.db.get(columns::CHANGES_TRIE, b"key").unwrap().map(|v| v.to_vec()), Some(b"value".to_vec()));
+	}
+
+	#[test]
+	fn newer_database_with_more_columns_is_rejected() {
+		let path = TempDir::new("substrate-db-upgrade").unwrap();
+		{
+			let config = DatabaseConfig::with_columns(Some(columns::NUM_COLUMNS + 1));
+			let db = Database::open(&config, path.path().to_str().unwrap()).unwrap();
+			let mut transaction = DBTransaction::new();
+			write_version(&mut transaction, CURRENT_VERSION + 1);
+			db.write(transaction).unwrap();
+		}
+
+		match open_backend(&path) {
+			Err(client::error::Error(client::error::ErrorKind::DatabaseVersionTooNew(version, supported), _)) => {
+				assert_eq!(version, CURRENT_VERSION + 1);
+				assert_eq!(supported, CURRENT_VERSION);
+			},
+			_ => panic!("a database with the columns of a newer version must be rejected"),
+		}
+	}
+
+	#[test]
+	fn newer_database_is_rejected() {
+		let db: Arc<KeyValueDB> = Arc::new(::kvdb_memorydb::create(columns::NUM_COLUMNS));
+		let mut transaction = DBTransaction::new();
+		write_version(&mut transaction, CURRENT_VERSION + 1);
+		db.write(transaction).unwrap();
+
+		match Backend::<Block>::from_kvdb(db.clone(), false, 0) {
+			Err(client::error::Error(client::error::ErrorKind::DatabaseVersionTooNew(version, supported), _)) => {
+				assert_eq!(version, CURRENT_VERSION + 1);
+				assert_eq!(supported, CURRENT_VERSION);
+			},
+			_ => panic!("a database of a newer version must be rejected"),
+		}
+		assert_eq!(read_version(&*db).unwrap(), Some(CURRENT_VERSION + 1));
+	}
+}
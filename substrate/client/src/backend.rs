@@ -16,12 +16,23 @@
 
 //! Polkadot Client data backend
 
+use std::sync::Arc;
 use state_machine::backend::Backend as StateBackend;
+use state_machine::{ChangesTrieTransaction, Storage as ChangesTrieStorage};
 use error;
 use runtime_primitives::bft::Justification;
-use runtime_primitives::traits::Block as BlockT;
+use runtime_primitives::traits::{Block as BlockT, Header as HeaderT};
 use runtime_primitives::generic::BlockId;
 
+/// State cache hit and miss counters.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+	/// Number of reads of the cached state answered from the cache.
+	pub hits: usize,
+	/// Number of reads of the cached state which had to go to the database.
+	pub misses: usize,
+}
+
 /// Block insertion operation. Keeps hold if the inserted block state and data.
 pub trait BlockImportOperation<Block: BlockT> {
 	/// Associated state backend type.
@@ -42,6 +53,10 @@ pub trait BlockImportOperation<Block: BlockT> {
 	fn update_storage(&mut self, update: <Self::State as StateBackend>::Transaction) -> error::Result<()>;
 	/// Inject storage data into the database replacing any existing data.
 	fn reset_storage<I: Iterator<Item=(Vec<u8>, Vec<u8>)>>(&mut self, iter: I) -> error::Result<()>;
+	/// Record keys of the storage entries changed by the block.
+	fn set_changed_keys(&mut self, keys: Vec<Vec<u8>>) -> error::Result<()>;
+	/// Inject the nodes of the block's changes trie into the database.
+	fn update_changes_trie(&mut self, update: ChangesTrieTransaction) -> error::Result<()>;
 }
 
 /// Client backend. Manages the data layer.
@@ -69,6 +84,14 @@ pub trait Backend<Block: BlockT>: Send + Sync {
 	fn blockchain(&self) -> &Self::Blockchain;
 	/// Returns state backend with post-state of given block.
 	fn state_at(&self, block: BlockId<Block>) -> error::Result<Self::State>;
+	/// Returns the storage of changes trie nodes. None for backends which do not keep them.
+	fn changes_trie_storage(&self) -> Option<Arc<ChangesTrieStorage>>;
+	/// Returns the hit and miss counters of the state cache. None for backends without one.
+	fn state_cache_stats(&self) -> Option<CacheStats>;
+	/// Revert the chain by removing up to `n` best blocks, making the parent of each removed
+	/// block the best one. Genesis is never removed. Fails if the backend can't restore the state
+	/// of the new best block. Returns the number of removed blocks.
+	fn revert(&self, n: <<Block as BlockT>::Header as HeaderT>::Number) -> error::Result<<<Block as BlockT>::Header as HeaderT>::Number>;
 }
 
 /// Mark for all Backend implementations, that are making use of state data, stored locally.
@@ -21,6 +21,7 @@ use codec::Slicable;
 use state_machine;
 use runtime_primitives::traits::{Header as HeaderT, Hashing as HashingT, Block as BlockT, One, HashingFor};
 use runtime_primitives::generic::BlockId;
+use runtime_primitives::ApplyOutcome;
 use {backend, error, Client, CallExecutor};
 
 /// Utility for building new (valid) blocks from a stream of extrinsics.
@@ -33,6 +34,7 @@ pub struct BlockBuilder<B, E, Block> where
 	header: <Block as BlockT>::Header,
 	extrinsics: Vec<<Block as BlockT>::Extrinsic>,
 	executor: E,
+	strategy: state_machine::ExecutionStrategy,
 	state: B::State,
 	changes: state_machine::OverlayedChanges,
 }
@@ -59,6 +61,7 @@ impl<B, E, Block> BlockBuilder<B, E, Block> where
 			.ok_or_else(|| error::ErrorKind::UnknownBlock(format!("{}", block_id)))?;
 
 		let executor = client.executor().clone();
+		let strategy = client.execution_strategies().block_construction;
 		let state = client.state_at(block_id)?;
 		let mut changes = Default::default();
 		let header = <<Block as BlockT>::Header as HeaderT>::new(
@@ -69,25 +72,29 @@ impl<B, E, Block> BlockBuilder<B, E, Block> where
 			Default::default()
 		);
 
-		executor.call_at_state(&state, &mut changes, "initialise_block", &header.encode())?;
+		executor.call_at_state(&state, &mut changes, "initialise_block", &header.encode(), strategy)?;
 
 		Ok(BlockBuilder {
 			header,
 			extrinsics: Vec::new(),
 			executor,
+			strategy,
 			state,
 			changes,
 		})
 	}
 
 	/// Push onto the block's list of extrinsics. This will ensure the extrinsic
-	/// can be validly executed (by executing it); if it is invalid, it'll be returned along with
-	/// the error. Otherwise, it will return a mutable reference to self (in order to chain).
-	pub fn push(&mut self, xt: <Block as BlockT>::Extrinsic) -> error::Result<()> {
-		match self.executor.call_at_state(&self.state, &mut self.changes, "apply_extrinsic", &xt.encode()) {
-			Ok(_) => {
+	/// can be validly executed (by executing it); if it is invalid, the error is returned.
+	/// Otherwise, it returns the outcome of dispatching the extrinsic; extrinsics whose
+	/// dispatch failed are still included, as their fees are paid.
+	pub fn push(&mut self, xt: <Block as BlockT>::Extrinsic) -> error::Result<ApplyOutcome> {
+		let result = self.executor.call_at_state(&self.state, &mut self.changes, "apply_extrinsic", &xt.encode(), self.strategy)
+			.and_then(|(output, _)| decode_outcome(&output));
+		match result {
+			Ok(outcome) => {
 				self.extrinsics.push(xt);
-				Ok(())
+				Ok(outcome)
 			}
 			Err(e) => {
 				self.changes.discard_prospective();
@@ -96,6 +103,14 @@ impl<B, E, Block> BlockBuilder<B, E, Block> where
 		}
 	}
 
+	/// Execute an encoded extrinsic on top of the block's state without adding it to the block,
+	/// returning the outcome of dispatching it. Any changes it makes are discarded.
+	pub fn dry_run(&self, xt: &[u8]) -> error::Result<ApplyOutcome> {
+		let mut changes = self.changes.clone();
+		let (output, _) = self.executor.call_at_state(&self.state, &mut changes, "apply_extrinsic", xt, self.strategy)?;
+		decode_outcome(&output)
+	}
+
 	/// Consume the builder to return a valid `Block` containing all pushed extrinsics.
 	pub fn bake(mut self) -> error::Result<Block> {
 		let (output, _) = self.executor.call_at_state(
@@ -103,6 +118,7 @@ impl<B, E, Block> BlockBuilder<B, E, Block> where
 			&mut self.changes,
 			"finalise_block",
 			&[],
+			self.strategy,
 		)?;
 		self.header = <<Block as BlockT>::Header as Slicable>::decode(&mut &output[..])
 			.expect("Header came straight out of runtime so must be valid");
@@ -115,3 +131,13 @@ impl<B, E, Block> BlockBuilder<B, E, Block> where
 		Ok(<Block as BlockT>::new(self.header, self.extrinsics))
 	}
 }
+
+/// Decode the outcome of `apply_extrinsic`. Runtimes built before the outcome was returned
+/// have no output, and fail instead of returning a failed outcome, so no output is success.
+fn decode_outcome(output: &[u8]) -> error::Result<ApplyOutcome> {
+	if output.is_empty() {
+		return Ok(ApplyOutcome::Success);
+	}
+
+	ApplyOutcome::decode(&mut &output[..]).ok_or_else(|| error::ErrorKind::InvalidApplyOutcome.into())
+}
@@ -30,6 +30,9 @@ pub trait Backend<Block: BlockT>: Send + Sync {
 	fn body(&self, id: BlockId<Block>) -> Result<Option<Vec<<Block as BlockT>::Extrinsic>>>;
 	/// Get block justification. Returns `None` if justification does not exist.
 	fn justification(&self, id: BlockId<Block>) -> Result<Option<Justification<Block::Hash>>>;
+	/// Get keys of the storage entries changed by the block. Returns `None` if the block is not found
+	/// or its storage changes were not indexed.
+	fn changed_keys(&self, id: BlockId<Block>) -> Result<Option<Vec<Vec<u8>>>>;
 	/// Get blockchain info.
 	fn info(&self) -> Result<Info<Block>>;
 	/// Get block status.
@@ -18,8 +18,7 @@ use std::sync::Arc;
 use futures::{IntoFuture, Future};
 use runtime_primitives::generic::BlockId;
 use runtime_primitives::traits::Block as BlockT;
-use state_machine::{self, OverlayedChanges, Backend as StateBackend, CodeExecutor};
-use state_machine::backend::InMemory as InMemoryStateBackend;
+use state_machine::{self, OverlayedChanges, Backend as StateBackend, CodeExecutor, ExecutionStrategy};
 
 use backend;
 use blockchain::Backend as ChainBackend;
@@ -40,15 +39,21 @@ pub trait CallExecutor<B: BlockT> {
 	/// Externalities error type.
 	type Error: state_machine::Error;
 
-	/// Execute a call to a contract on top of state in a block of given hash.
+	/// Execute a call to a contract on top of state in a block of given hash,
+	/// using the given execution strategy.
 	///
 	/// No changes are made.
-	fn call(&self, id: &BlockId<B>, method: &str, call_data: &[u8]) -> Result<CallResult, error::Error>;
+	fn call(&self, id: &BlockId<B>, method: &str, call_data: &[u8], strategy: ExecutionStrategy) -> Result<CallResult, error::Error>;
 
-	/// Execute a call to a contract on top of given state.
+	/// Execute a call to a contract on top of given state, using the given execution strategy.
 	///
 	/// No changes are made.
-	fn call_at_state<S: state_machine::Backend>(&self, state: &S, overlay: &mut OverlayedChanges, method: &str, call_data: &[u8]) -> Result<(Vec<u8>, S::Transaction), error::Error>;
+	fn call_at_state<S: state_machine::Backend>(&self, state: &S, overlay: &mut OverlayedChanges, method: &str, call_data: &[u8], strategy: ExecutionStrategy) -> Result<(Vec<u8>, S::Transaction), error::Error>;
+
+	/// Execute a call to a contract on top of given state, gathering execution proof.
+	///
+	/// No changes are made.
+	fn prove_at_state<S: state_machine::Backend>(&self, state: S, overlay: &mut OverlayedChanges, method: &str, call_data: &[u8]) -> Result<(Vec<u8>, Vec<Vec<u8>>), error::Error>;
 }
 
 /// Call executor that executes methods locally, querying all required
@@ -90,21 +95,34 @@ impl<B, E, Block> CallExecutor<Block> for LocalCallExecutor<B, E>
 {
 	type Error = E::Error;
 
-	fn call(&self, id: &BlockId<Block>, method: &str, call_data: &[u8]) -> error::Result<CallResult> {
+	fn call(&self, id: &BlockId<Block>, method: &str, call_data: &[u8], strategy: ExecutionStrategy) -> error::Result<CallResult> {
 		let mut changes = OverlayedChanges::default();
-		let (return_data, _) = self.call_at_state(&self.backend.state_at(*id)?, &mut changes, method, call_data)?;
+		let (return_data, _) = self.call_at_state(&self.backend.state_at(*id)?, &mut changes, method, call_data, strategy)?;
 		Ok(CallResult{ return_data, changes })
 	}
 
-	fn call_at_state<S: state_machine::Backend>(&self, state: &S, changes: &mut OverlayedChanges, method: &str, call_data: &[u8]) -> error::Result<(Vec<u8>, S::Transaction)> {
+	fn call_at_state<S: state_machine::Backend>(&self, state: &S, changes: &mut OverlayedChanges, method: &str, call_data: &[u8], strategy: ExecutionStrategy) -> error::Result<(Vec<u8>, S::Transaction)> {
 		state_machine::execute(
 			state,
 			changes,
 			&self.executor,
 			method,
 			call_data,
+			strategy,
 		).map_err(Into::into)
 	}
+
+	fn prove_at_state<S: state_machine::Backend>(&self, state: S, changes: &mut OverlayedChanges, method: &str, call_data: &[u8]) -> Result<(Vec<u8>, Vec<Vec<u8>>), error::Error> {
+		state_machine::prove_execution(
+			state,
+			changes,
+			&self.executor,
+			method,
+			call_data,
+		)
+		.map(|(result, proof, _)| (result, proof))
+		.map_err(Into::into)
+	}
 }
 
 impl<B, F> RemoteCallExecutor<B, F> {
@@ -123,7 +141,7 @@ impl<B, F, Block> CallExecutor<Block> for RemoteCallExecutor<B, F>
 {
 	type Error = error::Error;
 
-	fn call(&self, id: &BlockId<Block>, method: &str, call_data: &[u8]) -> error::Result<CallResult> {
+	fn call(&self, id: &BlockId<Block>, method: &str, call_data: &[u8], _strategy: ExecutionStrategy) -> error::Result<CallResult> {
 		let block_hash = match *id {
 			BlockId::Hash(hash) => hash,
 			BlockId::Number(number) => self.backend.blockchain().hash(number)?
@@ -137,7 +155,11 @@ impl<B, F, Block> CallExecutor<Block> for RemoteCallExecutor<B, F>
 		}).into_future().wait()
 	}
 
-	fn call_at_state<S: state_machine::Backend>(&self, _state: &S, _changes: &mut OverlayedChanges, _method: &str, _call_data: &[u8]) -> error::Result<(Vec<u8>, S::Transaction)> {
+	fn call_at_state<S: state_machine::Backend>(&self, _state: &S, _changes: &mut OverlayedChanges, _method: &str, _call_data: &[u8], _strategy: ExecutionStrategy) -> error::Result<(Vec<u8>, S::Transaction)> {
+		Err(error::ErrorKind::NotAvailableOnLightClient.into())
+	}
+
+	fn prove_at_state<S: state_machine::Backend>(&self, _state: S, _changes: &mut OverlayedChanges, _method: &str, _call_data: &[u8]) -> Result<(Vec<u8>, Vec<Vec<u8>>), error::Error> {
 		Err(error::ErrorKind::NotAvailableOnLightClient.into())
 	}
 }
@@ -148,31 +170,26 @@ pub fn check_execution_proof<B, E, Block>(backend: &B, executor: &E, request: &R
 		B: backend::RemoteBackend<Block>,
 		E: CodeExecutor,
 		Block: BlockT,
+		Block::Hash: Into<[u8; 32]>, // TODO: remove when patricia_trie generic.
 		error::Error: From<<<B as backend::Backend<Block>>::State as StateBackend>::Error>,
 {
-	use runtime_primitives::traits::{Header, Hashing, HashingFor};
+	use runtime_primitives::traits::Header;
 
 	let (remote_result, remote_proof) = remote_proof;
 
-	let remote_state = state_from_execution_proof(remote_proof);
-	let remote_state_root = HashingFor::<Block>::trie_root(remote_state.pairs().into_iter());
-
 	let local_header = backend.blockchain().header(BlockId::Hash(request.block))?;
 	let local_header = local_header.ok_or_else(|| error::ErrorKind::UnknownBlock(format!("{:?}", request.block)))?;
 	let local_state_root = local_header.state_root().clone();
 
-	if remote_state_root != local_state_root {
-		return Err(error::ErrorKind::InvalidExecutionProof.into());
-	}
-
 	let mut changes = OverlayedChanges::default();
-	let (local_result, _) = state_machine::execute(
-		&remote_state,
+	let (local_result, _) = state_machine::execution_proof_check(
+		local_state_root.into(),
+		remote_proof,
 		&mut changes,
 		executor,
 		&request.method,
 		&request.call_data,
-	)?;
+	).map_err(|_| error::ErrorKind::InvalidExecutionProof)?;
 
 	if local_result != remote_result {
 		return Err(error::ErrorKind::InvalidExecutionProof.into());
@@ -180,29 +197,3 @@ pub fn check_execution_proof<B, E, Block>(backend: &B, executor: &E, request: &R
 
 	Ok(CallResult { return_data: local_result, changes })
 }
-
-/// Convert state to execution proof. Proof is simple the whole state (temporary).
-// TODO [light]: this method must be removed after trie-based proofs are landed.
-pub fn state_to_execution_proof<B: state_machine::Backend>(state: &B) -> Vec<Vec<u8>> {
-	state.pairs().into_iter()
-		.flat_map(|(k, v)| ::std::iter::once(k).chain(::std::iter::once(v)))
-		.collect()
-}
-
-/// Convert execution proof to in-memory state for check. Reverse function for state_to_execution_proof.
-// TODO [light]: this method must be removed after trie-based proofs are landed.
-fn state_from_execution_proof(proof: Vec<Vec<u8>>) -> InMemoryStateBackend {
-	let mut changes = Vec::new();
-	let mut proof_iter = proof.into_iter();
-	loop {
-		let key = proof_iter.next();
-		let value = proof_iter.next();
-		if let (Some(key), Some(value)) = (key, value) {
-			changes.push((key, Some(value)));
-		} else {
-			break;
-		}
-	}
-
-	InMemoryStateBackend::default().update(changes)
-}
@@ -16,19 +16,23 @@
 
 //! Substrate Client
 
+use std::collections::{BTreeSet, HashSet};
 use std::sync::Arc;
 use futures::sync::mpsc;
 use parking_lot::{Mutex, RwLock};
 use primitives::AuthorityId;
 use runtime_primitives::{bft::Justification, generic::BlockId};
-use runtime_primitives::traits::{Block as BlockT, Header as HeaderT, Zero, One};
-use primitives::storage::{StorageKey, StorageData};
+use runtime_primitives::version::RuntimeVersion;
+use runtime_primitives::ApplyOutcome;
+use runtime_primitives::traits::{Block as BlockT, Header as HeaderT, Digest as DigestT, Zero, One};
+use primitives::storage::{StorageKey, StorageData, StorageChangeSet};
 use codec::{Slicable};
-use state_machine::{self, Ext, OverlayedChanges, Backend as StateBackend, CodeExecutor};
+use state_machine::{self, Ext, OverlayedChanges, Backend as StateBackend, CodeExecutor, ExecutionStrategy};
 
 use backend::{self, BlockImportOperation};
 use blockchain::{self, Info as ChainInfo, Backend as ChainBackend};
-use call_executor::{CallExecutor, LocalCallExecutor};
+use call_executor::{CallExecutor, CallResult, LocalCallExecutor};
+use notifications::{StorageNotifications, StorageEventStream};
 use {error, in_mem, block_builder, runtime_io, bft};
 
 /// Type that implements `futures::Stream` of block import events.
@@ -40,10 +44,33 @@ pub trait GenesisBuilder<B: BlockT> {
 	fn build(self) -> (B::Header, Vec<(Vec<u8>, Vec<u8>)>);
 }
 
+/// Execution strategies to use for the different contexts in which the client calls into the runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExecutionStrategies {
+	/// Strategy used when executing blocks that are being imported.
+	pub importing: ExecutionStrategy,
+	/// Strategy used when building new blocks.
+	pub block_construction: ExecutionStrategy,
+	/// Strategy used for all other calls, such as RPC queries.
+	pub other: ExecutionStrategy,
+}
+
+impl Default for ExecutionStrategies {
+	fn default() -> Self {
+		ExecutionStrategies {
+			importing: ExecutionStrategy::NativeWhenPossible,
+			block_construction: ExecutionStrategy::NativeWhenPossible,
+			other: ExecutionStrategy::NativeWhenPossible,
+		}
+	}
+}
+
 /// Polkadot Client
 pub struct Client<B, E, Block> where Block: BlockT {
 	backend: Arc<B>,
 	executor: E,
+	execution_strategies: ExecutionStrategies,
+	storage_notifications: Mutex<StorageNotifications<Block>>,
 	import_notification_sinks: Mutex<Vec<mpsc::UnboundedSender<BlockImportNotification<Block>>>>,
 	import_lock: Mutex<()>,
 	importing_block: RwLock<Option<Block::Hash>>, // holds the block hash currently being imported. TODO: replace this with block queue
@@ -53,6 +80,11 @@ pub struct Client<B, E, Block> where Block: BlockT {
 pub trait BlockchainEvents<Block: BlockT> {
 	/// Get block import event stream.
 	fn import_notification_stream(&self) -> mpsc::UnboundedReceiver<BlockImportNotification<Block>>;
+
+	/// Get storage changes event stream.
+	///
+	/// Passing `None` as `filter_keys` subscribes to all storage changes.
+	fn storage_changes_notification_stream(&self, filter_keys: Option<&[StorageKey]>) -> error::Result<StorageEventStream<Block::Hash>>;
 }
 
 /// Chain head information.
@@ -157,7 +189,7 @@ pub fn new_in_mem<E, F, Block>(
 {
 	let backend = Arc::new(in_mem::Backend::new());
 	let executor = LocalCallExecutor::new(backend.clone(), executor);
-	Client::new(backend, executor, genesis_builder)
+	Client::new(backend, executor, genesis_builder, Default::default())
 }
 
 impl<B, E, Block: BlockT> Client<B, E, Block> where
@@ -171,6 +203,7 @@ impl<B, E, Block: BlockT> Client<B, E, Block> where
 		backend: Arc<B>,
 		executor: E,
 		genesis_builder: F,
+		execution_strategies: ExecutionStrategies,
 	) -> error::Result<Self>
 		where
 			F: GenesisBuilder<Block>
@@ -186,6 +219,8 @@ impl<B, E, Block: BlockT> Client<B, E, Block> where
 		Ok(Client {
 			backend,
 			executor,
+			execution_strategies,
+			storage_notifications: Default::default(),
 			import_notification_sinks: Mutex::new(Vec::new()),
 			import_lock: Mutex::new(()),
 			importing_block: RwLock::new(None),
@@ -210,6 +245,135 @@ impl<B, E, Block: BlockT> Client<B, E, Block> where
 			.to_vec()))
 	}
 
+	/// Get changes of the given storage keys in the canonical blocks from `from` to `to`,
+	/// inclusive. Fails if either block is not in the canonical chain.
+	///
+	/// Only the blocks changing at least one of the keys are returned, together with
+	/// the values of the changed keys at that block. Blocks are looked up in the changed
+	/// keys index, so state is only read for the returned blocks.
+	pub fn query_storage(
+		&self,
+		keys: &[StorageKey],
+		from: &BlockId<Block>,
+		to: &BlockId<Block>,
+	) -> error::Result<Vec<StorageChangeSet<Block::Hash>>> {
+		// per-block data may be kept by number, so only canonical blocks can be told apart.
+		let canonical_number = |id: &BlockId<Block>| -> error::Result<<<Block as BlockT>::Header as HeaderT>::Number> {
+			let header = self.backend.blockchain().header(*id)?
+				.ok_or_else(|| error::ErrorKind::UnknownBlock(format!("{}", id)))?;
+			let hash = header.hash();
+			let is_requested = match *id {
+				BlockId::Hash(ref requested) => *requested == hash,
+				BlockId::Number(_) => true,
+			};
+			if !is_requested || self.backend.blockchain().hash(*header.number())? != Some(hash) {
+				return Err(error::ErrorKind::InvalidBlockRange(format!("{} is not in the canonical chain", id)).into());
+			}
+			Ok(*header.number())
+		};
+		let from_number = canonical_number(from)?;
+		let to_number = canonical_number(to)?;
+		if from_number > to_number {
+			return Err(error::ErrorKind::InvalidBlockRange(format!("{} is after {}", from, to)).into());
+		}
+		let keys = keys.iter().map(|key| &key.0).collect::<HashSet<_>>();
+
+		let mut result = Vec::new();
+		let mut number = from_number;
+		loop {
+			let id = BlockId::Number(number);
+			let changed_keys = self.backend.blockchain().changed_keys(id)?
+				.ok_or_else(|| error::ErrorKind::ChangesNotIndexed(format!("{}", id)))?;
+			let changed_keys = changed_keys.into_iter()
+				.filter(|key| keys.contains(key))
+				.collect::<Vec<_>>();
+
+			if !changed_keys.is_empty() {
+				let hash = self.backend.blockchain().hash(number)?
+					.ok_or_else(|| error::ErrorKind::UnknownBlock(format!("{}", id)))?;
+				let state = self.state_at(&id)?;
+				let mut changes = Vec::with_capacity(changed_keys.len());
+				for key in changed_keys {
+					let value = state.storage(&key)?.map(StorageData);
+					changes.push((StorageKey(key), value));
+				}
+				result.push(StorageChangeSet {
+					block: hash,
+					changes,
+				});
+			}
+
+			if number == to_number {
+				break;
+			}
+			number = number + One::one();
+		}
+
+		Ok(result)
+	}
+
+	/// Get the canonical blocks `first..=last` in which the given storage key was changed,
+	/// along with the index of each extrinsic which changed it. Read from the changes tries
+	/// the blocks commit to in their digests.
+	pub fn key_changes(
+		&self,
+		first: &BlockId<Block>,
+		last: &BlockId<Block>,
+		key: &StorageKey,
+	) -> error::Result<Vec<(<<Block as BlockT>::Header as HeaderT>::Number, u32)>> {
+		self.key_changes_proof(first, last, key).map(|(changes, _)| changes)
+	}
+
+	/// Same as `key_changes`, along with the proof of all changes trie reads. The proof can be
+	/// checked against the headers of the blocks only.
+	pub fn key_changes_proof(
+		&self,
+		first: &BlockId<Block>,
+		last: &BlockId<Block>,
+		key: &StorageKey,
+	) -> error::Result<(Vec<(<<Block as BlockT>::Header as HeaderT>::Number, u32)>, Vec<Vec<u8>>)> {
+		let storage = self.backend.changes_trie_storage()
+			.ok_or_else(|| error::ErrorKind::NotAvailableOnLightClient)?;
+		let number_of = |id: &BlockId<Block>| -> error::Result<_> {
+			self.backend.blockchain().header(*id)?
+				.map(|header| header.number().clone())
+				.ok_or_else(|| error::ErrorKind::UnknownBlock(format!("{}", id)).into())
+		};
+		let mut number = number_of(first)?;
+		let last = number_of(last)?;
+
+		let mut changes = Vec::new();
+		let mut proof = BTreeSet::new();
+		while number <= last {
+			let id = BlockId::Number(number);
+			let root = self.backend.blockchain().header(id)?
+				.ok_or_else(|| error::ErrorKind::UnknownBlock(format!("{}", id)))?
+				.digest().changes_trie_root()
+				.ok_or_else(|| error::ErrorKind::ChangesTrieNotFound(format!("{}", id)))?;
+
+			let (extrinsics, block_proof) = state_machine::prove_key_changes(storage.clone(), root, &key.0)?;
+			changes.extend(extrinsics.unwrap_or_default().into_iter().map(|index| (number, index)));
+			proof.extend(block_proof);
+
+			number = number + One::one();
+		}
+
+		Ok((changes, proof.into_iter().collect()))
+	}
+
+	/// Get storage keys starting with the given prefix at a given block, in lexicographic order.
+	pub fn storage_keys(&self, id: &BlockId<Block>, prefix: &StorageKey) -> error::Result<Vec<StorageKey>> {
+		let keys = self.state_at(id)?.keys(&prefix.0)?;
+		Ok(keys.into_iter().map(StorageKey).collect())
+	}
+
+	/// Get at most `count` storage keys starting with the given prefix at a given block,
+	/// in lexicographic order, beginning after `start_key` if it is given.
+	pub fn storage_keys_paged(&self, id: &BlockId<Block>, prefix: &StorageKey, start_key: Option<&StorageKey>, count: usize) -> error::Result<Vec<StorageKey>> {
+		let keys = self.state_at(id)?.keys_paged(&prefix.0, start_key.map(|key| &key.0[..]), count)?;
+		Ok(keys.into_iter().map(StorageKey).collect())
+	}
+
 	/// Get the code at a given block.
 	pub fn code_at(&self, id: &BlockId<Block>) -> error::Result<Vec<u8>> {
 		self.storage(id, &StorageKey(b":code".to_vec())).map(|data| data.0)
@@ -217,27 +381,55 @@ impl<B, E, Block: BlockT> Client<B, E, Block> where
 
 	/// Get the set of authorities at a given block.
 	pub fn authorities_at(&self, id: &BlockId<Block>) -> error::Result<Vec<AuthorityId>> {
-		self.executor.call(id, "authorities",&[])
+		self.call(id, "authorities", &[])
 			.and_then(|r| Vec::<AuthorityId>::decode(&mut &r.return_data[..])
 				.ok_or(error::ErrorKind::AuthLenInvalid.into()))
 	}
 
+	/// Get the version of the runtime at a given block.
+	///
+	/// The version is read from the on-chain wasm, so it reflects the runtime actually in force
+	/// rather than the one compiled into the node. Wasm runtimes which don't export their version
+	/// are only identified when their code is the native equivalent, whose version is reported.
+	pub fn runtime_version_at(&self, id: &BlockId<Block>) -> error::Result<RuntimeVersion> {
+		self.executor.call(id, "version", &[], ExecutionStrategy::AlwaysWasm)
+			.or_else(|_| self.executor.call(id, "version", &[], ExecutionStrategy::NativeWhenPossible))
+			.and_then(|r| RuntimeVersion::decode(&mut &r.return_data[..])
+				.ok_or(error::ErrorKind::InvalidRuntimeVersion.into()))
+	}
+
+	/// Get the JSON metadata of the runtime at a given block.
+	pub fn json_metadata(&self, id: &BlockId<Block>) -> error::Result<String> {
+		self.call(id, "json_metadata", &[])
+			.and_then(|r| Vec::<u8>::decode(&mut &r.return_data[..])
+				.and_then(|metadata| String::from_utf8(metadata).ok())
+				.ok_or(error::ErrorKind::InvalidMetadata.into()))
+	}
+
 	/// Get call executor reference.
 	pub fn executor(&self) -> &E {
 		&self.executor
 	}
 
+	/// Get the execution strategies used by the client.
+	pub fn execution_strategies(&self) -> &ExecutionStrategies {
+		&self.execution_strategies
+	}
+
+	/// Execute a call to the runtime on top of state in a block of given hash, using the
+	/// strategy configured for calls outside of block import and construction.
+	///
+	/// No changes are made.
+	pub fn call(&self, id: &BlockId<Block>, method: &str, call_data: &[u8]) -> error::Result<CallResult> {
+		self.executor.call(id, method, call_data, self.execution_strategies.other)
+	}
+
 	/// Execute a call to a contract on top of state in a block of given hash
 	/// AND returning execution proof.
 	///
 	/// No changes are made.
 	pub fn execution_proof(&self, id: &BlockId<Block>, method: &str, call_data: &[u8]) -> error::Result<(Vec<u8>, Vec<Vec<u8>>)> {
-		use call_executor::state_to_execution_proof;
-
-		let result = self.executor.call(id, method, call_data);
-		let result = result?.return_data;
-		let proof = self.backend.state_at(*id).map(|state| state_to_execution_proof(&state))?;
-		Ok((result, proof))
+		self.state_at(id).and_then(|state| self.executor.prove_at_state(state, &mut Default::default(), method, call_data))
 	}
 
 	/// Set up the native execution environment to call into a native runtime code.
@@ -267,15 +459,34 @@ impl<B, E, Block: BlockT> Client<B, E, Block> where
 		block_builder::BlockBuilder::at_block(parent, &self)
 	}
 
+	/// Apply an encoded extrinsic on top of the state of `at`, as if it was the first extrinsic
+	/// of a new block. No changes are committed.
+	///
+	/// Returns the outcome of dispatching the extrinsic, or an execution error if it can
+	/// not be applied at all.
+	pub fn dry_run(&self, at: &BlockId<Block>, extrinsic: &[u8]) -> error::Result<ApplyOutcome> where E: Clone {
+		self.new_block_at(at)?.dry_run(extrinsic)
+	}
+
 	/// Check a header's justification.
 	pub fn check_justification(
 		&self,
 		header: <Block as BlockT>::Header,
 		justification: ::bft::UncheckedJustification<Block::Hash>,
+	) -> error::Result<JustifiedHeader<Block>> {
+		let authorities = self.authorities_at(&BlockId::Hash(header.parent_hash().clone()))?;
+		self.check_justification_with_authorities(&authorities, header, justification)
+	}
+
+	/// Check a header's justification against the given authorities of its parent.
+	pub fn check_justification_with_authorities(
+		&self,
+		authorities: &[AuthorityId],
+		header: <Block as BlockT>::Header,
+		justification: ::bft::UncheckedJustification<Block::Hash>,
 	) -> error::Result<JustifiedHeader<Block>> {
 		let parent_hash = header.parent_hash().clone();
-		let authorities = self.authorities_at(&BlockId::Hash(parent_hash))?;
-		let just = ::bft::check_justification::<Block>(&authorities[..], parent_hash, justification)
+		let just = ::bft::check_justification::<Block>(authorities, parent_hash, justification)
 			.map_err(|_|
 				error::ErrorKind::BadJustification(
 					format!("{}", header.hash())
@@ -325,15 +536,17 @@ impl<B, E, Block: BlockT> Client<B, E, Block> where
 		let mut transaction = self.backend.begin_operation(BlockId::Hash(parent_hash))?;
 		let storage_update = match transaction.state()? {
 			Some(transaction_state) => {
-				let mut overlay = Default::default();
+				let mut overlay = OverlayedChanges::default();
 				let (_, storage_update) = self.executor.call_at_state(
 					transaction_state,
 					&mut overlay,
 					"execute_block",
-					&<Block as BlockT>::new(header.clone(), body.clone().unwrap_or_default()).encode()
+					&<Block as BlockT>::new(header.clone(), body.clone().unwrap_or_default()).encode(),
+					self.execution_strategies.importing,
 				)?;
 
-				Some(storage_update)
+				let changes_trie = state_machine::changes_trie_root(transaction_state, &overlay)?;
+				Some((storage_update, changes_trie, overlay.drain().collect::<Vec<_>>()))
 			},
 			None => None,
 		};
@@ -341,10 +554,29 @@ impl<B, E, Block: BlockT> Client<B, E, Block> where
 		let is_new_best = header.number() == &(self.backend.blockchain().info()?.best_number + One::one());
 		trace!("Imported {}, (#{}), best={}, origin={:?}", hash, header.number(), is_new_best, origin);
 		transaction.set_block_data(header.clone(), body, Some(justification.uncheck().into()), is_new_best)?;
-		if let Some(storage_update) = storage_update {
-			transaction.update_storage(storage_update)?;
-		}
+		let storage_changes = match storage_update {
+			Some((storage_update, changes_trie, storage_changes)) => {
+				transaction.update_storage(storage_update)?;
+				if let Some((_, changes_trie_update)) = changes_trie {
+					transaction.update_changes_trie(changes_trie_update)?;
+				}
+				let mut changed_keys = storage_changes.iter().map(|&(ref key, _)| key.clone()).collect::<Vec<_>>();
+				changed_keys.sort();
+				transaction.set_changed_keys(changed_keys)?;
+				Some(storage_changes)
+			},
+			None => None,
+		};
 		self.backend.commit_operation(transaction)?;
+
+		if is_new_best {
+			if let Some(storage_changes) = storage_changes {
+				// TODO: re-orgs are not handled; changes of retracted blocks are not re-emitted.
+				self.storage_notifications.lock()
+					.trigger(&hash, &storage_changes);
+			}
+		}
+
 		if origin == BlockOrigin::NetworkBroadcast || origin == BlockOrigin::Own || origin == BlockOrigin::ConsensusBroadcast {
 			let notification = BlockImportNotification::<Block> {
 				hash: hash,
@@ -358,6 +590,12 @@ impl<B, E, Block: BlockT> Client<B, E, Block> where
 		Ok(ImportResult::Queued)
 	}
 
+	/// Revert the chain by up to `n` best blocks. Returns the number of blocks actually reverted.
+	pub fn revert(&self, n: <<Block as BlockT>::Header as HeaderT>::Number) -> error::Result<<<Block as BlockT>::Header as HeaderT>::Number> {
+		let _import_lock = self.import_lock.lock();
+		self.backend.revert(n)
+	}
+
 	/// Get blockchain info.
 	pub fn info(&self) -> error::Result<ClientInfo<Block>> {
 		let info = self.backend.blockchain().info().map_err(|e| error::Error::from_blockchain(Box::new(e)))?;
@@ -471,6 +709,11 @@ impl<B, E, Block> BlockchainEvents<Block> for Client<B, E, Block>
 		self.import_notification_sinks.lock().push(sink);
 		stream
 	}
+
+	/// Get storage changes event stream.
+	fn storage_changes_notification_stream(&self, filter_keys: Option<&[StorageKey]>) -> error::Result<StorageEventStream<Block::Hash>> {
+		Ok(self.storage_notifications.lock().listen(filter_keys))
+	}
 }
 
 impl<B, E, Block> ChainHead<Block> for Client<B, E, Block>
@@ -516,6 +759,90 @@ mod tests {
 		]);
 	}
 
+	#[test]
+	fn runtime_version_call_works() {
+		let client = test_client::new();
+
+		assert_eq!(client.runtime_version_at(&BlockId::Number(0)).unwrap(), test_runtime::VERSION);
+	}
+
+	#[test]
+	fn execution_proof_is_generated_and_checked() {
+		let client = test_client::new();
+		let block = BlockId::Number(0);
+		let state_root: [u8; 32] = client.header(&block).unwrap().unwrap().state_root.into();
+
+		let (remote_result, remote_proof) = client.execution_proof(&block, "authorities", &[]).unwrap();
+		assert!(!remote_proof.is_empty());
+
+		let (local_result, _) = ::state_machine::execution_proof_check(
+			state_root,
+			remote_proof,
+			&mut Default::default(),
+			&test_client::NativeExecutor::new(),
+			"authorities",
+			&[],
+		).unwrap();
+		assert_eq!(local_result, remote_result);
+	}
+
+	#[test]
+	fn key_changes_proof_is_generated_and_checked() {
+		use std::collections::HashMap;
+		use runtime_primitives::traits::DigestItem;
+		use primitives::storage::well_known_keys::{CHANGES_TRIE_CONFIG, EXTRINSIC_INDEX};
+		use state_machine::Externalities;
+		use state_machine::backend::InMemory;
+		use test_client::client::backend::{Backend as BackendT, BlockImportOperation as OperationT};
+		use test_client::client::light::{self, FetchChecker, RemoteChangesRequest};
+
+		let client = test_client::new();
+		let light_backend = light::new_light_backend();
+		let mut parent_hash = client.block_hash(0).unwrap().unwrap();
+		for (number, extrinsics) in vec![(1u64, vec![1u32]), (2, vec![]), (3, vec![0, 2])] {
+			let mut storage = HashMap::new();
+			storage.insert(CHANGES_TRIE_CONFIG.to_vec(), vec![1]);
+			let state = InMemory::from(storage);
+			let mut overlay = OverlayedChanges::default();
+			{
+				let mut ext = Ext::new(&mut overlay, &state);
+				ext.set_storage(b"other".to_vec(), vec![number as u8]);
+				for index in extrinsics {
+					ext.set_storage(EXTRINSIC_INDEX.to_vec(), index.encode());
+					ext.set_storage(b"key".to_vec(), vec![index as u8]);
+				}
+			}
+			let (root, changes_trie) = state_machine::changes_trie_root(&state, &overlay).unwrap().unwrap();
+
+			let mut header = client.header(&BlockId::Number(0)).unwrap().unwrap();
+			header.number = number;
+			header.parent_hash = parent_hash;
+			header.digest.logs = vec![DigestItem::changes_trie_root(root).unwrap()];
+			parent_hash = header.hash();
+
+			let mut op = client.backend().begin_operation(BlockId::Number(number - 1)).unwrap();
+			op.set_block_data(header.clone(), None, None, true).unwrap();
+			op.update_changes_trie(changes_trie).unwrap();
+			client.backend().commit_operation(op).unwrap();
+
+			let mut op = light_backend.begin_operation(BlockId::Number(number - 1)).unwrap();
+			op.set_block_data(header, None, None, true).unwrap();
+			light_backend.commit_operation(op).unwrap();
+		}
+
+		let key = StorageKey(b"key".to_vec());
+		let (changes, proof) = client.key_changes_proof(&BlockId::Number(1), &BlockId::Number(3), &key).unwrap();
+		assert_eq!(changes, vec![(1, 1), (3, 0), (3, 2)]);
+		assert_eq!(client.key_changes(&BlockId::Number(1), &BlockId::Number(3), &key).unwrap(), changes);
+		// genesis does not commit to a changes trie.
+		assert!(client.key_changes(&BlockId::Number(0), &BlockId::Number(3), &key).is_err());
+
+		let checker = light::new_fetch_checker(light_backend, test_client::NativeExecutor::new());
+		let request = RemoteChangesRequest { first_block: 1, last_block: 3, key: key.0.clone() };
+		assert_eq!(checker.check_changes_proof(&request, (changes.clone(), proof.clone())).unwrap(), changes);
+		assert!(checker.check_changes_proof(&request, (vec![(2, 0)], proof)).is_err());
+	}
+
 	#[test]
 	fn block_builder_works_with_no_transactions() {
 		let client = test_client::new();
@@ -552,4 +879,131 @@ mod tests {
 		assert_eq!(client.using_environment(|| test_runtime::system::balance_of(Keyring::Alice.to_raw_public().into())).unwrap(), 958);
 		assert_eq!(client.using_environment(|| test_runtime::system::balance_of(Keyring::Ferdie.to_raw_public().into())).unwrap(), 42);
 	}
+
+	#[test]
+	fn revert_restores_parent_as_best_block() {
+		let client = test_client::new();
+
+		let mut builder = client.new_block().unwrap();
+		builder.push(sign_tx(Transfer {
+			from: Keyring::Alice.to_raw_public().into(),
+			to: Keyring::Ferdie.to_raw_public().into(),
+			amount: 42,
+			nonce: 0,
+		})).unwrap();
+		let block = builder.bake().unwrap();
+		client.justify_and_import(BlockOrigin::Own, block.clone()).unwrap();
+		assert_eq!(client.info().unwrap().chain.best_number, 1);
+
+		assert_eq!(client.revert(10).unwrap(), 1);
+		assert_eq!(client.info().unwrap().chain.best_number, 0);
+		assert_eq!(client.block_hash(1).unwrap(), None);
+		assert_eq!(client.using_environment(|| test_runtime::system::balance_of(Keyring::Alice.to_raw_public().into())).unwrap(), 1000);
+
+		// the reverted block can be imported again.
+		client.justify_and_import(BlockOrigin::Own, block).unwrap();
+		assert_eq!(client.info().unwrap().chain.best_number, 1);
+		assert_eq!(client.using_environment(|| test_runtime::system::balance_of(Keyring::Alice.to_raw_public().into())).unwrap(), 958);
+	}
+
+	#[test]
+	fn dry_run_does_not_commit_changes() {
+		let client = test_client::new();
+		let transfer = |amount| sign_tx(Transfer {
+			from: Keyring::Alice.to_raw_public().into(),
+			to: Keyring::Ferdie.to_raw_public().into(),
+			amount,
+			nonce: 0,
+		}).encode();
+
+		assert_eq!(client.dry_run(&BlockId::Number(0), &transfer(42)).unwrap(), ApplyOutcome::Success);
+		// the nonce is still valid since nothing was committed.
+		assert_eq!(client.dry_run(&BlockId::Number(0), &transfer(42)).unwrap(), ApplyOutcome::Success);
+		assert!(client.dry_run(&BlockId::Number(0), &transfer(1001)).is_err());
+
+		assert_eq!(client.info().unwrap().chain.best_number, 0);
+		assert_eq!(client.using_environment(|| test_runtime::system::balance_of(Keyring::Alice.to_raw_public().into())).unwrap(), 1000);
+	}
+
+	#[test]
+	fn query_storage_returns_only_blocks_with_changes() {
+		use codec::KeyedVec;
+
+		let client = test_client::new();
+		let alice = test_runtime::AccountId::from(Keyring::Alice.to_raw_public());
+		let balance_key = StorageKey(runtime_io::twox_128(&alice.to_keyed_vec(b"balance:")).to_vec());
+
+		let mut builder = client.new_block().unwrap();
+		builder.push(sign_tx(Transfer {
+			from: Keyring::Alice.to_raw_public().into(),
+			to: Keyring::Ferdie.to_raw_public().into(),
+			amount: 42,
+			nonce: 0,
+		})).unwrap();
+		client.justify_and_import(BlockOrigin::Own, builder.bake().unwrap()).unwrap();
+
+		let builder = client.new_block().unwrap();
+		client.justify_and_import(BlockOrigin::Own, builder.bake().unwrap()).unwrap();
+
+		let changes = client.query_storage(&[balance_key.clone()], &BlockId::Number(0), &BlockId::Number(2)).unwrap();
+		assert_eq!(changes, vec![
+			StorageChangeSet {
+				block: client.block_hash(0).unwrap().unwrap(),
+				changes: vec![(balance_key.clone(), Some(StorageData(1000u64.encode())))],
+			},
+			StorageChangeSet {
+				block: client.block_hash(1).unwrap().unwrap(),
+				changes: vec![(balance_key.clone(), Some(StorageData(958u64.encode())))],
+			},
+		]);
+
+		assert_eq!(client.query_storage(&[balance_key.clone()], &BlockId::Number(2), &BlockId::Number(2)).unwrap(), vec![]);
+		assert!(client.query_storage(&[balance_key], &BlockId::Number(2), &BlockId::Number(1)).is_err());
+	}
+
+	#[test]
+	fn query_storage_rejects_non_canonical_blocks() {
+		use codec::KeyedVec;
+
+		let client = test_client::new();
+		let alice = test_runtime::AccountId::from(Keyring::Alice.to_raw_public());
+		let balance_key = StorageKey(runtime_io::twox_128(&alice.to_keyed_vec(b"balance:")).to_vec());
+		let transfer = |amount| sign_tx(Transfer {
+			from: Keyring::Alice.to_raw_public().into(),
+			to: Keyring::Ferdie.to_raw_public().into(),
+			amount,
+			nonce: 0,
+		});
+
+		// two competing children of genesis; the first one imported stays the best block.
+		let mut builder = client.new_block().unwrap();
+		builder.push(transfer(42)).unwrap();
+		let best = builder.bake().unwrap();
+		let best_hash = best.header.hash();
+		client.justify_and_import(BlockOrigin::Own, best).unwrap();
+
+		let mut builder = client.new_block_at(&BlockId::Number(0)).unwrap();
+		builder.push(transfer(10)).unwrap();
+		let fork = builder.bake().unwrap();
+		let fork_hash = fork.header.hash();
+		client.justify_and_import(BlockOrigin::Own, fork).unwrap();
+		assert_eq!(client.block_hash(1).unwrap(), Some(best_hash));
+
+		let changes = client.query_storage(&[balance_key.clone()], &BlockId::Number(0), &BlockId::Hash(best_hash)).unwrap();
+		assert_eq!(changes, vec![
+			StorageChangeSet {
+				block: client.block_hash(0).unwrap().unwrap(),
+				changes: vec![(balance_key.clone(), Some(StorageData(1000u64.encode())))],
+			},
+			StorageChangeSet {
+				block: best_hash,
+				changes: vec![(balance_key.clone(), Some(StorageData(958u64.encode())))],
+			},
+		]);
+
+		match client.query_storage(&[balance_key], &BlockId::Number(0), &BlockId::Hash(fork_hash)) {
+			Err(error::Error(error::ErrorKind::InvalidBlockRange(_), _)) => {},
+			other => panic!("expected the fork to be rejected, got {:?}", other),
+		}
+	}
 }
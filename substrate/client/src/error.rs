@@ -28,6 +28,12 @@ error_chain! {
 			display("Backend error: {}", s),
 		}
 
+		/// Database was written by a newer version of the client.
+		DatabaseVersionTooNew(version: u32, supported: u32) {
+			description("database version is too new"),
+			display("Database version {} is newer than the latest version {} supported by this client", version, supported),
+		}
+
 		/// Unknown block.
 		UnknownBlock(h: String) {
 			description("unknown block"),
@@ -46,6 +52,24 @@ error_chain! {
 			display("Blockchain: {}", e),
 		}
 
+		/// Storage changes of the block are not indexed.
+		ChangesNotIndexed(h: String) {
+			description("storage changes are not indexed"),
+			display("Storage changes are not indexed for block: {}", &*h),
+		}
+
+		/// The block range is invalid.
+		InvalidBlockRange(h: String) {
+			description("invalid block range"),
+			display("Invalid block range: {}", &*h),
+		}
+
+		/// The block does not commit to a changes trie.
+		ChangesTrieNotFound(h: String) {
+			description("changes trie not found"),
+			display("Block does not commit to a changes trie: {}", &*h),
+		}
+
 		/// Attempt to read storage yet nothing set for that key.
 		NoValueForKey(key: Vec<u8>) {
 			description("storage doesn't contain key"),
@@ -76,6 +100,24 @@ error_chain! {
 			display("Current state of blockchain has invalid authority value for index {}", i),
 		}
 
+		/// Invalid outcome of applying an extrinsic.
+		InvalidApplyOutcome {
+			description("invalid apply outcome"),
+			display("Runtime returned invalid outcome of applying an extrinsic"),
+		}
+
+		/// Invalid runtime metadata.
+		InvalidMetadata {
+			description("invalid runtime metadata"),
+			display("Runtime returned invalid metadata"),
+		}
+
+		/// Invalid runtime version.
+		InvalidRuntimeVersion {
+			description("invalid runtime version"),
+			display("Runtime returned invalid version"),
+		}
+
 		/// Bad justification for header.
 		BadJustification(h: String) {
 			description("bad justification for header"),
@@ -94,6 +136,12 @@ error_chain! {
 			display("Remote node has responded with invalid execution proof"),
 		}
 
+		/// Invalid remote proof.
+		InvalidChangesProof {
+			description("invalid changes proof"),
+			display("Remote node has responded with invalid changes proof"),
+		}
+
 		/// Invalid remote proof.
 		RemoteFetchCancelled {
 			description("remote fetch cancelled"),
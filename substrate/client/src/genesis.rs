@@ -45,7 +45,7 @@ mod tests {
 	use codec::{Slicable, Joiner};
 	use keyring::Keyring;
 	use executor::WasmExecutor;
-	use state_machine::{execute, OverlayedChanges};
+	use state_machine::{execute, OverlayedChanges, ExecutionStrategy};
 	use state_machine::backend::InMemory;
 	use test_client;
 	use test_client::runtime::genesismap::{GenesisConfig, additional_storage_with_genesis};
@@ -83,6 +83,7 @@ mod tests {
 			&Executor::new(),
 			"initialise_block",
 			&header.encode(),
+			ExecutionStrategy::NativeWhenPossible,
 		).unwrap();
 
 		for tx in transactions.iter() {
@@ -92,6 +93,7 @@ mod tests {
 				&Executor::new(),
 				"apply_extrinsic",
 				&tx.encode(),
+				ExecutionStrategy::NativeWhenPossible,
 			).unwrap();
 		}
 
@@ -100,7 +102,8 @@ mod tests {
 			&mut overlay,
 			&Executor::new(),
 			"finalise_block",
-			&[]
+			&[],
+			ExecutionStrategy::NativeWhenPossible,
 		).unwrap();
 		header = Header::decode(&mut &ret_data[..]).unwrap();
 		println!("root after: {:?}", header.extrinsics_root);
@@ -141,7 +144,8 @@ mod tests {
 			&mut overlay,
 			&Executor::new(),
 			"execute_block",
-			&b1data
+			&b1data,
+			ExecutionStrategy::NativeWhenPossible,
 		).unwrap();
 	}
 
@@ -163,7 +167,31 @@ mod tests {
 			&mut overlay,
 			&WasmExecutor,
 			"execute_block",
-			&b1data
+			&b1data,
+			ExecutionStrategy::AlwaysWasm,
+		).unwrap();
+	}
+
+	#[test]
+	fn construct_genesis_should_work_with_native_and_wasm_agreeing() {
+		let mut storage = GenesisConfig::new_simple(
+			vec![Keyring::One.to_raw_public(), Keyring::Two.to_raw_public()], 1000
+		).genesis_map();
+		let block = construct_genesis_block::<Block>(&storage);
+		let genesis_hash = block.header.hash();
+		storage.extend(additional_storage_with_genesis(&block).into_iter());
+
+		let backend = InMemory::from(storage);
+		let (b1data, _b1hash) = block1(genesis_hash, &backend);
+
+		let mut overlay = OverlayedChanges::default();
+		let _ = execute(
+			&backend,
+			&mut overlay,
+			&Executor::new(),
+			"execute_block",
+			&b1data,
+			ExecutionStrategy::Both,
 		).unwrap();
 	}
 
@@ -186,7 +214,8 @@ mod tests {
 			&mut overlay,
 			&Executor::new(),
 			"execute_block",
-			&b1data
+			&b1data,
+			ExecutionStrategy::NativeWhenPossible,
 		).unwrap();
 	}
 }
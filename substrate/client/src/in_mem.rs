@@ -17,14 +17,16 @@
 //! In memory client backend
 
 use std::collections::HashMap;
+use std::sync::Arc;
 use parking_lot::RwLock;
 use error;
 use backend;
 use runtime_primitives::generic::BlockId;
-use runtime_primitives::traits::{Block as BlockT, Header as HeaderT, Zero};
+use runtime_primitives::traits::{Block as BlockT, Header as HeaderT, Zero, One};
 use runtime_primitives::bft::Justification;
 use blockchain::{self, BlockStatus};
 use state_machine::backend::{Backend as StateBackend, InMemory};
+use state_machine::{ChangesTrieTransaction, InMemoryChangesTrieStorage, Storage as ChangesTrieStorage};
 
 struct PendingBlock<B: BlockT> {
 	block: StoredBlock<B>,
@@ -79,6 +81,7 @@ impl<B: BlockT> StoredBlock<B> {
 #[derive(Clone)]
 struct BlockchainStorage<Block: BlockT> {
 	blocks: HashMap<Block::Hash, StoredBlock<Block>>,
+	changed_keys: HashMap<Block::Hash, Vec<Vec<u8>>>,
 	hashes: HashMap<<<Block as BlockT>::Header as HeaderT>::Number, Block::Hash>,
 	best_hash: Block::Hash,
 	best_number: <<Block as BlockT>::Header as HeaderT>::Number,
@@ -113,6 +116,7 @@ impl<Block: BlockT> Blockchain<Block> {
 			storage: RwLock::new(
 				BlockchainStorage {
 					blocks: HashMap::new(),
+					changed_keys: HashMap::new(),
 					hashes: HashMap::new(),
 					best_hash: Default::default(),
 					best_number: Zero::zero(),
@@ -143,6 +147,31 @@ impl<Block: BlockT> Blockchain<Block> {
 		}
 	}
 
+	/// Record keys of the storage entries changed by the block.
+	pub fn set_changed_keys(&self, hash: Block::Hash, keys: Vec<Vec<u8>>) {
+		self.storage.write().changed_keys.insert(hash, keys);
+	}
+
+	/// Remove the best block, making its parent the best one. Returns the hash of the removed
+	/// block, or `None` if the best block is genesis.
+	pub fn revert_best(&self) -> Option<Block::Hash> {
+		let mut storage = self.storage.write();
+		if storage.best_number == Zero::zero() {
+			return None;
+		}
+
+		let hash = storage.best_hash;
+		let number = storage.best_number;
+		let parent_hash = storage.blocks.remove(&hash)
+			.map(|block| block.header().parent_hash().clone())
+			.expect("the best block is always stored; qed");
+		storage.changed_keys.remove(&hash);
+		storage.hashes.remove(&number);
+		storage.best_hash = parent_hash;
+		storage.best_number = number - One::one();
+		Some(hash)
+	}
+
 	/// Compare this blockchain with another in-mem blockchain
 	pub fn equals_to(&self, other: &Self) -> bool {
 		self.canon_equals_to(other) && self.storage.read().blocks == other.storage.read().blocks
@@ -179,6 +208,10 @@ impl<Block: BlockT> blockchain::Backend<Block> for Blockchain<Block> {
 		))
 	}
 
+	fn changed_keys(&self, id: BlockId<Block>) -> error::Result<Option<Vec<Vec<u8>>>> {
+		Ok(self.id(id).and_then(|hash| self.storage.read().changed_keys.get(&hash).cloned()))
+	}
+
 	fn info(&self) -> error::Result<blockchain::Info<Block>> {
 		let storage = self.storage.read();
 		Ok(blockchain::Info {
@@ -205,6 +238,8 @@ pub struct BlockImportOperation<Block: BlockT> {
 	pending_block: Option<PendingBlock<Block>>,
 	old_state: InMemory,
 	new_state: Option<InMemory>,
+	changed_keys: Option<Vec<Vec<u8>>>,
+	changes_trie_update: Option<ChangesTrieTransaction>,
 }
 
 impl<Block: BlockT> backend::BlockImportOperation<Block> for BlockImportOperation<Block> {
@@ -235,7 +270,21 @@ impl<Block: BlockT> backend::BlockImportOperation<Block> for BlockImportOperatio
 	}
 
 	fn reset_storage<I: Iterator<Item=(Vec<u8>, Vec<u8>)>>(&mut self, iter: I) -> error::Result<()> {
-		self.new_state = Some(InMemory::from(iter.collect::<HashMap<_, _>>()));
+		let storage = iter.collect::<HashMap<_, _>>();
+		let mut keys = storage.keys().cloned().collect::<Vec<_>>();
+		keys.sort();
+		self.changed_keys = Some(keys);
+		self.new_state = Some(InMemory::from(storage));
+		Ok(())
+	}
+
+	fn set_changed_keys(&mut self, keys: Vec<Vec<u8>>) -> error::Result<()> {
+		self.changed_keys = Some(keys);
+		Ok(())
+	}
+
+	fn update_changes_trie(&mut self, update: ChangesTrieTransaction) -> error::Result<()> {
+		self.changes_trie_update = Some(update);
 		Ok(())
 	}
 }
@@ -245,6 +294,7 @@ pub struct Backend<Block> where
 	Block: BlockT,
 {
 	states: RwLock<HashMap<Block::Hash, InMemory>>,
+	changes_trie_storage: Arc<InMemoryChangesTrieStorage>,
 	blockchain: Blockchain<Block>,
 }
 
@@ -255,6 +305,7 @@ impl<Block> Backend<Block> where
 	pub fn new() -> Backend<Block> {
 		Backend {
 			states: RwLock::new(HashMap::new()),
+			changes_trie_storage: Default::default(),
 			blockchain: Blockchain::new(),
 		}
 	}
@@ -277,6 +328,8 @@ impl<Block> backend::Backend<Block> for Backend<Block> where
 			pending_block: None,
 			old_state: state,
 			new_state: None,
+			changed_keys: None,
+			changes_trie_update: None,
 		})
 	}
 
@@ -288,6 +341,12 @@ impl<Block> backend::Backend<Block> for Backend<Block> where
 
 			self.states.write().insert(hash, operation.new_state.unwrap_or_else(|| old_state.clone()));
 			self.blockchain.insert(hash, header, justification, body, pending_block.is_best);
+			if let Some(changed_keys) = operation.changed_keys {
+				self.blockchain.set_changed_keys(hash, changed_keys);
+			}
+			if let Some(changes_trie_update) = operation.changes_trie_update {
+				self.changes_trie_storage.insert(changes_trie_update);
+			}
 		}
 		Ok(())
 	}
@@ -302,6 +361,26 @@ impl<Block> backend::Backend<Block> for Backend<Block> where
 			None => Err(error::ErrorKind::UnknownBlock(format!("{}", block)).into()),
 		}
 	}
+
+	fn changes_trie_storage(&self) -> Option<Arc<ChangesTrieStorage>> {
+		Some(self.changes_trie_storage.clone())
+	}
+
+	fn state_cache_stats(&self) -> Option<backend::CacheStats> {
+		None
+	}
+
+	fn revert(&self, n: <<Block as BlockT>::Header as HeaderT>::Number) -> error::Result<<<Block as BlockT>::Header as HeaderT>::Number> {
+		let mut reverted = Zero::zero();
+		while reverted < n {
+			match self.blockchain.revert_best() {
+				Some(hash) => { self.states.write().remove(&hash); },
+				None => break,
+			}
+			reverted = reverted + One::one();
+		}
+		Ok(reverted)
+	}
 }
 
 impl<Block: BlockT> backend::LocalBackend<Block> for Backend<Block> {}
@@ -48,14 +48,17 @@ pub mod block_builder;
 pub mod light;
 mod call_executor;
 mod client;
+mod notifications;
 
 pub use client::{
 	new_in_mem,
 	BlockStatus, BlockOrigin, BlockchainEventStream, BlockchainEvents,
 	Client, ClientInfo, ChainHead,
-	ImportResult, GenesisBuilder,
+	ImportResult, GenesisBuilder, JustifiedHeader, ExecutionStrategies,
 };
+pub use notifications::{StorageEventStream, StorageNotifications};
 pub use blockchain::Info as ChainInfo;
+pub use state_machine::ExecutionStrategy;
 pub use call_executor::{
 	CallResult, CallExecutor, LocalCallExecutor, RemoteCallExecutor,
 };
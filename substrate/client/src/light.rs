@@ -19,11 +19,12 @@
 
 use std::sync::Arc;
 use futures::future::IntoFuture;
-use state_machine::CodeExecutor;
+use state_machine::{self, CodeExecutor, ChangesTrieTransaction, Storage as ChangesTrieStorage};
+use state_machine::TrieBackend;
 use state_machine::backend::Backend as StateBackend;
 use runtime_primitives::generic::BlockId;
 use runtime_primitives::bft::Justification;
-use runtime_primitives::traits::{Block as BlockT, Header as HeaderT};
+use runtime_primitives::traits::{Block as BlockT, Header as HeaderT, Digest as DigestT, Zero, One};
 use blockchain::{self, BlockStatus};
 use backend;
 use call_executor::{CallResult, RemoteCallExecutor, check_execution_proof};
@@ -41,6 +42,16 @@ pub struct RemoteCallRequest<H> {
 	pub call_data: Vec<u8>,
 }
 
+/// Remote storage key changes request.
+pub struct RemoteChangesRequest<N> {
+	/// Number of the first block of the range.
+	pub first_block: N,
+	/// Number of the last block of the range.
+	pub last_block: N,
+	/// Storage key to look for.
+	pub key: Vec<u8>,
+}
+
 /// Light client data fetcher. Implementations of this trait must check if remote data
 /// is correct (see FetchedDataChecker) and return already checked data.
 pub trait Fetcher<B: BlockT>: Send + Sync {
@@ -55,6 +66,12 @@ pub trait Fetcher<B: BlockT>: Send + Sync {
 pub trait FetchChecker<B: BlockT>: Send + Sync {
 	/// Check remote method execution proof.
 	fn check_execution_proof(&self, request: &RemoteCallRequest<B::Hash>, remote_proof: (Vec<u8>, Vec<Vec<u8>>)) -> error::Result<CallResult>;
+	/// Check remote storage key changes proof against the changes trie roots of local headers.
+	fn check_changes_proof(
+		&self,
+		request: &RemoteChangesRequest<<B::Header as HeaderT>::Number>,
+		remote_proof: (Vec<(<B::Header as HeaderT>::Number, u32)>, Vec<Vec<u8>>),
+	) -> error::Result<Vec<(<B::Header as HeaderT>::Number, u32)>>;
 }
 
 /// Light client backend.
@@ -121,6 +138,22 @@ impl<B: BlockT> backend::Backend<B> for Backend<B> {
 			_block: self.blockchain.storage.id(block).ok_or(error::ErrorKind::UnknownBlock(format!("{:?}", block)))?,
 		})
 	}
+
+	fn changes_trie_storage(&self) -> Option<Arc<ChangesTrieStorage>> {
+		None
+	}
+
+	fn state_cache_stats(&self) -> Option<backend::CacheStats> {
+		None
+	}
+
+	fn revert(&self, n: <B::Header as HeaderT>::Number) -> error::Result<<B::Header as HeaderT>::Number> {
+		let mut reverted = Zero::zero();
+		while reverted < n && self.blockchain.storage.revert_best().is_some() {
+			reverted = reverted + One::one();
+		}
+		Ok(reverted)
+	}
 }
 
 impl<B: BlockT> backend::RemoteBackend<B> for Backend<B> {}
@@ -152,6 +185,16 @@ impl<B: BlockT> backend::BlockImportOperation<B> for BlockImportOperation<B> {
 		// we're not storing anything locally => ignore changes
 		Ok(())
 	}
+
+	fn set_changed_keys(&mut self, _keys: Vec<Vec<u8>>) -> error::Result<()> {
+		// we're not storing anything locally => ignore changes
+		Ok(())
+	}
+
+	fn update_changes_trie(&mut self, _update: ChangesTrieTransaction) -> error::Result<()> {
+		// we're not storing anything locally => ignore changes
+		Ok(())
+	}
 }
 
 impl<B: BlockT> blockchain::Backend<B> for Blockchain<B> {
@@ -168,6 +211,10 @@ impl<B: BlockT> blockchain::Backend<B> for Blockchain<B> {
 		self.storage.justification(id)
 	}
 
+	fn changed_keys(&self, _id: BlockId<B>) -> error::Result<Option<Vec<Vec<u8>>>> {
+		Err(error::ErrorKind::NotAvailableOnLightClient.into())
+	}
+
 	fn info(&self) -> error::Result<blockchain::Info<B>> {
 		self.storage.info()
 	}
@@ -190,26 +237,89 @@ impl<H: Clone> StateBackend for OnDemandState<H> {
 		Err(error::ErrorKind::NotAvailableOnLightClient.into())
 	}
 
+	fn child_storage(&self, _storage_key: &[u8], _key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+		// TODO [light]: fetch from remote node
+		Err(error::ErrorKind::NotAvailableOnLightClient.into())
+	}
+
 	fn storage_root<I>(&self, _delta: I) -> ([u8; 32], Self::Transaction)
 		where I: IntoIterator<Item=(Vec<u8>, Option<Vec<u8>>)>
 	{
 		([0; 32], ())
 	}
 
+	fn child_storage_root<I>(&self, _storage_key: &[u8], _delta: I) -> ([u8; 32], bool, Self::Transaction)
+		where I: IntoIterator<Item=(Vec<u8>, Option<Vec<u8>>)>
+	{
+		([0; 32], true, ())
+	}
+
 	fn pairs(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
 		// whole state is not available on light node
 		Vec::new()
 	}
+
+	fn keys(&self, _prefix: &[u8]) -> Result<Vec<Vec<u8>>, Self::Error> {
+		// whole state is not available on light node
+		Err(error::ErrorKind::NotAvailableOnLightClient.into())
+	}
+
+	fn keys_paged(&self, _prefix: &[u8], _start_key: Option<&[u8]>, _count: usize) -> Result<Vec<Vec<u8>>, Self::Error> {
+		// whole state is not available on light node
+		Err(error::ErrorKind::NotAvailableOnLightClient.into())
+	}
+
+	fn child_keys(&self, _storage_key: &[u8], _prefix: &[u8]) -> Result<Vec<Vec<u8>>, Self::Error> {
+		// whole state is not available on light node
+		Err(error::ErrorKind::NotAvailableOnLightClient.into())
+	}
+
+	fn try_into_trie_backend(self) -> Option<TrieBackend> {
+		None
+	}
 }
 
 impl<E, B> FetchChecker<B> for LightDataChecker<E, B>
 	where
 		E: CodeExecutor,
 		B: BlockT,
+		B::Hash: Into<[u8; 32]>, // TODO: remove when patricia_trie generic.
 {
 	fn check_execution_proof(&self, request: &RemoteCallRequest<B::Hash>, remote_proof: (Vec<u8>, Vec<Vec<u8>>)) -> error::Result<CallResult> {
 		check_execution_proof(&*self.backend, &self.executor, request, remote_proof)
 	}
+
+	fn check_changes_proof(
+		&self,
+		request: &RemoteChangesRequest<<B::Header as HeaderT>::Number>,
+		remote_proof: (Vec<(<B::Header as HeaderT>::Number, u32)>, Vec<Vec<u8>>),
+	) -> error::Result<Vec<(<B::Header as HeaderT>::Number, u32)>> {
+		use blockchain::Backend as BlockchainBackend;
+
+		let (remote_changes, remote_proof) = remote_proof;
+
+		let mut changes = Vec::new();
+		let mut number = request.first_block;
+		while number <= request.last_block {
+			let id = BlockId::Number(number);
+			let root = self.backend.blockchain.header(id)?
+				.ok_or_else(|| error::ErrorKind::UnknownBlock(format!("{}", id)))?
+				.digest().changes_trie_root()
+				.ok_or_else(|| error::ErrorKind::ChangesTrieNotFound(format!("{}", id)))?;
+
+			let extrinsics = state_machine::check_key_changes_proof(root, remote_proof.clone(), &request.key)
+				.map_err(|_| error::ErrorKind::InvalidChangesProof)?;
+			changes.extend(extrinsics.unwrap_or_default().into_iter().map(|index| (number, index)));
+
+			number = number + One::one();
+		}
+
+		if changes != remote_changes {
+			return Err(error::ErrorKind::InvalidChangesProof.into());
+		}
+
+		Ok(changes)
+	}
 }
 
 /// Create an instance of light client backend.
@@ -231,7 +341,7 @@ pub fn new_light<F, B, Block>(
 		Block: BlockT,
 {
 	let executor = RemoteCallExecutor::new(backend.clone(), fetcher);
-	Client::new(backend, executor, genesis_builder)
+	Client::new(backend, executor, genesis_builder, Default::default())
 }
 
 /// Create an instance of fetch data checker.
@@ -0,0 +1,109 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Storage notifications
+
+use std::collections::HashSet;
+
+use futures::sync::mpsc;
+use primitives::storage::{StorageKey, StorageData, StorageChangeSet};
+use runtime_primitives::traits::Block as BlockT;
+
+/// Storage change notifications stream.
+pub type StorageEventStream<H> = mpsc::UnboundedReceiver<StorageChangeSet<H>>;
+
+type Sink<H> = (mpsc::UnboundedSender<StorageChangeSet<H>>, Option<HashSet<StorageKey>>);
+
+/// Manages storage listeners.
+pub struct StorageNotifications<Block: BlockT> {
+	sinks: Vec<Sink<Block::Hash>>,
+}
+
+impl<Block: BlockT> Default for StorageNotifications<Block> {
+	fn default() -> Self {
+		StorageNotifications {
+			sinks: Vec::new(),
+		}
+	}
+}
+
+impl<Block: BlockT> StorageNotifications<Block> {
+	/// Trigger notification to all listeners.
+	///
+	/// Every listener receives only the changes to the keys it is interested in.
+	/// Listeners with no matching changes are not notified.
+	pub fn trigger(&mut self, hash: &Block::Hash, changeset: &[(Vec<u8>, Option<Vec<u8>>)]) {
+		self.sinks.retain(|&(ref sink, ref filter)| {
+			let changes = changeset.iter()
+				.filter(|&&(ref key, _)| match *filter {
+					Some(ref filter) => filter.contains(&StorageKey(key.clone())),
+					None => true,
+				})
+				.map(|&(ref key, ref value)| (StorageKey(key.clone()), value.clone().map(StorageData)))
+				.collect::<Vec<_>>();
+
+			if changes.is_empty() {
+				return true;
+			}
+
+			sink.unbounded_send(StorageChangeSet {
+				block: hash.clone(),
+				changes,
+			}).is_ok()
+		});
+	}
+
+	/// Start listening for changes of particular storage keys.
+	///
+	/// `None` filter means listening to changes of all keys.
+	pub fn listen(&mut self, filter_keys: Option<&[StorageKey]>) -> StorageEventStream<Block::Hash> {
+		let (tx, rx) = mpsc::unbounded();
+		let filter = filter_keys.map(|keys| keys.iter().cloned().collect());
+		self.sinks.push((tx, filter));
+		rx
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::Stream;
+	use runtime_primitives::testing::{H256 as Hash, Block as RawBlock};
+
+	type Block = RawBlock<u64>;
+
+	#[test]
+	fn triggering_notifies_only_interested_listeners() {
+		let mut notifications = StorageNotifications::<Block>::default();
+		let all = notifications.listen(None);
+		let filtered = notifications.listen(Some(&[StorageKey(vec![2])]));
+		let unrelated = notifications.listen(Some(&[StorageKey(vec![5])]));
+
+		let hash = Hash::from(1);
+		notifications.trigger(&hash, &[(vec![1], Some(vec![3])), (vec![2], None)]);
+		drop(notifications);
+
+		assert_eq!(all.wait().map(Result::unwrap).collect::<Vec<_>>(), vec![StorageChangeSet {
+			block: hash,
+			changes: vec![(StorageKey(vec![1]), Some(StorageData(vec![3]))), (StorageKey(vec![2]), None)],
+		}]);
+		assert_eq!(filtered.wait().map(Result::unwrap).collect::<Vec<_>>(), vec![StorageChangeSet {
+			block: hash,
+			changes: vec![(StorageKey(vec![2]), None)],
+		}]);
+		assert_eq!(unrelated.wait().map(Result::unwrap).collect::<Vec<_>>(), vec![]);
+	}
+}
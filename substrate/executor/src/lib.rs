@@ -26,10 +26,12 @@
 //! I leave it as is for now as it might be removed before this is ever done.
 
 #![warn(missing_docs)]
+#![recursion_limit="128"]
 
 extern crate substrate_codec as codec;
 extern crate substrate_runtime_io as runtime_io;
 extern crate substrate_primitives as primitives;
+extern crate substrate_runtime_primitives as runtime_primitives;
 extern crate substrate_serializer as serializer;
 extern crate substrate_state_machine as state_machine;
 extern crate ed25519;
@@ -59,5 +61,6 @@ mod sandbox;
 
 pub mod error;
 pub use wasm_executor::WasmExecutor;
-pub use native_executor::{with_native_environment, NativeExecutor, NativeExecutionDispatch};
+pub use native_executor::{with_native_environment, runtime_version, NativeExecutor, NativeExecutionDispatch};
 pub use state_machine::Externalities;
+pub use runtime_primitives::version::RuntimeVersion;
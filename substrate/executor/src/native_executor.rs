@@ -14,10 +14,35 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::cell::RefCell;
+use std::collections::HashMap;
+use codec::Slicable;
 use error::{Error, ErrorKind, Result};
+use primitives::blake2_256;
+use runtime_primitives::version::RuntimeVersion;
 use state_machine::{CodeExecutor, Externalities};
 use wasm_executor::WasmExecutor;
 
+thread_local! {
+	/// Versions reported by the wasm runtimes executed so far, keyed by the hash of their code.
+	/// `None` if the runtime doesn't report a version.
+	static RUNTIME_VERSIONS: RefCell<HashMap<[u8; 32], Option<RuntimeVersion>>> = RefCell::new(HashMap::new());
+}
+
+/// Fetch the version reported by the given wasm runtime code, calling into it the first time
+/// the code is seen.
+pub fn runtime_version<E: Externalities>(ext: &mut E, code: &[u8]) -> Option<RuntimeVersion> {
+	let code_hash = blake2_256(code);
+	if let Some(version) = RUNTIME_VERSIONS.with(|versions| versions.borrow().get(&code_hash).cloned()) {
+		return version;
+	}
+
+	let version = WasmExecutor.call(ext, code, "version", &[], false).0.ok()
+		.and_then(|v| RuntimeVersion::decode(&mut v.as_slice()));
+	RUNTIME_VERSIONS.with(|versions| versions.borrow_mut().insert(code_hash, version.clone()));
+	version
+}
+
 fn safe_call<F, U>(f: F) -> Result<U>
 	where F: ::std::panic::UnwindSafe + FnOnce() -> U
 {
@@ -38,13 +63,17 @@ pub trait NativeExecutionDispatch {
 	/// Get the wasm code that the native dispatch will be equivalent to.
 	fn native_equivalent() -> &'static [u8];
 
+	/// Get the version of the native runtime.
+	fn native_version() -> RuntimeVersion;
+
 	/// Dispatch a method and input data to be executed natively. Returns `Some` result or `None`
 	/// if the `method` is unknown. Panics if there's an unrecoverable error.
 	fn dispatch(ext: &mut Externalities, method: &str, data: &[u8]) -> Result<Vec<u8>>;
 }
 
-/// A generic `CodeExecutor` implementation that uses a delegate to determine wasm code equivalence
-/// and dispatch to native code when possible, falling back on `WasmExecutor` when not.
+/// A generic `CodeExecutor` implementation that uses a delegate to determine whether the wasm
+/// code is compatible with the native runtime, by comparing their versions, and dispatch to
+/// native code when possible, falling back on `WasmExecutor` when not.
 #[derive(Debug, Default)]
 pub struct NativeExecutor<D: NativeExecutionDispatch + Sync + Send> {
 	/// Dummy field to avoid the compiler complaining about us not using `D`.
@@ -66,29 +95,33 @@ impl<D: NativeExecutionDispatch + Sync + Send> CodeExecutor for NativeExecutor<D
 		code: &[u8],
 		method: &str,
 		data: &[u8],
-	) -> Result<Vec<u8>> {
-		if code == D::native_equivalent() {
+		use_native: bool,
+	) -> (Result<Vec<u8>>, bool) {
+		let native_compatible = use_native && (code == D::native_equivalent() ||
+			runtime_version(ext, code).map_or(false, |version| D::native_version().can_call_with(&version)));
+
+		if native_compatible {
 			// call native
-			D::dispatch(ext, method, data)
+			(D::dispatch(ext, method, data), true)
 		} else {
 			// call into wasm.
-			WasmExecutor.call(ext, code, method, data)
+			WasmExecutor.call(ext, code, method, data, false)
 		}
 	}
 }
 
 #[macro_export]
 macro_rules! native_executor_instance {
-	(pub $name:ident, $dispatcher:path, $code:expr) => {
+	(pub $name:ident, $dispatcher:path, $version:path, $code:expr) => {
 		pub struct $name;
-		native_executor_instance!(IMPL $name, $dispatcher, $code);
+		native_executor_instance!(IMPL $name, $dispatcher, $version, $code);
 	};
-	($name:ident, $dispatcher:path, $code:expr) => {
+	($name:ident, $dispatcher:path, $version:path, $code:expr) => {
 		/// A unit struct which implements `NativeExecutionDispatch` feeding in the hard-coded runtime.
 		struct $name;
-		native_executor_instance!(IMPL $name, $dispatcher, $code);
+		native_executor_instance!(IMPL $name, $dispatcher, $version, $code);
 	};
-	(IMPL $name:ident, $dispatcher:path, $code:expr) => {
+	(IMPL $name:ident, $dispatcher:path, $version:path, $code:expr) => {
 		impl $crate::NativeExecutionDispatch for $name {
 			fn native_equivalent() -> &'static [u8] {
 				// WARNING!!! This assumes that the runtime was built *before* the main project. Until we
@@ -96,6 +129,10 @@ macro_rules! native_executor_instance {
 				$code
 			}
 
+			fn native_version() -> $crate::RuntimeVersion {
+				$version
+			}
+
 			fn dispatch(ext: &mut $crate::Externalities, method: &str, data: &[u8]) -> $crate::error::Result<Vec<u8>> {
 				$crate::with_native_environment(ext, move || $dispatcher(method, data))?
 					.ok_or_else(|| $crate::error::ErrorKind::MethodNotFound(method.to_owned()).into())
@@ -547,7 +547,7 @@ mod tests {
 		"#).unwrap();
 
 		assert_eq!(
-			WasmExecutor.call(&mut ext, &test_code[..], "test_sandbox", &code).unwrap(),
+			WasmExecutor.call(&mut ext, &test_code[..], "test_sandbox", &code, false).0.unwrap(),
 			vec![1],
 		);
 	}
@@ -568,7 +568,7 @@ mod tests {
 		"#).unwrap();
 
 		assert_eq!(
-			WasmExecutor.call(&mut ext, &test_code[..], "test_sandbox", &code).unwrap(),
+			WasmExecutor.call(&mut ext, &test_code[..], "test_sandbox", &code, false).0.unwrap(),
 			vec![0],
 		);
 	}
@@ -606,7 +606,7 @@ mod tests {
 		"#).unwrap();
 
 		assert_eq!(
-			WasmExecutor.call(&mut ext, &test_code[..], "test_sandbox", &code).unwrap(),
+			WasmExecutor.call(&mut ext, &test_code[..], "test_sandbox", &code, false).0.unwrap(),
 			vec![1],
 		);
 	}
@@ -225,6 +225,60 @@ impl_function_executor!(this: FunctionExecutor<'e, E>,
 		this.ext.clear_storage(&key);
 		Ok(())
 	},
+	ext_clear_prefix(prefix_data: *const u8, prefix_len: u32) => {
+		let prefix = this.memory.get(prefix_data, prefix_len as usize).map_err(|_| DummyUserError)?;
+		trace!(target: "wasm-trace", "*** Clearing prefix:  {}   [k={}]", ascii_format(&prefix), HexDisplay::from(&prefix));
+		this.ext.clear_prefix(&prefix);
+		Ok(())
+	},
+	ext_set_child_storage(storage_key_data: *const u8, storage_key_len: u32, key_data: *const u8, key_len: u32, value_data: *const u8, value_len: u32) => {
+		let storage_key = this.memory.get(storage_key_data, storage_key_len as usize).map_err(|_| DummyUserError)?;
+		let key = this.memory.get(key_data, key_len as usize).map_err(|_| DummyUserError)?;
+		let value = this.memory.get(value_data, value_len as usize).map_err(|_| DummyUserError)?;
+		trace!(target: "wasm-trace", "*** Setting child storage: {} -> {} -> {}   [k={}]", ascii_format(&storage_key), ascii_format(&key), HexDisplay::from(&value), HexDisplay::from(&key));
+		if this.ext.set_child_storage(storage_key, key, value) {
+			Ok(())
+		} else {
+			Err(DummyUserError)
+		}
+	},
+	ext_clear_child_storage(storage_key_data: *const u8, storage_key_len: u32, key_data: *const u8, key_len: u32) => {
+		let storage_key = this.memory.get(storage_key_data, storage_key_len as usize).map_err(|_| DummyUserError)?;
+		let key = this.memory.get(key_data, key_len as usize).map_err(|_| DummyUserError)?;
+		trace!(target: "wasm-trace", "*** Clearing child storage: {} -> {}   [k={}]", ascii_format(&storage_key), ascii_format(&key), HexDisplay::from(&key));
+		if this.ext.clear_child_storage(&storage_key, &key) {
+			Ok(())
+		} else {
+			Err(DummyUserError)
+		}
+	},
+	ext_kill_child_storage(storage_key_data: *const u8, storage_key_len: u32) => {
+		let storage_key = this.memory.get(storage_key_data, storage_key_len as usize).map_err(|_| DummyUserError)?;
+		trace!(target: "wasm-trace", "*** Killing child storage: {}", ascii_format(&storage_key));
+		this.ext.kill_child_storage(&storage_key);
+		Ok(())
+	},
+	ext_start_transaction() => {
+		trace!(target: "wasm-trace", "*** Starting storage transaction");
+		this.ext.start_transaction();
+		Ok(())
+	},
+	ext_commit_transaction() => {
+		trace!(target: "wasm-trace", "*** Committing storage transaction");
+		if this.ext.commit_transaction() {
+			Ok(())
+		} else {
+			Err(DummyUserError)
+		}
+	},
+	ext_rollback_transaction() => {
+		trace!(target: "wasm-trace", "*** Rolling back storage transaction");
+		if this.ext.rollback_transaction() {
+			Ok(())
+		} else {
+			Err(DummyUserError)
+		}
+	},
 	// return 0 and place u32::max_value() into written_out if no value exists for the key.
 	ext_get_allocated_storage(key_data: *const u8, key_len: u32, written_out: *mut u32) -> *mut u8 => {
 		let key = this.memory.get(key_data, key_len as usize).map_err(|_| DummyUserError)?;
@@ -246,6 +300,24 @@ impl_function_executor!(this: FunctionExecutor<'e, E>,
 			Ok(0)
 		}
 	},
+	// return 0 and place u32::max_value() into written_out if no value exists for the key.
+	ext_get_allocated_child_storage(storage_key_data: *const u8, storage_key_len: u32, key_data: *const u8, key_len: u32, written_out: *mut u32) -> *mut u8 => {
+		let storage_key = this.memory.get(storage_key_data, storage_key_len as usize).map_err(|_| DummyUserError)?;
+		let key = this.memory.get(key_data, key_len as usize).map_err(|_| DummyUserError)?;
+		let maybe_value = this.ext.child_storage(&storage_key, &key);
+
+		trace!(target: "wasm-trace", "    Getting child storage: {} -> {} == {}   [k={}]", ascii_format(&storage_key), ascii_format(&key), if let Some(ref b) = maybe_value { format!("{}", HexDisplay::from(b)) } else { "<empty>".to_owned() }, HexDisplay::from(&key));
+
+		if let Some(value) = maybe_value {
+			let offset = this.heap.allocate(value.len() as u32) as u32;
+			this.memory.set(offset, &value).map_err(|_| DummyUserError)?;
+			this.memory.write_primitive(written_out, value.len() as u32)?;
+			Ok(offset)
+		} else {
+			this.memory.write_primitive(written_out, u32::max_value())?;
+			Ok(0)
+		}
+	},
 	// return u32::max_value() if no value exists for the key.
 	ext_get_storage_into(key_data: *const u8, key_len: u32, value_data: *mut u8, value_len: u32, value_offset: u32) -> u32 => {
 		let key = this.memory.get(key_data, key_len as usize).map_err(|_| DummyUserError)?;
@@ -269,6 +341,22 @@ impl_function_executor!(this: FunctionExecutor<'e, E>,
 		this.memory.set(result, &r[..]).map_err(|_| DummyUserError)?;
 		Ok(())
 	},
+	// return 0 and leave `result` untouched if changes tries are not enabled.
+	ext_storage_changes_root(result: *mut u8) -> u32 => {
+		match this.ext.storage_changes_root() {
+			Some(r) => {
+				this.memory.set(result, &r[..]).map_err(|_| DummyUserError)?;
+				Ok(1)
+			}
+			None => Ok(0),
+		}
+	},
+	ext_child_storage_root(storage_key_data: *const u8, storage_key_len: u32, result: *mut u8) => {
+		let storage_key = this.memory.get(storage_key_data, storage_key_len as usize).map_err(|_| DummyUserError)?;
+		let r = this.ext.child_storage_root(&storage_key).ok_or(DummyUserError)?;
+		this.memory.set(result, &r[..]).map_err(|_| DummyUserError)?;
+		Ok(())
+	},
 	ext_enumerated_trie_root(values_data: *const u8, lens_data: *const u32, lens_len: u32, result: *mut u8) => {
 		let values = (0..lens_len)
 			.map(|i| this.memory.read_primitive(lens_data + i * 4))
@@ -423,10 +511,8 @@ impl_function_executor!(this: FunctionExecutor<'e, E>,
 #[derive(Debug, Default, Clone)]
 pub struct WasmExecutor;
 
-impl CodeExecutor for WasmExecutor {
-	type Error = Error;
-
-	fn call<E: Externalities>(
+impl WasmExecutor {
+	fn call_in_wasm<E: Externalities>(
 		&self,
 		ext: &mut E,
 		code: &[u8],
@@ -484,6 +570,21 @@ impl CodeExecutor for WasmExecutor {
 	}
 }
 
+impl CodeExecutor for WasmExecutor {
+	type Error = Error;
+
+	fn call<E: Externalities>(
+		&self,
+		ext: &mut E,
+		code: &[u8],
+		method: &str,
+		data: &[u8],
+		_use_native: bool,
+	) -> (Result<Vec<u8>>, bool) {
+		(self.call_in_wasm(ext, code, method, data), false)
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -503,7 +604,7 @@ mod tests {
 		let mut ext = TestExternalities::default();
 		let test_code = include_bytes!("../wasm/target/wasm32-unknown-unknown/release/runtime_test.compact.wasm");
 
-		let output = WasmExecutor.call(&mut ext, &test_code[..], "test_empty_return", &[]).unwrap();
+		let output = WasmExecutor.call(&mut ext, &test_code[..], "test_empty_return", &[], false).0.unwrap();
 		assert_eq!(output, vec![0u8; 0]);
 	}
 
@@ -512,10 +613,10 @@ mod tests {
 		let mut ext = TestExternalities::default();
 		let test_code = include_bytes!("../wasm/target/wasm32-unknown-unknown/release/runtime_test.compact.wasm");
 
-		let output = WasmExecutor.call(&mut ext, &test_code[..], "test_panic", &[]);
+		let output = WasmExecutor.call(&mut ext, &test_code[..], "test_panic", &[], false).0;
 		assert!(output.is_err());
 
-		let output = WasmExecutor.call(&mut ext, &test_code[..], "test_conditional_panic", &[2]);
+		let output = WasmExecutor.call(&mut ext, &test_code[..], "test_conditional_panic", &[2], false).0;
 		assert!(output.is_err());
 	}
 
@@ -525,11 +626,11 @@ mod tests {
 		ext.set_storage(b"foo".to_vec(), b"bar".to_vec());
 		let test_code = include_bytes!("../wasm/target/wasm32-unknown-unknown/release/runtime_test.compact.wasm");
 
-		let output = WasmExecutor.call(&mut ext, &test_code[..], "test_data_in", b"Hello world").unwrap();
+		let output = WasmExecutor.call(&mut ext, &test_code[..], "test_data_in", b"Hello world", false).0.unwrap();
 
 		assert_eq!(output, b"all ok!".to_vec());
 
-		let expected: HashMap<_, _> = map![
+		let expected: TestExternalities = map![
 			b"input".to_vec() => b"Hello world".to_vec(),
 			b"foo".to_vec() => b"bar".to_vec(),
 			b"baz".to_vec() => b"bar".to_vec()
@@ -542,11 +643,11 @@ mod tests {
 		let mut ext = TestExternalities::default();
 		let test_code = include_bytes!("../wasm/target/wasm32-unknown-unknown/release/runtime_test.compact.wasm");
 		assert_eq!(
-			WasmExecutor.call(&mut ext, &test_code[..], "test_blake2_256", &[]).unwrap(),
+			WasmExecutor.call(&mut ext, &test_code[..], "test_blake2_256", &[], false).0.unwrap(),
 			blake2_256(&b""[..]).encode()
 		);
 		assert_eq!(
-			WasmExecutor.call(&mut ext, &test_code[..], "test_blake2_256", b"Hello world!").unwrap(),
+			WasmExecutor.call(&mut ext, &test_code[..], "test_blake2_256", b"Hello world!", false).0.unwrap(),
 			blake2_256(&b"Hello world!"[..]).encode()
 		);
 	}
@@ -556,11 +657,11 @@ mod tests {
 		let mut ext = TestExternalities::default();
 		let test_code = include_bytes!("../wasm/target/wasm32-unknown-unknown/release/runtime_test.compact.wasm");
 		assert_eq!(
-			WasmExecutor.call(&mut ext, &test_code[..], "test_twox_256", &[]).unwrap(),
+			WasmExecutor.call(&mut ext, &test_code[..], "test_twox_256", &[], false).0.unwrap(),
 			FromHex::from_hex("99e9d85137db46ef4bbea33613baafd56f963c64b1f3685a4eb4abd67ff6203a").unwrap()
 		);
 		assert_eq!(
-			WasmExecutor.call(&mut ext, &test_code[..], "test_twox_256", b"Hello world!").unwrap(),
+			WasmExecutor.call(&mut ext, &test_code[..], "test_twox_256", b"Hello world!", false).0.unwrap(),
 			FromHex::from_hex("b27dfd7f223f177f2a13647b533599af0c07f68bda23d96d059da2b451a35a74").unwrap()
 		);
 	}
@@ -570,11 +671,11 @@ mod tests {
 		let mut ext = TestExternalities::default();
 		let test_code = include_bytes!("../wasm/target/wasm32-unknown-unknown/release/runtime_test.compact.wasm");
 		assert_eq!(
-			WasmExecutor.call(&mut ext, &test_code[..], "test_twox_128", &[]).unwrap(),
+			WasmExecutor.call(&mut ext, &test_code[..], "test_twox_128", &[], false).0.unwrap(),
 			FromHex::from_hex("99e9d85137db46ef4bbea33613baafd5").unwrap()
 		);
 		assert_eq!(
-			WasmExecutor.call(&mut ext, &test_code[..], "test_twox_128", b"Hello world!").unwrap(),
+			WasmExecutor.call(&mut ext, &test_code[..], "test_twox_128", b"Hello world!", false).0.unwrap(),
 			FromHex::from_hex("b27dfd7f223f177f2a13647b533599af").unwrap()
 		);
 	}
@@ -590,7 +691,7 @@ mod tests {
 		calldata.extend_from_slice(sig.as_ref());
 
 		assert_eq!(
-			WasmExecutor.call(&mut ext, &test_code[..], "test_ed25519_verify", &calldata).unwrap(),
+			WasmExecutor.call(&mut ext, &test_code[..], "test_ed25519_verify", &calldata, false).0.unwrap(),
 			vec![1]
 		);
 
@@ -600,7 +701,7 @@ mod tests {
 		calldata.extend_from_slice(other_sig.as_ref());
 
 		assert_eq!(
-			WasmExecutor.call(&mut ext, &test_code[..], "test_ed25519_verify", &calldata).unwrap(),
+			WasmExecutor.call(&mut ext, &test_code[..], "test_ed25519_verify", &calldata, false).0.unwrap(),
 			vec![0]
 		);
 	}
@@ -610,7 +711,7 @@ mod tests {
 		let mut ext = TestExternalities::default();
 		let test_code = include_bytes!("../wasm/target/wasm32-unknown-unknown/release/runtime_test.compact.wasm");
 		assert_eq!(
-			WasmExecutor.call(&mut ext, &test_code[..], "test_enumerated_trie_root", &[]).unwrap(),
+			WasmExecutor.call(&mut ext, &test_code[..], "test_enumerated_trie_root", &[], false).0.unwrap(),
 			ordered_trie_root(vec![b"zero".to_vec(), b"one".to_vec(), b"two".to_vec()]).0.encode()
 		);
 	}
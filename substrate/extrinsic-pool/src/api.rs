@@ -20,6 +20,8 @@ use std::fmt;
 use std::ops::Deref;
 use txpool::{self, VerifiedTransaction};
 
+use watcher::Watcher;
+
 /// Extrinsic pool error.
 pub trait Error: ::std::error::Error + Send + Sized {
 	/// Try to extract original `txpool::Error`
@@ -41,6 +43,17 @@ pub trait ExtrinsicPool<Ex, Hash>: Send + Sync + 'static {
 
 	/// Submit a collection of extrinsics to the pool.
 	fn submit(&self, xt: Vec<Ex>) -> Result<Vec<Hash>, Self::Error>;
+
+	/// Submit an extrinsic to the pool and watch its status changes.
+	fn submit_and_watch(&self, xt: Ex) -> Result<Watcher<Hash>, Self::Error>;
+
+	/// Returns all extrinsics currently in the pool.
+	fn pending(&self) -> Vec<Ex>;
+
+	/// Remove extrinsics with given hashes from the pool.
+	///
+	/// Returns hashes of the extrinsics that were actually removed.
+	fn remove_extrinsics(&self, hashes: &[Hash]) -> Vec<Hash>;
 }
 
 // Blanket implementation for anything that `Derefs` to the pool.
@@ -49,7 +62,8 @@ impl<Ex, Hash, V, S, E, T> ExtrinsicPool<Ex, Hash> for T where
 	T: Deref<Target=super::Pool<Ex, Hash, V, S, E>> + Send + Sync + 'static,
 	V: txpool::Verifier<Ex>,
 	S: txpool::Scoring<V::VerifiedTransaction>,
-	V::VerifiedTransaction: txpool::VerifiedTransaction<Hash=Hash>,
+	V::VerifiedTransaction: txpool::VerifiedTransaction<Hash=Hash> + AsRef<Ex>,
+	Ex: Clone,
 	E: From<V::Error>,
 	E: From<txpool::Error>,
 	E: Error,
@@ -59,4 +73,16 @@ impl<Ex, Hash, V, S, E, T> ExtrinsicPool<Ex, Hash> for T where
 	fn submit(&self, xt: Vec<Ex>) -> Result<Vec<Hash>, Self::Error> {
 		self.deref().submit(xt).map(|result| result.into_iter().map(|xt| *xt.hash()).collect())
 	}
+
+	fn submit_and_watch(&self, xt: Ex) -> Result<Watcher<Hash>, Self::Error> {
+		self.deref().submit_and_watch(xt)
+	}
+
+	fn pending(&self) -> Vec<Ex> {
+		self.deref().all().into_iter().map(|xt| AsRef::<Ex>::as_ref(&*xt).clone()).collect()
+	}
+
+	fn remove_extrinsics(&self, hashes: &[Hash]) -> Vec<Hash> {
+		self.deref().remove(hashes, false).into_iter().filter_map(|xt| xt.map(|xt| *xt.hash())).collect()
+	}
 }
@@ -20,9 +20,12 @@
 
 extern crate futures;
 extern crate parking_lot;
+extern crate serde;
 
 #[macro_use]
 extern crate log;
+#[macro_use]
+extern crate serde_derive;
 
 pub extern crate transaction_pool as txpool;
 
@@ -33,4 +36,4 @@ mod pool;
 mod watcher;
 
 pub use self::pool::Pool;
-pub use self::watcher::Watcher;
+pub use self::watcher::{Status, Watcher};
@@ -25,7 +25,9 @@ use watcher;
 
 #[derive(Default)]
 pub struct Listener<H: ::std::hash::Hash + Eq> {
-	watchers: HashMap<H, watcher::Sender<H>>
+	watchers: HashMap<H, watcher::Sender<H>>,
+	/// Hash of the block extrinsics are currently being culled for.
+	block: H,
 }
 
 impl<H: ::std::hash::Hash + Eq + Copy + fmt::Debug + fmt::LowerHex + Default> Listener<H> {
@@ -34,6 +36,11 @@ impl<H: ::std::hash::Hash + Eq + Copy + fmt::Debug + fmt::LowerHex + Default> Li
 		sender.new_watcher()
 	}
 
+	/// Set the hash of the block which includes any extrinsics mined from now on.
+	pub fn set_block(&mut self, hash: H) {
+		self.block = hash;
+	}
+
 	pub fn broadcasted(&mut self, hash: &H, peers: Vec<String>) {
 		self.fire(hash, |watcher| watcher.broadcast(peers));
 	}
@@ -76,15 +83,16 @@ impl<H, T> txpool::Listener<T> for Listener<H> where
 
 	fn invalid(&mut self, tx: &Arc<T>) {
 		warn!("Extrinsic invalid: {:?}", tx);
+		self.fire(tx.hash(), |watcher| watcher.invalid());
 	}
 
 	fn canceled(&mut self, tx: &Arc<T>) {
 		warn!("Extrinsic canceled: {:?}", tx);
+		self.fire(tx.hash(), |watcher| watcher.dropped());
 	}
 
 	fn mined(&mut self, tx: &Arc<T>) {
-		// TODO [ToDr] latest block number?
-		let header_hash = Default::default();
+		let header_hash = self.block;
 		self.fire(tx.hash(), |watcher| watcher.finalised(header_hash))
 	}
 }
@@ -104,12 +104,13 @@ impl<Ex, Hash, V, S, E> Pool<Ex, Hash, V, S, E> where
 		Ok(self.pool.write().listener_mut().create_watcher(xt))
 	}
 
-	/// Remove from the pool.
-	pub fn remove(&self, hashes: &[Hash], is_valid: bool) -> Vec<Option<Arc<V::VerifiedTransaction>>> {
+	/// Remove from the pool. Watchers of the removed extrinsics are notified that they are
+	/// invalid if `is_invalid` is set, and that they were dropped otherwise.
+	pub fn remove(&self, hashes: &[Hash], is_invalid: bool) -> Vec<Option<Arc<V::VerifiedTransaction>>> {
 		let mut pool = self.pool.write();
 		let mut results = Vec::with_capacity(hashes.len());
 		for hash in hashes {
-			results.push(pool.remove(hash, is_valid));
+			results.push(pool.remove(hash, is_invalid));
 		}
 		results
 	}
@@ -121,6 +122,22 @@ impl<Ex, Hash, V, S, E> Pool<Ex, Hash, V, S, E> where
 		self.pool.write().cull(senders, ready)
 	}
 
+	/// Cull transactions from the queue after the block with given hash was imported.
+	///
+	/// Watchers of culled extrinsics are notified that they were finalised in that block.
+	pub fn cull_from<R>(&self, block: Hash, senders: Option<&[<V::VerifiedTransaction as txpool::VerifiedTransaction>::Sender]>, ready: R) -> usize where
+		R: txpool::Ready<V::VerifiedTransaction>,
+	{
+		let mut pool = self.pool.write();
+		pool.listener_mut().set_block(block);
+		pool.cull(senders, ready)
+	}
+
+	/// Returns all transactions currently in the pool, ready or not.
+	pub fn all(&self) -> Vec<Arc<V::VerifiedTransaction>> {
+		self.pool.read().pending(|_: &V::VerifiedTransaction| txpool::Readiness::Ready).collect()
+	}
+
 	/// Cull transactions from the queue and then compute the pending set.
 	pub fn cull_and_get_pending<R, F, T>(&self, ready: R, f: F) -> T where
 		R: txpool::Ready<V::VerifiedTransaction> + Clone,
@@ -14,10 +14,12 @@
 // You should have received a copy of the GNU General Public License
 // along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
 
+use futures::{Poll, Stream};
 use futures::sync::mpsc;
 
 /// Possible extrinsic status events
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub enum Status<H> {
 	/// Extrinsic has been finalised in block with given hash.
 	Finalised(H),
@@ -25,8 +27,10 @@ pub enum Status<H> {
 	Usurped(H),
 	/// The extrinsic has been broadcast to the given peers.
 	Broadcast(Vec<String>),
-	/// Extrinsic has been dropped from the pool because of the limit.
+	/// Extrinsic has been dropped from the pool because of the limit or was removed explicitly.
 	Dropped,
+	/// Extrinsic was found to be invalid and removed from the pool.
+	Invalid,
 }
 
 /// Extrinsic watcher.
@@ -37,10 +41,19 @@ pub struct Watcher<H> {
 	receiver: mpsc::UnboundedReceiver<Status<H>>,
 }
 
+impl<H> Stream for Watcher<H> {
+	type Item = Status<H>;
+	type Error = ();
+
+	fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+		self.receiver.poll()
+	}
+}
+
 #[derive(Debug, Default)]
 pub(crate) struct Sender<H> {
 	receivers: Vec<mpsc::UnboundedSender<Status<H>>>,
-	finalised: bool,
+	is_final: bool,
 }
 
 impl<H: Clone> Sender<H> {
@@ -61,12 +74,19 @@ impl<H: Clone> Sender<H> {
 	/// Extrinsic has been finalised in block with given hash.
 	pub fn finalised(&mut self, hash: H) {
 		self.send(Status::Finalised(hash));
-		self.finalised = true;
+		self.is_final = true;
 	}
 
-	/// Transaction has been dropped from the pool because of the limit.
+	/// Transaction has been dropped from the pool because of the limit or was removed explicitly.
 	pub fn dropped(&mut self) {
 		self.send(Status::Dropped);
+		self.is_final = true;
+	}
+
+	/// Transaction was found to be invalid and removed from the pool.
+	pub fn invalid(&mut self) {
+		self.send(Status::Invalid);
+		self.is_final = true;
 	}
 
 	/// The extrinsic has been broadcast to the given peers.
@@ -74,9 +94,9 @@ impl<H: Clone> Sender<H> {
 		self.send(Status::Broadcast(peers))
 	}
 
-	/// Returns true if the are no more listeners for this extrinsic or it was finalised.
+	/// Returns true if the are no more listeners for this extrinsic or it has left the pool.
 	pub fn is_done(&self) -> bool {
-		self.finalised || self.receivers.is_empty()
+		self.is_final || self.receivers.is_empty()
 	}
 
 	fn send(&mut self, status: Status<H>) {
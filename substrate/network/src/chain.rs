@@ -16,8 +16,9 @@
 
 //! Blockchain access trait
 
-use client::{self, Client as PolkadotClient, ImportResult, ClientInfo, BlockStatus, BlockOrigin, CallExecutor};
+use client::{self, Client as PolkadotClient, ImportResult, ClientInfo, BlockStatus, BlockOrigin, CallExecutor, JustifiedHeader};
 use client::error::Error;
+use primitives::AuthorityId;
 use state_machine;
 use runtime_primitives::traits::{Block as BlockT, Header as HeaderT};
 use runtime_primitives::generic::BlockId;
@@ -25,7 +26,13 @@ use runtime_primitives::bft::Justification;
 
 pub trait Client<Block: BlockT>: Send + Sync {
 	/// Import a new block. Parent is supposed to be existing in the blockchain.
-	fn import(&self, is_best: bool, header: Block::Header, justification: Justification<Block::Hash>, body: Option<Vec<Block::Extrinsic>>) -> Result<ImportResult, Error>;
+	fn import(&self, origin: BlockOrigin, header: JustifiedHeader<Block>, body: Option<Vec<Block::Extrinsic>>) -> Result<ImportResult, Error>;
+
+	/// Check a header's justification against the given authorities of its parent.
+	fn check_justification(&self, authorities: &[AuthorityId], header: Block::Header, justification: Justification<Block::Hash>) -> Result<JustifiedHeader<Block>, Error>;
+
+	/// Get the authorities at given block.
+	fn authorities(&self, id: &BlockId<Block>) -> Result<Vec<AuthorityId>, Error>;
 
 	/// Get blockchain info.
 	fn info(&self) -> Result<ClientInfo<Block>, Error>;
@@ -55,11 +62,16 @@ impl<B, E, Block> Client<Block> for PolkadotClient<B, E, Block> where
 	Block: BlockT,
 	Error: From<<<B as client::backend::Backend<Block>>::State as state_machine::backend::Backend>::Error>, {
 
-	fn import(&self, is_best: bool, header: Block::Header, justification: Justification<Block::Hash>, body: Option<Vec<Block::Extrinsic>>) -> Result<ImportResult, Error> {
-		// TODO: defer justification check.
-		let justified_header = self.check_justification(header, justification.into())?;
-		let origin = if is_best { BlockOrigin::NetworkBroadcast } else { BlockOrigin::NetworkInitialSync };
-		(self as &PolkadotClient<B, E, Block>).import_block(origin, justified_header, body)
+	fn import(&self, origin: BlockOrigin, header: JustifiedHeader<Block>, body: Option<Vec<Block::Extrinsic>>) -> Result<ImportResult, Error> {
+		(self as &PolkadotClient<B, E, Block>).import_block(origin, header, body)
+	}
+
+	fn check_justification(&self, authorities: &[AuthorityId], header: Block::Header, justification: Justification<Block::Hash>) -> Result<JustifiedHeader<Block>, Error> {
+		(self as &PolkadotClient<B, E, Block>).check_justification_with_authorities(authorities, header, justification.into())
+	}
+
+	fn authorities(&self, id: &BlockId<Block>) -> Result<Vec<AuthorityId>, Error> {
+		(self as &PolkadotClient<B, E, Block>).authorities_at(id)
 	}
 
 	fn info(&self) -> Result<ClientInfo<Block>, Error> {
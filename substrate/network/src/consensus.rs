@@ -28,17 +28,43 @@ use message::{self, generic::Message as GenericMessage};
 
 // TODO: Add additional spam/DoS attack protection.
 const MESSAGE_LIFETIME: Duration = Duration::from_secs(600);
+// Maximum number of erasure chunks accepted from a single peer for one consensus round:
+// a chunk for each of 256 validators for 64 candidates.
+const MAX_ERASURE_CHUNKS_PER_PEER: usize = 256 * 64;
+// Maximum number of collations accepted from a single peer for one relay parent.
+pub(crate) const MAX_COLLATIONS_PER_PEER: usize = 4;
 
 struct PeerConsensus<H> {
 	known_messages: HashSet<H>,
+	// number of erasure chunks received, by the parent hash of the round.
+	erasure_chunks: HashMap<H, usize>,
+	// number of collations received, by relay parent.
+	collations: HashMap<H, usize>,
+	// collators only submit collations and are not sent any gossip.
+	is_collator: bool,
+}
+
+impl<H: ::std::hash::Hash + Eq> PeerConsensus<H> {
+	fn new(known_messages: HashSet<H>, is_collator: bool) -> Self {
+		PeerConsensus {
+			known_messages,
+			erasure_chunks: HashMap::new(),
+			collations: HashMap::new(),
+			is_collator,
+		}
+	}
 }
 
 /// Consensus network protocol handler. Manages statements and candidate requests.
 pub struct Consensus<B: BlockT> {
 	peers: HashMap<PeerId, PeerConsensus<B::Hash>>,
 	bft_message_sink: Option<(mpsc::UnboundedSender<message::LocalizedBftMessage<B>>, B::Hash)>,
+	erasure_chunk_sink: Option<(mpsc::UnboundedSender<message::ErasureChunkMessage<B>>, B::Hash)>,
+	collation_sink: Option<(mpsc::UnboundedSender<message::CollationMessage<B>>, B::Hash)>,
 	messages: Vec<(B::Hash, Instant, message::Message<B>)>,
 	message_hashes: HashSet<B::Hash>,
+	// peers collations were first received from, by message hash.
+	collation_origins: HashMap<B::Hash, PeerId>,
 }
 
 impl<B: BlockT> Consensus<B> where B::Header: HeaderT<Number=u64> {
@@ -47,14 +73,19 @@ impl<B: BlockT> Consensus<B> where B::Header: HeaderT<Number=u64> {
 		Consensus {
 			peers: HashMap::new(),
 			bft_message_sink: None,
+			erasure_chunk_sink: None,
+			collation_sink: None,
 			messages: Default::default(),
 			message_hashes: Default::default(),
+			collation_origins: Default::default(),
 		}
 	}
 
 	/// Closes all notification streams.
 	pub fn restart(&mut self) {
 		self.bft_message_sink = None;
+		self.erasure_chunk_sink = None;
+		self.collation_sink = None;
 	}
 
 	/// Handle new connected peer.
@@ -68,14 +99,18 @@ impl<B: BlockT> Consensus<B> where B::Header: HeaderT<Number=u64> {
 				known_messages.insert(hash.clone());
 				protocol.send_message(io, peer_id, message.clone());
 			}
-			self.peers.insert(peer_id, PeerConsensus {
-				known_messages,
-			});
+			self.peers.insert(peer_id, PeerConsensus::new(known_messages, false));
+		} else if roles.iter().any(|r| *r == message::Role::Collator) {
+			trace!(target:"sync", "Registering collator {}", peer_id);
+			self.peers.insert(peer_id, PeerConsensus::new(HashSet::new(), true));
 		}
 	}
 
 	fn propagate(&mut self, io: &mut SyncIo, protocol: &Protocol<B>, message: message::Message<B>, hash: B::Hash) {
 		for (id, ref mut peer) in self.peers.iter_mut() {
+			if peer.is_collator {
+				continue;
+			}
 			if peer.known_messages.insert(hash.clone()) {
 				protocol.send_message(io, *id, message.clone());
 			}
@@ -88,24 +123,36 @@ impl<B: BlockT> Consensus<B> where B::Header: HeaderT<Number=u64> {
 		}
 	}
 
+	fn is_collator(&self, peer_id: &PeerId) -> bool {
+		self.peers.get(peer_id).map_or(false, |peer| peer.is_collator)
+	}
+
+	// whether a message for the round on `parent_hash` is too old to be of interest.
+	fn is_ancient(&self, protocol: &Protocol<B>, parent_hash: &B::Hash) -> bool {
+		match (protocol.chain().info(), protocol.chain().header(&BlockId::Hash(*parent_hash))) {
+			(_, Err(e)) | (Err(e), _) => {
+				debug!(target:"sync", "Error reading blockchain: {:?}", e);
+				true
+			},
+			(Ok(info), Ok(Some(header))) => header.number() < &info.chain.best_number,
+			(Ok(_), Ok(None)) => false,
+		}
+	}
+
 	pub fn on_bft_message(&mut self, io: &mut SyncIo, protocol: &Protocol<B>, peer_id: PeerId, message: message::LocalizedBftMessage<B>, hash: B::Hash) {
 		if self.message_hashes.contains(&hash) {
 			trace!(target:"sync", "Ignored already known BFT message from {}", peer_id);
 			return;
 		}
 
-		match (protocol.chain().info(), protocol.chain().header(&BlockId::Hash(message.parent_hash))) {
-			(_, Err(e)) | (Err(e), _) => {
-				debug!(target:"sync", "Error reading blockchain: {:?}", e);
-				return;
-			},
-			(Ok(info), Ok(Some(header))) => {
-				if header.number() < &info.chain.best_number {
-					trace!(target:"sync", "Ignored ancient BFT message from {}, hash={}", peer_id, message.parent_hash);
-					return;
-				}
-			},
-			(Ok(_), Ok(None)) => {},
+		if self.is_collator(&peer_id) {
+			trace!(target:"sync", "Ignored BFT message from collator {}", peer_id);
+			return;
+		}
+
+		if self.is_ancient(protocol, &message.parent_hash) {
+			trace!(target:"sync", "Ignored ancient BFT message from {}, hash={}", peer_id, message.parent_hash);
+			return;
 		}
 
 		if let Some(ref mut peer) = self.peers.get_mut(&peer_id) {
@@ -158,6 +205,173 @@ impl<B: BlockT> Consensus<B> where B::Header: HeaderT<Number=u64> {
 		self.propagate(io, protocol, message, hash);
 	}
 
+	pub fn on_erasure_chunk(&mut self, io: &mut SyncIo, protocol: &Protocol<B>, peer_id: PeerId, message: message::ErasureChunkMessage<B>, hash: B::Hash) {
+		if self.message_hashes.contains(&hash) {
+			trace!(target:"sync", "Ignored already known erasure chunk from {}", peer_id);
+			return;
+		}
+
+		if self.is_collator(&peer_id) {
+			trace!(target:"sync", "Ignored erasure chunk from collator {}", peer_id);
+			return;
+		}
+
+		if self.is_ancient(protocol, &message.parent_hash) {
+			trace!(target:"sync", "Ignored ancient erasure chunk from {}, hash={}", peer_id, message.parent_hash);
+			return;
+		}
+
+		if let Some(ref mut peer) = self.peers.get_mut(&peer_id) {
+			let received = peer.erasure_chunks.entry(message.parent_hash).or_insert(0);
+			if *received >= MAX_ERASURE_CHUNKS_PER_PEER {
+				trace!(target:"sync", "Ignored erasure chunk from {}: too many chunks for round {}", peer_id, message.parent_hash);
+				return;
+			}
+			*received += 1;
+
+			peer.known_messages.insert(hash);
+			// chunk proofs are checked by the receiver of the stream.
+			if let Some((sink, parent_hash)) = self.erasure_chunk_sink.take() {
+				if message.parent_hash == parent_hash {
+					if let Err(e) = sink.unbounded_send(message.clone()) {
+						trace!(target:"sync", "Error broadcasting erasure chunk notification: {:?}", e);
+					} else {
+						self.erasure_chunk_sink = Some((sink, parent_hash));
+					}
+				} else {
+					self.erasure_chunk_sink = Some((sink, parent_hash));
+				}
+			}
+		} else {
+			trace!(target:"sync", "Ignored erasure chunk from unregistered peer {}", peer_id);
+			return;
+		}
+
+		let message = GenericMessage::ErasureChunk(message);
+		self.register_message(hash.clone(), message.clone());
+		// Propagate to other peers.
+		self.propagate(io, protocol, message, hash);
+	}
+
+	pub fn erasure_chunks(&mut self, parent_hash: B::Hash) -> mpsc::UnboundedReceiver<message::ErasureChunkMessage<B>> {
+		let (sink, stream) = mpsc::unbounded();
+
+		for &(_, _, ref message) in self.messages.iter() {
+			let chunk = match *message {
+				GenericMessage::ErasureChunk(ref msg) => msg,
+				_ => continue,
+			};
+
+			if chunk.parent_hash == parent_hash {
+				sink.unbounded_send(chunk.clone()).expect("receiving end known to be open; qed");
+			}
+		}
+
+		self.erasure_chunk_sink = Some((sink, parent_hash));
+		stream
+	}
+
+	pub fn send_erasure_chunk(&mut self, io: &mut SyncIo, protocol: &Protocol<B>, message: message::ErasureChunkMessage<B>) {
+		trace!(target:"sync", "Broadcasting erasure chunk {} for candidate {}", message.index, message.candidate_hash);
+		let message = GenericMessage::ErasureChunk(message);
+		let hash = Protocol::hash_message(&message);
+		self.register_message(hash.clone(), message.clone());
+		self.propagate(io, protocol, message, hash);
+	}
+
+	pub fn on_collation(&mut self, io: &mut SyncIo, protocol: &Protocol<B>, peer_id: PeerId, message: message::CollationMessage<B>, hash: B::Hash) {
+		if self.message_hashes.contains(&hash) {
+			trace!(target:"sync", "Ignored already known collation from {}", peer_id);
+			return;
+		}
+
+		if self.is_ancient(protocol, &message.relay_parent) {
+			trace!(target:"sync", "Ignored ancient collation from {}, hash={}", peer_id, message.relay_parent);
+			return;
+		}
+
+		if let Some(ref mut peer) = self.peers.get_mut(&peer_id) {
+			let received = peer.collations.entry(message.relay_parent).or_insert(0);
+			if *received >= MAX_COLLATIONS_PER_PEER {
+				trace!(target:"sync", "Ignored collation from {}: too many collations on {}", peer_id, message.relay_parent);
+				return;
+			}
+			*received += 1;
+
+			peer.known_messages.insert(hash);
+		} else {
+			trace!(target:"sync", "Ignored collation from unregistered peer {}", peer_id);
+			return;
+		}
+
+		self.collation_origins.insert(hash, peer_id);
+		if let Some((sink, relay_parent)) = self.collation_sink.take() {
+			if message.relay_parent == relay_parent {
+				if let Err(e) = sink.unbounded_send(message.clone()) {
+					trace!(target:"sync", "Error broadcasting collation notification: {:?}", e);
+				} else {
+					self.collation_sink = Some((sink, relay_parent));
+				}
+			} else {
+				self.collation_sink = Some((sink, relay_parent));
+			}
+		}
+
+		let message = GenericMessage::Collation(message);
+		self.register_message(hash.clone(), message.clone());
+		// Propagate to other peers.
+		self.propagate(io, protocol, message, hash);
+	}
+
+	pub fn collations(&mut self, relay_parent: B::Hash) -> mpsc::UnboundedReceiver<message::CollationMessage<B>> {
+		let (sink, stream) = mpsc::unbounded();
+
+		for &(_, _, ref message) in self.messages.iter() {
+			let collation = match *message {
+				GenericMessage::Collation(ref msg) => msg,
+				_ => continue,
+			};
+
+			if collation.relay_parent == relay_parent {
+				sink.unbounded_send(collation.clone()).expect("receiving end known to be open; qed");
+			}
+		}
+
+		self.collation_sink = Some((sink, relay_parent));
+		stream
+	}
+
+	pub fn send_collation(&mut self, io: &mut SyncIo, protocol: &Protocol<B>, message: message::CollationMessage<B>) {
+		trace!(target:"sync", "Submitting collation on {}", message.relay_parent);
+		let message = GenericMessage::Collation(message);
+		let hash = Protocol::hash_message(&message);
+		self.register_message(hash.clone(), message.clone());
+		self.propagate(io, protocol, message, hash);
+	}
+
+	/// Drop a collation found to be invalid and disable the collator which submitted it.
+	pub fn report_bad_collation(&mut self, io: &mut SyncIo, message: message::CollationMessage<B>) {
+		// messages are known by the hash of the data they were received as.
+		let hash = match self.messages.iter().find(|&&(_, _, ref m)| match *m {
+			GenericMessage::Collation(ref m) => m == &message,
+			_ => false,
+		}) {
+			Some(&(hash, _, _)) => hash,
+			None => return,
+		};
+		self.message_hashes.remove(&hash);
+		self.messages.retain(|&(ref h, _, _)| h != &hash);
+
+		if let Some(peer_id) = self.collation_origins.remove(&hash) {
+			// validators only relay collations, so only the submitting collator is punished.
+			if self.is_collator(&peer_id) {
+				debug!(target:"sync", "Disabling collator {}: bad collation", peer_id);
+				io.disable_peer(peer_id);
+				self.peers.remove(&peer_id);
+			}
+		}
+	}
+
 	pub fn peer_disconnected(&mut self, _io: &mut SyncIo, _protocol: &Protocol<B>, peer_id: PeerId) {
 		self.peers.remove(&peer_id);
 	}
@@ -171,6 +385,8 @@ impl<B: BlockT> Consensus<B> where B::Header: HeaderT<Number=u64> {
 				best_header.map_or(true, |header|
 					match *message {
 						GenericMessage::BftMessage(ref msg) => &msg.parent_hash != header.parent_hash(),
+						GenericMessage::ErasureChunk(ref msg) => &msg.parent_hash != header.parent_hash(),
+						GenericMessage::Collation(ref msg) => &msg.relay_parent != header.parent_hash(),
 						_ => true,
 					})
 			{
@@ -183,8 +399,19 @@ impl<B: BlockT> Consensus<B> where B::Header: HeaderT<Number=u64> {
 		if self.messages.len() != before {
 			trace!(target:"sync", "Cleaned up {} stale messages", before - self.messages.len());
 		}
+		let live_rounds: HashSet<B::Hash> = self.messages.iter().filter_map(|&(_, _, ref message)| match *message {
+			GenericMessage::ErasureChunk(ref msg) => Some(msg.parent_hash),
+			_ => None,
+		}).collect();
+		let live_relay_parents: HashSet<B::Hash> = self.messages.iter().filter_map(|&(_, _, ref message)| match *message {
+			GenericMessage::Collation(ref msg) => Some(msg.relay_parent),
+			_ => None,
+		}).collect();
+		self.collation_origins.retain(|h, _| hashes.contains(h));
 		for (_, ref mut peer) in self.peers.iter_mut() {
 			peer.known_messages.retain(|h| hashes.contains(h));
+			peer.erasure_chunks.retain(|parent_hash, _| live_rounds.contains(parent_hash));
+			peer.collations.retain(|relay_parent, _| live_relay_parents.contains(relay_parent));
 		}
 	}
 }
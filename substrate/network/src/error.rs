@@ -22,6 +22,7 @@ use client;
 error_chain! {
 	foreign_links {
 		Network(NetworkError) #[doc = "Devp2p error."];
+		Io(::std::io::Error) #[doc = "IO error."];
 	}
 
 	links {
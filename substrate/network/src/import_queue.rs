@@ -0,0 +1,741 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Blocks import queue.
+//!
+//! Blocks downloaded by the sync are verified and imported away from the network thread.
+//! Justifications of a batch of blocks are checked in parallel by a pool of verifier threads,
+//! against the authorities at the parent of the first block of the batch. The importer then
+//! executes the blocks in order, re-checking justifications of blocks whose parent authorities
+//! differ.
+
+use std::cmp;
+use std::collections::{HashSet, VecDeque};
+use std::marker::PhantomData;
+use std::mem;
+use std::sync::{mpsc, Arc, Weak};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread::{self, JoinHandle};
+use parking_lot::{Condvar, Mutex, RwLock};
+
+use client::{BlockOrigin, BlockStatus, ImportResult, JustifiedHeader};
+use network::PeerId;
+use primitives::AuthorityId;
+use runtime_primitives::bft::Justification;
+use runtime_primitives::generic::BlockId;
+use runtime_primitives::traits::{Block as BlockT, Header as HeaderT};
+
+use blocks::BlockData;
+use chain::Client;
+use error;
+use io::SyncIo;
+use protocol::Protocol;
+use service::ExecuteInContext;
+use sync::ChainSync;
+
+/// Number of threads checking justifications of blocks.
+const VERIFIER_THREADS: usize = 4;
+
+/// Blocks import queue API.
+pub trait ImportQueue<B: BlockT>: Send + Sync {
+	/// Clear the queue when sync is restarting. Blocks being imported are dropped.
+	fn clear(&self);
+	/// Clear the queue and stop importing.
+	fn stop(&self);
+	/// Is block with given hash queued for import.
+	fn is_importing(&self, hash: &B::Hash) -> bool;
+	/// Schedule import of a batch of blocks, given in ascending order.
+	fn import_blocks(&self, sync: &mut ChainSync<B>, io: &mut SyncIo, protocol: &Protocol<B>, blocks: (BlockOrigin, Vec<BlockData<B>>));
+}
+
+/// Receives the outcome of block imports which requires an action of the sync.
+pub trait Link<B: BlockT> {
+	/// Disable the peer which provided a bad block.
+	fn disable_peer(&mut self, peer: PeerId);
+	/// Search for the common ancestor with the peer which provided a block with the given number
+	/// whose parent is not in our chain.
+	fn request_ancestry(&mut self, peer: PeerId, number: u64);
+	/// Restart the sync. Causes the search for a common ancestor with all peers.
+	fn restart(&mut self);
+}
+
+/// Import queue verifying and importing blocks in background threads.
+pub struct AsyncImportQueue<B: BlockT> {
+	handle: Mutex<Option<JoinHandle<()>>>,
+	data: Arc<AsyncImportQueueData<B>>,
+}
+
+struct AsyncImportQueueData<B: BlockT> {
+	signal: Condvar,
+	queue: Mutex<VecDeque<(BlockOrigin, Vec<BlockData<B>>)>>,
+	queued_blocks: RwLock<HashSet<B::Hash>>,
+	// Incremented each time the queue is cleared, to drop the batch being imported.
+	generation: AtomicUsize,
+	is_stopping: AtomicBool,
+}
+
+impl<B: BlockT + 'static> AsyncImportQueue<B> where
+	B::Header: HeaderT<Number=u64>,
+{
+	/// Create a new import queue. Nothing is imported until it is started.
+	pub fn new() -> Self {
+		AsyncImportQueue {
+			handle: Mutex::new(None),
+			data: Arc::new(AsyncImportQueueData {
+				signal: Condvar::new(),
+				queue: Mutex::new(VecDeque::new()),
+				queued_blocks: RwLock::new(HashSet::new()),
+				generation: AtomicUsize::new(0),
+				is_stopping: AtomicBool::new(false),
+			}),
+		}
+	}
+
+	/// Start the importer thread, reporting import failures over the given link.
+	pub fn start<L: Link<B> + Send + 'static>(&self, link: L, chain: Arc<Client<B>>) -> error::Result<()> {
+		debug_assert!(self.handle.lock().is_none());
+
+		let data = self.data.clone();
+		let handle = thread::Builder::new()
+			.name("ImportQueue".into())
+			.spawn(move || import_thread(link, chain, data))?;
+		*self.handle.lock() = Some(handle);
+		Ok(())
+	}
+
+	/// Whether all scheduled blocks have been processed.
+	#[cfg(test)]
+	pub fn is_idle(&self) -> bool {
+		self.data.queue.lock().is_empty() && self.data.queued_blocks.read().is_empty()
+	}
+}
+
+impl<B: BlockT> ImportQueue<B> for AsyncImportQueue<B> {
+	fn clear(&self) {
+		let mut queue = self.data.queue.lock();
+		self.data.generation.fetch_add(1, Ordering::SeqCst);
+		self.data.queued_blocks.write().clear();
+		queue.clear();
+	}
+
+	fn stop(&self) {
+		self.clear();
+		if let Some(handle) = self.handle.lock().take() {
+			{
+				// the importer checks the flag only while holding the queue lock.
+				let _queue = self.data.queue.lock();
+				self.data.is_stopping.store(true, Ordering::SeqCst);
+			}
+			self.data.signal.notify_one();
+
+			// the queue may be dropped by the importer itself, when it holds the last
+			// reference to the network service.
+			if handle.thread().id() != thread::current().id() {
+				let _ = handle.join();
+			}
+		}
+	}
+
+	fn is_importing(&self, hash: &B::Hash) -> bool {
+		self.data.queued_blocks.read().contains(hash)
+	}
+
+	fn import_blocks(&self, _sync: &mut ChainSync<B>, _io: &mut SyncIo, _protocol: &Protocol<B>, blocks: (BlockOrigin, Vec<BlockData<B>>)) {
+		if blocks.1.is_empty() {
+			return;
+		}
+
+		trace!(target: "sync", "Scheduling {} blocks for import", blocks.1.len());
+		let mut queue = self.data.queue.lock();
+		self.data.queued_blocks.write().extend(blocks.1.iter().map(|block| block.block.hash));
+		queue.push_back(blocks);
+		self.data.signal.notify_one();
+	}
+}
+
+impl<B: BlockT> Drop for AsyncImportQueue<B> {
+	fn drop(&mut self) {
+		self.stop();
+	}
+}
+
+fn import_thread<B: BlockT + 'static, L: Link<B>>(mut link: L, chain: Arc<Client<B>>, data: Arc<AsyncImportQueueData<B>>) where
+	B::Header: HeaderT<Number=u64>,
+{
+	trace!(target: "sync", "Starting import thread");
+	let verifiers = VerifierPool::new(chain.clone(), VERIFIER_THREADS);
+	loop {
+		let ((origin, blocks), generation) = {
+			let mut queue = data.queue.lock();
+			while queue.is_empty() && !data.is_stopping.load(Ordering::SeqCst) {
+				data.signal.wait(&mut queue);
+			}
+			if data.is_stopping.load(Ordering::SeqCst) {
+				break;
+			}
+
+			let blocks = queue.pop_front().expect("loop above exits only when the queue is not empty or stopping; qed");
+			(blocks, data.generation.load(Ordering::SeqCst))
+		};
+
+		let hashes: Vec<_> = blocks.iter().map(|block| block.block.hash).collect();
+		let blocks = verify_many_blocks(&verifiers, &*chain, blocks);
+		import_many_blocks(&*chain, &mut link, origin, blocks, &|| data.generation.load(Ordering::SeqCst) != generation);
+
+		let mut queued_blocks = data.queued_blocks.write();
+		for hash in hashes {
+			queued_blocks.remove(&hash);
+		}
+	}
+	trace!(target: "sync", "Stopping import thread");
+}
+
+/// Link reporting import failures to the sync of the network service.
+pub struct NetworkLink<B, E> {
+	service: Weak<E>,
+	_block: PhantomData<B>,
+}
+
+impl<B, E> NetworkLink<B, E> {
+	/// Create a new link to the given service.
+	pub fn new(service: Weak<E>) -> Self {
+		NetworkLink {
+			service,
+			_block: PhantomData,
+		}
+	}
+}
+
+impl<B: BlockT, E: ExecuteInContext<B>> Link<B> for NetworkLink<B, E> where
+	B::Header: HeaderT<Number=u64>,
+{
+	fn disable_peer(&mut self, peer: PeerId) {
+		if let Some(service) = self.service.upgrade() {
+			service.execute_in_context(|io, _| io.disable_peer(peer));
+		}
+	}
+
+	fn request_ancestry(&mut self, peer: PeerId, number: u64) {
+		if let Some(service) = self.service.upgrade() {
+			service.execute_in_context(|io, protocol| protocol.on_unknown_parent(io, peer, number));
+		}
+	}
+
+	fn restart(&mut self) {
+		if let Some(service) = self.service.upgrade() {
+			service.execute_in_context(|io, protocol| protocol.restart_sync(io));
+		}
+	}
+}
+
+/// Import queue importing blocks right away, on the calling thread.
+#[cfg(test)]
+pub struct SyncImportQueue;
+
+#[cfg(test)]
+impl<B: BlockT> ImportQueue<B> for SyncImportQueue where
+	B::Header: HeaderT<Number=u64>,
+{
+	fn clear(&self) { }
+
+	fn stop(&self) { }
+
+	fn is_importing(&self, _hash: &B::Hash) -> bool {
+		false
+	}
+
+	fn import_blocks(&self, sync: &mut ChainSync<B>, io: &mut SyncIo, protocol: &Protocol<B>, blocks: (BlockOrigin, Vec<BlockData<B>>)) {
+		let (origin, blocks) = blocks;
+		let blocks = blocks.into_iter().map(|block| QueuedBlock::new(protocol.chain(), None, block)).collect();
+		import_many_blocks(protocol.chain(), &mut SyncLink { sync, io, protocol }, origin, blocks, &|| false);
+	}
+}
+
+#[cfg(test)]
+struct SyncLink<'a, B: 'a + BlockT> {
+	sync: &'a mut ChainSync<B>,
+	io: &'a mut SyncIo,
+	protocol: &'a Protocol<B>,
+}
+
+#[cfg(test)]
+impl<'a, B: 'a + BlockT> Link<B> for SyncLink<'a, B> where
+	B::Header: HeaderT<Number=u64>,
+{
+	fn disable_peer(&mut self, peer: PeerId) {
+		self.io.disable_peer(peer);
+	}
+
+	fn request_ancestry(&mut self, peer: PeerId, number: u64) {
+		self.sync.on_unknown_parent(self.io, self.protocol, peer, number);
+	}
+
+	fn restart(&mut self) {
+		self.sync.restart(self.io, self.protocol);
+	}
+}
+
+/// Reason a block could not be imported.
+#[derive(Debug, PartialEq)]
+enum BlockImportError {
+	/// Block is invalid. The peer which provided it should be disabled.
+	Disable(PeerId),
+	/// Parent of the block with the given number is not in our chain. The common ancestor with
+	/// the peer which provided it should be searched for.
+	UnknownParent(PeerId, u64),
+	/// Block can not be imported on top of our chain. Sync should be restarted.
+	Restart,
+}
+
+/// State of the justification of a block.
+enum Verification<B: BlockT> {
+	/// Justified by the given authorities.
+	Justified(JustifiedHeader<B>, Arc<Vec<AuthorityId>>),
+	/// Justification has to be checked against the authorities at the block's parent.
+	Unchecked(B::Header, Justification<B::Hash>),
+}
+
+/// Block passed through the verifier stage.
+struct QueuedBlock<B: BlockT> {
+	origin: PeerId,
+	hash: B::Hash,
+	number: <B::Header as HeaderT>::Number,
+	parent_hash: B::Hash,
+	verification: Verification<B>,
+	body: Option<Vec<B::Extrinsic>>,
+}
+
+impl<B: BlockT> QueuedBlock<B> {
+	/// Check the justification of a block against the given authorities, if any.
+	fn new(chain: &Client<B>, authorities: Option<&Arc<Vec<AuthorityId>>>, block: BlockData<B>) -> Result<Self, BlockImportError> {
+		let origin = block.origin;
+		let block = block.block;
+		let (header, justification) = match (block.header, block.justification) {
+			(Some(header), Some(justification)) => (header, justification),
+			(None, _) => {
+				debug!(target: "sync", "Header {} was not provided by {} ", block.hash, origin);
+				return Err(BlockImportError::Disable(origin));
+			},
+			(_, None) => {
+				debug!(target: "sync", "Justification set for block {} was not provided by {} ", block.hash, origin);
+				return Err(BlockImportError::Disable(origin));
+			},
+		};
+
+		let hash = header.hash();
+		let number = header.number().clone();
+		let parent_hash = header.parent_hash().clone();
+		let verification = match authorities {
+			Some(authorities) => match chain.check_justification(authorities, header.clone(), justification.clone()) {
+				Ok(justified) => Verification::Justified(justified, authorities.clone()),
+				Err(_) => Verification::Unchecked(header, justification),
+			},
+			None => Verification::Unchecked(header, justification),
+		};
+
+		Ok(QueuedBlock {
+			origin,
+			hash,
+			number,
+			parent_hash,
+			verification,
+			body: block.body,
+		})
+	}
+}
+
+/// Chunk of a batch of blocks to be verified by the pool.
+struct VerifierJob<B: BlockT> {
+	index: usize,
+	blocks: Vec<BlockData<B>>,
+	authorities: Arc<Vec<AuthorityId>>,
+	result: mpsc::Sender<(usize, Vec<Result<QueuedBlock<B>, BlockImportError>>)>,
+}
+
+/// Long-lived threads checking justifications of blocks for the importer.
+struct VerifierPool<B: BlockT> {
+	jobs: Option<mpsc::Sender<VerifierJob<B>>>,
+	threads: Vec<JoinHandle<()>>,
+}
+
+impl<B: BlockT + 'static> VerifierPool<B> {
+	/// Spawn the given number of verifier threads. If none could be spawned, jobs are verified
+	/// by the importer itself.
+	fn new(chain: Arc<Client<B>>, count: usize) -> Self {
+		let (jobs, receiver) = mpsc::channel();
+		let receiver = Arc::new(Mutex::new(receiver));
+		let mut threads = Vec::with_capacity(count);
+		for _ in 0..count {
+			let chain = chain.clone();
+			let receiver = receiver.clone();
+			match thread::Builder::new().name("ImportQueueVerifier".into()).spawn(move || verifier_thread(chain, receiver)) {
+				Ok(thread) => threads.push(thread),
+				Err(e) => {
+					warn!(target: "sync", "Error spawning verifier thread: {:?}", e);
+					break;
+				},
+			}
+		}
+
+		VerifierPool {
+			jobs: Some(jobs),
+			threads,
+		}
+	}
+
+	/// Schedule a job. The job is given back when there is no verifier to run it.
+	fn verify(&self, job: VerifierJob<B>) -> Result<(), VerifierJob<B>> {
+		match self.jobs {
+			Some(ref jobs) => jobs.send(job).map_err(|mpsc::SendError(job)| job),
+			None => Err(job),
+		}
+	}
+}
+
+impl<B: BlockT> Drop for VerifierPool<B> {
+	fn drop(&mut self) {
+		// verifiers stop once the jobs channel is closed.
+		self.jobs.take();
+		for thread in self.threads.drain(..) {
+			let _ = thread.join();
+		}
+	}
+}
+
+fn verifier_thread<B: BlockT>(chain: Arc<Client<B>>, jobs: Arc<Mutex<mpsc::Receiver<VerifierJob<B>>>>) {
+	loop {
+		let job = match jobs.lock().recv() {
+			Ok(job) => job,
+			Err(_) => break,
+		};
+
+		let VerifierJob { index, blocks, authorities, result } = job;
+		let verified = blocks.into_iter()
+			.map(|block| QueuedBlock::new(&*chain, Some(&authorities), block))
+			.collect();
+		let _ = result.send((index, verified));
+	}
+}
+
+/// Check justifications of a batch of blocks in parallel, against the authorities at the parent
+/// of the first block. Blocks which can not be justified that way are left for the importer.
+fn verify_many_blocks<B: BlockT + 'static>(verifiers: &VerifierPool<B>, chain: &Client<B>, mut blocks: Vec<BlockData<B>>) -> Vec<Result<QueuedBlock<B>, BlockImportError>> {
+	let count = blocks.len();
+	let authorities = blocks.first()
+		.and_then(|block| block.block.header.as_ref())
+		.and_then(|header| chain.authorities(&BlockId::Hash(header.parent_hash().clone())).ok())
+		.map(Arc::new);
+
+	let authorities = match authorities {
+		Some(authorities) => authorities,
+		None => return blocks.into_iter().map(|block| QueuedBlock::new(chain, None, block)).collect(),
+	};
+
+	let (result, results) = mpsc::channel();
+	let chunk_size = cmp::max(1, (blocks.len() + VERIFIER_THREADS - 1) / VERIFIER_THREADS);
+	let mut index = 0;
+	while !blocks.is_empty() {
+		let rest = blocks.split_off(cmp::min(chunk_size, blocks.len()));
+		let job = VerifierJob {
+			index,
+			blocks: mem::replace(&mut blocks, rest),
+			authorities: authorities.clone(),
+			result: result.clone(),
+		};
+		if let Err(job) = verifiers.verify(job) {
+			let verified = job.blocks.into_iter()
+				.map(|block| QueuedBlock::new(chain, Some(&job.authorities), block))
+				.collect();
+			let _ = result.send((job.index, verified));
+		}
+		index += 1;
+	}
+	drop(result);
+
+	// the channel is closed once every job is either done or lost to a panicking verifier.
+	let mut chunks: Vec<_> = results.iter().collect();
+	chunks.sort_by_key(|&(index, _)| index);
+	let mut verified = Vec::with_capacity(count);
+	for (expected, (index, chunk)) in chunks.into_iter().enumerate() {
+		if index != expected {
+			warn!(target: "sync", "Verifier thread panicked");
+			break;
+		}
+		verified.extend(chunk);
+	}
+
+	// blocks which could not be verified are downloaded again.
+	if verified.len() < count {
+		verified.push(Err(BlockImportError::Restart));
+	}
+	verified
+}
+
+/// Import a batch of blocks in order. Stops at the first failure, or when cancelled.
+fn import_many_blocks<B: BlockT>(
+	chain: &Client<B>,
+	link: &mut Link<B>,
+	origin: BlockOrigin,
+	blocks: Vec<Result<QueuedBlock<B>, BlockImportError>>,
+	is_cancelled: &Fn() -> bool,
+) where
+	B::Header: HeaderT<Number=u64>,
+{
+	let count = blocks.len();
+	let mut imported = 0;
+	for block in blocks {
+		if is_cancelled() {
+			trace!(target: "sync", "Import of {} blocks cancelled after {}", count, imported);
+			return;
+		}
+
+		match block.and_then(|block| import_single_block(chain, origin.clone(), block)) {
+			Ok(()) => imported += 1,
+			Err(BlockImportError::Disable(peer)) => {
+				link.disable_peer(peer); //TODO: use persistent ID
+				link.restart();
+				return;
+			},
+			Err(BlockImportError::UnknownParent(peer, number)) => {
+				link.request_ancestry(peer, number);
+				return;
+			},
+			Err(BlockImportError::Restart) => {
+				link.restart();
+				return;
+			},
+		}
+	}
+	trace!(target: "sync", "Imported {} of {}", imported, count);
+}
+
+fn import_single_block<B: BlockT>(chain: &Client<B>, origin: BlockOrigin, block: QueuedBlock<B>) -> Result<(), BlockImportError> where
+	B::Header: HeaderT<Number=u64>,
+{
+	let QueuedBlock { origin: peer, hash, number, parent_hash, verification, body } = block;
+
+	// check whether the block is known before importing.
+	match chain.block_status(&BlockId::Hash(hash)) {
+		Ok(BlockStatus::InChain) => return Ok(()),
+		Ok(_) => {},
+		Err(e) => {
+			debug!(target: "sync", "Error importing block {}: {:?}: {:?}", number, hash, e);
+			return Err(BlockImportError::Restart);
+		}
+	}
+
+	match chain.block_status(&BlockId::Hash(parent_hash)) {
+		Ok(BlockStatus::InChain) => {},
+		Ok(_) => {
+			debug!(target: "sync", "Block with unknown parent {}: {:?}, parent: {:?}", number, hash, parent_hash);
+			return Err(BlockImportError::UnknownParent(peer, number));
+		},
+		Err(e) => {
+			debug!(target: "sync", "Error importing block {}: {:?}: {:?}", number, hash, e);
+			return Err(BlockImportError::Restart);
+		}
+	}
+
+	let authorities = chain.authorities(&BlockId::Hash(parent_hash)).map_err(|e| {
+		debug!(target: "sync", "Error reading authorities for block {}: {:?}: {:?}", number, hash, e);
+		BlockImportError::Restart
+	})?;
+
+	let justified = match verification {
+		Verification::Justified(justified, justified_by) => if *justified_by == authorities {
+			Ok(justified)
+		} else {
+			let (header, justification) = justified.into_inner();
+			chain.check_justification(&authorities, header, justification.uncheck().into())
+		},
+		Verification::Unchecked(header, justification) => chain.check_justification(&authorities, header, justification),
+	};
+	let justified = justified.map_err(|e| {
+		debug!(target: "sync", "Bad justification for block {}: {:?}: {:?}", number, hash, e);
+		BlockImportError::Disable(peer)
+	})?;
+
+	match chain.import(origin, justified, body) {
+		Ok(ImportResult::AlreadyInChain) => {
+			trace!(target: "sync", "Block already in chain {}: {:?}", number, hash);
+			Ok(())
+		},
+		Ok(ImportResult::AlreadyQueued) => {
+			trace!(target: "sync", "Block already queued {}: {:?}", number, hash);
+			Ok(())
+		},
+		Ok(ImportResult::Queued) => {
+			trace!(target: "sync", "Block queued {}: {:?}", number, hash);
+			Ok(())
+		},
+		Ok(ImportResult::UnknownParent) => {
+			debug!(target: "sync", "Block with unknown parent {}: {:?}, parent: {:?}", number, hash, parent_hash);
+			Err(BlockImportError::UnknownParent(peer, number))
+		},
+		Ok(ImportResult::KnownBad) => {
+			debug!(target: "sync", "Bad block {}: {:?}", number, hash);
+			Err(BlockImportError::Disable(peer))
+		},
+		Err(e) => {
+			debug!(target: "sync", "Error importing block {}: {:?}: {:?}", number, hash, e);
+			Err(BlockImportError::Restart)
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::mpsc;
+	use std::time::Duration;
+	use client;
+	use message;
+	use test_client::{self, TestClient};
+	use test_client::runtime::Block;
+
+	#[derive(Debug, PartialEq)]
+	enum Event {
+		Disable(PeerId),
+		RequestAncestry(PeerId, u64),
+		Restart,
+	}
+
+	struct TestLink(mpsc::Sender<Event>);
+
+	impl Link<Block> for TestLink {
+		fn disable_peer(&mut self, peer: PeerId) {
+			let _ = self.0.send(Event::Disable(peer));
+		}
+
+		fn request_ancestry(&mut self, peer: PeerId, number: u64) {
+			let _ = self.0.send(Event::RequestAncestry(peer, number));
+		}
+
+		fn restart(&mut self) {
+			let _ = self.0.send(Event::Restart);
+		}
+	}
+
+	fn test_link() -> (TestLink, mpsc::Receiver<Event>) {
+		let (tx, rx) = mpsc::channel();
+		(TestLink(tx), rx)
+	}
+
+	/// Produce a chain of `count` blocks as downloaded from the peer `origin`.
+	fn prepare_blocks(count: u64, origin: PeerId) -> Vec<BlockData<Block>> {
+		let source = test_client::new();
+		for _ in 0..count {
+			let block = source.new_block().unwrap().bake().unwrap();
+			source.justify_and_import(BlockOrigin::File, block).unwrap();
+		}
+
+		(1..count + 1).map(|number| {
+			let id = BlockId::Number(number);
+			BlockData {
+				block: message::generic::BlockData {
+					hash: source.block_hash(number).unwrap().unwrap(),
+					header: source.header(&id).unwrap(),
+					body: source.body(&id).unwrap(),
+					receipt: None,
+					message_queue: None,
+					justification: source.justification(&id).unwrap(),
+				},
+				origin,
+			}
+		}).collect()
+	}
+
+	fn import(client: &Arc<client::Client<test_client::Backend, test_client::Executor, Block>>, blocks: Vec<BlockData<Block>>) -> Vec<Event> {
+		let chain = client.clone() as Arc<Client<Block>>;
+		let (mut link, events) = test_link();
+		let verifiers = VerifierPool::new(chain.clone(), VERIFIER_THREADS);
+		let blocks = verify_many_blocks(&verifiers, &*chain, blocks);
+		import_many_blocks(&*chain, &mut link, BlockOrigin::NetworkInitialSync, blocks, &|| false);
+		events.try_iter().collect()
+	}
+
+	#[test]
+	fn imports_verified_blocks_in_order() {
+		let client = Arc::new(test_client::new());
+
+		assert_eq!(import(&client, prepare_blocks(10, 1)), vec![]);
+		assert_eq!(client.info().unwrap().chain.best_number, 10);
+	}
+
+	#[test]
+	fn requests_ancestry_on_unknown_parent() {
+		let client = Arc::new(test_client::new());
+		let mut blocks = prepare_blocks(3, 1);
+		blocks.remove(0);
+
+		assert_eq!(import(&client, blocks), vec![Event::RequestAncestry(1, 2)]);
+		assert_eq!(client.info().unwrap().chain.best_number, 0);
+	}
+
+	#[test]
+	fn disables_peer_providing_bad_justification() {
+		let client = Arc::new(test_client::new());
+		let mut blocks = prepare_blocks(3, 1);
+		blocks[1].origin = 2;
+		blocks[1].block.justification.as_mut().unwrap().signatures.clear();
+
+		assert_eq!(import(&client, blocks), vec![Event::Disable(2), Event::Restart]);
+		assert_eq!(client.info().unwrap().chain.best_number, 1);
+	}
+
+	#[test]
+	fn disables_peer_not_providing_header() {
+		let client = Arc::new(test_client::new());
+		let mut blocks = prepare_blocks(1, 1);
+		blocks[0].block.header = None;
+
+		assert_eq!(import(&client, blocks), vec![Event::Disable(1), Event::Restart]);
+	}
+
+	#[test]
+	fn async_queue_imports_blocks_in_background() {
+		let client = Arc::new(test_client::new());
+		let queue = AsyncImportQueue::new();
+		let (link, events) = test_link();
+		queue.start(link, client.clone()).unwrap();
+
+		let mut blocks = prepare_blocks(3, 1);
+		blocks.remove(1);
+		let hash = blocks[0].block.hash;
+		queue.data.queued_blocks.write().extend(blocks.iter().map(|block| block.block.hash));
+		queue.data.queue.lock().push_back((BlockOrigin::NetworkInitialSync, blocks));
+		queue.data.signal.notify_one();
+
+		assert_eq!(events.recv_timeout(Duration::from_secs(10)), Ok(Event::RequestAncestry(1, 3)));
+		assert_eq!(client.info().unwrap().chain.best_hash, hash);
+		queue.stop();
+		assert!(!queue.is_importing(&hash));
+	}
+
+	#[test]
+	fn verifies_blocks_without_verifier_threads() {
+		let client = Arc::new(test_client::new());
+		let chain = client.clone() as Arc<Client<Block>>;
+		let verifiers = VerifierPool::new(chain.clone(), 0);
+		let (mut link, events) = test_link();
+
+		let blocks = verify_many_blocks(&verifiers, &*chain, prepare_blocks(5, 1));
+		assert_eq!(blocks.len(), 5);
+		import_many_blocks(&*chain, &mut link, BlockOrigin::NetworkInitialSync, blocks, &|| false);
+		assert_eq!(events.try_iter().collect::<Vec<_>>(), vec![]);
+		assert_eq!(client.info().unwrap().chain.best_number, 5);
+	}
+}
@@ -56,16 +56,18 @@ mod chain;
 mod blocks;
 mod consensus;
 mod on_demand;
+mod import_queue;
 pub mod error;
 
 #[cfg(test)] mod test;
 
-pub use service::{Service, FetchFuture, ConsensusService, BftMessageStream,
-	TransactionPool, Params, ManageNetwork, SyncProvider};
-pub use protocol::{ProtocolStatus};
+pub use service::{Service, FetchFuture, ConsensusService, BftMessageStream, ErasureChunkStream, CollationStream,
+	TransactionPool, Params, ManageNetwork, SyncProvider, PeerInfo};
+pub use protocol::{ProtocolStatus, PeerInfo as ProtocolPeerInfo};
 pub use sync::{Status as SyncStatus, SyncState};
 pub use network::{NonReservedPeerMode, NetworkConfiguration, ConnectionFilter, ConnectionDirection};
-pub use message::{generic as generic_message, BftMessage, LocalizedBftMessage, ConsensusVote, SignedConsensusVote, SignedConsensusMessage, SignedConsensusProposal};
+pub use message::{generic as generic_message, BftMessage, LocalizedBftMessage, ConsensusVote, SignedConsensusVote, SignedConsensusMessage, SignedConsensusProposal,
+	ErasureChunkMessage, CollationMessage};
 pub use error::Error;
 pub use config::{Role, ProtocolConfig};
 pub use on_demand::{OnDemand, OnDemandService, Response as OnDemandResponse};
@@ -50,6 +50,16 @@ pub type LocalizedBftMessage<B> = generic::LocalizedBftMessage<
 	<B as BlockT>::Hash,
 >;
 
+/// Type alias for using the erasure chunk message type using block type parameters.
+pub type ErasureChunkMessage<B> = generic::ErasureChunkMessage<
+	<B as BlockT>::Hash,
+>;
+
+/// Type alias for using the collation message type using block type parameters.
+pub type CollationMessage<B> = generic::CollationMessage<
+	<B as BlockT>::Hash,
+>;
+
 /// Type alias for using the BlockData type using block type parameters.
 pub type BlockData<B> = generic::BlockData<
 	<B as BlockT>::Header,
@@ -259,6 +269,32 @@ pub mod generic {
 		AdvanceRound(u32),
 	}
 
+	/// An erasure-coded chunk of candidate data, addressed to the validator
+	/// with the given index.
+	#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+	pub struct ErasureChunkMessage<Hash> {
+		/// Parent header hash of the consensus round the candidate belongs to.
+		pub parent_hash: Hash,
+		/// Hash of the candidate receipt.
+		pub candidate_hash: Hash,
+		/// Index of the validator the chunk is meant for.
+		pub index: u32,
+		/// The chunk data.
+		pub chunk: Vec<u8>,
+		/// Merkle proof of the chunk against the candidate's erasure root.
+		pub proof: Vec<Hash>,
+	}
+
+	/// A collation submitted by a collator to the validators of the consensus
+	/// round on the given relay parent.
+	#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+	pub struct CollationMessage<Hash> {
+		/// Relay chain parent hash the collation builds upon.
+		pub relay_parent: Hash,
+		/// The encoded collation. Opaque to the network.
+		pub collation: Vec<u8>,
+	}
+
 	/// A localized message.
 	#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 	pub enum SignedConsensusMessage<Block, Hash> {
@@ -283,6 +319,10 @@ pub mod generic {
 		Transactions(Transactions<Extrinsic>),
 		/// BFT Consensus statement.
 		BftMessage(LocalizedBftMessage<Block, Hash>),
+		/// Erasure-coded chunk of parachain candidate data.
+		ErasureChunk(ErasureChunkMessage<Hash>),
+		/// Parachain collation.
+		Collation(CollationMessage<Hash>),
 		/// Remote method call request.
 		RemoteCallRequest(RemoteCallRequest<Hash>),
 		/// Remote method call response.
@@ -294,7 +294,7 @@ mod tests {
 	use futures::Future;
 	use parking_lot::RwLock;
 	use client;
-	use client::light::{FetchChecker, RemoteCallRequest};
+	use client::light::{FetchChecker, RemoteCallRequest, RemoteChangesRequest};
 	use io::NetSyncIo;
 	use message;
 	use network::PeerId;
@@ -302,7 +302,7 @@ mod tests {
 	use service::{Role, ExecuteInContext};
 	use test::TestIo;
 	use super::{REQUEST_TIMEOUT, OnDemand, OnDemandService};
-	use test_client::runtime::{Block, Hash};
+	use test_client::runtime::{Block, BlockNumber, Hash};
 
 	struct DummyExecutor;
 	struct DummyFetchChecker { ok: bool }
@@ -321,6 +321,13 @@ mod tests {
 				false => Err(client::error::ErrorKind::Backend("Test error".into()).into()),
 			}
 		}
+
+		fn check_changes_proof(&self, _request: &RemoteChangesRequest<BlockNumber>, remote_proof: (Vec<(BlockNumber, u32)>, Vec<Vec<u8>>)) -> client::error::Result<Vec<(BlockNumber, u32)>> {
+			match self.ok {
+				true => Ok(remote_proof.0),
+				false => Err(client::error::ErrorKind::Backend("Test error".into()).into()),
+			}
+		}
 	}
 
 	fn dummy(ok: bool) -> (Arc<DummyExecutor>, Arc<OnDemand<Block, DummyExecutor>>) {
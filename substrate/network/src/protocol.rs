@@ -28,10 +28,11 @@ use message::{self, Message};
 use message::generic::Message as GenericMessage;
 use sync::{ChainSync, Status as SyncStatus, SyncState};
 use consensus::Consensus;
-use service::{Role, TransactionPool, BftMessageStream};
+use service::{Role, TransactionPool, BftMessageStream, ErasureChunkStream, CollationStream};
 use config::ProtocolConfig;
 use chain::Client;
 use on_demand::OnDemandService;
+use import_queue::ImportQueue;
 use io::SyncIo;
 use error;
 
@@ -108,11 +109,12 @@ impl<B: BlockT> Protocol<B> where
 	pub fn new(
 		config: ProtocolConfig,
 		chain: Arc<Client<B>>,
+		import_queue: Arc<ImportQueue<B>>,
 		on_demand: Option<Arc<OnDemandService>>,
 		transaction_pool: Arc<TransactionPool<B>>
 	) -> error::Result<Self>  {
 		let info = chain.info()?;
-		let sync = ChainSync::new(config.roles, &info);
+		let sync = ChainSync::new(config.roles, &info, import_queue);
 		let protocol = Protocol {
 			config: config,
 			chain: chain,
@@ -138,6 +140,11 @@ impl<B: BlockT> Protocol<B> where
 		}
 	}
 
+	/// Returns the roles of this node.
+	pub fn roles(&self) -> Role {
+		self.config.roles
+	}
+
 	pub fn handle_packet(&self, io: &mut SyncIo, peer_id: PeerId, data: &[u8]) {
 		let message: Message<B> = match serde_json::from_slice(data) {
 			Ok(m) => m,
@@ -180,6 +187,8 @@ impl<B: BlockT> Protocol<B> where
 				self.on_block_announce(io, peer_id, announce);
 			},
 			GenericMessage::BftMessage(m) => self.on_bft_message(io, peer_id, m, HashingFor::<B>::hash(data)),
+			GenericMessage::ErasureChunk(m) => self.on_erasure_chunk(io, peer_id, m, HashingFor::<B>::hash(data)),
+			GenericMessage::Collation(m) => self.on_collation(io, peer_id, m, HashingFor::<B>::hash(data)),
 			GenericMessage::Transactions(m) => self.on_transactions(io, peer_id, m),
 			GenericMessage::RemoteCallRequest(request) => self.on_remote_call_request(io, peer_id, request),
 			GenericMessage::RemoteCallResponse(response) => self.on_remote_call_response(io, peer_id, response)
@@ -306,6 +315,41 @@ impl<B: BlockT> Protocol<B> where
 		self.consensus.lock().bft_messages(parent_hash)
 	}
 
+	fn on_erasure_chunk(&self, io: &mut SyncIo, peer: PeerId, message: message::ErasureChunkMessage<B>, hash: B::Hash) {
+		trace!(target: "sync", "Erasure chunk {} for candidate {} from {}", message.index, message.candidate_hash, peer);
+		self.consensus.lock().on_erasure_chunk(io, self, peer, message, hash);
+	}
+
+	/// See `ConsensusService` trait.
+	pub fn send_erasure_chunk(&self, io: &mut SyncIo, message: message::ErasureChunkMessage<B>) {
+		self.consensus.lock().send_erasure_chunk(io, self, message)
+	}
+
+	/// See `ConsensusService` trait.
+	pub fn erasure_chunks(&self, parent_hash: B::Hash) -> ErasureChunkStream<B> {
+		self.consensus.lock().erasure_chunks(parent_hash)
+	}
+
+	fn on_collation(&self, io: &mut SyncIo, peer: PeerId, message: message::CollationMessage<B>, hash: B::Hash) {
+		trace!(target: "sync", "Collation on {} from {}", message.relay_parent, peer);
+		self.consensus.lock().on_collation(io, self, peer, message, hash);
+	}
+
+	/// See `ConsensusService` trait.
+	pub fn send_collation(&self, io: &mut SyncIo, message: message::CollationMessage<B>) {
+		self.consensus.lock().send_collation(io, self, message)
+	}
+
+	/// See `ConsensusService` trait.
+	pub fn collations(&self, relay_parent: B::Hash) -> CollationStream<B> {
+		self.consensus.lock().collations(relay_parent)
+	}
+
+	/// See `ConsensusService` trait.
+	pub fn report_bad_collation(&self, io: &mut SyncIo, message: message::CollationMessage<B>) {
+		self.consensus.lock().report_bad_collation(io, message)
+	}
+
 	/// Perform time based maintenance.
 	pub fn tick(&self, io: &mut SyncIo) {
 		self.maintain_peers(io);
@@ -487,6 +531,16 @@ impl<B: BlockT> Protocol<B> where
 		self.sync.write().on_block_announce(io, self, peer_id, hash, &header);
 	}
 
+	/// Restart the sync, e.g. after a failed block import.
+	pub fn restart_sync(&self, io: &mut SyncIo) {
+		self.sync.write().restart(io, self);
+	}
+
+	/// Search for the common ancestor with a peer which provided a block with unknown parent.
+	pub fn on_unknown_parent(&self, io: &mut SyncIo, peer_id: PeerId, number: u64) {
+		self.sync.write().on_unknown_parent(io, self, peer_id, number);
+	}
+
 	pub fn on_block_imported(&self, io: &mut SyncIo, hash: B::Hash, header: &B::Header) {
 		self.sync.write().update_chain_info(&header);
 		// send out block announcements
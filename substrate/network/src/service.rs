@@ -28,7 +28,8 @@ use protocol::{Protocol, ProtocolStatus, PeerInfo as ProtocolPeerInfo};
 use config::{ProtocolConfig};
 use error::Error;
 use chain::Client;
-use message::LocalizedBftMessage;
+use import_queue::{AsyncImportQueue, ImportQueue, NetworkLink};
+use message::{LocalizedBftMessage, ErasureChunkMessage, CollationMessage};
 use on_demand::OnDemandService;
 use runtime_primitives::traits::{Block as BlockT, Header as HeaderT};
 
@@ -41,6 +42,10 @@ const V0_PACKET_COUNT: u8 = 1;
 pub type FetchFuture = oneshot::Receiver<Vec<u8>>;
 /// Type that represents bft messages stream.
 pub type BftMessageStream<B> = mpsc::UnboundedReceiver<LocalizedBftMessage<B>>;
+/// Type that represents erasure chunk message stream.
+pub type ErasureChunkStream<B> = mpsc::UnboundedReceiver<ErasureChunkMessage<B>>;
+/// Type that represents collation message stream.
+pub type CollationStream<B> = mpsc::UnboundedReceiver<CollationMessage<B>>;
 
 const TICK_TOKEN: TimerToken = 0;
 const TICK_TIMEOUT: Duration = Duration::from_millis(1000);
@@ -72,6 +77,8 @@ pub trait SyncProvider<B: BlockT>: Send + Sync {
 	fn peers(&self) -> Vec<PeerInfo<B>>;
 	/// Get this node id if available.
 	fn node_id(&self) -> Option<String>;
+	/// Get the roles of this node.
+	fn roles(&self) -> Role;
 }
 
 /// Transaction pool interface
@@ -94,6 +101,19 @@ pub trait ConsensusService<B: BlockT>: Send + Sync {
 	fn bft_messages(&self, parent_hash: B::Hash) -> BftMessageStream<B>;
 	/// Send out a BFT message.
 	fn send_bft_message(&self, message: LocalizedBftMessage<B>);
+
+	/// Get a stream of erasure chunks gossiped for the consensus round on the
+	/// given parent hash.
+	fn erasure_chunks(&self, parent_hash: B::Hash) -> ErasureChunkStream<B>;
+	/// Gossip an erasure chunk to other validators.
+	fn send_erasure_chunk(&self, message: ErasureChunkMessage<B>);
+
+	/// Get a stream of collations submitted for the given relay parent.
+	fn collations(&self, relay_parent: B::Hash) -> CollationStream<B>;
+	/// Submit a collation to connected validators.
+	fn send_collation(&self, message: CollationMessage<B>);
+	/// Report an invalid collation. The collator which submitted it is disabled.
+	fn report_bad_collation(&self, message: CollationMessage<B>);
 }
 
 /// Service able to execute closure in the network context.
@@ -144,18 +164,23 @@ pub struct Service<B: BlockT + 'static> where B::Header: HeaderT<Number=u64> {
 	network: NetworkService,
 	/// Devp2p protocol handler
 	handler: Arc<ProtocolHandler<B>>,
+	/// Blocks import queue
+	import_queue: Arc<AsyncImportQueue<B>>,
 }
 
 impl<B: BlockT + 'static> Service<B> where B::Header: HeaderT<Number=u64> {
 	/// Creates and register protocol with the network service
 	pub fn new(params: Params<B>) -> Result<Arc<Service<B>>, Error> {
 		let service = NetworkService::new(params.network_config.clone(), None)?;
+		let import_queue = Arc::new(AsyncImportQueue::new());
 		let sync = Arc::new(Service {
 			network: service,
 			handler: Arc::new(ProtocolHandler {
-				protocol: Protocol::new(params.config, params.chain, params.on_demand, params.transaction_pool)?,
+				protocol: Protocol::new(params.config, params.chain.clone(), import_queue.clone(), params.on_demand, params.transaction_pool)?,
 			}),
+			import_queue: import_queue.clone(),
 		});
+		import_queue.start(NetworkLink::new(Arc::downgrade(&sync)), params.chain)?;
 
 		Ok(sync)
 	}
@@ -194,6 +219,7 @@ impl<B: BlockT + 'static> Service<B> where B::Header: HeaderT<Number=u64> {
 impl<B: BlockT + 'static> Drop for Service<B> where B::Header: HeaderT<Number=u64> {
 	fn drop(&mut self) {
 		self.stop();
+		self.import_queue.stop();
 	}
 }
 
@@ -237,6 +263,10 @@ impl<B: BlockT + 'static> SyncProvider<B> for Service<B> where B::Header: Header
 	fn node_id(&self) -> Option<String> {
 		self.network.external_url()
 	}
+
+	fn roles(&self) -> Role {
+		self.handler.protocol.roles()
+	}
 }
 
 /// ConsensusService
@@ -254,6 +284,32 @@ impl<B: BlockT + 'static> ConsensusService<B> for Service<B> where B::Header: He
 			self.handler.protocol.send_bft_message(&mut NetSyncIo::new(context), message);
 		});
 	}
+
+	fn erasure_chunks(&self, parent_hash: B::Hash) -> ErasureChunkStream<B> {
+		self.handler.protocol.erasure_chunks(parent_hash)
+	}
+
+	fn send_erasure_chunk(&self, message: ErasureChunkMessage<B>) {
+		self.network.with_context(DOT_PROTOCOL_ID, |context| {
+			self.handler.protocol.send_erasure_chunk(&mut NetSyncIo::new(context), message);
+		});
+	}
+
+	fn collations(&self, relay_parent: B::Hash) -> CollationStream<B> {
+		self.handler.protocol.collations(relay_parent)
+	}
+
+	fn send_collation(&self, message: CollationMessage<B>) {
+		self.network.with_context(DOT_PROTOCOL_ID, |context| {
+			self.handler.protocol.send_collation(&mut NetSyncIo::new(context), message);
+		});
+	}
+
+	fn report_bad_collation(&self, message: CollationMessage<B>) {
+		self.network.with_context(DOT_PROTOCOL_ID, |context| {
+			self.handler.protocol.report_bad_collation(&mut NetSyncIo::new(context), message);
+		});
+	}
 }
 
 impl<B: BlockT + 'static> NetworkProtocolHandler for ProtocolHandler<B> where B::Header: HeaderT<Number=u64> {
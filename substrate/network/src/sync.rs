@@ -14,12 +14,15 @@
 // You should have received a copy of the GNU General Public License
 // along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.?
 
+use std::cmp;
 use std::collections::HashMap;
+use std::sync::Arc;
 use io::SyncIo;
 use protocol::Protocol;
 use network::PeerId;
-use client::{ImportResult, BlockStatus, ClientInfo};
+use client::{BlockStatus, BlockOrigin, ClientInfo};
 use blocks::{self, BlockCollection};
+use import_queue::ImportQueue;
 use runtime_primitives::traits::{Block as BlockT, Header as HeaderT};
 use runtime_primitives::generic::BlockId;
 use message::{self, generic::Message as GenericMessage};
@@ -52,6 +55,7 @@ pub struct ChainSync<B: BlockT> {
 	best_queued_number: u64,
 	best_queued_hash: B::Hash,
 	required_block_attributes: Vec<message::BlockAttribute>,
+	import_queue: Arc<ImportQueue<B>>,
 }
 
 /// Reported sync state.
@@ -70,13 +74,17 @@ pub struct Status<B: BlockT> {
 	pub state: SyncState,
 	/// Target sync block number.
 	pub best_seen_block: Option<<B::Header as HeaderT>::Number>,
+	/// Best block number imported or queued for import.
+	pub best_queued_number: <B::Header as HeaderT>::Number,
+	/// Best block hash imported or queued for import.
+	pub best_queued_hash: B::Hash,
 }
 
 impl<B: BlockT> ChainSync<B> where
 	B::Header: HeaderT<Number=u64>,
 {
 	/// Create a new instance.
-	pub fn new(role: Role, info: &ClientInfo<B>) -> Self {
+	pub fn new(role: Role, info: &ClientInfo<B>, import_queue: Arc<ImportQueue<B>>) -> Self {
 		let mut required_block_attributes = vec![
 			message::BlockAttribute::Header,
 			message::BlockAttribute::Justification
@@ -92,6 +100,7 @@ impl<B: BlockT> ChainSync<B> where
 			best_queued_hash: info.best_queued_hash.unwrap_or(info.chain.best_hash),
 			best_queued_number: info.best_queued_number.unwrap_or(info.chain.best_number),
 			required_block_attributes: required_block_attributes,
+			import_queue,
 		}
 	}
 
@@ -109,6 +118,8 @@ impl<B: BlockT> ChainSync<B> where
 		Status {
 			state: state,
 			best_seen_block: best_seen,
+			best_queued_number: self.best_queued_number,
+			best_queued_hash: self.best_queued_hash,
 		}
 	}
 
@@ -168,8 +179,6 @@ impl<B: BlockT> ChainSync<B> where
 	}
 
 	pub fn on_block_data(&mut self, io: &mut SyncIo, protocol: &Protocol<B>, peer_id: PeerId, _request: message::BlockRequest<B>, response: message::BlockResponse<B>) {
-		let count = response.blocks.len();
-		let mut imported: usize = 0;
 		let new_blocks = if let Some(ref mut peer) = self.peers.get_mut(&peer_id) {
 			match peer.state {
 				PeerSyncState::DownloadingNew(start_block) => {
@@ -230,80 +239,24 @@ impl<B: BlockT> ChainSync<B> where
 			vec![]
 		};
 
-		let best_seen = self.best_seen_block();
-		// Blocks in the response/drain should be in ascending order.
-		for block in new_blocks {
-			let origin = block.origin;
-			let block = block.block;
-			match (block.header, block.justification) {
-				(Some(header), Some(justification)) => {
-					let number = header.number().clone();
-					let hash = header.hash();
-					let parent = header.parent_hash().clone();
-					let is_best = best_seen.as_ref().map_or(false, |n| number >= *n);
-
-					// check whether the block is known before importing.
-					match protocol.chain().block_status(&BlockId::Hash(hash)) {
-						Ok(BlockStatus::InChain) => continue,
-						Ok(_) => {},
-						Err(e) => {
-							debug!(target: "sync", "Error importing block {}: {:?}: {:?}", number, hash, e);
-							self.restart(io, protocol);
-							return;
-						}
-					}
-
-					let result = protocol.chain().import(
-						is_best,
-						header,
-						justification,
-						block.body
-					);
-					match result {
-						Ok(ImportResult::AlreadyInChain) => {
-							trace!(target: "sync", "Block already in chain {}: {:?}", number, hash);
-							self.block_imported(&hash, number);
-						},
-						Ok(ImportResult::AlreadyQueued) => {
-							trace!(target: "sync", "Block already queued {}: {:?}", number, hash);
-							self.block_imported(&hash, number);
-						},
-						Ok(ImportResult::Queued) => {
-							trace!(target: "sync", "Block queued {}: {:?}", number, hash);
-							self.block_imported(&hash, number);
-							imported = imported + 1;
-						},
-						Ok(ImportResult::UnknownParent) => {
-							debug!(target: "sync", "Block with unknown parent {}: {:?}, parent: {:?}", number, hash, parent);
-							self.restart(io, protocol);
-							return;
-						},
-						Ok(ImportResult::KnownBad) => {
-							debug!(target: "sync", "Bad block {}: {:?}", number, hash);
-							io.disable_peer(origin); //TODO: use persistent ID
-							self.restart(io, protocol);
-							return;
-						}
-						Err(e) => {
-							debug!(target: "sync", "Error importing block {}: {:?}: {:?}", number, hash, e);
-							self.restart(io, protocol);
-							return;
-						}
-					}
-				},
-				(None, _) => {
-					debug!(target: "sync", "Header {} was not provided by {} ", block.hash, origin);
-					io.disable_peer(origin); //TODO: use persistent ID
-					return;
-				},
-				(_, None) => {
-					debug!(target: "sync", "Justification set for block {} was not provided by {} ", block.hash, origin);
-					io.disable_peer(origin); //TODO: use persistent ID
-					return;
+		if !new_blocks.is_empty() {
+			let best_seen = self.best_seen_block();
+			let is_best = new_blocks.last()
+				.and_then(|block| block.block.header.as_ref())
+				.map_or(false, |header| best_seen.map_or(false, |n| *header.number() >= n));
+			let origin = if is_best { BlockOrigin::NetworkBroadcast } else { BlockOrigin::NetworkInitialSync };
+
+			// Blocks in the response/drain should be in ascending order.
+			for block in &new_blocks {
+				if let Some(ref header) = block.block.header {
+					self.block_imported(&header.hash(), *header.number());
 				}
 			}
+
+			let import_queue = self.import_queue.clone();
+			import_queue.import_blocks(self, io, protocol, (origin, new_blocks));
 		}
-		trace!(target: "sync", "Imported {} of {}", imported, count);
+
 		self.maintain_sync(io, protocol);
 	}
 
@@ -368,6 +321,7 @@ impl<B: BlockT> ChainSync<B> where
 
 	fn is_known_or_already_downloading(&self, protocol: &Protocol<B>, hash: &B::Hash) -> bool {
 		self.peers.iter().any(|(_, p)| p.state == PeerSyncState::DownloadingStale(*hash))
+			|| self.import_queue.is_importing(hash)
 			|| protocol.chain().block_status(&BlockId::Hash(*hash)).ok().map_or(false, |s| s != BlockStatus::Unknown)
 	}
 
@@ -378,11 +332,48 @@ impl<B: BlockT> ChainSync<B> where
 	}
 
 	pub fn restart(&mut self, io: &mut SyncIo, protocol: &Protocol<B>) {
+		self.import_queue.clear();
 		self.blocks.clear();
 		let ids: Vec<PeerId> = self.peers.keys().map(|p| *p).collect();
 		for id in ids {
 			self.new_peer(io, protocol, id);
 		}
+		self.reset_best_queued(protocol);
+	}
+
+	/// Handle a block with the given number which could not be imported because its parent is
+	/// not in our chain. Instead of restarting the sync, the common ancestor is searched for with
+	/// the peer which provided it.
+	pub fn on_unknown_parent(&mut self, io: &mut SyncIo, protocol: &Protocol<B>, peer_id: PeerId, number: u64) {
+		self.import_queue.clear();
+		self.blocks.clear();
+		self.reset_best_queued(protocol);
+
+		// blocks above our best were dropped from the queue and have to be downloaded again.
+		let (best_hash, best_number) = (self.best_queued_hash, self.best_queued_number);
+		for peer in self.peers.values_mut() {
+			if peer.common_number > best_number {
+				peer.common_number = best_number;
+				peer.common_hash = best_hash;
+			}
+		}
+
+		let ancestor = cmp::min(number.saturating_sub(1), best_number);
+		let search = match self.peers.get_mut(&peer_id) {
+			Some(ref mut peer) if ancestor > 0 => {
+				debug!(target:"sync", "Block #{} from {} has unknown parent, searching for common ancestor.", number, peer_id);
+				peer.state = PeerSyncState::AncestorSearch(ancestor);
+				true
+			},
+			_ => false,
+		};
+		if search {
+			Self::request_ancestry(io, protocol, peer_id, ancestor);
+		}
+		self.maintain_sync(io, protocol);
+	}
+
+	fn reset_best_queued(&mut self, protocol: &Protocol<B>) {
 		match protocol.chain().info() {
 			Ok(info) => {
 				self.best_queued_hash = info.best_queued_hash.unwrap_or(info.chain.best_hash);
@@ -397,6 +388,7 @@ impl<B: BlockT> ChainSync<B> where
 	}
 
 	pub fn clear(&mut self) {
+		self.import_queue.clear();
 		self.blocks.clear();
 		self.peers.clear();
 	}
@@ -51,3 +51,120 @@ fn bft_messages_include_those_sent_before_asking_for_stream() {
 
 	assert_eq!(stream.wait().next(), Some(Ok(localized)));
 }
+
+#[test]
+fn erasure_chunks_include_those_sent_before_asking_for_stream() {
+	let mut config = ::config::ProtocolConfig::default();
+	config.roles = ::service::Role::VALIDATOR | ::service::Role::FULL;
+
+	let mut net = TestNet::new_with_config(2, config);
+	net.sync(); // necessary for handshaking
+
+	let peer = net.peer(0);
+	let mut io = TestIo::new(&peer.queue, None);
+	let parent_hash = peer.genesis_hash();
+
+	let chunk = ::message::ErasureChunkMessage::<Block> {
+		parent_hash: parent_hash,
+		candidate_hash: [1; 32].into(),
+		index: 1,
+		chunk: vec![1, 2, 3],
+		proof: vec![[2; 32].into()],
+	};
+
+	let message: Message<Block> = generic::Message::ErasureChunk(chunk.clone());
+
+	let as_bytes = ::serde_json::to_vec(&message).unwrap();
+	peer.sync.handle_packet(&mut io, 1, &as_bytes[..]);
+
+	let stream = peer.sync.erasure_chunks(parent_hash);
+
+	assert_eq!(stream.wait().next(), Some(Ok(chunk)));
+}
+
+fn collation(relay_parent: Hash, data: u8) -> ::message::CollationMessage<Block> {
+	::message::CollationMessage::<Block> {
+		relay_parent,
+		collation: vec![data; 4],
+	}
+}
+
+fn send_collation(peer: &Peer, io: &mut TestIo, from: PeerId, collation: &::message::CollationMessage<Block>) {
+	let message: Message<Block> = generic::Message::Collation(collation.clone());
+	let as_bytes = ::serde_json::to_vec(&message).unwrap();
+	peer.sync.handle_packet(io, from, &as_bytes[..]);
+}
+
+#[test]
+fn collations_are_only_accepted_from_collators() {
+	let mut config = ::config::ProtocolConfig::default();
+	config.roles = ::service::Role::COLLATOR | ::service::Role::FULL;
+
+	let mut net = TestNet::new_with_config(2, config);
+	net.sync(); // necessary for handshaking
+
+	let peer = net.peer(0);
+	let mut io = TestIo::new(&peer.queue, None);
+	let relay_parent = peer.genesis_hash();
+
+	// peer 5 never completed a handshake.
+	send_collation(peer, &mut io, 5, &collation(relay_parent, 1));
+	send_collation(peer, &mut io, 1, &collation(relay_parent, 2));
+
+	let stream = peer.sync.collations(relay_parent);
+
+	assert_eq!(stream.wait().next(), Some(Ok(collation(relay_parent, 2))));
+}
+
+#[test]
+fn collations_are_limited_per_peer() {
+	let mut config = ::config::ProtocolConfig::default();
+	config.roles = ::service::Role::COLLATOR | ::service::Role::FULL;
+
+	let mut net = TestNet::new_with_config(3, config);
+	net.sync(); // necessary for handshaking
+
+	let peer = net.peer(0);
+	let mut io = TestIo::new(&peer.queue, None);
+	let relay_parent = peer.genesis_hash();
+
+	let limit = ::consensus::MAX_COLLATIONS_PER_PEER as u8;
+	for i in 0..limit + 1 {
+		send_collation(peer, &mut io, 1, &collation(relay_parent, i));
+	}
+	send_collation(peer, &mut io, 2, &collation(relay_parent, 100));
+
+	let received: Vec<_> = peer.sync.collations(relay_parent).wait()
+		.take(limit as usize + 1)
+		.map(|c| c.unwrap().collation[0])
+		.collect();
+
+	let mut expected: Vec<_> = (0..limit).collect();
+	expected.push(100);
+	assert_eq!(received, expected);
+}
+
+#[test]
+fn bad_collations_disable_their_collator() {
+	let mut config = ::config::ProtocolConfig::default();
+	config.roles = ::service::Role::COLLATOR | ::service::Role::FULL;
+
+	let mut net = TestNet::new_with_config(3, config);
+	net.sync(); // necessary for handshaking
+
+	let peer = net.peer(0);
+	let mut io = TestIo::new(&peer.queue, None);
+	let relay_parent = peer.genesis_hash();
+
+	send_collation(peer, &mut io, 1, &collation(relay_parent, 1));
+	peer.sync.report_bad_collation(&mut io, collation(relay_parent, 1));
+	assert!(io.to_disconnect.contains(&1));
+
+	// further collations from the disabled collator are ignored.
+	send_collation(peer, &mut io, 1, &collation(relay_parent, 2));
+	send_collation(peer, &mut io, 2, &collation(relay_parent, 3));
+
+	let stream = peer.sync.collations(relay_parent);
+
+	assert_eq!(stream.wait().next(), Some(Ok(collation(relay_parent, 3))));
+}
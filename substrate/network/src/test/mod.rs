@@ -18,7 +18,9 @@ mod consensus;
 mod sync;
 
 use std::collections::{VecDeque, HashSet, HashMap};
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
+use std::thread;
+use std::time::Duration;
 
 use parking_lot::RwLock;
 use client;
@@ -28,6 +30,7 @@ use runtime_primitives::generic::BlockId;
 use io::SyncIo;
 use protocol::Protocol;
 use config::ProtocolConfig;
+use import_queue::{AsyncImportQueue, ImportQueue, Link, SyncImportQueue};
 use service::TransactionPool;
 use network::{PeerId, SessionInfo, Error as NetworkError};
 use keyring::Keyring;
@@ -103,6 +106,7 @@ pub struct Peer {
 	client: Arc<client::Client<test_client::Backend, test_client::Executor, Block>>,
 	pub sync: Protocol<Block>,
 	pub queue: RwLock<VecDeque<TestPacket>>,
+	import_queue: Option<Arc<AsyncImportQueue<Block>>>,
 }
 
 impl Peer {
@@ -141,6 +145,7 @@ impl Peer {
 
 	/// Whether this peer is done syncing (has no messages to send).
 	fn is_done(&self) -> bool {
+		self.flush();
 		self.queue.read().is_empty()
 	}
 
@@ -155,7 +160,13 @@ impl Peer {
 		self.sync.abort();
 	}
 
+	/// Wait for the blocks scheduled for import to be processed.
 	fn flush(&self) {
+		if let Some(ref import_queue) = self.import_queue {
+			while !import_queue.is_idle() {
+				thread::sleep(Duration::from_millis(1));
+			}
+		}
 	}
 
 	fn generate_blocks<F>(&self, count: usize, mut edit_block: F) where F: FnMut(&mut BlockBuilder<test_client::Backend, test_client::Executor, Block>) {
@@ -193,6 +204,28 @@ impl Peer {
 	}
 }
 
+/// Link feeding results of the async import queue back to the sync of a peer.
+/// Peers disabled by the import queue are not disconnected.
+struct PeerLink(Weak<Peer>);
+
+impl Link<Block> for PeerLink {
+	fn disable_peer(&mut self, peer_id: PeerId) {
+		trace!("Import queue disabled peer {}", peer_id);
+	}
+
+	fn request_ancestry(&mut self, peer_id: PeerId, number: u64) {
+		if let Some(peer) = self.0.upgrade() {
+			peer.sync.on_unknown_parent(&mut TestIo::new(&peer.queue, None), peer_id, number);
+		}
+	}
+
+	fn restart(&mut self) {
+		if let Some(peer) = self.0.upgrade() {
+			peer.sync.restart_sync(&mut TestIo::new(&peer.queue, None));
+		}
+	}
+}
+
 struct EmptyTransactionPool;
 
 impl TransactionPool<Block> for EmptyTransactionPool {
@@ -219,6 +252,15 @@ impl TestNet {
 	}
 
 	pub fn new_with_config(n: usize, config: ProtocolConfig) -> Self {
+		Self::new_with_import_queue(n, config, false)
+	}
+
+	/// Create a network whose peers import blocks in the background, as the network service does.
+	pub fn new_async(n: usize) -> Self {
+		Self::new_with_import_queue(n, ProtocolConfig::default(), true)
+	}
+
+	fn new_with_import_queue(n: usize, config: ProtocolConfig, async_import: bool) -> Self {
 		let mut net = TestNet {
 			peers: Vec::new(),
 			started: false,
@@ -228,12 +270,24 @@ impl TestNet {
 		for _ in 0..n {
 			let client = Arc::new(test_client::new());
 			let tx_pool = Arc::new(EmptyTransactionPool);
-			let sync = Protocol::new(config.clone(), client.clone(), None, tx_pool).unwrap();
-			net.peers.push(Arc::new(Peer {
+			let import_queue = if async_import { Some(Arc::new(AsyncImportQueue::new())) } else { None };
+			let sync = {
+				let queue: Arc<ImportQueue<Block>> = match import_queue {
+					Some(ref import_queue) => import_queue.clone(),
+					None => Arc::new(SyncImportQueue),
+				};
+				Protocol::new(config.clone(), client.clone(), queue, None, tx_pool).unwrap()
+			};
+			let peer = Arc::new(Peer {
 				sync: sync,
-				client: client,
+				client: client.clone(),
 				queue: RwLock::new(VecDeque::new()),
-			}));
+				import_queue: import_queue.clone(),
+			});
+			if let Some(import_queue) = import_queue {
+				import_queue.start(PeerLink(Arc::downgrade(&peer)), client).unwrap();
+			}
+			net.peers.push(peer);
 		}
 		net
 	}
@@ -85,3 +85,45 @@ fn sync_after_fork_works() {
 	assert!(net.peer(2).client.backend().blockchain().canon_equals_to(&peer1_chain));
 }
 
+#[test]
+fn sync_from_two_peers_with_async_import_works() {
+	::env_logger::init().ok();
+	let mut net = TestNet::new_async(3);
+	net.peer(1).push_blocks(100, false);
+	net.peer(2).push_blocks(100, false);
+	net.sync();
+	assert!(net.peer(0).client.backend().blockchain().equals_to(net.peer(1).client.backend().blockchain()));
+	let status = net.peer(0).sync.status();
+	assert_eq!(status.sync.state, SyncState::Idle);
+}
+
+#[test]
+fn sync_long_chain_with_async_import_works() {
+	let mut net = TestNet::new_async(2);
+	net.peer(1).push_blocks(500, false);
+	net.sync();
+	assert!(net.peer(0).client.backend().blockchain().equals_to(net.peer(1).client.backend().blockchain()));
+}
+
+#[test]
+fn sync_after_fork_with_async_import_works() {
+	::env_logger::init().ok();
+	let mut net = TestNet::new_async(3);
+	net.peer(0).push_blocks(30, false);
+	net.peer(1).push_blocks(30, false);
+	net.peer(2).push_blocks(30, false);
+
+	net.peer(0).push_blocks(10, true);
+	net.peer(1).push_blocks(20, false);
+	net.peer(2).push_blocks(20, false);
+
+	net.peer(1).push_blocks(10, true);
+	net.peer(2).push_blocks(1, false);
+
+	// peer 1 has the best chain
+	let peer1_chain = net.peer(1).client.backend().blockchain().clone();
+	net.sync();
+	assert!(net.peer(0).client.backend().blockchain().canon_equals_to(&peer1_chain));
+	assert!(net.peer(1).client.backend().blockchain().canon_equals_to(&peer1_chain));
+	assert!(net.peer(2).client.backend().blockchain().canon_equals_to(&peer1_chain));
+}
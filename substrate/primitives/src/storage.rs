@@ -21,11 +21,41 @@ use bytes;
 use rstd::vec::Vec;
 
 /// Contract storage key.
-#[derive(PartialEq, Eq)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Hash)]
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
 pub struct StorageKey(#[cfg_attr(feature = "std", serde(with="bytes"))] pub Vec<u8>);
 
 /// Contract storage entry data.
-#[derive(PartialEq, Eq)]
+#[derive(PartialEq, Eq, Clone)]
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug))]
 pub struct StorageData(#[cfg_attr(feature = "std", serde(with="bytes"))] pub Vec<u8>);
+
+/// Storage change set
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize, Debug, PartialEq, Eq))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct StorageChangeSet<Hash> {
+	/// Block hash
+	pub block: Hash,
+	/// A list of changes
+	pub changes: Vec<(StorageKey, Option<StorageData>)>,
+}
+
+/// Storage keys which have a special meaning to the state machine.
+pub mod well_known_keys {
+	/// Prefix of child storage keys. The value at such a key in the main trie is the root
+	/// of the child trie addressed by that key.
+	pub const CHILD_STORAGE_KEY_PREFIX: &'static [u8] = b":child_storage:";
+
+	/// Index of the extrinsic currently being applied, as an encoded `u32`. Storage changes
+	/// are attributed to it in the changes trie.
+	pub const EXTRINSIC_INDEX: &'static [u8] = b":extrinsic_index";
+
+	/// Changes trie configuration. A changes trie is only built for blocks executed while
+	/// this key is set.
+	pub const CHANGES_TRIE_CONFIG: &'static [u8] = b":changes_trie";
+
+	/// Whether a key addresses a child trie.
+	pub fn is_child_storage_key(key: &[u8]) -> bool {
+		key.starts_with(CHILD_STORAGE_KEY_PREFIX)
+	}
+}
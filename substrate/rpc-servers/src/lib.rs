@@ -22,18 +22,28 @@ pub extern crate substrate_rpc as apis;
 
 extern crate jsonrpc_core as rpc;
 extern crate jsonrpc_http_server as http;
+extern crate jsonrpc_ipc_server as ipc;
 extern crate jsonrpc_pubsub as pubsub;
 extern crate jsonrpc_ws_server as ws;
+#[cfg(unix)]
 extern crate substrate_runtime_primitives;
 
 #[macro_use]
 extern crate log;
 
+mod middleware;
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::io;
-use substrate_runtime_primitives::traits::Block as BlockT;
+use substrate_runtime_primitives::traits::{Block as BlockT, Header as HeaderT};
+
+pub use middleware::{RpcMiddleware, is_unsafe};
 
-type Metadata = apis::metadata::Metadata;
-type RpcHandler = pubsub::PubSubHandler<Metadata>;
+/// RPC metadata used by all the servers.
+pub type Metadata = apis::metadata::Metadata;
+/// The RPC handler served over HTTP and WebSockets.
+pub type RpcHandler = pubsub::PubSubHandler<Metadata, RpcMiddleware>;
 
 /// Construct rpc `IoHandler`
 pub fn rpc_handler<Block: BlockT, S, C, A, Y>(
@@ -41,14 +51,15 @@ pub fn rpc_handler<Block: BlockT, S, C, A, Y>(
 	chain: C,
 	author: A,
 	system: Y,
+	middleware: RpcMiddleware,
 ) -> RpcHandler where
 	Block: 'static,
-	S: apis::state::StateApi<Block::Hash>,
-	C: apis::chain::ChainApi<Block::Hash, Block::Header, Metadata=Metadata>,
-	A: apis::author::AuthorApi<Block::Hash, Block::Extrinsic>,
-	Y: apis::system::SystemApi,
+	S: apis::state::StateApi<Block::Hash, Metadata=Metadata>,
+	C: apis::chain::ChainApi<Block::Hash, Block::Header, <Block::Header as HeaderT>::Number, Block::Extrinsic, Metadata=Metadata>,
+	A: apis::author::AuthorApi<Block::Hash, Block::Extrinsic, Metadata=Metadata>,
+	Y: apis::system::SystemApi<Block::Hash, <Block::Header as HeaderT>::Number>,
 {
-	let mut io = pubsub::PubSubHandler::default();
+	let mut io = pubsub::PubSubHandler::new(rpc::MetaIoHandler::with_middleware(middleware));
 	io.extend_with(state.to_delegate());
 	io.extend_with(chain.to_delegate());
 	io.extend_with(author.to_delegate());
@@ -57,23 +68,41 @@ pub fn rpc_handler<Block: BlockT, S, C, A, Y>(
 }
 
 /// Start HTTP server listening on given address.
+///
+/// `cors` and `hosts` are the allowed values of the `Origin` and `Host` headers, `None` allows any.
 pub fn start_http(
 	addr: &std::net::SocketAddr,
+	cors: Option<&Vec<String>>,
+	hosts: Option<&Vec<String>>,
 	io: RpcHandler,
 ) -> io::Result<http::Server> {
 	http::ServerBuilder::new(io)
 		.threads(4)
-		.rest_api(http::RestApi::Unsecure)
-		.cors(http::DomainsValidation::Disabled)
+		.rest_api(http::RestApi::Secure)
+		.cors(map_domains(cors))
+		.allowed_hosts(map_domains(hosts))
+		.meta_extractor(|request: &http::hyper::server::Request| match request.remote_addr() {
+			// every HTTP connection has its own remote port, so requests are limited per address.
+			Some(address) => Metadata::with_connection_id(connection_id(&address.ip())),
+			None => Default::default(),
+		})
 		.start_http(addr)
 }
 
 /// Start WS server listening on given address.
+///
+/// `cors` and `hosts` are the allowed values of the `Origin` and `Host` headers, `None` allows any.
 pub fn start_ws(
 	addr: &std::net::SocketAddr,
+	cors: Option<&Vec<String>>,
+	hosts: Option<&Vec<String>>,
 	io: RpcHandler,
 ) -> io::Result<ws::Server> {
-	ws::ServerBuilder::with_meta_extractor(io, |context: &ws::RequestContext| Metadata::new(context.sender()))
+	ws::ServerBuilder::with_meta_extractor(io, |context: &ws::RequestContext| {
+		Metadata::with_session(context.session_id, context.sender())
+	})
+		.allowed_origins(map_domains(cors))
+		.allowed_hosts(map_domains(hosts))
 		.start(addr)
 		.map_err(|err| match err {
 			ws::Error(ws::ErrorKind::Io(io), _) => io,
@@ -84,3 +113,127 @@ pub fn start_ws(
 			}
 		})
 }
+
+/// Start IPC server listening on given path.
+///
+/// On Unix the socket is a file which is made accessible only to the user running the node once
+/// bound.
+/// An existing socket file at the path is replaced, any other file is an error.
+pub fn start_ipc(
+	path: &std::path::Path,
+	io: RpcHandler,
+) -> io::Result<ipc::Server> {
+	remove_stale_socket(path)?;
+	let builder = ipc::ServerBuilder::with_meta_extractor(io, |context: &ipc::RequestContext| {
+		Metadata::with_session(context.session_id, context.sender.clone())
+	});
+	let server = builder.start(&path.to_string_lossy())?;
+	restrict_to_owner(path)?;
+	Ok(server)
+}
+
+// a socket file left by a previous run would prevent binding.
+#[cfg(unix)]
+fn remove_stale_socket(path: &std::path::Path) -> io::Result<()> {
+	use std::os::unix::fs::FileTypeExt;
+
+	match std::fs::symlink_metadata(path) {
+		Ok(ref metadata) if metadata.file_type().is_socket() => std::fs::remove_file(path),
+		Ok(_) => Err(io::Error::new(
+			io::ErrorKind::AlreadyExists,
+			format!("{} exists and is not a socket", path.display()),
+		)),
+		Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+		Err(e) => Err(e),
+	}
+}
+
+#[cfg(not(unix))]
+fn remove_stale_socket(_path: &std::path::Path) -> io::Result<()> {
+	Ok(())
+}
+
+// the umask is process-wide, so the mode of the socket is changed after binding
+// rather than creating it under a restrictive umask.
+#[cfg(unix)]
+fn restrict_to_owner(path: &std::path::Path) -> io::Result<()> {
+	use std::os::unix::fs::PermissionsExt;
+
+	std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+}
+
+#[cfg(not(unix))]
+fn restrict_to_owner(_path: &std::path::Path) -> io::Result<()> {
+	Ok(())
+}
+
+fn map_domains<T: for<'a> From<&'a str>>(domains: Option<&Vec<String>>) -> http::DomainsValidation<T> {
+	domains.map(|domains| domains.iter().map(|domain| domain.as_str().into()).collect::<Vec<_>>()).into()
+}
+
+fn connection_id(address: &std::net::IpAddr) -> u64 {
+	let mut hasher = DefaultHasher::new();
+	address.hash(&mut hasher);
+	hasher.finish()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+	use super::*;
+	use std::fs;
+	use std::io::{BufRead, BufReader, Write};
+	use std::os::unix::fs::PermissionsExt;
+	use std::os::unix::net::UnixStream;
+	use std::path::PathBuf;
+	use std::thread;
+	use rpc::futures::{future, Future};
+
+	fn socket_path(name: &str) -> PathBuf {
+		std::env::temp_dir().join(format!("substrate-rpc-test-{}-{}.ipc", name, std::process::id()))
+	}
+
+	#[test]
+	fn ipc_subscriptions_are_notified() {
+		let mut io: RpcHandler = pubsub::PubSubHandler::new(rpc::MetaIoHandler::with_middleware(RpcMiddleware::new(false, None)));
+		io.add_subscription(
+			"test_notification",
+			("test_subscribe", |_params, _meta: Metadata, subscriber: pubsub::Subscriber| {
+				let sink = subscriber.assign_id(pubsub::SubscriptionId::Number(5)).unwrap();
+				thread::spawn(move || sink.notify(rpc::Params::Array(vec![rpc::Value::from(42)])).wait());
+			}),
+			("test_unsubscribe", |_id| future::ok::<_, rpc::Error>(rpc::Value::Bool(true))),
+		);
+
+		let path = socket_path("subscriptions");
+		let _server = start_ipc(&path, io).unwrap();
+		assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o077, 0);
+
+		let mut stream = UnixStream::connect(&path).unwrap();
+		stream.write_all(b"{\"jsonrpc\":\"2.0\",\"method\":\"test_subscribe\",\"params\":[],\"id\":1}\n").unwrap();
+
+		// the notification may be sent before the subscription id is returned.
+		let mut reader = BufReader::new(stream);
+		let mut received: Vec<_> = (0..2).map(|_| {
+			let mut line = String::new();
+			reader.read_line(&mut line).unwrap();
+			line.trim().to_owned()
+		}).collect();
+		received.sort();
+
+		assert_eq!(received, vec![
+			r#"{"jsonrpc":"2.0","method":"test_notification","params":[42]}"#.to_owned(),
+			r#"{"jsonrpc":"2.0","result":5,"id":1}"#.to_owned(),
+		]);
+		fs::remove_file(&path).unwrap();
+	}
+
+	#[test]
+	fn refuses_to_replace_files_other_than_sockets() {
+		let path = socket_path("file");
+		fs::File::create(&path).unwrap().write_all(b"data").unwrap();
+
+		assert_eq!(remove_stale_socket(&path).unwrap_err().kind(), io::ErrorKind::AlreadyExists);
+		assert!(path.exists());
+		fs::remove_file(&path).unwrap();
+	}
+}
@@ -0,0 +1,234 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RPC middleware denying unsafe methods and limiting the request rate of connections.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use apis;
+use rpc;
+use rpc::futures::{future, Future};
+use Metadata;
+
+const RATE_LIMIT_WINDOW: Duration = Duration::from_millis(1000);
+
+/// Returns true if the method should not be exposed publicly.
+pub fn is_unsafe(method: &str) -> bool {
+	apis::author::UNSAFE_METHODS.iter()
+		.chain(apis::system::UNSAFE_METHODS)
+		.any(|unsafe_method| *unsafe_method == method)
+}
+
+/// RPC middleware denying unsafe methods and limiting the request rate of connections.
+#[derive(Debug, Default)]
+pub struct RpcMiddleware {
+	deny_unsafe: bool,
+	rate_limit: Option<u32>,
+	// Start of the current window and number of calls in it, per connection.
+	connections: Mutex<HashMap<u64, (Instant, u32)>>,
+}
+
+impl RpcMiddleware {
+	/// Create new middleware.
+	///
+	/// `rate_limit` is the maximal number of calls per second a single connection can make,
+	/// each call of a batch request counting separately.
+	pub fn new(deny_unsafe: bool, rate_limit: Option<u32>) -> Self {
+		RpcMiddleware {
+			deny_unsafe,
+			rate_limit,
+			connections: Default::default(),
+		}
+	}
+
+	fn is_denied(&self, request: &rpc::Request) -> bool {
+		if !self.deny_unsafe {
+			return false;
+		}
+
+		let is_unsafe_call = |call: &rpc::Call| match *call {
+			rpc::Call::MethodCall(ref call) => is_unsafe(&call.method),
+			rpc::Call::Notification(ref notification) => is_unsafe(&notification.method),
+			rpc::Call::Invalid(_) => false,
+		};
+		match *request {
+			rpc::Request::Single(ref call) => is_unsafe_call(call),
+			rpc::Request::Batch(ref calls) => calls.iter().any(is_unsafe_call),
+		}
+	}
+
+	fn is_rate_limited(&self, connection_id: u64, calls: u32, now: Instant) -> bool {
+		let limit = match self.rate_limit {
+			Some(limit) => limit,
+			None => return false,
+		};
+
+		let mut connections = self.connections.lock().expect("Rate limiter lock is never poisoned; qed");
+		// forget about connections which have been idle for a whole window.
+		connections.retain(|_, &mut (start, _)| now < start + RATE_LIMIT_WINDOW);
+		let &mut (_, ref mut count) = connections.entry(connection_id).or_insert((now, 0));
+		*count = count.saturating_add(calls);
+		*count > limit
+	}
+}
+
+impl rpc::Middleware<Metadata> for RpcMiddleware {
+	type Future = Box<Future<Item=Option<rpc::Response>, Error=()> + Send>;
+
+	fn on_request<F, X>(&self, request: rpc::Request, meta: Metadata, next: F) -> Self::Future where
+		F: FnOnce(rpc::Request, Metadata) -> X + Send,
+		X: Future<Item=Option<rpc::Response>, Error=()> + Send + 'static,
+	{
+		let calls = match request {
+			rpc::Request::Single(_) => 1,
+			rpc::Request::Batch(ref calls) => calls.len() as u32,
+		};
+		let rate_limited = meta.connection_id()
+			.map_or(false, |connection_id| self.is_rate_limited(connection_id, calls, Instant::now()));
+		if rate_limited {
+			return Box::new(future::ok(reject(&request, rpc::Error {
+				code: rpc::ErrorCode::ServerError(-32011),
+				message: "Too many requests".into(),
+				data: None,
+			})));
+		}
+
+		if self.is_denied(&request) {
+			return Box::new(future::ok(reject(&request, rpc::Error {
+				code: rpc::ErrorCode::ServerError(-32010),
+				message: "Method is unsafe and not allowed".into(),
+				data: None,
+			})));
+		}
+
+		Box::new(next(request, meta))
+	}
+}
+
+/// Respond to every call of the request with given error.
+fn reject(request: &rpc::Request, error: rpc::Error) -> Option<rpc::Response> {
+	let output = |call: &rpc::Call| match *call {
+		rpc::Call::MethodCall(ref call) => Some(rpc::Output::from(Err(error.clone()), call.id.clone(), call.jsonrpc)),
+		rpc::Call::Notification(_) => None,
+		rpc::Call::Invalid(ref id) => Some(rpc::Output::invalid_request(id.clone(), None)),
+	};
+
+	match *request {
+		rpc::Request::Single(ref call) => output(call).map(rpc::Response::Single),
+		rpc::Request::Batch(ref calls) => {
+			let outputs = calls.iter().filter_map(output).collect::<Vec<_>>();
+			if outputs.is_empty() {
+				None
+			} else {
+				Some(rpc::Response::Batch(outputs))
+			}
+		},
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn handler(middleware: RpcMiddleware) -> rpc::MetaIoHandler<Metadata, RpcMiddleware> {
+		let mut io = rpc::MetaIoHandler::with_middleware(middleware);
+		io.add_method("author_submitExtrinsic", |_| Ok(rpc::Value::Bool(true)));
+		io.add_method("system_name", |_| Ok(rpc::Value::String("test".into())));
+		io
+	}
+
+	fn call(io: &rpc::MetaIoHandler<Metadata, RpcMiddleware>, meta: Metadata, method: &str) -> String {
+		let request = format!(r#"{{"jsonrpc":"2.0","method":"{}","params":[],"id":1}}"#, method);
+		io.handle_request_sync(&request, meta).unwrap()
+	}
+
+	#[test]
+	fn classifies_unsafe_methods() {
+		assert!(is_unsafe("author_submitExtrinsic"));
+		assert!(is_unsafe("system_networkState"));
+		assert!(!is_unsafe("system_name"));
+		assert!(!is_unsafe("state_getStorage"));
+	}
+
+	#[test]
+	fn denies_unsafe_methods() {
+		let io = handler(RpcMiddleware::new(true, None));
+
+		assert_eq!(
+			call(&io, Default::default(), "author_submitExtrinsic"),
+			r#"{"jsonrpc":"2.0","error":{"code":-32010,"message":"Method is unsafe and not allowed"},"id":1}"#
+		);
+		assert_eq!(
+			call(&io, Default::default(), "system_name"),
+			r#"{"jsonrpc":"2.0","result":"test","id":1}"#
+		);
+	}
+
+	#[test]
+	fn allows_unsafe_methods_if_not_denied() {
+		let io = handler(RpcMiddleware::new(false, None));
+
+		assert_eq!(
+			call(&io, Default::default(), "author_submitExtrinsic"),
+			r#"{"jsonrpc":"2.0","result":true,"id":1}"#
+		);
+	}
+
+	#[test]
+	fn limits_request_rate_per_connection() {
+		let io = handler(RpcMiddleware::new(false, Some(2)));
+		let limited = r#"{"jsonrpc":"2.0","error":{"code":-32011,"message":"Too many requests"},"id":1}"#;
+
+		assert!(call(&io, Metadata::with_connection_id(1), "system_name") != limited);
+		assert!(call(&io, Metadata::with_connection_id(1), "system_name") != limited);
+		assert_eq!(call(&io, Metadata::with_connection_id(1), "system_name"), limited);
+		// other connections are not affected.
+		assert!(call(&io, Metadata::with_connection_id(2), "system_name") != limited);
+		// requests without a known connection are not limited.
+		assert!(call(&io, Default::default(), "system_name") != limited);
+	}
+
+	#[test]
+	fn counts_every_call_of_a_batch() {
+		let io = handler(RpcMiddleware::new(false, Some(2)));
+		let batch = r#"[
+			{"jsonrpc":"2.0","method":"system_name","params":[],"id":1},
+			{"jsonrpc":"2.0","method":"system_name","params":[],"id":2},
+			{"jsonrpc":"2.0","method":"system_name","params":[],"id":3}
+		]"#;
+
+		assert_eq!(
+			io.handle_request_sync(batch, Metadata::with_connection_id(1)).unwrap(),
+			concat!(
+				r#"[{"jsonrpc":"2.0","error":{"code":-32011,"message":"Too many requests"},"id":1},"#,
+				r#"{"jsonrpc":"2.0","error":{"code":-32011,"message":"Too many requests"},"id":2},"#,
+				r#"{"jsonrpc":"2.0","error":{"code":-32011,"message":"Too many requests"},"id":3}]"#,
+			)
+		);
+	}
+
+	#[test]
+	fn forgets_idle_connections() {
+		let middleware = RpcMiddleware::new(false, Some(1));
+		let now = Instant::now();
+
+		assert!(!middleware.is_rate_limited(1, 1, now));
+		assert!(middleware.is_rate_limited(1, 1, now));
+		assert!(!middleware.is_rate_limited(1, 1, now + RATE_LIMIT_WINDOW));
+	}
+}
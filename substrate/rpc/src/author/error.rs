@@ -16,12 +16,14 @@
 
 //! Authoring RPC module errors.
 
+use client;
 use extrinsic_pool::txpool;
 use rpc;
 
 error_chain! {
 	links {
 		Pool(txpool::Error, txpool::ErrorKind) #[doc = "Pool error"];
+		Client(client::error::Error, client::error::ErrorKind) #[doc = "Client error"];
 	}
 	errors {
 		/// Not implemented yet
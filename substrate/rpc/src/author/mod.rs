@@ -17,7 +17,21 @@
 //! Substrate block-author/full-node API.
 
 use std::sync::Arc;
-use extrinsic_pool::api::{Error, ExtrinsicPool};
+
+use client::{self, Client, CallExecutor};
+use codec::Slicable;
+use extrinsic_pool::{self, api::{Error, ExtrinsicPool}};
+
+use jsonrpc_macros::{pubsub, Trailing};
+use jsonrpc_pubsub::SubscriptionId;
+use rpc::Result as RpcResult;
+use rpc::futures::{Future, Sink, Stream};
+use tokio_core::reactor::Remote;
+use runtime_primitives::ApplyOutcome;
+use runtime_primitives::generic::BlockId;
+use runtime_primitives::traits::Block as BlockT;
+
+use subscriptions::Subscriptions;
 
 pub mod error;
 
@@ -26,26 +40,166 @@ mod tests;
 
 use self::error::Result;
 
+/// Author API methods which modify the extrinsic pool and should not be exposed publicly.
+pub const UNSAFE_METHODS: &'static [&'static str] = &[
+	"author_submitExtrinsic",
+	"author_submitAndWatchExtrinsic",
+	"author_removeExtrinsic",
+];
+
 build_rpc_trait! {
 	/// Substrate authoring RPC API
 	pub trait AuthorApi<Hash, Extrinsic> {
+		type Metadata;
+
 		/// Submit extrinsic for inclusion in block.
 		#[rpc(name = "author_submitExtrinsic")]
 		fn submit_extrinsic(&self, Extrinsic) -> Result<Hash>;
+
+		/// Returns all extrinsics currently in the pool.
+		#[rpc(name = "author_pendingExtrinsics")]
+		fn pending_extrinsics(&self) -> Result<Vec<Extrinsic>>;
+
+		/// Remove given extrinsics from the pool. Returns hashes of removed extrinsics.
+		#[rpc(name = "author_removeExtrinsic")]
+		fn remove_extrinsic(&self, Vec<Hash>) -> Result<Vec<Hash>>;
+
+		/// Apply an extrinsic on top of a block's state, or the best block if none is given,
+		/// without committing any changes. Returns the outcome and the fee of the extrinsic.
+		#[rpc(name = "author_dryRun")]
+		fn dry_run(&self, Extrinsic, Trailing<Hash>) -> Result<DryRunResult>;
+
+		#[pubsub(name = "author_extrinsicUpdate")] {
+			/// Submit an extrinsic to watch.
+			#[rpc(name = "author_submitAndWatchExtrinsic")]
+			fn watch_extrinsic(&self, Self::Metadata, pubsub::Subscriber<extrinsic_pool::Status<Hash>>, Extrinsic);
+
+			/// Unsubscribe from extrinsic watching.
+			#[rpc(name = "author_unwatchExtrinsic")]
+			fn unwatch_extrinsic(&self, SubscriptionId) -> RpcResult<bool>;
+		}
 	}
 }
 
-impl<Ex, Hash, T> AuthorApi<Hash, Ex> for Arc<T> where
-	T: ExtrinsicPool<Ex, Hash>,
-	T::Error: 'static,
+/// Outcome of applying an extrinsic without committing it.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DryRunResult {
+	/// Was the extrinsic applied successfully.
+	pub success: bool,
+	/// The reason the extrinsic could not be applied, if any.
+	pub error: Option<String>,
+	/// Fee to be paid for the extrinsic, if the runtime supports fee queries.
+	pub fee: Option<String>,
+}
+
+/// Authoring API
+pub struct Author<B, E, Block: BlockT, P> {
+	/// Substrate client
+	client: Arc<Client<B, E, Block>>,
+	/// Extrinsic pool
+	pool: Arc<P>,
+	/// Subscriptions manager
+	subscriptions: Subscriptions,
+}
+
+impl<B, E, Block: BlockT, P> Author<B, E, Block, P> {
+	/// Create new instance of Authoring API.
+	pub fn new(client: Arc<Client<B, E, Block>>, pool: Arc<P>, remote: Remote) -> Self {
+		Author {
+			client,
+			pool,
+			subscriptions: Subscriptions::new(remote),
+		}
+	}
+}
+
+/// Decode a fee returned by the runtime. Balances are encoded as little-endian unsigned integers
+/// of at most 128 bits.
+fn decode_fee(encoded: &[u8]) -> Option<u128> {
+	if encoded.is_empty() || encoded.len() > 16 {
+		return None;
+	}
+
+	Some(encoded.iter().rev().fold(0u128, |fee, byte| (fee << 8) | *byte as u128))
+}
+
+fn into_rpc_error<E: Error + 'static>(e: E) -> error::Error {
+	e.into_pool_error()
+		.map(Into::into)
+		.unwrap_or_else(|e| error::ErrorKind::Verification(Box::new(e)).into())
+}
+
+impl<B, E, Block, Ex, P> AuthorApi<Block::Hash, Ex> for Author<B, E, Block, P> where
+	Block: BlockT + 'static,
+	B: client::backend::Backend<Block> + Send + Sync + 'static,
+	E: CallExecutor<Block> + Clone + Send + Sync + 'static,
+	client::error::Error: From<<<B as client::backend::Backend<Block>>::State as state_machine::backend::Backend>::Error>,
+	Ex: Slicable,
+	P: ExtrinsicPool<Ex, Block::Hash>,
+	P::Error: 'static,
 {
-	fn submit_extrinsic(&self, xt: Ex) -> Result<Hash> {
-		self
+	type Metadata = ::metadata::Metadata;
+
+	fn submit_extrinsic(&self, xt: Ex) -> Result<Block::Hash> {
+		self.pool
 			.submit(vec![xt])
 			.map(|mut res| res.pop().expect("One extrinsic passed; one result back; qed"))
-			.map_err(|e| e.into_pool_error()
-				.map(Into::into)
-				.unwrap_or_else(|e| error::ErrorKind::Verification(Box::new(e)).into())
-			)
+			.map_err(into_rpc_error)
+	}
+
+	fn pending_extrinsics(&self) -> Result<Vec<Ex>> {
+		Ok(self.pool.pending())
+	}
+
+	fn remove_extrinsic(&self, hashes: Vec<Block::Hash>) -> Result<Vec<Block::Hash>> {
+		Ok(self.pool.remove_extrinsics(&hashes))
+	}
+
+	fn dry_run(&self, xt: Ex, block: Trailing<Block::Hash>) -> Result<DryRunResult> {
+		let block = match block.into() {
+			Some(block) => block,
+			None => self.client.info()?.chain.best_hash,
+		};
+		let at = BlockId::Hash(block);
+		let encoded = xt.encode();
+
+		let error = match self.client.dry_run(&at, &encoded) {
+			Ok(ApplyOutcome::Success) => None,
+			Ok(ApplyOutcome::Fail) => Some("Extrinsic dispatch failed".into()),
+			Err(client::error::Error(client::error::ErrorKind::Execution(e), _)) => Some(e.to_string()),
+			Err(e) => return Err(e.into()),
+		};
+		let fee = self.client.call(&at, "payment_query_info", &encoded).ok()
+			.and_then(|result| decode_fee(&result.return_data))
+			.map(|fee| fee.to_string());
+
+		Ok(DryRunResult {
+			success: error.is_none(),
+			error,
+			fee,
+		})
+	}
+
+	fn watch_extrinsic(&self, _metadata: Self::Metadata, subscriber: pubsub::Subscriber<extrinsic_pool::Status<Block::Hash>>, xt: Ex) {
+		let watcher = match self.pool.submit_and_watch(xt).map_err(into_rpc_error) {
+			Ok(watcher) => watcher,
+			Err(err) => {
+				// reject the subscriber (ignore errors - we don't care if subscriber is no longer there).
+				let _ = subscriber.reject(err.into());
+				return;
+			},
+		};
+
+		self.subscriptions.add(subscriber, |sink| {
+			sink
+				.sink_map_err(|e| warn!("Error sending notifications: {:?}", e))
+				.send_all(watcher.map(Ok))
+				.map(|_| ())
+		})
+	}
+
+	fn unwatch_extrinsic(&self, id: SubscriptionId) -> RpcResult<bool> {
+		Ok(self.subscriptions.cancel(id))
 	}
 }
@@ -16,12 +16,22 @@
 
 use super::*;
 
-use std::{fmt, sync::Arc};
-use extrinsic_pool::api;
+use std::fmt;
+use extrinsic_pool::{api, Pool, txpool};
+use jsonrpc_macros::pubsub;
 use parking_lot::Mutex;
+use primitives::H256;
+use rpc::futures::Stream;
+use test_client;
 
 type Extrinsic = u64;
-type Hash = u64;
+type Hash = H256;
+
+fn hash(xt: Extrinsic) -> Hash {
+	let mut hash = [0u8; 32];
+	hash[24..].copy_from_slice(&xt.encode());
+	hash.into()
+}
 
 #[derive(Default)]
 struct DummyTxPool {
@@ -47,24 +57,139 @@ impl api::ExtrinsicPool<Extrinsic, Hash> for DummyTxPool {
 	fn submit(&self, xt: Vec<Extrinsic>) -> ::std::result::Result<Vec<Hash>, Self::Error> {
 		let mut submitted = self.submitted.lock();
 		if submitted.len() < 1 {
-			let hashes = xt.iter().map(|_xt| 1).collect();
+			let hashes = xt.iter().cloned().map(hash).collect();
 			submitted.extend(xt);
 			Ok(hashes)
 		} else {
 			Err(Error)
 		}
 	}
+
+	fn submit_and_watch(&self, _xt: Extrinsic) -> ::std::result::Result<extrinsic_pool::Watcher<Hash>, Self::Error> {
+		Err(Error)
+	}
+
+	fn pending(&self) -> Vec<Extrinsic> {
+		self.submitted.lock().clone()
+	}
+
+	fn remove_extrinsics(&self, hashes: &[Hash]) -> Vec<Hash> {
+		let mut submitted = self.submitted.lock();
+		let before = submitted.len();
+		submitted.retain(|xt| !hashes.contains(&hash(*xt)));
+		if before == submitted.len() { Vec::new() } else { hashes.to_vec() }
+	}
 }
 
 #[test]
 fn submit_transaction_should_not_cause_error() {
-	let p = Arc::new(DummyTxPool::default());
+	let core = ::tokio_core::reactor::Core::new().unwrap();
+	let p = Author::new(Arc::new(test_client::new()), Arc::new(DummyTxPool::default()), core.remote());
 
-	assert_matches!(
-		AuthorApi::submit_extrinsic(&p, 5),
-		Ok(1)
+	assert_eq!(
+		AuthorApi::submit_extrinsic(&p, 5).unwrap(),
+		hash(5)
 	);
 	assert!(
 		AuthorApi::submit_extrinsic(&p, 5).is_err()
 	);
 }
+
+#[test]
+fn should_return_and_remove_pending_extrinsics() {
+	let core = ::tokio_core::reactor::Core::new().unwrap();
+	let p = Author::new(Arc::new(test_client::new()), Arc::new(DummyTxPool::default()), core.remote());
+
+	AuthorApi::submit_extrinsic(&p, 5).unwrap();
+
+	assert_eq!(AuthorApi::pending_extrinsics(&p).unwrap(), vec![5]);
+	assert_eq!(AuthorApi::remove_extrinsic(&p, vec![hash(6)]).unwrap(), vec![]);
+	assert_eq!(AuthorApi::remove_extrinsic(&p, vec![hash(5)]).unwrap(), vec![hash(5)]);
+	assert_eq!(AuthorApi::pending_extrinsics(&p).unwrap(), vec![]);
+}
+
+#[test]
+fn should_dry_run_without_submitting() {
+	let core = ::tokio_core::reactor::Core::new().unwrap();
+	let p = Author::new(Arc::new(test_client::new()), Arc::new(DummyTxPool::default()), core.remote());
+
+	// not a valid extrinsic of the test runtime, which doesn't support fee queries either.
+	let result = AuthorApi::dry_run(&p, 5, None.into()).unwrap();
+	assert!(!result.success);
+	assert!(result.error.is_some());
+	assert_eq!(result.fee, None);
+
+	assert_eq!(AuthorApi::pending_extrinsics(&p).unwrap(), vec![]);
+}
+
+#[test]
+fn should_decode_fees() {
+	assert_eq!(decode_fee(&42u64.encode()), Some(42));
+	assert_eq!(decode_fee(&(u64::max_value() as u128 + 1).encode()), Some(u64::max_value() as u128 + 1));
+	assert_eq!(decode_fee(&[]), None);
+	assert_eq!(decode_fee(&[1; 17]), None);
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct VerifiedExtrinsic(Extrinsic, Hash);
+
+impl txpool::VerifiedTransaction for VerifiedExtrinsic {
+	type Hash = Hash;
+	type Sender = u64;
+
+	fn hash(&self) -> &Hash { &self.1 }
+	fn sender(&self) -> &u64 { &self.0 }
+	fn mem_usage(&self) -> usize { 1 }
+}
+
+impl AsRef<Extrinsic> for VerifiedExtrinsic {
+	fn as_ref(&self) -> &Extrinsic { &self.0 }
+}
+
+struct Verifier;
+impl txpool::Verifier<Extrinsic> for Verifier {
+	type VerifiedTransaction = VerifiedExtrinsic;
+	type Error = txpool::Error;
+
+	fn verify_transaction(&self, xt: Extrinsic) -> ::std::result::Result<VerifiedExtrinsic, txpool::Error> {
+		Ok(VerifiedExtrinsic(xt, hash(xt)))
+	}
+}
+
+#[derive(Debug)]
+struct Scoring;
+impl txpool::Scoring<VerifiedExtrinsic> for Scoring {
+	type Score = u64;
+	type Event = ();
+
+	fn compare(&self, old: &VerifiedExtrinsic, other: &VerifiedExtrinsic) -> ::std::cmp::Ordering { old.0.cmp(&other.0) }
+	fn choose(&self, _old: &VerifiedExtrinsic, _new: &VerifiedExtrinsic) -> txpool::scoring::Choice { txpool::scoring::Choice::InsertNew }
+	fn update_scores(&self, xts: &[txpool::Transaction<VerifiedExtrinsic>], scores: &mut [u64], _change: txpool::scoring::Change<()>) {
+		for i in 0..xts.len() { scores[i] = 1 }
+	}
+	fn should_replace(&self, _old: &VerifiedExtrinsic, _new: &VerifiedExtrinsic) -> bool { false }
+}
+
+type TestPool = Pool<Extrinsic, Hash, Verifier, Scoring, txpool::Error>;
+
+#[test]
+fn should_watch_extrinsic() {
+	let mut core = ::tokio_core::reactor::Core::new().unwrap();
+	let pool = Arc::new(TestPool::new(Default::default(), Verifier, Scoring));
+	let p = Author::new(Arc::new(test_client::new()), pool.clone(), core.remote());
+	let (subscriber, id, transport) = pubsub::Subscriber::new_test("test");
+
+	p.watch_extrinsic(Default::default(), subscriber, 5);
+
+	// assert id assigned
+	assert_eq!(core.run(id), Ok(Ok(SubscriptionId::Number(0))));
+
+	// the extrinsic leaves the pool
+	assert_eq!(AuthorApi::<Hash, Extrinsic>::remove_extrinsic(&p, vec![hash(5)]).unwrap(), vec![hash(5)]);
+
+	// assert notification sent to transport
+	let (notification, _) = core.run(transport.into_future()).unwrap();
+	assert_eq!(notification, Some(
+		r#"{"jsonrpc":"2.0","method":"test","params":{"result":"dropped","subscription":0}}"#.to_owned()
+	));
+}
@@ -18,12 +18,13 @@
 
 use std::sync::Arc;
 
-use runtime_primitives::traits::Block as BlockT;
-use runtime_primitives::generic::BlockId;
+use runtime_primitives::bft::Justification;
+use runtime_primitives::traits::{Block as BlockT, Header as HeaderT};
+use runtime_primitives::generic::{BlockId, SignedBlock};
 use client::{self, Client, BlockchainEvents};
 use state_machine;
 
-use jsonrpc_macros::pubsub;
+use jsonrpc_macros::{pubsub, Trailing};
 use jsonrpc_pubsub::SubscriptionId;
 use rpc::Result as RpcResult;
 use rpc::futures::{Future, Sink, Stream};
@@ -39,13 +40,28 @@ use self::error::{Result, ResultExt};
 
 build_rpc_trait! {
 	/// Polkadot blockchain API
-	pub trait ChainApi<Hash, Header> {
+	pub trait ChainApi<Hash, Header, Number, Extrinsic> {
 		type Metadata;
 
 		/// Get header of a relay chain block.
 		#[rpc(name = "chain_getHeader")]
 		fn header(&self, Hash) -> Result<Option<Header>>;
 
+		/// Get header, extrinsics and justification of a relay chain block. `None` if the block
+		/// or its body is not known.
+		#[rpc(name = "chain_getBlock")]
+		fn block(&self, Hash) -> Result<Option<SignedBlock<Header, Extrinsic, Hash>>>;
+
+		/// Get hash of the n-th block in the canon chain.
+		///
+		/// By default returns latest block hash.
+		#[rpc(name = "chain_getBlockHash")]
+		fn block_hash(&self, Trailing<Number>) -> Result<Option<Hash>>;
+
+		/// Get justification of a relay chain block.
+		#[rpc(name = "chain_getJustification")]
+		fn justification(&self, Hash) -> Result<Option<Justification<Hash>>>;
+
 		/// Get hash of the head.
 		#[rpc(name = "chain_getHead")]
 		fn head(&self) -> Result<Hash>;
@@ -80,7 +96,7 @@ impl<B, E, Block: BlockT> Chain<B, E, Block> {
 	}
 }
 
-impl<B, E, Block> ChainApi<Block::Hash, Block::Header> for Chain<B, E, Block> where
+impl<B, E, Block> ChainApi<Block::Hash, Block::Header, <Block::Header as HeaderT>::Number, Block::Extrinsic> for Chain<B, E, Block> where
 	Block: BlockT + 'static,
 	B: client::backend::Backend<Block> + Send + Sync + 'static,
 	E: client::CallExecutor<Block> + Send + Sync + 'static,
@@ -92,6 +108,35 @@ impl<B, E, Block> ChainApi<Block::Hash, Block::Header> for Chain<B, E, Block> wh
 		self.client.header(&BlockId::Hash(hash)).chain_err(|| "Blockchain error")
 	}
 
+	fn block(&self, hash: Block::Hash) -> Result<Option<SignedBlock<Block::Header, Block::Extrinsic, Block::Hash>>> {
+		let id = BlockId::Hash(hash);
+		let header = match self.client.header(&id).chain_err(|| "Blockchain error")? {
+			Some(header) => header,
+			None => return Ok(None),
+		};
+		let extrinsics = match self.client.body(&id).chain_err(|| "Blockchain error")? {
+			Some(extrinsics) => extrinsics,
+			None => return Ok(None),
+		};
+		let justification = self.client.justification(&id).chain_err(|| "Blockchain error")?;
+
+		Ok(Some(SignedBlock {
+			block: ::runtime_primitives::generic::Block { header, extrinsics },
+			justification,
+		}))
+	}
+
+	fn block_hash(&self, number: Trailing<<Block::Header as HeaderT>::Number>) -> Result<Option<Block::Hash>> {
+		Ok(match number.into() {
+			None => Some(self.head()?),
+			Some(number) => self.client.block_hash(number).chain_err(|| "Blockchain error")?,
+		})
+	}
+
+	fn justification(&self, hash: Block::Hash) -> Result<Option<Justification<Block::Hash>>> {
+		self.client.justification(&BlockId::Hash(hash)).chain_err(|| "Blockchain error")
+	}
+
 	fn head(&self) -> Result<Block::Hash> {
 		Ok(self.client.info().chain_err(|| "Blockchain error")?.chain.best_hash)
 	}
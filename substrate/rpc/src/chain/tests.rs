@@ -75,3 +75,78 @@ fn should_notify_about_latest_block() {
 	// no more notifications on this channel
 	assert_eq!(core.run(next.into_future()).unwrap().0, None);
 }
+
+#[test]
+fn should_return_a_block() {
+	let core = ::tokio_core::reactor::Core::new().unwrap();
+	let remote = core.remote();
+
+	let api = Chain {
+		client: Arc::new(test_client::new()),
+		subscriptions: Subscriptions::new(remote),
+	};
+
+	let block = api.client.new_block().unwrap().bake().unwrap();
+	let block_hash = block.header.hash();
+	api.client.justify_and_import(BlockOrigin::Own, block).unwrap();
+
+	// the genesis block has no justification.
+	assert_matches!(
+		api.block(api.client.genesis_hash()),
+		Ok(Some(SignedBlock { justification: None, .. }))
+	);
+
+	assert_matches!(
+		api.block(block_hash),
+		Ok(Some(ref x)) if x.block.header.number == 1 && x.block.extrinsics.is_empty() && x.justification.is_some()
+	);
+
+	assert_matches!(
+		api.justification(block_hash),
+		Ok(Some(ref x)) if x.hash == block_hash
+	);
+
+	assert_matches!(
+		api.block(5.into()),
+		Ok(None)
+	);
+}
+
+#[test]
+fn should_return_block_hash() {
+	let core = ::tokio_core::reactor::Core::new().unwrap();
+	let remote = core.remote();
+
+	let client = Chain {
+		client: Arc::new(test_client::new()),
+		subscriptions: Subscriptions::new(remote),
+	};
+
+	assert_matches!(
+		client.block_hash(None.into()),
+		Ok(Some(ref x)) if x == &client.client.genesis_hash()
+	);
+
+	assert_matches!(
+		client.block_hash(Some(0u64).into()),
+		Ok(Some(ref x)) if x == &client.client.genesis_hash()
+	);
+
+	assert_matches!(
+		client.block_hash(Some(1u64).into()),
+		Ok(None)
+	);
+
+	let block = client.client.new_block().unwrap().bake().unwrap();
+	let block_hash = block.header.hash();
+	client.client.justify_and_import(BlockOrigin::Own, block).unwrap();
+
+	assert_matches!(
+		client.block_hash(None.into()),
+		Ok(Some(ref x)) if x == &block_hash
+	);
+	assert_matches!(
+		client.block_hash(Some(1u64).into()),
+		Ok(Some(ref x)) if x == &block_hash
+	);
+}
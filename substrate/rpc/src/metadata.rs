@@ -27,6 +27,7 @@ use jsonrpc_pubsub::{Session, PubSubMetadata};
 #[derive(Default, Clone)]
 pub struct Metadata {
 	session: Option<Arc<Session>>,
+	connection_id: Option<u64>,
 }
 
 impl ::rpc::Metadata for Metadata {}
@@ -41,6 +42,28 @@ impl Metadata {
 	pub fn new(transport: ::rpc::futures::sync::mpsc::Sender<String>) -> Self {
 		Metadata {
 			session: Some(Arc::new(Session::new(transport))),
+			connection_id: None,
 		}
 	}
+
+	/// Create new `Metadata` for requests coming in over the given transport connection.
+	pub fn with_connection_id(connection_id: u64) -> Self {
+		Metadata {
+			session: None,
+			connection_id: Some(connection_id),
+		}
+	}
+
+	/// Create new `Metadata` with session (Pub/Sub) support for the given transport connection.
+	pub fn with_session(connection_id: u64, transport: ::rpc::futures::sync::mpsc::Sender<String>) -> Self {
+		Metadata {
+			session: Some(Arc::new(Session::new(transport))),
+			connection_id: Some(connection_id),
+		}
+	}
+
+	/// Identifier of the transport connection the request came in on, if known.
+	pub fn connection_id(&self) -> Option<u64> {
+		self.connection_id
+	}
 }
@@ -22,19 +22,30 @@ mod error;
 mod tests;
 
 use std::sync::Arc;
-use client::{self, Client, CallExecutor};
+use client::{self, Client, CallExecutor, BlockchainEvents};
+use jsonrpc_macros::{pubsub, Trailing};
+use jsonrpc_pubsub::SubscriptionId;
+use rpc::Result as RpcResult;
+use rpc::futures::{stream, Future, Sink, Stream};
+use tokio_core::reactor::Remote;
 
 use runtime_primitives::generic::BlockId;
 use runtime_primitives::traits::Block as BlockT;
-use primitives::storage::{StorageKey, StorageData};
+use runtime_primitives::version::RuntimeVersion;
+use primitives::storage::{StorageKey, StorageData, StorageChangeSet};
 use primitives::hexdisplay::HexDisplay;
+use serde_json;
 use state_machine;
 
+use subscriptions::Subscriptions;
+
 use self::error::Result;
 
 build_rpc_trait! {
 	/// Polkadot state API
 	pub trait StateApi<Hash> {
+		type Metadata;
+
 		/// Returns a storage entry at a specific block's state.
 		#[rpc(name = "state_getStorageAt")]
 		fn storage_at(&self, StorageKey, Hash) -> Result<StorageData>;
@@ -66,23 +77,92 @@ build_rpc_trait! {
 		/// Call a contract at the best block.
 		#[rpc(name = "state_call")]
 		fn call(&self, String, Vec<u8>) -> Result<Vec<u8>>;
+
+		/// Returns the keys with given prefix at a block's state, or at the best block if none is given.
+		#[rpc(name = "state_getKeys")]
+		fn storage_keys(&self, StorageKey, Trailing<Hash>) -> Result<Vec<StorageKey>>;
+
+		/// Returns at most `count` keys with given prefix, strictly after `start_key` if given,
+		/// at a block's state, or at the best block if none is given.
+		#[rpc(name = "state_getKeysPaged")]
+		fn storage_keys_paged(&self, StorageKey, u32, Option<StorageKey>, Trailing<Hash>) -> Result<Vec<StorageKey>>;
+
+		/// Query historical storage entries (by key) starting from a block given as the second parameter.
+		///
+		/// Returns only the blocks where any of the given keys changed, up to the block given as
+		/// the third parameter, or the best block if none is given. Both blocks must be in the
+		/// canonical chain.
+		#[rpc(name = "state_queryStorage")]
+		fn query_storage(&self, Vec<StorageKey>, Hash, Trailing<Hash>) -> Result<Vec<StorageChangeSet<Hash>>>;
+
+		/// Returns the runtime metadata at a block's state, or at the best block if none is given.
+		#[rpc(name = "state_getMetadata")]
+		fn metadata(&self, Trailing<Hash>) -> Result<serde_json::Value>;
+
+		/// Returns the runtime version at a block's state, or at the best block if none is given.
+		#[rpc(name = "state_getRuntimeVersion")]
+		fn runtime_version(&self, Trailing<Hash>) -> Result<RuntimeVersion>;
+
+		#[pubsub(name = "state_storage")] {
+			/// New storage subscription
+			///
+			/// Subscribes to changes of the given keys, or of all keys if none are given.
+			#[rpc(name = "state_subscribeStorage")]
+			fn subscribe_storage(&self, Self::Metadata, pubsub::Subscriber<StorageChangeSet<Hash>>, Trailing<Vec<StorageKey>>);
+
+			/// Unsubscribe from storage subscription
+			#[rpc(name = "state_unsubscribeStorage")]
+			fn unsubscribe_storage(&self, SubscriptionId) -> RpcResult<bool>;
+		}
+
+		#[pubsub(name = "state_runtimeVersion")] {
+			/// New runtime version subscription
+			///
+			/// Sends the current runtime version, and then the new version whenever the runtime changes.
+			#[rpc(name = "state_subscribeRuntimeVersion")]
+			fn subscribe_runtime_version(&self, Self::Metadata, pubsub::Subscriber<RuntimeVersion>);
+
+			/// Unsubscribe from runtime version subscription
+			#[rpc(name = "state_unsubscribeRuntimeVersion")]
+			fn unsubscribe_runtime_version(&self, SubscriptionId) -> RpcResult<bool>;
+		}
 	}
 }
 
-impl<B, E, Block> StateApi<Block::Hash> for Arc<Client<B, E, Block>> where
+/// State API with subscriptions support.
+pub struct State<B, E, Block: BlockT> {
+	/// Substrate client.
+	client: Arc<Client<B, E, Block>>,
+	/// Current subscriptions.
+	subscriptions: Subscriptions,
+}
+
+impl<B, E, Block: BlockT> State<B, E, Block> {
+	/// Create new State API RPC handler.
+	pub fn new(client: Arc<Client<B, E, Block>>, remote: Remote) -> Self {
+		State {
+			client,
+			subscriptions: Subscriptions::new(remote),
+		}
+	}
+}
+
+impl<B, E, Block> StateApi<Block::Hash> for State<B, E, Block> where
 	Block: BlockT + 'static,
 	B: client::backend::Backend<Block> + Send + Sync + 'static,
 	E: CallExecutor<Block> + Send + Sync + 'static,
 	client::error::Error: From<<<B as client::backend::Backend<Block>>::State as state_machine::backend::Backend>::Error>,
 {
+	type Metadata = ::metadata::Metadata;
+
 	fn storage_at(&self, key: StorageKey, block: Block::Hash) -> Result<StorageData> {
 		trace!(target: "rpc", "Querying storage at {:?} for key {}", block, HexDisplay::from(&key.0));
-		Ok(self.as_ref().storage(&BlockId::Hash(block), &key)?)
+		Ok(self.client.storage(&BlockId::Hash(block), &key)?)
 	}
 
 	fn call_at(&self, method: String, data: Vec<u8>, block: Block::Hash) -> Result<Vec<u8>> {
 		trace!(target: "rpc", "Calling runtime at {:?} for method {} ({})", block, method, HexDisplay::from(&data));
-		Ok(self.as_ref().executor().call(&BlockId::Hash(block), &method, &data)?.return_data)
+		Ok(self.client.call(&BlockId::Hash(block), &method, &data)?.return_data)
 	}
 
 	fn storage_hash_at(&self, key: StorageKey, block: Block::Hash) -> Result<Block::Hash> {
@@ -95,18 +175,161 @@ impl<B, E, Block> StateApi<Block::Hash> for Arc<Client<B, E, Block>> where
 	}
 
 	fn storage_hash(&self, key: StorageKey) -> Result<Block::Hash> {
-		self.storage_hash_at(key, self.as_ref().info()?.chain.best_hash)
+		self.storage_hash_at(key, self.client.info()?.chain.best_hash)
 	}
 
 	fn storage_size(&self, key: StorageKey) -> Result<u64> {
-		self.storage_size_at(key, self.as_ref().info()?.chain.best_hash)
+		self.storage_size_at(key, self.client.info()?.chain.best_hash)
 	}
 
 	fn storage(&self, key: StorageKey) -> Result<StorageData> {
-		self.storage_at(key, self.as_ref().info()?.chain.best_hash)
+		self.storage_at(key, self.client.info()?.chain.best_hash)
 	}
 
 	fn call(&self, method: String, data: Vec<u8>) -> Result<Vec<u8>> {
-		self.call_at(method, data, self.as_ref().info()?.chain.best_hash)
+		self.call_at(method, data, self.client.info()?.chain.best_hash)
+	}
+
+	fn storage_keys(&self, prefix: StorageKey, block: Trailing<Block::Hash>) -> Result<Vec<StorageKey>> {
+		let block = match block.into() {
+			Some(block) => block,
+			None => self.client.info()?.chain.best_hash,
+		};
+		trace!(target: "rpc", "Querying storage keys at {:?} for prefix {}", block, HexDisplay::from(&prefix.0));
+		Ok(self.client.storage_keys(&BlockId::Hash(block), &prefix)?)
+	}
+
+	fn storage_keys_paged(
+		&self,
+		prefix: StorageKey,
+		count: u32,
+		start_key: Option<StorageKey>,
+		block: Trailing<Block::Hash>,
+	) -> Result<Vec<StorageKey>> {
+		let block = match block.into() {
+			Some(block) => block,
+			None => self.client.info()?.chain.best_hash,
+		};
+		trace!(target: "rpc", "Querying {} storage keys at {:?} for prefix {}", count, block, HexDisplay::from(&prefix.0));
+		Ok(self.client.storage_keys_paged(&BlockId::Hash(block), &prefix, start_key.as_ref(), count as usize)?)
+	}
+
+	fn query_storage(&self, keys: Vec<StorageKey>, from: Block::Hash, to: Trailing<Block::Hash>) -> Result<Vec<StorageChangeSet<Block::Hash>>> {
+		let to = match to.into() {
+			Some(to) => to,
+			None => self.client.info()?.chain.best_hash,
+		};
+		trace!(target: "rpc", "Querying storage changes of {} keys from {:?} to {:?}", keys.len(), from, to);
+		Ok(self.client.query_storage(&keys, &BlockId::Hash(from), &BlockId::Hash(to))?)
+	}
+
+	fn metadata(&self, block: Trailing<Block::Hash>) -> Result<serde_json::Value> {
+		let block = match block.into() {
+			Some(block) => block,
+			None => self.client.info()?.chain.best_hash,
+		};
+		trace!(target: "rpc", "Getting runtime metadata at {:?}", block);
+		let metadata = self.client.json_metadata(&BlockId::Hash(block))?;
+		serde_json::from_str(&metadata)
+			.map_err(|_| client::error::Error::from(client::error::ErrorKind::InvalidMetadata).into())
+	}
+
+	fn runtime_version(&self, block: Trailing<Block::Hash>) -> Result<RuntimeVersion> {
+		let block = match block.into() {
+			Some(block) => block,
+			None => self.client.info()?.chain.best_hash,
+		};
+		trace!(target: "rpc", "Getting runtime version at {:?}", block);
+		Ok(self.client.runtime_version_at(&BlockId::Hash(block))?)
+	}
+
+	fn subscribe_storage(
+		&self,
+		_meta: Self::Metadata,
+		subscriber: pubsub::Subscriber<StorageChangeSet<Block::Hash>>,
+		keys: Trailing<Vec<StorageKey>>
+	) {
+		let keys = Into::<Option<Vec<_>>>::into(keys);
+		let changes = match self.client.storage_changes_notification_stream(keys.as_ref().map(|x| &**x)) {
+			Ok(stream) => stream,
+			Err(err) => {
+				// reject the subscriber (ignore errors - we don't care if subscriber is no longer there).
+				let _ = subscriber.reject(error::Error::from(err).into());
+				return;
+			},
+		};
+
+		// send current values of the subscribed keys first
+		let initial = match keys {
+			Some(keys) => {
+				let block = self.client.info().map(|info| info.chain.best_hash).unwrap_or_default();
+				let changes = keys.into_iter()
+					.map(|key| {
+						let value = self.storage_at(key.clone(), block).ok();
+						(key, value)
+					})
+					.collect();
+				vec![Ok(StorageChangeSet { block, changes })]
+			},
+			None => Vec::new(),
+		};
+
+		self.subscriptions.add(subscriber, |sink| {
+			let changes = changes
+				.map_err(|e| warn!("Storage notification stream error: {:?}", e))
+				.map(Ok);
+
+			sink
+				.sink_map_err(|e| warn!("Error sending notifications: {:?}", e))
+				.send_all(stream::iter_ok(initial).chain(changes))
+				// we ignore the resulting Stream (if the first stream is over we are unsubscribed)
+				.map(|_| ())
+		})
+	}
+
+	fn unsubscribe_storage(&self, id: SubscriptionId) -> RpcResult<bool> {
+		Ok(self.subscriptions.cancel(id))
+	}
+
+	fn subscribe_runtime_version(&self, _meta: Self::Metadata, subscriber: pubsub::Subscriber<RuntimeVersion>) {
+		let code_changes = match self.client.storage_changes_notification_stream(Some(&[StorageKey(b":code".to_vec())])) {
+			Ok(stream) => stream,
+			Err(err) => {
+				// reject the subscriber (ignore errors - we don't care if subscriber is no longer there).
+				let _ = subscriber.reject(error::Error::from(err).into());
+				return;
+			},
+		};
+
+		// send the current version first
+		let initial = self.runtime_version(None.into());
+		let mut previous_version = initial.as_ref().ok().cloned();
+		let initial = vec![initial.map_err(Into::into)];
+
+		let client = self.client.clone();
+		self.subscriptions.add(subscriber, |sink| {
+			let versions = code_changes
+				.map_err(|e| warn!("Storage notification stream error: {:?}", e))
+				.filter_map(move |changes| {
+					match client.runtime_version_at(&BlockId::Hash(changes.block)) {
+						Ok(ref version) if previous_version.as_ref() == Some(version) => None,
+						Ok(version) => {
+							previous_version = Some(version.clone());
+							Some(Ok(version))
+						},
+						Err(err) => Some(Err(error::Error::from(err).into())),
+					}
+				});
+
+			sink
+				.sink_map_err(|e| warn!("Error sending notifications: {:?}", e))
+				.send_all(stream::iter_ok(initial).chain(versions))
+				// we ignore the resulting Stream (if the first stream is over we are unsubscribed)
+				.map(|_| ())
+		})
+	}
+
+	fn unsubscribe_runtime_version(&self, id: SubscriptionId) -> RpcResult<bool> {
+		Ok(self.subscriptions.cancel(id))
 	}
 }
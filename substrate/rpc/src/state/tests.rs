@@ -16,12 +16,15 @@
 
 use super::*;
 use self::error::{Error, ErrorKind};
+use client::BlockOrigin;
 use test_client::{self, TestClient};
 
 #[test]
 fn should_return_storage() {
+	let core = ::tokio_core::reactor::Core::new().unwrap();
 	let client = Arc::new(test_client::new());
 	let genesis_hash = client.genesis_hash();
+	let client = State::new(client, core.remote());
 
 	assert_matches!(
 		StateApi::storage_at(&client, StorageKey(vec![10]), genesis_hash),
@@ -31,11 +34,169 @@ fn should_return_storage() {
 
 #[test]
 fn should_call_contract() {
+	let core = ::tokio_core::reactor::Core::new().unwrap();
 	let client = Arc::new(test_client::new());
 	let genesis_hash = client.genesis_hash();
+	let client = State::new(client, core.remote());
 
 	assert_matches!(
 		StateApi::call_at(&client, "balanceOf".into(), vec![1,2,3], genesis_hash),
 		Err(Error(ErrorKind::Client(client::error::ErrorKind::Execution(_)), _))
 	)
 }
+
+#[test]
+fn should_fail_to_return_metadata_without_runtime_support() {
+	let core = ::tokio_core::reactor::Core::new().unwrap();
+	let client = State::new(Arc::new(test_client::new()), core.remote());
+
+	// the test runtime does not describe its modules.
+	assert_matches!(
+		StateApi::metadata(&client, None.into()),
+		Err(Error(ErrorKind::Client(client::error::ErrorKind::Execution(_)), _))
+	)
+}
+
+#[test]
+fn should_return_runtime_version() {
+	let core = ::tokio_core::reactor::Core::new().unwrap();
+	let client = State::new(Arc::new(test_client::new()), core.remote());
+
+	assert_eq!(
+		StateApi::runtime_version(&client, None.into()).unwrap(),
+		test_client::runtime::VERSION
+	);
+}
+
+#[test]
+fn should_send_initial_runtime_version_only_until_code_changes() {
+	let mut core = ::tokio_core::reactor::Core::new().unwrap();
+	let remote = core.remote();
+	let (subscriber, id, transport) = pubsub::Subscriber::new_test("test");
+
+	{
+		let api = State::new(Arc::new(test_client::new()), remote);
+
+		api.subscribe_runtime_version(Default::default(), subscriber);
+
+		// assert id assigned
+		assert_eq!(core.run(id), Ok(Ok(SubscriptionId::Number(0))));
+
+		let builder = api.client.new_block().unwrap();
+		api.client.justify_and_import(BlockOrigin::Own, builder.bake().unwrap()).unwrap();
+	}
+
+	// assert initial version sent to transport
+	let (notification, next) = core.run(transport.into_future()).unwrap();
+	assert!(notification.is_some());
+	// the code didn't change, so no more notifications on this channel
+	assert_eq!(core.run(next.into_future()).unwrap().0, None);
+}
+
+#[test]
+fn should_return_keys_with_prefix() {
+	let core = ::tokio_core::reactor::Core::new().unwrap();
+	let client = Arc::new(test_client::new());
+	let genesis_hash = client.genesis_hash();
+	let client = State::new(client, core.remote());
+
+	let all = StateApi::storage_keys(&client, StorageKey(vec![]), Some(genesis_hash).into()).unwrap();
+	assert!(!all.is_empty());
+	assert!(all.windows(2).all(|w| w[0].0 < w[1].0));
+
+	let code_keys = StateApi::storage_keys(&client, StorageKey(b":code".to_vec()), None.into()).unwrap();
+	assert_eq!(code_keys, vec![StorageKey(b":code".to_vec())]);
+
+	assert_eq!(
+		StateApi::storage_keys(&client, StorageKey(b":nonexistent".to_vec()), None.into()).unwrap(),
+		vec![]
+	);
+}
+
+#[test]
+fn should_return_keys_in_pages() {
+	let core = ::tokio_core::reactor::Core::new().unwrap();
+	let client = State::new(Arc::new(test_client::new()), core.remote());
+
+	let all = StateApi::storage_keys(&client, StorageKey(vec![]), None.into()).unwrap();
+	assert!(all.len() > 2);
+
+	let first = StateApi::storage_keys_paged(&client, StorageKey(vec![]), 2, None, None.into()).unwrap();
+	assert_eq!(first, all[..2].iter().map(|k| StorageKey(k.0.clone())).collect::<Vec<_>>());
+
+	let start = StorageKey(first[1].0.clone());
+	let rest = StateApi::storage_keys_paged(&client, StorageKey(vec![]), all.len() as u32, Some(start), None.into()).unwrap();
+	assert_eq!(rest, all[2..].iter().map(|k| StorageKey(k.0.clone())).collect::<Vec<_>>());
+}
+
+#[test]
+fn should_notify_about_storage_changes() {
+	let mut core = ::tokio_core::reactor::Core::new().unwrap();
+	let remote = core.remote();
+	let (subscriber, id, transport) = pubsub::Subscriber::new_test("test");
+
+	{
+		let api = State::new(Arc::new(test_client::new()), remote);
+
+		api.subscribe_storage(Default::default(), subscriber, None.into());
+
+		// assert id assigned
+		assert_eq!(core.run(id), Ok(Ok(SubscriptionId::Number(0))));
+
+		let builder = api.client.new_block().unwrap();
+		api.client.justify_and_import(BlockOrigin::Own, builder.bake().unwrap()).unwrap();
+	}
+
+	// assert notification sent to transport
+	let (notification, next) = core.run(transport.into_future()).unwrap();
+	assert!(notification.is_some());
+	// no more notifications on this channel
+	assert_eq!(core.run(next.into_future()).unwrap().0, None);
+}
+
+#[test]
+fn should_send_initial_storage_changes_and_notifications() {
+	let mut core = ::tokio_core::reactor::Core::new().unwrap();
+	let remote = core.remote();
+	let (subscriber, id, transport) = pubsub::Subscriber::new_test("test");
+
+	{
+		let api = State::new(Arc::new(test_client::new()), remote);
+
+		// `:code` is not touched by block execution, so only the initial value is sent.
+		api.subscribe_storage(Default::default(), subscriber, Some(vec![StorageKey(b":code".to_vec())]).into());
+
+		// assert id assigned
+		assert_eq!(core.run(id), Ok(Ok(SubscriptionId::Number(0))));
+
+		let builder = api.client.new_block().unwrap();
+		api.client.justify_and_import(BlockOrigin::Own, builder.bake().unwrap()).unwrap();
+	}
+
+	// assert initial values sent to transport
+	let (notification, next) = core.run(transport.into_future()).unwrap();
+	assert!(notification.is_some());
+	// no more notifications on this channel
+	assert_eq!(core.run(next.into_future()).unwrap().0, None);
+}
+
+#[test]
+fn should_query_storage_changes() {
+	let core = ::tokio_core::reactor::Core::new().unwrap();
+	let client = Arc::new(test_client::new());
+	let genesis_hash = client.genesis_hash();
+	let api = State::new(client.clone(), core.remote());
+
+	let builder = client.new_block().unwrap();
+	client.justify_and_import(BlockOrigin::Own, builder.bake().unwrap()).unwrap();
+	let block1_hash = client.block_hash(1).unwrap().unwrap();
+
+	// `:code` is only set at genesis.
+	let code_key = StorageKey(b":code".to_vec());
+	let changes = api.query_storage(vec![code_key.clone()], genesis_hash, None.into()).unwrap();
+	assert_eq!(changes.len(), 1);
+	assert_eq!(changes[0].block, genesis_hash);
+	assert_eq!(changes[0].changes[0].0, code_key);
+
+	assert_eq!(api.query_storage(vec![code_key], block1_hash, None.into()).unwrap(), vec![]);
+}
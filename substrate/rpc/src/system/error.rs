@@ -16,9 +16,14 @@
 
 //! System RPC module errors.
 
+use client;
 use rpc;
 
 error_chain! {
+	links {
+		Client(client::error::Error, client::error::ErrorKind) #[doc = "Client error"];
+	}
+
 	errors {
 		/// Not implemented yet
 		Unimplemented {
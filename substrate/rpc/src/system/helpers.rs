@@ -0,0 +1,125 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Substrate system API helpers.
+
+use network::Role;
+
+/// Static information about the running node.
+#[derive(Debug, Clone, Default)]
+pub struct SystemInfo {
+	/// Implementation name.
+	pub impl_name: String,
+	/// Implementation version.
+	pub impl_version: String,
+	/// Chain name.
+	pub chain_name: String,
+}
+
+/// Health of the node.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Health<Number> {
+	/// Number of connected peers.
+	pub peers: usize,
+	/// Is the node syncing.
+	pub is_syncing: bool,
+	/// Best imported block number.
+	pub best_number: Number,
+	/// Best block number imported or queued for import.
+	pub best_queued_number: Number,
+	/// Best block number seen among the peers, if any.
+	pub target_number: Option<Number>,
+}
+
+/// Information about a connected peer.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerInfo<Hash, Number> {
+	/// Peer id.
+	pub peer_id: Option<String>,
+	/// Peer roles.
+	pub roles: Vec<NodeRole>,
+	/// Protocol version.
+	pub protocol_version: u32,
+	/// Peer best block hash.
+	pub best_hash: Hash,
+	/// Peer best block number.
+	pub best_number: Number,
+}
+
+/// Network connection of a peer.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectedPeer {
+	/// Peer id.
+	pub peer_id: Option<String>,
+	/// Peer client version.
+	pub client_version: String,
+	/// Peer capabilities.
+	pub capabilities: Vec<String>,
+	/// Remote endpoint address.
+	pub remote_address: String,
+	/// Local endpoint address.
+	pub local_address: String,
+}
+
+/// State of the network of the node.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkState {
+	/// Id of this node, if available.
+	pub peer_id: Option<String>,
+	/// Number of connected peers.
+	pub num_peers: usize,
+	/// Number of peers we are downloading blocks from.
+	pub num_active_peers: usize,
+	/// Connections to other peers.
+	pub connected_peers: Vec<ConnectedPeer>,
+}
+
+/// A role a node can play in the network.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum NodeRole {
+	/// Full node, does not participate in consensus.
+	Full,
+	/// Light client node.
+	LightClient,
+	/// Act as a validator.
+	Authority,
+	/// Act as a collator.
+	Collator,
+}
+
+impl NodeRole {
+	/// Describe roles given as network role flags.
+	pub fn from_roles(roles: Role) -> Vec<NodeRole> {
+		let mut result = Vec::new();
+		if roles.contains(Role::FULL) {
+			result.push(NodeRole::Full);
+		}
+		if roles.contains(Role::LIGHT) {
+			result.push(NodeRole::LightClient);
+		}
+		if roles.contains(Role::VALIDATOR) {
+			result.push(NodeRole::Authority);
+		}
+		if roles.contains(Role::COLLATOR) {
+			result.push(NodeRole::Collator);
+		}
+		result
+	}
+}
@@ -17,15 +17,31 @@
 //! Substrate system API.
 
 pub mod error;
+pub mod helpers;
 
 #[cfg(test)]
 mod tests;
 
+use std::sync::Arc;
+
+use client::{self, Client};
+use network::{self, SyncProvider, SyncState};
+use runtime_primitives::traits::{Block as BlockT, Header as HeaderT};
+
 use self::error::Result;
+use self::helpers::{Health, PeerInfo, ConnectedPeer, NetworkState, NodeRole};
+
+pub use self::helpers::SystemInfo;
+
+/// System API methods which reveal details of the node's connections and should not be exposed publicly.
+pub const UNSAFE_METHODS: &'static [&'static str] = &[
+	"system_peers",
+	"system_networkState",
+];
 
 build_rpc_trait! {
 	/// Substrate system RPC API
-	pub trait SystemApi {
+	pub trait SystemApi<Hash, Number> {
 		/// Get the node's implementation name. Plain old string.
 		#[rpc(name = "system_name")]
 		fn system_name(&self) -> Result<String>;
@@ -37,5 +53,106 @@ build_rpc_trait! {
 		/// Get the chain's type. Given as a string identifier.
 		#[rpc(name = "system_chain")]
 		fn system_chain(&self) -> Result<String>;
+
+		/// Return health status of the node: number of peers, whether it is syncing
+		/// and the best and target block numbers.
+		#[rpc(name = "system_health")]
+		fn system_health(&self) -> Result<Health<Number>>;
+
+		/// Returns currently connected peers with their roles and best blocks.
+		#[rpc(name = "system_peers")]
+		fn system_peers(&self) -> Result<Vec<PeerInfo<Hash, Number>>>;
+
+		/// Returns the state of the network of this node.
+		#[rpc(name = "system_networkState")]
+		fn system_network_state(&self) -> Result<NetworkState>;
+
+		/// Returns the roles this node is running as.
+		#[rpc(name = "system_nodeRoles")]
+		fn system_node_roles(&self) -> Result<Vec<NodeRole>>;
+	}
+}
+
+/// System API implementation.
+pub struct System<B, E, Block: BlockT> {
+	info: SystemInfo,
+	client: Arc<Client<B, E, Block>>,
+	sync: Arc<SyncProvider<Block>>,
+}
+
+impl<B, E, Block: BlockT> System<B, E, Block> {
+	/// Create new System API RPC handler.
+	pub fn new(info: SystemInfo, client: Arc<Client<B, E, Block>>, sync: Arc<SyncProvider<Block>>) -> Self {
+		System {
+			info,
+			client,
+			sync,
+		}
+	}
+}
+
+impl<B, E, Block> SystemApi<Block::Hash, <Block::Header as HeaderT>::Number> for System<B, E, Block> where
+	Block: BlockT + 'static,
+	B: client::backend::Backend<Block> + Send + Sync + 'static,
+	E: client::CallExecutor<Block> + Send + Sync + 'static,
+{
+	fn system_name(&self) -> Result<String> {
+		Ok(self.info.impl_name.clone())
+	}
+
+	fn system_version(&self) -> Result<String> {
+		Ok(self.info.impl_version.clone())
+	}
+
+	fn system_chain(&self) -> Result<String> {
+		Ok(self.info.chain_name.clone())
+	}
+
+	fn system_health(&self) -> Result<Health<<Block::Header as HeaderT>::Number>> {
+		let status = self.sync.status();
+		Ok(Health {
+			peers: status.num_peers,
+			is_syncing: status.sync.state == SyncState::Downloading,
+			best_number: self.client.info()?.chain.best_number,
+			best_queued_number: status.sync.best_queued_number,
+			target_number: status.sync.best_seen_block,
+		})
+	}
+
+	fn system_peers(&self) -> Result<Vec<PeerInfo<Block::Hash, <Block::Header as HeaderT>::Number>>> {
+		Ok(self.sync.peers().into_iter()
+			.filter_map(|peer| {
+				let id = peer.id;
+				peer.dot_info.map(|info| PeerInfo {
+					peer_id: id,
+					roles: NodeRole::from_roles(info.roles),
+					protocol_version: info.protocol_version,
+					best_hash: info.best_hash,
+					best_number: info.best_number,
+				})
+			})
+			.collect())
+	}
+
+	fn system_network_state(&self) -> Result<NetworkState> {
+		let status = self.sync.status();
+		Ok(NetworkState {
+			peer_id: self.sync.node_id(),
+			num_peers: status.num_peers,
+			num_active_peers: status.num_active_peers,
+			connected_peers: self.sync.peers().into_iter()
+				.map(|peer: network::PeerInfo<Block>| ConnectedPeer {
+					peer_id: peer.id,
+					client_version: peer.client_version,
+					capabilities: peer.capabilities,
+					remote_address: peer.remote_address,
+					local_address: peer.local_address,
+				})
+				.collect(),
+		})
+	}
+
+	fn system_node_roles(&self) -> Result<Vec<NodeRole>> {
+		Ok(NodeRole::from_roles(self.sync.roles()))
 	}
 }
@@ -15,24 +15,73 @@
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
 use super::*;
-use super::error::*;
+use network::{ProtocolStatus, SyncStatus, ProtocolPeerInfo, Role};
+use primitives::H256 as Hash;
+use client::BlockOrigin;
+use test_client::{self, TestClient};
+use test_client::runtime::Block;
 
-impl SystemApi for () {
-	fn system_name(&self) -> Result<String> {
-		Ok("testclient".into())
+struct Status {
+	peers: usize,
+	is_syncing: bool,
+}
+
+impl SyncProvider<Block> for Status {
+	fn status(&self) -> ProtocolStatus<Block> {
+		ProtocolStatus {
+			sync: SyncStatus {
+				state: if self.is_syncing { SyncState::Downloading } else { SyncState::Idle },
+				best_seen_block: if self.peers > 0 { Some(10) } else { None },
+				best_queued_number: 1,
+				best_queued_hash: Hash::from(1),
+			},
+			num_peers: self.peers,
+			num_active_peers: if self.is_syncing { self.peers } else { 0 },
+		}
 	}
-	fn system_version(&self) -> Result<String> {
-		Ok("0.2.0".into())
+
+	fn peers(&self) -> Vec<network::PeerInfo<Block>> {
+		(0..self.peers).map(|i| network::PeerInfo {
+			id: Some(format!("peer{}", i)),
+			client_version: "substrate/0.1.0".into(),
+			capabilities: vec!["dot/0".into()],
+			remote_address: "127.0.0.1:30333".into(),
+			local_address: "127.0.0.1:30334".into(),
+			// only the first peer has completed the handshake.
+			dot_info: if i == 0 {
+				Some(ProtocolPeerInfo {
+					roles: Role::FULL,
+					protocol_version: 0,
+					best_hash: Hash::from(10),
+					best_number: 10,
+				})
+			} else {
+				None
+			},
+		}).collect()
 	}
-	fn system_chain(&self) -> Result<String> {
-		Ok("testchain".into())
+
+	fn node_id(&self) -> Option<String> {
+		Some("enode://node".into())
 	}
+
+	fn roles(&self) -> Role {
+		Role::VALIDATOR | Role::FULL
+	}
+}
+
+fn api(peers: usize, is_syncing: bool) -> System<test_client::Backend, test_client::Executor, Block> {
+	System::new(SystemInfo {
+		impl_name: "testclient".into(),
+		impl_version: "0.2.0".into(),
+		chain_name: "testchain".into(),
+	}, Arc::new(test_client::new()), Arc::new(Status { peers, is_syncing }))
 }
 
 #[test]
 fn system_name_works() {
 	assert_eq!(
-		SystemApi::system_name(&()).unwrap(),
+		api(0, false).system_name().unwrap(),
 		"testclient".to_owned()
 	);
 }
@@ -40,7 +89,7 @@ fn system_name_works() {
 #[test]
 fn system_version_works() {
 	assert_eq!(
-		SystemApi::system_version(&()).unwrap(),
+		api(0, false).system_version().unwrap(),
 		"0.2.0".to_owned()
 	);
 }
@@ -48,7 +97,75 @@ fn system_version_works() {
 #[test]
 fn system_chain_works() {
 	assert_eq!(
-		SystemApi::system_chain(&()).unwrap(),
+		api(0, false).system_chain().unwrap(),
 		"testchain".to_owned()
 	);
 }
+
+#[test]
+fn system_health_works() {
+	assert_eq!(
+		api(0, false).system_health().unwrap(),
+		Health {
+			peers: 0,
+			is_syncing: false,
+			best_number: 0,
+			best_queued_number: 1,
+			target_number: None,
+		}
+	);
+
+	assert_eq!(
+		api(5, true).system_health().unwrap(),
+		Health {
+			peers: 5,
+			is_syncing: true,
+			best_number: 0,
+			best_queued_number: 1,
+			target_number: Some(10),
+		}
+	);
+}
+
+#[test]
+fn system_health_reports_imported_best_block() {
+	let api = api(0, false);
+	let block = api.client.new_block().unwrap().bake().unwrap();
+	api.client.justify_and_import(BlockOrigin::Own, block).unwrap();
+
+	let health = api.system_health().unwrap();
+	assert_eq!(health.best_number, 1);
+	assert_eq!(health.best_queued_number, 1);
+}
+
+#[test]
+fn system_peers_works() {
+	assert_eq!(
+		api(2, false).system_peers().unwrap(),
+		vec![PeerInfo {
+			peer_id: Some("peer0".into()),
+			roles: vec![NodeRole::Full],
+			protocol_version: 0,
+			best_hash: Hash::from(10),
+			best_number: 10,
+		}]
+	);
+}
+
+#[test]
+fn system_network_state_works() {
+	let state = api(2, true).system_network_state().unwrap();
+	assert_eq!(state.peer_id, Some("enode://node".into()));
+	assert_eq!(state.num_peers, 2);
+	assert_eq!(state.num_active_peers, 2);
+	assert_eq!(state.connected_peers.len(), 2);
+	assert_eq!(state.connected_peers[1].peer_id, Some("peer1".into()));
+}
+
+#[test]
+fn system_node_roles_works() {
+	assert_eq!(
+		api(0, false).system_node_roles().unwrap(),
+		vec![NodeRole::Full, NodeRole::Authority]
+	);
+}
@@ -68,6 +68,74 @@ pub fn clear_storage(key: &[u8]) {
 	);
 }
 
+/// Clear the storage entries with a key that starts with the given prefix.
+pub fn clear_prefix(prefix: &[u8]) {
+	ext::with(|ext|
+		ext.clear_prefix(prefix)
+	);
+}
+
+/// Get entry `key` of the child trie addressed by `storage_key`.
+pub fn child_storage(storage_key: &[u8], key: &[u8]) -> Option<Vec<u8>> {
+	ext::with(|ext| ext.child_storage(storage_key, key))
+		.expect("child_storage cannot be called outside of an Externalities-provided environment.")
+}
+
+/// Set entry `key` of the child trie addressed by `storage_key` to Some value.
+///
+/// Panics if `storage_key` is not a valid child storage key.
+pub fn set_child_storage(storage_key: &[u8], key: &[u8], value: &[u8]) {
+	let set = ext::with(|ext|
+		ext.set_child_storage(storage_key.to_vec(), key.to_vec(), value.to_vec())
+	).unwrap_or(false);
+	assert!(set, "set_child_storage called with an invalid child storage key");
+}
+
+/// Clear entry `key` of the child trie addressed by `storage_key`.
+///
+/// Panics if `storage_key` is not a valid child storage key.
+pub fn clear_child_storage(storage_key: &[u8], key: &[u8]) {
+	let cleared = ext::with(|ext|
+		ext.clear_child_storage(storage_key, key)
+	).unwrap_or(false);
+	assert!(cleared, "clear_child_storage called with an invalid child storage key");
+}
+
+/// Clear all entries of the child trie addressed by `storage_key`.
+pub fn kill_child_storage(storage_key: &[u8]) {
+	ext::with(|ext|
+		ext.kill_child_storage(storage_key)
+	);
+}
+
+/// Start a new nested storage transaction. Changes made from now on can be discarded
+/// with `rollback_transaction` or kept with `commit_transaction`.
+pub fn start_transaction() {
+	ext::with(|ext|
+		ext.start_transaction()
+	);
+}
+
+/// Commit the innermost storage transaction into its parent.
+///
+/// Panics if there is no open transaction.
+pub fn commit_transaction() {
+	let committed = ext::with(|ext|
+		ext.commit_transaction()
+	).unwrap_or(false);
+	assert!(committed, "commit_transaction called without an open transaction");
+}
+
+/// Discard all storage changes made since the innermost transaction was started.
+///
+/// Panics if there is no open transaction.
+pub fn rollback_transaction() {
+	let rolled_back = ext::with(|ext|
+		ext.rollback_transaction()
+	).unwrap_or(false);
+	assert!(rolled_back, "rollback_transaction called without an open transaction");
+}
+
 /// The current relay chain identifier.
 pub fn chain_id() -> u64 {
 	ext::with(|ext|
@@ -82,6 +150,24 @@ pub fn storage_root() -> [u8; 32] {
 	).unwrap_or([0u8; 32])
 }
 
+/// Get the root of the child trie addressed by `storage_key`.
+///
+/// Panics if `storage_key` is not a valid child storage key.
+pub fn child_storage_root(storage_key: &[u8]) -> [u8; 32] {
+	ext::with(|ext|
+		ext.child_storage_root(storage_key)
+	).unwrap_or(Some([0u8; 32]))
+		.expect("child_storage_root called with an invalid child storage key")
+}
+
+/// The root of the changes trie of the changes made so far in the current block, or
+/// `None` if changes tries are not enabled.
+pub fn storage_changes_root() -> Option<[u8; 32]> {
+	ext::with(|ext|
+		ext.storage_changes_root()
+	).unwrap_or(None)
+}
+
 /// A trie root formed from the enumerated items.
 pub fn enumerated_trie_root(serialised_values: &[&[u8]]) -> [u8; 32] {
 	triehash::ordered_trie_root(serialised_values.iter().map(|s| s.to_vec())).0
@@ -211,4 +297,31 @@ mod std_tests {
 			assert_eq!(&w, b"Hello world");
 		});
 	}
+
+	#[test]
+	fn child_storage_works() {
+		let child = b":child_storage:test";
+		let mut t = TestExternalities::new();
+		with_externalities(&mut t, || {
+			assert_eq!(child_storage(child, b"hello"), None);
+			set_child_storage(child, b"hello", b"world");
+			assert_eq!(child_storage(child, b"hello"), Some(b"world".to_vec()));
+			assert_eq!(storage(b"hello"), None);
+			assert_eq!(child_storage_root(child), trie_root(vec![(b"hello".to_vec(), b"world".to_vec())]));
+
+			clear_child_storage(child, b"hello");
+			assert_eq!(child_storage(child, b"hello"), None);
+
+			set_child_storage(child, b"foo", b"bar");
+			kill_child_storage(child);
+			assert_eq!(child_storage(child, b"foo"), None);
+		});
+	}
+
+	#[test]
+	#[should_panic]
+	fn set_child_storage_panics_on_invalid_key() {
+		let mut t = TestExternalities::new();
+		with_externalities(&mut t, || set_child_storage(b"test", b"hello", b"world"));
+	}
 }
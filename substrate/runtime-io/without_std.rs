@@ -56,9 +56,19 @@ extern "C" {
 	fn ext_print_num(value: u64);
 	fn ext_set_storage(key_data: *const u8, key_len: u32, value_data: *const u8, value_len: u32);
 	fn ext_clear_storage(key_data: *const u8, key_len: u32);
+	fn ext_clear_prefix(prefix_data: *const u8, prefix_len: u32);
+	fn ext_get_allocated_child_storage(storage_key_data: *const u8, storage_key_len: u32, key_data: *const u8, key_len: u32, written_out: *mut u32) -> *mut u8;
+	fn ext_set_child_storage(storage_key_data: *const u8, storage_key_len: u32, key_data: *const u8, key_len: u32, value_data: *const u8, value_len: u32);
+	fn ext_clear_child_storage(storage_key_data: *const u8, storage_key_len: u32, key_data: *const u8, key_len: u32);
+	fn ext_kill_child_storage(storage_key_data: *const u8, storage_key_len: u32);
+	fn ext_child_storage_root(storage_key_data: *const u8, storage_key_len: u32, result: *mut u8);
+	fn ext_start_transaction();
+	fn ext_commit_transaction();
+	fn ext_rollback_transaction();
 	fn ext_get_allocated_storage(key_data: *const u8, key_len: u32, written_out: *mut u32) -> *mut u8;
 	fn ext_get_storage_into(key_data: *const u8, key_len: u32, value_data: *mut u8, value_len: u32, value_offset: u32) -> u32;
 	fn ext_storage_root(result: *mut u8);
+	fn ext_storage_changes_root(result: *mut u8) -> u32;
 	fn ext_enumerated_trie_root(values_data: *const u8, lens_data: *const u32, lens_len: u32, result: *mut u8);
 	fn ext_chain_id() -> u64;
 	fn ext_blake2_256(data: *const u8, len: u32, out: *mut u8);
@@ -99,6 +109,92 @@ pub fn clear_storage(key: &[u8]) {
 	}
 }
 
+/// Clear the storage entries with a key that starts with the given prefix.
+pub fn clear_prefix(prefix: &[u8]) {
+	unsafe {
+		ext_clear_prefix(
+			prefix.as_ptr(), prefix.len() as u32
+		);
+	}
+}
+
+/// Get entry `key` of the child trie addressed by `storage_key`.
+pub fn child_storage(storage_key: &[u8], key: &[u8]) -> Option<Vec<u8>> {
+	let mut length: u32 = 0;
+	unsafe {
+		let ptr = ext_get_allocated_child_storage(
+			storage_key.as_ptr(), storage_key.len() as u32,
+			key.as_ptr(), key.len() as u32,
+			&mut length
+		);
+		if length == u32::max_value() {
+			None
+		} else {
+			Some(Vec::from_raw_parts(ptr, length as usize, length as usize))
+		}
+	}
+}
+
+/// Set entry `key` of the child trie addressed by `storage_key` to some particular value.
+///
+/// Traps if `storage_key` is not a valid child storage key.
+pub fn set_child_storage(storage_key: &[u8], key: &[u8], value: &[u8]) {
+	unsafe {
+		ext_set_child_storage(
+			storage_key.as_ptr(), storage_key.len() as u32,
+			key.as_ptr(), key.len() as u32,
+			value.as_ptr(), value.len() as u32
+		);
+	}
+}
+
+/// Clear entry `key` of the child trie addressed by `storage_key`.
+///
+/// Traps if `storage_key` is not a valid child storage key.
+pub fn clear_child_storage(storage_key: &[u8], key: &[u8]) {
+	unsafe {
+		ext_clear_child_storage(
+			storage_key.as_ptr(), storage_key.len() as u32,
+			key.as_ptr(), key.len() as u32
+		);
+	}
+}
+
+/// Clear all entries of the child trie addressed by `storage_key`.
+pub fn kill_child_storage(storage_key: &[u8]) {
+	unsafe {
+		ext_kill_child_storage(
+			storage_key.as_ptr(), storage_key.len() as u32
+		);
+	}
+}
+
+/// Start a new nested storage transaction. Changes made from now on can be discarded
+/// with `rollback_transaction` or kept with `commit_transaction`.
+pub fn start_transaction() {
+	unsafe {
+		ext_start_transaction();
+	}
+}
+
+/// Commit the innermost storage transaction into its parent.
+///
+/// Traps if there is no open transaction.
+pub fn commit_transaction() {
+	unsafe {
+		ext_commit_transaction();
+	}
+}
+
+/// Discard all storage changes made since the innermost transaction was started.
+///
+/// Traps if there is no open transaction.
+pub fn rollback_transaction() {
+	unsafe {
+		ext_rollback_transaction();
+	}
+}
+
 /// Get `key` from storage, placing the value into `value_out` (as much as possible) and return
 /// the number of bytes that the key in storage was beyond the offset.
 pub fn read_storage(key: &[u8], value_out: &mut [u8], value_offset: usize) -> Option<usize> {
@@ -123,6 +219,32 @@ pub fn storage_root() -> [u8; 32] {
 	result
 }
 
+/// The root of the changes trie of the changes made so far in the current block, or
+/// `None` if changes tries are not enabled.
+pub fn storage_changes_root() -> Option<[u8; 32]> {
+	let mut result: [u8; 32] = Default::default();
+	let is_set = unsafe {
+		ext_storage_changes_root(result.as_mut_ptr())
+	};
+
+	if is_set != 0 {
+		Some(result)
+	} else {
+		None
+	}
+}
+
+/// The root of the child trie addressed by `storage_key`.
+///
+/// Traps if `storage_key` is not a valid child storage key.
+pub fn child_storage_root(storage_key: &[u8]) -> [u8; 32] {
+	let mut result: [u8; 32] = Default::default();
+	unsafe {
+		ext_child_storage_root(storage_key.as_ptr(), storage_key.len() as u32, result.as_mut_ptr());
+	}
+	result
+}
+
 /// A trie root calculated from enumerated values.
 pub fn enumerated_trie_root(values: &[&[u8]]) -> [u8; 32] {
 	let lens = values.iter().map(|v| (v.len() as u32).to_le()).collect::<Vec<_>>();
@@ -99,6 +99,11 @@ macro_rules! decl_module {
 			impl for $mod_type<$trait_instance: $trait_name>;
 			$($rest)*
 		}
+
+		__impl_call_json_metadata! {
+			impl for $mod_type<$trait_instance: $trait_name>;
+			$($rest)*
+		}
 	};
 	(
 		$(#[$attr:meta])*
@@ -116,6 +121,11 @@ macro_rules! decl_module {
 			impl for $mod_type<$trait_instance: $trait_name>;
 			$($rest)*
 		}
+
+		__impl_call_json_metadata! {
+			impl for $mod_type<$trait_instance: $trait_name>;
+			$($rest)*
+		}
 	}
 }
 
@@ -128,6 +138,7 @@ macro_rules! decl_dispatch {
 		$(#[$attr:meta])*
 		pub enum $call_type:ident {
 			$(
+				$(#[doc = $doc_attr:tt])*
 				fn $fn_name:ident(
 					$(
 						$param_name:ident : $param:ty
@@ -143,6 +154,7 @@ macro_rules! decl_dispatch {
 			$(#[$attr])*
 			pub enum $call_type;
 			$(
+				$(#[doc = $doc_attr])*
 				fn $fn_name( $( $param_name: $param ),* ) -> $result = $id;
 			)*
 		}
@@ -157,6 +169,7 @@ macro_rules! decl_dispatch {
 		$(#[$attr:meta])*
 		pub enum $call_type:ident where aux: $aux_type:ty {
 			$(
+				$(#[doc = $doc_attr:tt])*
 				fn $fn_name:ident(aux
 					$(
 						, $param_name:ident : $param:ty
@@ -172,6 +185,7 @@ macro_rules! decl_dispatch {
 			$(#[$attr])*
 			pub enum $call_type where aux: $aux_type;
 			$(
+				$(#[doc = $doc_attr])*
 				fn $fn_name(aux $(, $param_name: $param )*) -> $result = $id;
 			)*
 		}
@@ -203,6 +217,7 @@ macro_rules! __decl_dispatch_module_without_aux {
 		$(#[$attr:meta])*
 		pub enum $call_type:ident;
 		$(
+			$(#[doc = $doc_attr:tt])*
 			fn $fn_name:ident(
 				$(
 					$param_name:ident : $param:ty
@@ -216,7 +231,10 @@ macro_rules! __decl_dispatch_module_without_aux {
 			impl for $mod_type<$trait_instance: $trait_name>;
 			$(#[$attr])*
 			pub enum $call_type;
-			$( fn $fn_name( $( $param_name : $param ),* ) -> $result = $id ; )*
+			$(
+				$(#[doc = $doc_attr])*
+				fn $fn_name( $( $param_name : $param ),* ) -> $result = $id ;
+			)*
 		}
 		impl<$trait_instance: $trait_name> $crate::dispatch::Dispatchable
 			for $call_type<$trait_instance>
@@ -248,6 +266,7 @@ macro_rules! __decl_dispatch_module_with_aux {
 		$(#[$attr:meta])*
 		pub enum $call_type:ident where aux: $aux_type:ty;
 		$(
+			$(#[doc = $doc_attr:tt])*
 			fn $fn_name:ident(aux
 				$(
 					, $param_name:ident : $param:ty
@@ -261,7 +280,10 @@ macro_rules! __decl_dispatch_module_with_aux {
 			impl for $mod_type<$trait_instance: $trait_name>;
 			$(#[$attr])*
 			pub enum $call_type;
-			$( fn $fn_name( $( $param_name : $param ),* ) -> $result = $id ; )*
+			$(
+				$(#[doc = $doc_attr])*
+				fn $fn_name( $( $param_name : $param ),* ) -> $result = $id ;
+			)*
 		}
 		impl<$trait_instance: $trait_name> $crate::dispatch::AuxDispatchable
 			for $call_type<$trait_instance>
@@ -294,6 +316,7 @@ macro_rules! __decl_dispatch_module_common {
 		$(#[$attr:meta])*
 		pub enum $call_type:ident;
 		$(
+			$(#[doc = $doc_attr:tt])*
 			fn $fn_name:ident(
 				$(
 					$param_name:ident : $param:ty
@@ -308,6 +331,7 @@ macro_rules! __decl_dispatch_module_common {
 		pub enum $call_type<$trait_instance: $trait_name> {
 			__PhantomItem(::std::marker::PhantomData<$trait_instance>),
 			$(
+				$(#[doc = $doc_attr])*
 				#[allow(non_camel_case_types)]
 				$fn_name ( $( $param ),* ),
 			)*
@@ -318,6 +342,7 @@ macro_rules! __decl_dispatch_module_common {
 		pub enum $call_type<$trait_instance: $trait_name> {
 			__PhantomItem(::core::marker::PhantomData<$trait_instance>),
 			$(
+				$(#[doc = $doc_attr])*
 				#[allow(non_camel_case_types)]
 				$fn_name ( $( $param ),* ),
 			)*
@@ -426,6 +451,105 @@ macro_rules! __decl_dispatch_module_common {
 	}
 }
 
+/// Implement `call_json_metadata` for a module, describing its dispatchable calls.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __impl_call_json_metadata {
+	(
+		impl for $mod_type:ident<$trait_instance:ident: $trait_name:ident>;
+		$($rest:tt)*
+	) => {
+		impl<$trait_instance: $trait_name> $mod_type<$trait_instance> {
+			/// Write the JSON metadata of the dispatchable calls of this module.
+			pub fn call_json_metadata(out: &mut $crate::metadata::Vec<u8>) {
+				#[allow(unused_mut)]
+				let mut first = true;
+				out.push(b'[');
+				__call_json_metadata_enums!(out first $($rest)*);
+				out.push(b']');
+				let _ = first;
+			}
+		}
+	}
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __call_json_metadata_enums {
+	// WITHOUT AUX
+	(
+		$out:ident $first:ident
+		$(#[$attr:meta])*
+		pub enum $call_type:ident {
+			$(
+				$(#[doc = $doc_attr:tt])*
+				fn $fn_name:ident(
+					$(
+						$param_name:ident : $param:ty
+					),*
+				) -> $result:ty
+				= $id:expr ;
+			)*
+		}
+		$($rest:tt)*
+	) => {
+		$crate::metadata::begin_call($out, &mut $first, stringify!($call_type));
+		{
+			let mut first_function = true;
+			$(
+				$crate::metadata::write_call_function(
+					$out,
+					&mut first_function,
+					$id,
+					stringify!($fn_name),
+					&[ $( (stringify!($param_name), stringify!($param)) ),* ],
+					&[ $( $doc_attr ),* ],
+				);
+			)*
+			let _ = first_function;
+		}
+		$crate::metadata::end_call($out);
+		__call_json_metadata_enums!($out $first $($rest)*);
+	};
+	// WITH AUX
+	(
+		$out:ident $first:ident
+		$(#[$attr:meta])*
+		pub enum $call_type:ident where aux: $aux_type:ty {
+			$(
+				$(#[doc = $doc_attr:tt])*
+				fn $fn_name:ident(aux
+					$(
+						, $param_name:ident : $param:ty
+					)*
+				) -> $result:ty
+				= $id:expr ;
+			)*
+		}
+		$($rest:tt)*
+	) => {
+		$crate::metadata::begin_call($out, &mut $first, stringify!($call_type));
+		{
+			let mut first_function = true;
+			$(
+				$crate::metadata::write_call_function(
+					$out,
+					&mut first_function,
+					$id,
+					stringify!($fn_name),
+					&[ $( (stringify!($param_name), stringify!($param)) ),* ],
+					&[ $( $doc_attr ),* ],
+				);
+			)*
+			let _ = first_function;
+		}
+		$crate::metadata::end_call($out);
+		__call_json_metadata_enums!($out $first $($rest)*);
+	};
+	// BASE CASE
+	($out:ident $first:ident) => ();
+}
+
 pub trait IsSubType<T: Callable> {
 	fn is_sub_type(&self) -> Option<&<T as Callable>::Call>;
 }
@@ -555,3 +679,64 @@ macro_rules! impl_outer_dispatch_common {
 
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use serde;
+
+	pub trait Trait {
+		type PublicAux;
+	}
+
+	pub struct TraitImpl;
+	impl Trait for TraitImpl {
+		type PublicAux = u64;
+	}
+
+	decl_module! {
+		pub struct Module<T: Trait>;
+
+		pub enum Call where aux: T::PublicAux {
+			/// Transfer a value.
+			fn transfer(aux, dest: u64, value: u32) -> super::Result = 0;
+		}
+
+		pub enum PrivCall {
+			fn set_fee(new: u32) -> super::Result = 3;
+		}
+	}
+
+	impl<T: Trait> Module<T> {
+		fn transfer(_aux: &T::PublicAux, _dest: u64, _value: u32) -> super::Result {
+			Ok(())
+		}
+
+		fn set_fee(_new: u32) -> super::Result {
+			Ok(())
+		}
+	}
+
+	impl<T: Trait> serde::Serialize for Call<T> {
+		fn serialize<S: serde::Serializer>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error> {
+			serializer.serialize_unit()
+		}
+	}
+
+	impl<T: Trait> serde::Serialize for PrivCall<T> {
+		fn serialize<S: serde::Serializer>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error> {
+			serializer.serialize_unit()
+		}
+	}
+
+	#[test]
+	fn decl_module_json_metadata() {
+		let mut out = Vec::new();
+		Module::<TraitImpl>::call_json_metadata(&mut out);
+
+		assert_eq!(::std::str::from_utf8(&out).unwrap(), concat!(
+			r#"[{"name":"Call","functions":[{"id":0,"name":"transfer","arguments":[{"name":"dest","type":"u64"},"#,
+			r#"{"name":"value","type":"u32"}],"documentation":["Transfer a value."]}]},"#,
+			r#"{"name":"PrivCall","functions":[{"id":3,"name":"set_fee","arguments":[{"name":"new","type":"u32"}],"documentation":[]}]}]"#,
+		));
+	}
+}
@@ -31,9 +31,10 @@ pub use self::storage::generator::Storage as GenericStorage;
 
 pub mod dispatch;
 pub mod storage;
+pub mod metadata;
 mod hashable;
 
-pub use self::storage::{StorageVec, StorageList, StorageValue, StorageMap};
+pub use self::storage::{StorageVec, StorageList, StorageValue, StorageMap, with_transaction};
 pub use self::hashable::Hashable;
 pub use self::dispatch::{Parameter, Dispatchable, Callable, AuxDispatchable, AuxCallable, IsSubType, IsAuxSubType};
 pub use runtime_io::print;
@@ -0,0 +1,297 @@
+// Copyright 2018 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Runtime metadata support.
+//!
+//! `decl_module!` and `decl_storage!` describe the calls and storage items of a module
+//! as JSON, and `impl_json_metadata!` aggregates the descriptions of all modules of a runtime.
+//! The JSON is built at runtime, since the storage prefixes and documentation need escaping.
+
+pub use rstd::vec::Vec;
+
+const HEX: &[u8; 16] = b"0123456789abcdef";
+
+/// Write a separating comma before every item of a JSON array but the first one.
+pub fn separator(out: &mut Vec<u8>, first: &mut bool) {
+	if !*first {
+		out.push(b',');
+	}
+	*first = false;
+}
+
+/// Write a JSON string.
+pub fn write_str(out: &mut Vec<u8>, s: &str) {
+	out.push(b'"');
+	for &b in s.as_bytes() {
+		escape_byte(out, b);
+	}
+	out.push(b'"');
+}
+
+fn escape_byte(out: &mut Vec<u8>, b: u8) {
+	match b {
+		b'"' => out.extend_from_slice(b"\\\""),
+		b'\\' => out.extend_from_slice(b"\\\\"),
+		b'\n' => out.extend_from_slice(b"\\n"),
+		b if b < 0x20 => {
+			out.extend_from_slice(b"\\u00");
+			out.push(HEX[(b >> 4) as usize]);
+			out.push(HEX[(b & 0xf) as usize]);
+		}
+		b => out.push(b),
+	}
+}
+
+/// Write bytes as a `0x`-prefixed hex JSON string.
+pub fn write_hex(out: &mut Vec<u8>, bytes: &[u8]) {
+	out.extend_from_slice(b"\"0x");
+	for &b in bytes {
+		out.push(HEX[(b >> 4) as usize]);
+		out.push(HEX[(b & 0xf) as usize]);
+	}
+	out.push(b'"');
+}
+
+/// Write a type name, as produced by `stringify!`, as a JSON string without the whitespace.
+pub fn write_type(out: &mut Vec<u8>, ty: &str) {
+	out.push(b'"');
+	for &b in ty.as_bytes().iter().filter(|b| !b.is_ascii_whitespace()) {
+		escape_byte(out, b);
+	}
+	out.push(b'"');
+}
+
+/// Write documentation lines as a JSON array of strings.
+pub fn write_docs(out: &mut Vec<u8>, docs: &[&str]) {
+	out.push(b'[');
+	let mut first = true;
+	for doc in docs {
+		separator(out, &mut first);
+		write_str(out, doc.trim());
+	}
+	out.push(b']');
+}
+
+/// Write the description of a single storage item.
+pub fn write_storage_item(
+	out: &mut Vec<u8>,
+	first: &mut bool,
+	name: &str,
+	prefix: &[u8],
+	modifier: &str,
+	key_type: Option<&str>,
+	value_type: &str,
+	docs: &[&str],
+) {
+	separator(out, first);
+	out.extend_from_slice(b"{\"name\":");
+	write_str(out, name);
+	out.extend_from_slice(b",\"prefix\":");
+	write_hex(out, prefix);
+	out.extend_from_slice(b",\"modifier\":");
+	write_str(out, modifier);
+	out.extend_from_slice(b",\"type\":");
+	match key_type {
+		Some(key_type) => {
+			out.extend_from_slice(b"{\"map\":{\"key\":");
+			write_type(out, key_type);
+			out.extend_from_slice(b",\"value\":");
+			write_type(out, value_type);
+			out.extend_from_slice(b"}}");
+		}
+		None => {
+			out.extend_from_slice(b"{\"plain\":");
+			write_type(out, value_type);
+			out.push(b'}');
+		}
+	}
+	out.extend_from_slice(b",\"documentation\":");
+	write_docs(out, docs);
+	out.push(b'}');
+}
+
+/// Start the description of a call enum. Its functions follow, then `end_call`.
+pub fn begin_call(out: &mut Vec<u8>, first: &mut bool, name: &str) {
+	separator(out, first);
+	out.extend_from_slice(b"{\"name\":");
+	write_str(out, name);
+	out.extend_from_slice(b",\"functions\":[");
+}
+
+/// Finish the description of a call enum.
+pub fn end_call(out: &mut Vec<u8>) {
+	out.extend_from_slice(b"]}");
+}
+
+/// Write the description of a single dispatchable function of a call enum.
+pub fn write_call_function(
+	out: &mut Vec<u8>,
+	first: &mut bool,
+	id: u8,
+	name: &str,
+	arguments: &[(&str, &str)],
+	docs: &[&str],
+) {
+	separator(out, first);
+	out.extend_from_slice(b"{\"id\":");
+	if id >= 100 {
+		out.push(b'0' + id / 100);
+	}
+	if id >= 10 {
+		out.push(b'0' + id / 10 % 10);
+	}
+	out.push(b'0' + id % 10);
+	out.extend_from_slice(b",\"name\":");
+	write_str(out, name);
+	out.extend_from_slice(b",\"arguments\":[");
+	let mut first_argument = true;
+	for &(name, ty) in arguments {
+		separator(out, &mut first_argument);
+		out.extend_from_slice(b"{\"name\":");
+		write_str(out, name);
+		out.extend_from_slice(b",\"type\":");
+		write_type(out, ty);
+		out.push(b'}');
+	}
+	out.extend_from_slice(b"],\"documentation\":");
+	write_docs(out, docs);
+	out.push(b'}');
+}
+
+/// Write the description of a module. `storage` and `calls` write JSON arrays.
+pub fn write_module<S, C>(out: &mut Vec<u8>, first: &mut bool, name: &str, storage: S, calls: C) where
+	S: FnOnce(&mut Vec<u8>),
+	C: FnOnce(&mut Vec<u8>),
+{
+	separator(out, first);
+	out.extend_from_slice(b"{\"name\":");
+	write_str(out, name);
+	out.extend_from_slice(b",\"storage\":");
+	storage(out);
+	out.extend_from_slice(b",\"calls\":");
+	calls(out);
+	out.push(b'}');
+}
+
+/// Write an empty JSON array, for modules without storage or calls.
+pub fn write_empty(out: &mut Vec<u8>) {
+	out.extend_from_slice(b"[]");
+}
+
+/// Implement `json_metadata` for a runtime, describing the storage and calls of its modules.
+///
+/// Modules declaring storage with `decl_storage!` are marked with `[storage]`.
+///
+/// ```ignore
+/// impl_json_metadata! {
+/// 	for Concrete with modules {
+/// 		system: System [storage],
+/// 		consensus: Consensus,
+/// 	}
+/// }
+/// ```
+#[macro_export]
+macro_rules! impl_json_metadata {
+	(
+		for $runtime:ident with modules {
+			$( $rest:tt )*
+		}
+	) => {
+		impl $runtime {
+			/// Get the JSON metadata of all modules of this runtime.
+			pub fn json_metadata() -> $crate::metadata::Vec<u8> {
+				let mut out = $crate::metadata::Vec::new();
+				let mut first = true;
+				out.extend_from_slice(b"{\"modules\":[");
+				__impl_json_metadata_modules!(out first $( $rest )*);
+				out.extend_from_slice(b"]}");
+				out
+			}
+		}
+	}
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __impl_json_metadata_modules {
+	($out:ident $first:ident $name:ident : $module:ty [storage] $(, $( $rest:tt )* )*) => {
+		$crate::metadata::write_module(
+			&mut $out,
+			&mut $first,
+			stringify!($name),
+			|out| <$module>::store_json_metadata(out),
+			|out| <$module>::call_json_metadata(out),
+		);
+		__impl_json_metadata_modules!($out $first $( $( $rest )* )*);
+	};
+	($out:ident $first:ident $name:ident : $module:ty $(, $( $rest:tt )* )*) => {
+		$crate::metadata::write_module(
+			&mut $out,
+			&mut $first,
+			stringify!($name),
+			$crate::metadata::write_empty,
+			|out| <$module>::call_json_metadata(out),
+		);
+		__impl_json_metadata_modules!($out $first $( $( $rest )* )*);
+	};
+	($out:ident $first:ident) => ();
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn escapes_strings() {
+		let mut out = Vec::new();
+		write_str(&mut out, "a \"quoted\" \\ line\n\t");
+		assert_eq!(&out[..], &b"\"a \\\"quoted\\\" \\\\ line\\n\\u0009\""[..]);
+	}
+
+	#[test]
+	fn writes_storage_items() {
+		let mut out = Vec::new();
+		let mut first = true;
+		out.push(b'[');
+		write_storage_item(&mut out, &mut first, "Value", b"a:v", "required", None, "T :: Balance", &[" The value."]);
+		write_storage_item(&mut out, &mut first, "Map", &[1, 255], "default", Some("u32"), "Vec < u8 >", &[]);
+		out.push(b']');
+
+		assert_eq!(::std::str::from_utf8(&out).unwrap(), concat!(
+			r#"[{"name":"Value","prefix":"0x613a76","modifier":"required","type":{"plain":"T::Balance"},"documentation":["The value."]},"#,
+			r#"{"name":"Map","prefix":"0x01ff","modifier":"default","type":{"map":{"key":"u32","value":"Vec<u8>"}},"documentation":[]}]"#,
+		));
+	}
+
+	#[test]
+	fn writes_calls() {
+		let mut out = Vec::new();
+		let mut first = true;
+		out.push(b'[');
+		begin_call(&mut out, &mut first, "Call");
+		let mut first_function = true;
+		write_call_function(&mut out, &mut first_function, 0, "transfer", &[("dest", "T :: AccountId"), ("value", "u64")], &[" Transfer."]);
+		write_call_function(&mut out, &mut first_function, 12, "stake", &[], &[]);
+		end_call(&mut out);
+		out.push(b']');
+
+		assert_eq!(::std::str::from_utf8(&out).unwrap(), concat!(
+			r#"[{"name":"Call","functions":[{"id":0,"name":"transfer","arguments":[{"name":"dest","type":"T::AccountId"},"#,
+			r#"{"name":"value","type":"u64"}],"documentation":["Transfer."]},"#,
+			r#"{"id":12,"name":"stake","arguments":[],"documentation":[]}]}]"#,
+		));
+	}
+}
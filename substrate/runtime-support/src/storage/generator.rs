@@ -541,6 +541,14 @@ macro_rules! decl_storage {
 		}
 		impl<$traitinstance: $traittype> $modulename<$traitinstance> {
 			__impl_store_fns!($traitinstance $($t)*);
+
+			/// Write the JSON metadata of the storage items of this module.
+			pub fn store_json_metadata(out: &mut $crate::metadata::Vec<u8>) {
+				let mut first = true;
+				out.push(b'[');
+				__store_json_metadata_items!(out first () $($t)*);
+				out.push(b']');
+			}
 		}
 	};
 	(
@@ -556,6 +564,14 @@ macro_rules! decl_storage {
 		}
 		impl<$traitinstance: $traittype> $modulename<$traitinstance> {
 			__impl_store_fns!($traitinstance $($t)*);
+
+			/// Write the JSON metadata of the storage items of this module.
+			pub fn store_json_metadata(out: &mut $crate::metadata::Vec<u8>) {
+				let mut first = true;
+				out.push(b'[');
+				__store_json_metadata_items!(out first () $($t)*);
+				out.push(b']');
+			}
 		}
 	}
 }
@@ -569,6 +585,11 @@ macro_rules! __decl_store_item {
 #[macro_export]
 #[doc(hidden)]
 macro_rules! __decl_store_items {
+	// documentation
+	(#[doc = $doc:tt] $($t:tt)*) => {
+		__decl_store_items!($($t)*);
+	};
+
 	// simple values
 	($name:ident : $key:expr => $ty:ty; $($t:tt)*) => {
 		__decl_store_item!($name); __decl_store_items!($($t)*);
@@ -669,6 +690,11 @@ macro_rules! __impl_store_fn {
 #[macro_export]
 #[doc(hidden)]
 macro_rules! __impl_store_fns {
+	// documentation
+	($traitinstance:ident #[doc = $doc:tt] $($t:tt)*) => {
+		__impl_store_fns!($traitinstance $($t)*);
+	};
+
 	// simple values
 	($traitinstance:ident $name:ident : $key:expr => $ty:ty; $($t:tt)*) => {
 		__impl_store_fns!($traitinstance $($t)*);
@@ -772,6 +798,11 @@ macro_rules! __impl_store_item {
 #[macro_export]
 #[doc(hidden)]
 macro_rules! __impl_store_items {
+	// documentation
+	($traitinstance:ident #[doc = $doc:tt] $($t:tt)*) => {
+		__impl_store_items!($traitinstance $($t)*);
+	};
+
 	// simple values
 	($traitinstance:ident $name:ident : $key:expr => $ty:ty; $($t:tt)*) => {
 		__impl_store_item!($name $traitinstance);
@@ -881,6 +912,11 @@ macro_rules! __impl_store_items {
 #[macro_export]
 #[doc(hidden)]
 macro_rules! __decl_storage_items {
+	// documentation
+	($traittype:ident $traitinstance:ident #[doc = $doc:tt] $($t:tt)*) => {
+		__decl_storage_items!($traittype $traitinstance $($t)*);
+	};
+
 	// simple values
 	($traittype:ident $traitinstance:ident $name:ident : $key:expr => $ty:ty; $($t:tt)*) => {
 		__decl_storage_item!(() ($traittype as $traitinstance) () (Option<$ty>) (get) (take) $name: $key => $ty);
@@ -987,6 +1023,53 @@ macro_rules! __decl_storage_items {
 	($traittype:ident $traitinstance:ident) => ()
 }
 
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __store_json_metadata_items {
+	// documentation
+	($out:ident $first:ident ($($doc:tt)*) #[doc = $doc_attr:tt] $($t:tt)*) => {
+		__store_json_metadata_items!($out $first ($($doc)* $doc_attr) $($t)*);
+	};
+	// visibility and getters are not part of the metadata
+	($out:ident $first:ident $docs:tt pub $($t:tt)*) => {
+		__store_json_metadata_items!($out $first $docs $($t)*);
+	};
+	($out:ident $first:ident $docs:tt $name:ident get($getfn:ident) $($t:tt)*) => {
+		__store_json_metadata_items!($out $first $docs $name $($t)*);
+	};
+
+	// simple values
+	($out:ident $first:ident ($($doc:tt)*) $name:ident : $key:expr => $ty:ty; $($t:tt)*) => {
+		$crate::metadata::write_storage_item($out, &mut $first, stringify!($name), $key, "optional", None, stringify!($ty), &[ $( $doc ),* ]);
+		__store_json_metadata_items!($out $first () $($t)*);
+	};
+	($out:ident $first:ident ($($doc:tt)*) $name:ident : $key:expr => default $ty:ty; $($t:tt)*) => {
+		$crate::metadata::write_storage_item($out, &mut $first, stringify!($name), $key, "default", None, stringify!($ty), &[ $( $doc ),* ]);
+		__store_json_metadata_items!($out $first () $($t)*);
+	};
+	($out:ident $first:ident ($($doc:tt)*) $name:ident : $key:expr => required $ty:ty; $($t:tt)*) => {
+		$crate::metadata::write_storage_item($out, &mut $first, stringify!($name), $key, "required", None, stringify!($ty), &[ $( $doc ),* ]);
+		__store_json_metadata_items!($out $first () $($t)*);
+	};
+
+	// maps
+	($out:ident $first:ident ($($doc:tt)*) $name:ident : $prefix:expr => map [$kty:ty => $ty:ty]; $($t:tt)*) => {
+		$crate::metadata::write_storage_item($out, &mut $first, stringify!($name), $prefix, "optional", Some(stringify!($kty)), stringify!($ty), &[ $( $doc ),* ]);
+		__store_json_metadata_items!($out $first () $($t)*);
+	};
+	($out:ident $first:ident ($($doc:tt)*) $name:ident : $prefix:expr => default map [$kty:ty => $ty:ty]; $($t:tt)*) => {
+		$crate::metadata::write_storage_item($out, &mut $first, stringify!($name), $prefix, "default", Some(stringify!($kty)), stringify!($ty), &[ $( $doc ),* ]);
+		__store_json_metadata_items!($out $first () $($t)*);
+	};
+	($out:ident $first:ident ($($doc:tt)*) $name:ident : $prefix:expr => required map [$kty:ty => $ty:ty]; $($t:tt)*) => {
+		$crate::metadata::write_storage_item($out, &mut $first, stringify!($name), $prefix, "required", Some(stringify!($kty)), stringify!($ty), &[ $( $doc ),* ]);
+		__store_json_metadata_items!($out $first () $($t)*);
+	};
+
+	// exit
+	($out:ident $first:ident ()) => ()
+}
+
 #[cfg(test)]
 mod tests {
 	use std::collections::HashMap;
@@ -1057,4 +1140,31 @@ mod tests {
 		assert!(Map::get(&5, &storage).is_none());
 		assert!(Map::get(&999, &storage).is_none());
 	}
+
+	mod json_metadata {
+		pub trait Trait {}
+		pub struct Module<T: Trait>(::std::marker::PhantomData<T>);
+
+		decl_storage! {
+			trait Store for Module<T: Trait>;
+
+			/// The stored value.
+			pub Value get(value): b"tst:val" => required u32;
+			Balances: b"tst:bal:" => default map [ u64 => Vec<u8> ];
+		}
+
+		pub struct TraitImpl;
+		impl Trait for TraitImpl {}
+	}
+
+	#[test]
+	fn decl_storage_json_metadata() {
+		let mut out = Vec::new();
+		json_metadata::Module::<json_metadata::TraitImpl>::store_json_metadata(&mut out);
+
+		assert_eq!(::std::str::from_utf8(&out).unwrap(), concat!(
+			r#"[{"name":"Value","prefix":"0x7473743a76616c","modifier":"required","type":{"plain":"u32"},"documentation":["The stored value."]},"#,
+			r#"{"name":"Balances","prefix":"0x7473743a62616c3a","modifier":"default","type":{"map":{"key":"u64","value":"Vec<u8>"}},"documentation":[]}]"#,
+		));
+	}
 }
@@ -122,6 +122,19 @@ pub fn put_raw(key: &[u8], value: &[u8]) {
 	runtime_io::set_storage(&twox_128(key)[..], value)
 }
 
+/// Execute `f` within a new storage transaction. All storage changes it makes are kept if
+/// it returns `Ok` and discarded if it returns `Err`.
+pub fn with_transaction<R, E, F: FnOnce() -> Result<R, E>>(f: F) -> Result<R, E> {
+	runtime_io::start_transaction();
+	let result = f();
+	if result.is_ok() {
+		runtime_io::commit_transaction();
+	} else {
+		runtime_io::rollback_transaction();
+	}
+	result
+}
+
 /// The underlying runtime storage.
 pub struct RuntimeStorage;
 
@@ -459,6 +472,11 @@ pub mod unhashed {
 		runtime_io::clear_storage(key);
 	}
 
+	/// Ensure keys with the given `prefix` have no entries in storage.
+	pub fn kill_prefix(prefix: &[u8]) {
+		runtime_io::clear_prefix(prefix);
+	}
+
 	/// Get a Vec of bytes from storage.
 	pub fn get_raw(key: &[u8]) -> Option<Vec<u8>> {
 		runtime_io::storage(key)
@@ -522,6 +540,66 @@ pub mod unhashed {
 	}
 }
 
+/// Storage in child tries. Each child trie is addressed by a storage key starting with
+/// `CHILD_STORAGE_KEY_PREFIX`, and its keys are not hashed.
+pub mod child {
+	use super::{runtime_io, Slicable, Vec};
+
+	/// Return the value of the item under `key` in the child trie at `storage_key`, or `None`
+	/// if there is no explicit entry.
+	pub fn get<T: Slicable + Sized>(storage_key: &[u8], key: &[u8]) -> Option<T> {
+		runtime_io::child_storage(storage_key, key).map(|value| {
+			Slicable::decode(&mut &value[..]).expect("storage is not null, therefore must be a valid type")
+		})
+	}
+
+	/// Return the value of the item under `key` in the child trie at `storage_key`, or the
+	/// type's default if there is no explicit entry.
+	pub fn get_or_default<T: Slicable + Sized + Default>(storage_key: &[u8], key: &[u8]) -> T {
+		get(storage_key, key).unwrap_or_else(Default::default)
+	}
+
+	/// Put `value` under `key` in the child trie at `storage_key`.
+	pub fn put<T: Slicable>(storage_key: &[u8], key: &[u8], value: &T) {
+		value.using_encoded(|slice| runtime_io::set_child_storage(storage_key, key, slice));
+	}
+
+	/// Remove `key` from the child trie at `storage_key`, returning its value if it had an
+	/// explicit entry or `None` otherwise.
+	pub fn take<T: Slicable + Sized>(storage_key: &[u8], key: &[u8]) -> Option<T> {
+		let r = get(storage_key, key);
+		if r.is_some() {
+			kill(storage_key, key);
+		}
+		r
+	}
+
+	/// Check to see if `key` has an explicit entry in the child trie at `storage_key`.
+	pub fn exists(storage_key: &[u8], key: &[u8]) -> bool {
+		runtime_io::child_storage(storage_key, key).is_some()
+	}
+
+	/// Ensure `key` has no explicit entry in the child trie at `storage_key`.
+	pub fn kill(storage_key: &[u8], key: &[u8]) {
+		runtime_io::clear_child_storage(storage_key, key);
+	}
+
+	/// Remove all entries of the child trie at `storage_key`.
+	pub fn kill_storage(storage_key: &[u8]) {
+		runtime_io::kill_child_storage(storage_key);
+	}
+
+	/// Get a Vec of bytes from the child trie at `storage_key`.
+	pub fn get_raw(storage_key: &[u8], key: &[u8]) -> Option<Vec<u8>> {
+		runtime_io::child_storage(storage_key, key)
+	}
+
+	/// Put a raw byte slice into the child trie at `storage_key`.
+	pub fn put_raw(storage_key: &[u8], key: &[u8], value: &[u8]) {
+		runtime_io::set_child_storage(storage_key, key, value)
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -588,4 +666,59 @@ mod tests {
 			assert_eq!(x, y);
 		});
 	}
+
+	#[test]
+	fn with_transaction_keeps_changes_on_ok() {
+		let mut t = TestExternalities::new();
+		with_externalities(&mut t, || {
+			put(b":test", &1u32);
+			let r: Result<(), &'static str> = with_transaction(|| {
+				put(b":test", &2u32);
+				put(b":other", &3u32);
+				Ok(())
+			});
+			assert_eq!(r, Ok(()));
+			assert_eq!(get::<u32>(b":test"), Some(2));
+			assert_eq!(get::<u32>(b":other"), Some(3));
+		});
+	}
+
+	#[test]
+	fn with_transaction_discards_changes_on_err() {
+		let mut t = TestExternalities::new();
+		with_externalities(&mut t, || {
+			put(b":test", &1u32);
+			let r: Result<(), &'static str> = with_transaction(|| {
+				put(b":test", &2u32);
+				let inner: Result<(), &'static str> = with_transaction(|| {
+					kill(b":test");
+					Ok(())
+				});
+				assert_eq!(inner, Ok(()));
+				assert!(!exists(b":test"));
+				Err("failed")
+			});
+			assert_eq!(r, Err("failed"));
+			assert_eq!(get::<u32>(b":test"), Some(1));
+		});
+	}
+
+	#[test]
+	fn child_storage_works() {
+		let child_key = b":child_storage:test";
+		let mut t = TestExternalities::new();
+		with_externalities(&mut t, || {
+			child::put(child_key, b"foo", &42u32);
+			assert_eq!(child::get::<u32>(child_key, b"foo"), Some(42));
+			assert!(!exists(b"foo"));
+			assert!(!unhashed::exists(b"foo"));
+
+			assert_eq!(child::take::<u32>(child_key, b"foo"), Some(42));
+			assert!(!child::exists(child_key, b"foo"));
+
+			child::put_raw(child_key, b"bar", b"baz");
+			child::kill_storage(child_key);
+			assert_eq!(child::get_raw(child_key, b"bar"), None);
+		});
+	}
 }
@@ -80,31 +80,31 @@ decl_module! {
 decl_storage! {
 	trait Store for Module<T: Trait>;
 
-	// The number of (public) proposals that have been made so far.
+	/// The number of (public) proposals that have been made so far.
 	pub PublicPropCount get(public_prop_count): b"dem:ppc" => default PropIndex;
-	// The public proposals. Unsorted.
+	/// The public proposals. Unsorted.
 	pub PublicProps get(public_props): b"dem:pub" => default Vec<(PropIndex, T::Proposal, T::AccountId)>;
-	// Those who have locked a deposit.
+	/// Those who have locked a deposit.
 	pub DepositOf get(deposit_of): b"dem:dep:" => map [ PropIndex => (T::Balance, Vec<T::AccountId>) ];
-	// How often (in blocks) new public referenda are launched.
+	/// How often (in blocks) new public referenda are launched.
 	pub LaunchPeriod get(launch_period): b"dem:lau" => required T::BlockNumber;
-	// The minimum amount to be used as a deposit for a public referendum proposal.
+	/// The minimum amount to be used as a deposit for a public referendum proposal.
 	pub MinimumDeposit get(minimum_deposit): b"dem:min" => required T::Balance;
 
-	// How often (in blocks) to check for new votes.
+	/// How often (in blocks) to check for new votes.
 	pub VotingPeriod get(voting_period): b"dem:per" => required T::BlockNumber;
 
-	// The next free referendum index, aka the number of referendums started so far.
+	/// The next free referendum index, aka the number of referendums started so far.
 	pub ReferendumCount get(referendum_count): b"dem:rco" => required ReferendumIndex;
-	// The next referendum index that should be tallied.
+	/// The next referendum index that should be tallied.
 	pub NextTally get(next_tally): b"dem:nxt" => required ReferendumIndex;
-	// Information concerning any given referendum.
+	/// Information concerning any given referendum.
 	pub ReferendumInfoOf get(referendum_info): b"dem:pro:" => map [ ReferendumIndex => (T::BlockNumber, T::Proposal, VoteThreshold) ];
 
-	// Get the voters for the current proposal.
+	/// Get the voters for the current proposal.
 	pub VotersFor get(voters_for): b"dem:vtr:" => default map [ ReferendumIndex => Vec<T::AccountId> ];
 
-	// Get the vote, if Some, of `who`.
+	/// Get the vote, if Some, of `who`.
 	pub VoteOf get(vote_of): b"dem:vot:" => map [ (ReferendumIndex, T::AccountId) => bool ];
 }
 
@@ -333,7 +333,7 @@ impl<T: Trait> Default for GenesisConfig<T> {
 #[cfg(any(feature = "std", test))]
 impl<T: Trait> primitives::BuildExternalities for GenesisConfig<T>
 {
-	fn build_externalities(self) -> runtime_io::TestExternalities {
+	fn build_externalities(self) -> primitives::BuiltExternalities {
 		use codec::Slicable;
 		use runtime_io::twox_128;
 
@@ -395,7 +395,7 @@ mod tests {
 	}
 
 	fn new_test_ext() -> runtime_io::TestExternalities {
-		let mut t = system::GenesisConfig::<Test>::default().build_externalities();
+		let mut t: runtime_io::TestExternalities = system::GenesisConfig::<Test>::default().build_externalities().into();
 		t.extend(consensus::GenesisConfig::<Test>{
 			code: vec![],
 			authorities: vec![],
@@ -49,6 +49,7 @@ use rstd::marker::PhantomData;
 use runtime_support::StorageValue;
 use primitives::traits::{self, Header, Zero, One, Checkable, Applyable, CheckEqual, Executable,
 	MakePayment, Hashing};
+use primitives::ApplyOutcome;
 use codec::Slicable;
 use system::extrinsics_root;
 
@@ -98,7 +99,8 @@ impl<
 
 		// execute transactions
 		let (header, extrinsics) = block.deconstruct();
-		extrinsics.into_iter().for_each(Self::apply_extrinsic_no_note);
+		// dispatch failures are part of a valid block, only their fees are paid.
+		extrinsics.into_iter().for_each(|uxt| { Self::apply_extrinsic_no_note(uxt); });
 
 		// post-transactional book-keeping.
 		Finalisation::execute();
@@ -120,21 +122,21 @@ impl<
 	/// Apply extrinsic outside of the block execution function.
 	/// This doesn't attempt to validate anything regarding the block, but it builds a list of uxt
 	/// hashes.
-	pub fn apply_extrinsic(uxt: Block::Extrinsic) {
+	pub fn apply_extrinsic(uxt: Block::Extrinsic) -> ApplyOutcome {
 		let encoded = uxt.encode();
 		let encoded_len = encoded.len();
 		<system::Module<System>>::note_extrinsic(encoded);
-		Self::apply_extrinsic_no_note_with_len(uxt, encoded_len);
+		Self::apply_extrinsic_no_note_with_len(uxt, encoded_len)
 	}
 
 	/// Apply an extrinsic inside the block execution function.
-	fn apply_extrinsic_no_note(uxt: Block::Extrinsic) {
+	fn apply_extrinsic_no_note(uxt: Block::Extrinsic) -> ApplyOutcome {
 		let l = uxt.encode().len();
-		Self::apply_extrinsic_no_note_with_len(uxt, l);
+		Self::apply_extrinsic_no_note_with_len(uxt, l)
 	}
 
 	/// Actually apply an extrinsic given its `encoded_len`; this doesn't note its hash.
-	fn apply_extrinsic_no_note_with_len(uxt: Block::Extrinsic, encoded_len: usize) {
+	fn apply_extrinsic_no_note_with_len(uxt: Block::Extrinsic, encoded_len: usize) -> ApplyOutcome {
 		// Verify the signature is good.
 		let xt = match uxt.check() {
 			Ok(xt) => xt,
@@ -156,17 +158,24 @@ impl<
 		}
 
 		// decode parameters and dispatch
-		xt.apply();
+		let outcome = match xt.apply() {
+			Ok(()) => ApplyOutcome::Success,
+			Err(e) => {
+				runtime_io::print(e);
+				ApplyOutcome::Fail
+			}
+		};
 
-		<system::ExtrinsicIndex<System>>::put(<system::ExtrinsicIndex<System>>::get() + 1u32);
+		<system::Module<System>>::note_applied_extrinsic();
+		outcome
 	}
 
 	fn final_checks(header: &System::Header) {
-		// check digest
-		assert!(header.digest() == &<system::Module<System>>::digest());
-
 		// remove temporaries.
-		<system::Module<System>>::finalise();
+		let new_header = <system::Module<System>>::finalise();
+
+		// check digest; this includes the changes trie root, if any.
+		assert!(header.digest() == new_header.digest());
 
 		// check storage root.
 		let storage_root = System::Hashing::storage_root();
@@ -215,7 +224,7 @@ mod tests {
 
 	#[test]
 	fn staking_balance_transfer_dispatch_works() {
-		let mut t = system::GenesisConfig::<Test>::default().build_externalities();
+		let mut t: runtime_io::TestExternalities = system::GenesisConfig::<Test>::default().build_externalities().into();
 		t.extend(staking::GenesisConfig::<Test> {
 			sessions_per_era: 0,
 			current_era: 0,
@@ -229,14 +238,37 @@ mod tests {
 		let xt = primitives::testing::TestXt((1, 0, Call::transfer(2, 69)));
 		with_externalities(&mut t, || {
 			Executive::initialise_block(&Header::new(1, H256::default(), H256::default(), [69u8; 32].into(), Digest::default()));
-			Executive::apply_extrinsic(xt);
+			assert_eq!(Executive::apply_extrinsic(xt), ApplyOutcome::Success);
 			assert_eq!(<staking::Module<Test>>::balance(&1), 32);
 			assert_eq!(<staking::Module<Test>>::balance(&2), 69);
 		});
 	}
 
+	#[test]
+	fn failed_dispatch_is_reported_and_still_pays_fees() {
+		let mut t: runtime_io::TestExternalities = system::GenesisConfig::<Test>::default().build_externalities().into();
+		t.extend(staking::GenesisConfig::<Test> {
+			sessions_per_era: 0,
+			current_era: 0,
+			balances: vec![(1, 111)],
+			intentions: vec![],
+			validator_count: 0,
+			bonding_duration: 0,
+			transaction_base_fee: 10,
+			transaction_byte_fee: 0,
+		}.build_externalities());
+		// account 1 never staked.
+		let xt = primitives::testing::TestXt((1, 0, Call::unstake()));
+		with_externalities(&mut t, || {
+			Executive::initialise_block(&Header::new(1, H256::default(), H256::default(), [69u8; 32].into(), Digest::default()));
+			assert_eq!(Executive::apply_extrinsic(xt), ApplyOutcome::Fail);
+			assert_eq!(<staking::Module<Test>>::balance(&1), 101);
+			assert_eq!(<system::Module<Test>>::account_index(&1), 1);
+		});
+	}
+
 	fn new_test_ext() -> runtime_io::TestExternalities {
-		let mut t = system::GenesisConfig::<Test>::default().build_externalities();
+		let mut t: runtime_io::TestExternalities = system::GenesisConfig::<Test>::default().build_externalities().into();
 		t.extend(consensus::GenesisConfig::<Test>::default().build_externalities());
 		t.extend(session::GenesisConfig::<Test>::default().build_externalities());
 		t.extend(staking::GenesisConfig::<Test>::default().build_externalities());
@@ -28,6 +28,7 @@ use runtime_support::AuxDispatchable;
 use traits::{self, Member, SimpleArithmetic, SimpleBitOps, MaybeDisplay, Block as BlockT,
 	Header as HeaderT, Hashing as HashingT};
 use rstd::ops;
+use bft::Justification;
 
 /// A vetted and verified extrinsic from the external world.
 #[derive(PartialEq, Eq, Clone)]
@@ -221,9 +222,9 @@ where
 		&self.0.extrinsic.signed
 	}
 
-	fn apply(self) {
+	fn apply(self) -> Result<(), &'static str> {
 		let xt = self.0.extrinsic;
-		xt.function.dispatch(&xt.signed);
+		xt.function.dispatch(&xt.signed)
 	}
 }
 
@@ -244,12 +245,15 @@ impl<Item> Slicable for Digest<Item> where
 	}
 }
 impl<Item> traits::Digest for Digest<Item> where
- 	Item: Member + Default + Slicable
+ 	Item: traits::DigestItem + Default + Slicable
 {
 	type Item = Item;
 	fn push(&mut self, item: Self::Item) {
 		self.logs.push(item);
 	}
+	fn logs(&self) -> &[Self::Item] {
+		&self.logs
+	}
 }
 
 
@@ -340,7 +344,7 @@ impl<Number, Hashing, DigestItem> Slicable for Header<Number, Hashing, DigestIte
 impl<Number, Hashing, DigestItem> traits::Header for Header<Number, Hashing, DigestItem> where
  	Number: Member + ::rstd::hash::Hash + Copy + Slicable + MaybeDisplay + SimpleArithmetic + Slicable,
 	Hashing: HashingT,
-	DigestItem: Member + Default + Slicable,
+	DigestItem: traits::DigestItem + Default + Slicable,
 	Hashing::Output: Default + ::rstd::hash::Hash + Copy + Member + MaybeDisplay + SimpleBitOps + Slicable,
  {
 	type Number = Number;
@@ -450,6 +454,18 @@ impl<Header: Slicable, Extrinsic: Slicable> Slicable for Block<Header, Extrinsic
 	}
 }
 
+/// A substrate block paired with the justification it was imported with, if any.
+#[derive(PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "std", serde(deny_unknown_fields))]
+pub struct SignedBlock<Header, Extrinsic, Hash> {
+	/// The full block.
+	pub block: Block<Header, Extrinsic>,
+	/// The block justification. Only missing for the genesis block.
+	pub justification: Option<Justification<Hash>>,
+}
+
 impl<Header, Extrinsic> traits::Block for Block<Header, Extrinsic>
 where
 	Header: HeaderT,
@@ -528,4 +544,16 @@ mod tests {
 			assert_eq!(block, decoded);
 		}
 	}
+
+	#[test]
+	fn changes_trie_root_is_found_in_digest() {
+		use traits::{Digest as DigestT, DigestItem};
+
+		let mut digest: Digest<Vec<u8>> = Digest { logs: vec![vec![1, 2, 3]] };
+		assert_eq!(digest.changes_trie_root(), None);
+
+		digest.push(DigestItem::changes_trie_root([42u8; 32]).unwrap());
+		assert_eq!(digest.changes_trie_root(), Some([42u8; 32]));
+		assert_eq!(vec![42u8; 32].as_changes_trie_root(), None);
+	}
 }
@@ -49,6 +49,8 @@ pub mod testing;
 pub mod traits;
 pub mod generic;
 pub mod bft;
+#[macro_use]
+pub mod version;
 
 use traits::{Verify, Lazy};
 
@@ -122,6 +124,31 @@ impl<T> From<T> for MaybeUnsigned<T> {
 	}
 }
 
+/// The outcome of applying an extrinsic, returned by the runtime's `apply_extrinsic`.
+///
+/// Extrinsics which can not be applied at all, e.g. because of a bad signature or nonce,
+/// make the runtime panic instead.
+#[derive(Eq, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+#[repr(u8)]
+pub enum ApplyOutcome {
+	/// The extrinsic was dispatched successfully.
+	Success = 0,
+	/// Dispatching the extrinsic failed; its fees were still paid.
+	Fail = 1,
+}
+
+impl codec::Slicable for ApplyOutcome {
+	fn decode<I: codec::Input>(input: &mut I) -> Option<Self> {
+		match input.read_byte()? {
+			0 => Some(ApplyOutcome::Success),
+			1 => Some(ApplyOutcome::Fail),
+			_ => None,
+		}
+	}
+	fn using_encoded<R, F: FnOnce(&[u8]) -> R>(&self, f: F) -> R { f(&[*self as u8]) }
+}
+
 /// Verify a signature on an encoded value in a lazy manner. This can be
 /// an optimization if the signature scheme has an "unsigned" escape hash.
 pub fn verify_encoded_lazy<V: Verify, T: codec::Slicable>(sig: &V, item: &T, signer: &V::Signer) -> bool {
@@ -41,6 +41,14 @@ impl traits::Digest for Digest {
 	fn push(&mut self, item: Self::Item) {
 		self.logs.push(item);
 	}
+	fn logs(&self) -> &[Self::Item] {
+		&self.logs
+	}
+}
+
+impl traits::DigestItem for u64 {
+	fn changes_trie_root(_root: [u8; 32]) -> Option<Self> { None }
+	fn as_changes_trie_root(&self) -> Option<[u8; 32]> { None }
 }
 
 #[derive(PartialEq, Eq, Clone, Serialize, Deserialize, Debug)]
@@ -166,5 +174,5 @@ impl<Call: AuxDispatchable<Aux = u64> + Slicable + Sized + Send + Sync + Seriali
 	type Index = u64;
 	fn sender(&self) -> &u64 { &(self.0).0 }
 	fn index(&self) -> &u64 { &(self.0).1 }
-	fn apply(self) { (self.0).2.dispatch(&(self.0).0); }
+	fn apply(self) -> Result<(), &'static str> { (self.0).2.dispatch(&(self.0).0) }
 }
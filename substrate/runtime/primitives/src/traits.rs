@@ -282,11 +282,46 @@ impl<T> MaybeDisplay for T {}
 pub trait Member: Send + Sync + Sized + MaybeSerializeDebug + Eq + PartialEq + Clone + 'static {}
 impl<T: Send + Sync + Sized + MaybeSerializeDebug + Eq + PartialEq + Clone + 'static> Member for T {}
 
+/// Prefix of an opaque log item which commits to the root of its block's changes trie.
+pub const CHANGES_TRIE_ROOT_LOG_PREFIX: &'static [u8] = b":changes_trie_root:";
+
 /// Something that acts like a `Digest` - it can have `Log`s `push`ed onto it and these `Log`s are
 /// each `Slicable`.
 pub trait Digest {
-	type Item: Member;
+	type Item: DigestItem;
 	fn push(&mut self, item: Self::Item);
+	fn logs(&self) -> &[Self::Item];
+
+	/// The root of the block's changes trie, if any of the logs commits to one.
+	fn changes_trie_root(&self) -> Option<[u8; 32]> {
+		self.logs().iter().filter_map(DigestItem::as_changes_trie_root).next()
+	}
+}
+
+/// A single log item of a `Digest`.
+pub trait DigestItem: Member {
+	/// Create a log item committing to the root of the block's changes trie. `None` if this
+	/// kind of item cannot express it.
+	fn changes_trie_root(root: [u8; 32]) -> Option<Self>;
+	/// The root of the changes trie this item commits to, if any.
+	fn as_changes_trie_root(&self) -> Option<[u8; 32]>;
+}
+
+impl DigestItem for Vec<u8> {
+	fn changes_trie_root(root: [u8; 32]) -> Option<Self> {
+		let mut item = CHANGES_TRIE_ROOT_LOG_PREFIX.to_vec();
+		item.extend(&root[..]);
+		Some(item)
+	}
+
+	fn as_changes_trie_root(&self) -> Option<[u8; 32]> {
+		if self.len() != CHANGES_TRIE_ROOT_LOG_PREFIX.len() + 32 || !self.starts_with(CHANGES_TRIE_ROOT_LOG_PREFIX) {
+			return None;
+		}
+		let mut root = [0u8; 32];
+		root.copy_from_slice(&self[CHANGES_TRIE_ROOT_LOG_PREFIX.len()..]);
+		Some(root)
+	}
 }
 
 /// Something which fulfills the abstract idea of a Substrate header. It has types for a `Number`,
@@ -298,7 +333,7 @@ pub trait Header: Clone + Send + Sync + Slicable + Eq + MaybeSerializeDebug {
 	type Number: Member + ::rstd::hash::Hash + Copy + MaybeDisplay + SimpleArithmetic + Slicable;
 	type Hash: Member + ::rstd::hash::Hash + Copy + MaybeDisplay + Default + SimpleBitOps + Slicable + AsRef<[u8]>;
 	type Hashing: Hashing<Output = Self::Hash>;
-	type Digest: Member + Default;
+	type Digest: Member + Default + Digest;
 
 	fn new(
 		number: Self::Number,
@@ -367,5 +402,6 @@ pub trait Applyable: Sized + Send + Sync {
 	type Index: Member + MaybeDisplay + SimpleArithmetic;
 	fn index(&self) -> &Self::Index;
 	fn sender(&self) -> &Self::AccountId;
-	fn apply(self);
+	/// Dispatch the call, returning the reason if it failed.
+	fn apply(self) -> Result<(), &'static str>;
 }
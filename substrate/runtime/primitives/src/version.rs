@@ -0,0 +1,197 @@
+// Copyright 2017 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Version of the runtime, used to decide whether natively compiled runtime code can be
+//! used in place of the on-chain wasm.
+
+#[cfg(feature = "std")]
+use std::fmt;
+use rstd::prelude::*;
+use codec::{Slicable, Input};
+
+/// A string used in the runtime version. Static within the runtime, but may be owned
+/// when it was decoded outside of it.
+#[cfg(feature = "std")]
+pub type RuntimeString = ::std::borrow::Cow<'static, str>;
+/// A string used in the runtime version. Static within the runtime, but may be owned
+/// when it was decoded outside of it.
+#[cfg(not(feature = "std"))]
+pub type RuntimeString = &'static str;
+
+/// The set of APIs supported by a runtime, as pairs of API name and version.
+#[cfg(feature = "std")]
+pub type ApisVec = ::std::borrow::Cow<'static, [(RuntimeString, u32)]>;
+/// The set of APIs supported by a runtime, as pairs of API name and version.
+#[cfg(not(feature = "std"))]
+pub type ApisVec = &'static [(RuntimeString, u32)];
+
+/// Create a `RuntimeString` from a static string.
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! ver_str {
+	( $s:expr ) => { ::std::borrow::Cow::Borrowed($s) }
+}
+
+/// Create a `RuntimeString` from a static string.
+#[cfg(not(feature = "std"))]
+#[macro_export]
+macro_rules! ver_str {
+	( $s:expr ) => { $s }
+}
+
+/// Create an `ApisVec` from a static list of API name and version pairs.
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! ver_apis {
+	( $( ( $name:expr, $version:expr ) ),* ) => {
+		::std::borrow::Cow::Borrowed(&[ $( (ver_str!($name), $version) ),* ])
+	}
+}
+
+/// Create an `ApisVec` from a static list of API name and version pairs.
+#[cfg(not(feature = "std"))]
+#[macro_export]
+macro_rules! ver_apis {
+	( $( ( $name:expr, $version:expr ) ),* ) => {
+		&[ $( (ver_str!($name), $version) ),* ]
+	}
+}
+
+/// Runtime version.
+///
+/// This should not be thought of as classic Semver (major/minor/tiny).
+/// The native runtime may be used in place of the on-chain wasm only if both have the same
+/// `spec_name` and `spec_version`. Differences in `impl_name` and `impl_version` do not affect
+/// the result of execution.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct RuntimeVersion {
+	/// Identifies the different runtimes. A different on-chain spec name is never
+	/// compatible with the native runtime.
+	pub spec_name: RuntimeString,
+	/// Version of the runtime specification. Must be bumped whenever the behaviour
+	/// of the runtime changes.
+	pub spec_version: u32,
+	/// Name of the implementation of the specification.
+	pub impl_name: RuntimeString,
+	/// Version of the implementation of the specification. Changes which leave the
+	/// behaviour of the runtime unchanged (e.g. optimisations) only bump this.
+	pub impl_version: u32,
+	/// APIs supported by the runtime, with their versions.
+	pub apis: ApisVec,
+}
+
+impl RuntimeVersion {
+	/// Check if code of this version can be used in place of code of the `other` version.
+	pub fn can_call_with(&self, other: &RuntimeVersion) -> bool {
+		self.spec_name == other.spec_name && self.spec_version == other.spec_version
+	}
+
+	/// Check if the given API is supported at the given version.
+	pub fn has_api(&self, api: &str, version: u32) -> bool {
+		self.apis.iter().any(|&(ref name, v)| &name[..] == api && v == version)
+	}
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for RuntimeVersion {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}-{}:{}-{}", self.spec_name, self.spec_version, self.impl_name, self.impl_version)
+	}
+}
+
+#[cfg(feature = "std")]
+fn decode_string<I: Input>(input: &mut I) -> Option<RuntimeString> {
+	let bytes: Vec<u8> = Slicable::decode(input)?;
+	String::from_utf8(bytes).ok().map(Into::into)
+}
+
+impl Slicable for RuntimeVersion {
+	#[cfg(feature = "std")]
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Some(RuntimeVersion {
+			spec_name: decode_string(input)?,
+			spec_version: Slicable::decode(input)?,
+			impl_name: decode_string(input)?,
+			impl_version: Slicable::decode(input)?,
+			apis: {
+				let apis: Vec<(Vec<u8>, u32)> = Slicable::decode(input)?;
+				apis.into_iter()
+					.map(|(name, version)| String::from_utf8(name).ok().map(|name| (name.into(), version)))
+					.collect::<Option<Vec<_>>>()?
+					.into()
+			},
+		})
+	}
+
+	#[cfg(not(feature = "std"))]
+	fn decode<I: Input>(_input: &mut I) -> Option<Self> {
+		// strings are static within the runtime, and it only ever reports its own version.
+		None
+	}
+
+	fn encode(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+		self.spec_name.as_bytes().to_vec().using_encoded(|s| v.extend(s));
+		self.spec_version.using_encoded(|s| v.extend(s));
+		self.impl_name.as_bytes().to_vec().using_encoded(|s| v.extend(s));
+		self.impl_version.using_encoded(|s| v.extend(s));
+		self.apis.iter()
+			.map(|&(ref name, version)| (name.as_bytes().to_vec(), version))
+			.collect::<Vec<_>>()
+			.using_encoded(|s| v.extend(s));
+		v
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const VERSION: RuntimeVersion = RuntimeVersion {
+		spec_name: ver_str!("test"),
+		spec_version: 2,
+		impl_name: ver_str!("parity-test"),
+		impl_version: 1,
+		apis: ver_apis!(("core", 1), ("metadata", 1)),
+	};
+
+	#[test]
+	fn encoding_roundtrips() {
+		let encoded = VERSION.encode();
+		assert_eq!(RuntimeVersion::decode(&mut &encoded[..]), Some(VERSION));
+	}
+
+	#[test]
+	fn compatibility_ignores_implementation() {
+		let other_impl = RuntimeVersion { impl_name: ver_str!("other"), impl_version: 7, ..VERSION };
+		assert!(VERSION.can_call_with(&other_impl));
+
+		let other_spec = RuntimeVersion { spec_version: 3, ..VERSION };
+		assert!(!VERSION.can_call_with(&other_spec));
+
+		let other_name = RuntimeVersion { spec_name: ver_str!("other"), ..VERSION };
+		assert!(!VERSION.can_call_with(&other_name));
+	}
+
+	#[test]
+	fn has_api_checks_name_and_version() {
+		assert!(VERSION.has_api("core", 1));
+		assert!(!VERSION.has_api("core", 2));
+		assert!(!VERSION.has_api("session", 1));
+	}
+}
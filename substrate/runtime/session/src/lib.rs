@@ -70,18 +70,18 @@ decl_module! {
 decl_storage! {
 	trait Store for Module<T: Trait>;
 
-	// The current set of validators.
+	/// The current set of validators.
 	pub Validators get(validators): b"ses:val" => required Vec<T::AccountId>;
-	// Current length of the session.
+	/// Current length of the session.
 	pub SessionLength get(length): b"ses:len" => required T::BlockNumber;
-	// Current index of the session.
+	/// Current index of the session.
 	pub CurrentIndex get(current_index): b"ses:ind" => required T::BlockNumber;
 
-	// Block at which the session length last changed.
+	/// Block at which the session length last changed.
 	LastLengthChange: b"ses:llc" => T::BlockNumber;
-	// The next key for a given validator.
+	/// The next key for a given validator.
 	NextKeyFor: b"ses:nxt:" => map [ T::AccountId => T::SessionKey ];
-	// The next session length.
+	/// The next session length.
 	NextSessionLength: b"ses:nln" => T::BlockNumber;
 }
 
@@ -187,7 +187,7 @@ impl<T: Trait> Default for GenesisConfig<T> {
 #[cfg(any(feature = "std", test))]
 impl<T: Trait> primitives::BuildExternalities for GenesisConfig<T>
 {
-	fn build_externalities(self) -> runtime_io::TestExternalities {
+	fn build_externalities(self) -> primitives::BuiltExternalities {
 		use runtime_io::twox_128;
 		use codec::Slicable;
 		use primitives::traits::As;
@@ -234,7 +234,7 @@ mod tests {
 	type Session = Module<Test>;
 
 	fn new_test_ext() -> runtime_io::TestExternalities {
-		let mut t = system::GenesisConfig::<Test>::default().build_externalities();
+		let mut t: runtime_io::TestExternalities = system::GenesisConfig::<Test>::default().build_externalities().into();
 		t.extend(consensus::GenesisConfig::<Test>{
 			code: vec![],
 			authorities: vec![1, 2, 3],
@@ -51,7 +51,9 @@ use rstd::cell::RefCell;
 use rstd::collections::btree_map::{BTreeMap, Entry};
 use codec::Slicable;
 use runtime_support::{StorageValue, StorageMap, Parameter};
+use runtime_support::storage::child;
 use runtime_support::dispatch::Result;
+use substrate_primitives::storage::well_known_keys::CHILD_STORAGE_KEY_PREFIX;
 use primitives::traits::{Zero, One, As, Bounded, RefInto, SimpleArithmetic, Executable, MakePayment, Hashing as HashingT};
 
 #[cfg(test)]
@@ -114,43 +116,52 @@ decl_module! {
 decl_storage! {
 	trait Store for Module<T: Trait>;
 
-	// The length of the bonding duration in eras.
+	/// The length of the bonding duration in eras.
 	pub BondingDuration get(bonding_duration): b"sta:loc" => required T::BlockNumber;
-	// The length of a staking era in sessions.
+	/// The length of a staking era in sessions.
 	pub ValidatorCount get(validator_count): b"sta:vac" => required u32;
-	// The length of a staking era in sessions.
+	/// The length of a staking era in sessions.
 	pub SessionsPerEra get(sessions_per_era): b"sta:spe" => required T::BlockNumber;
-	// The total amount of stake on the system.
+	/// The total amount of stake on the system.
 	pub TotalStake get(total_stake): b"sta:tot" => required T::Balance;
-	// The fee to be paid for making a transaction; the base.
+	/// The fee to be paid for making a transaction; the base.
 	pub TransactionBaseFee get(transaction_base_fee): b"sta:basefee" => required T::Balance;
-	// The fee to be paid for making a transaction; the per-byte portion.
+	/// The fee to be paid for making a transaction; the per-byte portion.
 	pub TransactionByteFee get(transaction_byte_fee): b"sta:bytefee" => required T::Balance;
 
-	// The current era index.
+	/// The current era index.
 	pub CurrentEra get(current_era): b"sta:era" => required T::BlockNumber;
-	// All the accounts with a desire to stake.
+	/// All the accounts with a desire to stake.
 	pub Intentions: b"sta:wil:" => default Vec<T::AccountId>;
-	// The next value of sessions per era.
+	/// The next value of sessions per era.
 	pub NextSessionsPerEra get(next_sessions_per_era): b"sta:nse" => T::BlockNumber;
-	// The block number at which the era length last changed.
+	/// The block number at which the era length last changed.
 	pub LastEraLengthChange get(last_era_length_change): b"sta:lec" => default T::BlockNumber;
 
-	// The balance of a given account.
+	/// The balance of a given account.
 	pub FreeBalance get(free_balance): b"sta:bal:" => default map [ T::AccountId => T::Balance ];
 
-	// The amount of the balance of a given account that is exterally reserved; this can still get
-	// slashed, but gets slashed last of all.
+	/// The amount of the balance of a given account that is exterally reserved; this can still get
+	/// slashed, but gets slashed last of all.
 	pub ReservedBalance get(reserved_balance): b"sta:lbo:" => default map [ T::AccountId => T::Balance ];
 
-	// The block at which the `who`'s funds become entirely liquid.
+	/// The block at which the `who`'s funds become entirely liquid.
 	pub Bondage get(bondage): b"sta:bon:" => default map [ T::AccountId => T::BlockNumber ];
 
-	// The code associated with an account.
+	/// The code associated with an account.
 	pub CodeOf: b"sta:cod:" => default map [ T::AccountId => Vec<u8> ];	// TODO Vec<u8> values should be optimised to not do a length prefix.
+}
 
-	// The storage items associated with an account/key.
-	pub StorageOf: b"sta:sto:" => map [ (T::AccountId, Vec<u8>) => Vec<u8> ];	// TODO: keys should also be able to take AsRef<KeyType> to ensure Vec<u8>s can be passed as &[u8]
+/// The storage key of the child trie holding the storage items associated with an account.
+///
+/// Contract storage used to live in the main trie under hashed `sta:sto:` keys, which can't be
+/// enumerated to move it over. There is no migration: chains started before the move have to
+/// be reset.
+pub fn storage_key_for<AccountId: Slicable>(account: &AccountId) -> Vec<u8> {
+	let mut key = CHILD_STORAGE_KEY_PREFIX.to_vec();
+	key.extend_from_slice(b"sta:sto:");
+	account.using_encoded(|encoded| key.extend_from_slice(encoded));
+	key
 }
 
 impl<T: Trait> Module<T> {
@@ -183,6 +194,11 @@ impl<T: Trait> Module<T> {
 		}
 	}
 
+	/// The fee to be paid for making a transaction of encoded length `encoded_len` bytes.
+	pub fn transaction_fee(encoded_len: usize) -> T::Balance {
+		Self::transaction_base_fee() + Self::transaction_byte_fee() * <T::Balance as As<usize>>::sa(encoded_len)
+	}
+
 	/// The block at which the `who`'s funds become entirely liquid.
 	pub fn unlock_block(who: &T::AccountId) -> LockStatus<T::BlockNumber> {
 		match Self::bondage(who) {
@@ -435,7 +451,7 @@ trait AccountDb<T: Trait> {
 struct DirectAccountDb;
 impl<T: Trait> AccountDb<T> for DirectAccountDb {
 	fn get_storage(&self, account: &T::AccountId, location: &[u8]) -> Option<Vec<u8>> {
-		<StorageOf<T>>::get(&(account.clone(), location.to_vec()))
+		child::get_raw(&storage_key_for(account), location)
 	}
 	fn get_code(&self, account: &T::AccountId) -> Vec<u8> {
 		<CodeOf<T>>::get(account)
@@ -444,10 +460,11 @@ impl<T: Trait> AccountDb<T> for DirectAccountDb {
 		<FreeBalance<T>>::get(account)
 	}
 	fn set_storage(&mut self, account: &T::AccountId, location: Vec<u8>, value: Option<Vec<u8>>) {
+		let storage_key = storage_key_for(account);
 		if let Some(value) = value {
-			<StorageOf<T>>::insert(&(account.clone(), location), &value);
+			child::put_raw(&storage_key, &location, &value);
 		} else {
-			<StorageOf<T>>::remove(&(account.clone(), location));
+			child::kill(&storage_key, &location);
 		}
 	}
 	fn set_code(&mut self, account: &T::AccountId, code: Vec<u8>) {
@@ -464,11 +481,12 @@ impl<T: Trait> AccountDb<T> for DirectAccountDb {
 			if let Some(code) = changed.code {
 				<CodeOf<T>>::insert(&address, &code);
 			}
+			let storage_key = storage_key_for(&address);
 			for (k, v) in changed.storage.into_iter() {
 				if let Some(value) = v {
-					<StorageOf<T>>::insert((address.clone(), k), &value);
+					child::put_raw(&storage_key, &k, &value);
 				} else {
-					<StorageOf<T>>::remove((address.clone(), k));
+					child::kill(&storage_key, &k);
 				}
 			}
 		}
@@ -676,7 +694,7 @@ impl<'a, 'b: 'a, T: Trait> contract::Ext for StakingExt<'a, 'b, T> {
 impl<T: Trait> MakePayment<T::AccountId> for Module<T> {
 	fn make_payment(transactor: &T::AccountId, encoded_len: usize) -> bool {
 		let b = Self::free_balance(transactor);
-		let transaction_fee = Self::transaction_base_fee() + Self::transaction_byte_fee() * <T::Balance as As<usize>>::sa(encoded_len);
+		let transaction_fee = Self::transaction_fee(encoded_len);
 		if b < transaction_fee {
 			return false;
 		}
@@ -761,13 +779,13 @@ impl<T: Trait> Default for GenesisConfig<T> {
 
 #[cfg(any(feature = "std", test))]
 impl<T: Trait> primitives::BuildExternalities for GenesisConfig<T> {
-	fn build_externalities(self) -> runtime_io::TestExternalities {
+	fn build_externalities(self) -> primitives::BuiltExternalities {
 		use runtime_io::twox_128;
 		use codec::Slicable;
 
 		let total_stake: T::Balance = self.balances.iter().fold(Zero::zero(), |acc, &(_, n)| acc + n);
 
-		let mut r: runtime_io::TestExternalities = map![
+		let mut r: primitives::BuiltExternalities = map![
 			twox_128(<Intentions<T>>::key()).to_vec() => self.intentions.encode(),
 			twox_128(<SessionsPerEra<T>>::key()).to_vec() => self.sessions_per_era.encode(),
 			twox_128(<ValidatorCount<T>>::key()).to_vec() => self.validator_count.encode(),
@@ -820,7 +838,7 @@ mod tests {
 	}
 
 	fn new_test_ext(session_length: u64, sessions_per_era: u64, current_era: u64, monied: bool) -> runtime_io::TestExternalities {
-		let mut t = system::GenesisConfig::<Test>::default().build_externalities();
+		let mut t: runtime_io::TestExternalities = system::GenesisConfig::<Test>::default().build_externalities().into();
 		t.extend(consensus::GenesisConfig::<Test>{
 			code: vec![],
 			authorities: vec![],
@@ -1200,4 +1218,22 @@ r#"
 			assert_eq!(Staking::balance(&0), 111);
 		});
 	}
+
+	#[test]
+	fn contract_storage_is_kept_in_a_child_trie_per_account() {
+		with_externalities(&mut new_test_ext(1, 3, 1, false), || {
+			let mut db = DirectAccountDb;
+			AccountDb::<Test>::set_storage(&mut db, &1, b"foo".to_vec(), Some(b"bar".to_vec()));
+			assert_eq!(child::get_raw(&storage_key_for(&1u64), b"foo"), Some(b"bar".to_vec()));
+			assert_eq!(AccountDb::<Test>::get_storage(&db, &1, b"foo"), Some(b"bar".to_vec()));
+			assert_eq!(AccountDb::<Test>::get_storage(&db, &2, b"foo"), None);
+
+			let mut state = State::<Test>::new();
+			state.entry(2).or_insert_with(Default::default).storage.insert(b"foo".to_vec(), Some(b"baz".to_vec()));
+			state.entry(1).or_insert_with(Default::default).storage.insert(b"foo".to_vec(), None);
+			AccountDb::<Test>::merge(&mut db, state);
+			assert_eq!(AccountDb::<Test>::get_storage(&db, &1, b"foo"), None);
+			assert_eq!(child::get_raw(&storage_key_for(&2u64), b"foo"), Some(b"baz".to_vec()));
+		});
+	}
 }
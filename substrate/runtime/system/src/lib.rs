@@ -30,13 +30,16 @@ extern crate serde;
 
 extern crate substrate_runtime_io as runtime_io;
 extern crate substrate_codec as codec;
+extern crate substrate_primitives;
 extern crate substrate_runtime_primitives as primitives;
 extern crate safe_mix;
 
 use rstd::prelude::*;
 use primitives::traits::{self, CheckEqual, SimpleArithmetic, SimpleBitOps, Zero, One, Bounded,
-	Hashing, Member, MaybeDisplay};
+	Hashing, Member, MaybeDisplay, DigestItem};
 use runtime_support::{StorageValue, StorageMap, Parameter};
+use runtime_support::storage::unhashed;
+use substrate_primitives::storage::well_known_keys::EXTRINSIC_INDEX;
 use safe_mix::TripletMix;
 
 #[cfg(any(feature = "std", test))]
@@ -83,10 +86,9 @@ decl_storage! {
 	pub AccountIndex get(account_index): b"sys:non" => default map [ T::AccountId => T::Index ];
 	pub BlockHash get(block_hash): b"sys:old" => required map [ T::BlockNumber => T::Hash ];
 
-	pub ExtrinsicIndex get(extrinsic_index): b"sys:xti" => required u32;
 	pub ExtrinsicData get(extrinsic_data): b"sys:xtd" => required map [ u32 => Vec<u8> ];
 	RandomSeed get(random_seed): b"sys:rnd" => required T::Hash;
-	// The current block number being processed. Set by `execute_block`.
+	/// The current block number being processed. Set by `execute_block`.
 	Number get(block_number): b"sys:num" => required T::BlockNumber;
 	ParentHash get(parent_hash): b"sys:pha" => required T::Hash;
 	ExtrinsicsRoot get(extrinsics_root): b"sys:txr" => required T::Hash;
@@ -102,19 +104,27 @@ impl<T: Trait> Module<T> {
 		<BlockHash<T>>::insert(*number - One::one(), parent_hash);
 		<ExtrinsicsRoot<T>>::put(txs_root);
 		<RandomSeed<T>>::put(Self::calculate_random());
-		<ExtrinsicIndex<T>>::put(0);
+		unhashed::put(EXTRINSIC_INDEX, &0u32);
 	}
 
 	/// Remove temporary "environment" entries in storage.
 	pub fn finalise() -> T::Header {
 		<RandomSeed<T>>::kill();
-		<ExtrinsicIndex<T>>::kill();
+		unhashed::kill(EXTRINSIC_INDEX);
 
 		let number = <Number<T>>::take();
 		let parent_hash = <ParentHash<T>>::take();
-		let digest = <Digest<T>>::take();
+		let mut digest = <Digest<T>>::take();
 		let extrinsics_root = <ExtrinsicsRoot<T>>::take();
 		let storage_root = T::Hashing::storage_root();
+
+		// the changes trie root has to be computed after the last change is made.
+		if let Some(root) = runtime_io::storage_changes_root() {
+			if let Some(item) = <<T::Digest as traits::Digest>::Item as DigestItem>::changes_trie_root(root) {
+				traits::Digest::push(&mut digest, item);
+			}
+		}
+
 		<T::Header as traits::Header>::new(number, extrinsics_root, storage_root, parent_hash, digest)
 	}
 
@@ -125,6 +135,16 @@ impl<T: Trait> Module<T> {
 		<Digest<T>>::put(l);
 	}
 
+	/// Gets the index of the extrinsic that is currently executing.
+	pub fn extrinsic_index() -> u32 {
+		unhashed::get(EXTRINSIC_INDEX).expect("Required values must be in storage")
+	}
+
+	/// Note that the current extrinsic has been applied, moving on to the next one.
+	pub fn note_applied_extrinsic() {
+		unhashed::put(EXTRINSIC_INDEX, &(Self::extrinsic_index() + 1u32));
+	}
+
 	/// Calculate the current block's random seed.
 	fn calculate_random() -> T::Hash {
 		assert!(Self::block_number() > Zero::zero(), "Block number may never be zero");
@@ -201,7 +221,7 @@ impl<T: Trait> Default for GenesisConfig<T> {
 #[cfg(any(feature = "std", test))]
 impl<T: Trait> primitives::BuildExternalities for GenesisConfig<T>
 {
-	fn build_externalities(self) -> runtime_io::TestExternalities {
+	fn build_externalities(self) -> primitives::BuiltExternalities {
 		use runtime_io::twox_128;
 		use codec::Slicable;
 
@@ -210,7 +230,7 @@ impl<T: Trait> primitives::BuildExternalities for GenesisConfig<T>
 			twox_128(<Number<T>>::key()).to_vec() => 1u64.encode(),
 			twox_128(<ParentHash<T>>::key()).to_vec() => [69u8; 32].encode(),
 			twox_128(<RandomSeed<T>>::key()).to_vec() => [0u8; 32].encode(),
-			twox_128(<ExtrinsicIndex<T>>::key()).to_vec() => [0u8; 4].encode()
+			EXTRINSIC_INDEX.to_vec() => [0u8; 4].encode()
 		]
 	}
 }
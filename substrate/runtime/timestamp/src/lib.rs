@@ -61,7 +61,7 @@ decl_storage! {
 	trait Store for Module<T: Trait>;
 	pub Now get(now): b"tim:val" => required T::Value;
 
-	// Did the timestamp get updated in this block?
+	/// Did the timestamp get updated in this block?
 	DidUpdate: b"tim:did" => default bool;
 }
 
@@ -141,7 +141,7 @@ mod tests {
 
 	#[test]
 	fn timestamp_works() {
-		let mut t = system::GenesisConfig::<Test>::default().build_externalities();
+		let mut t: runtime_io::TestExternalities = system::GenesisConfig::<Test>::default().build_externalities().into();
 		t.extend(GenesisConfig::<Test> { now: 42 }.build_externalities());
 
 		with_externalities(&mut t, || {
@@ -19,6 +19,11 @@
 use std::{error, fmt};
 use std::collections::HashMap;
 use std::sync::Arc;
+use ethereum_types::H256 as TrieH256;
+use hashdb::HashDB;
+use memorydb::MemoryDB;
+use patricia_trie::{TrieDBMut, TrieMut};
+use trie_backend::TrieBackend;
 
 /// A state backend is used to read state data and can have changes committed
 /// to it.
@@ -29,18 +34,101 @@ pub trait Backend: Clone {
 	type Error: super::Error;
 
 	/// Changes to be applied if committing
-	type Transaction;
+	type Transaction: Consolidate + Default;
 
 	/// Get keyed storage associated with specific address, or None if there is nothing associated.
 	fn storage(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error>;
 
+	/// Get keyed storage of the child trie addressed by `storage_key`, or None if there is
+	/// nothing associated.
+	fn child_storage(&self, storage_key: &[u8], key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error>;
+
 	/// Calculate the storage root, with given delta over what is already stored in
 	/// the backend, and produce a "transaction" that can be used to commit.
 	fn storage_root<I>(&self, delta: I) -> ([u8; 32], Self::Transaction)
 		where I: IntoIterator<Item=(Vec<u8>, Option<Vec<u8>>)>;
 
+	/// Calculate the root of the child trie addressed by `storage_key`, with given delta
+	/// over what is already stored in the backend, and produce a "transaction" that can be
+	/// used to commit. Also returns whether the resulting child trie is empty.
+	fn child_storage_root<I>(&self, storage_key: &[u8], delta: I) -> ([u8; 32], bool, Self::Transaction)
+		where I: IntoIterator<Item=(Vec<u8>, Option<Vec<u8>>)>;
+
 	/// Get all key/value pairs into a Vec.
 	fn pairs(&self) -> Vec<(Vec<u8>, Vec<u8>)>;
+
+	/// Get all keys starting with the given prefix, in lexicographic order.
+	fn keys(&self, prefix: &[u8]) -> Result<Vec<Vec<u8>>, Self::Error>;
+
+	/// Get at most `count` keys starting with the given prefix, in lexicographic order,
+	/// beginning after `start_key` if it is given. Reads all keys with the prefix by default.
+	fn keys_paged(&self, prefix: &[u8], start_key: Option<&[u8]>, count: usize) -> Result<Vec<Vec<u8>>, Self::Error> {
+		Ok(self.keys(prefix)?.into_iter()
+			.filter(|key| start_key.map_or(true, |start| &key[..] > start))
+			.take(count)
+			.collect())
+	}
+
+	/// Get all keys of the child trie addressed by `storage_key` starting with the given
+	/// prefix, in lexicographic order.
+	fn child_keys(&self, storage_key: &[u8], prefix: &[u8]) -> Result<Vec<Vec<u8>>, Self::Error>;
+
+	/// Try to convert into a trie-based backend, which is required for generating proofs.
+	fn try_into_trie_backend(self) -> Option<TrieBackend>;
+
+	/// Calculate the storage root, with given deltas over the main trie and over child
+	/// tries. The roots of the changed child tries are committed into the main trie, and
+	/// child tries which end up empty are removed from it.
+	fn full_storage_root<I, C, CI>(&self, delta: I, child_deltas: C) -> ([u8; 32], Self::Transaction)
+		where
+			I: IntoIterator<Item=(Vec<u8>, Option<Vec<u8>>)>,
+			C: IntoIterator<Item=(Vec<u8>, CI)>,
+			CI: IntoIterator<Item=(Vec<u8>, Option<Vec<u8>>)>,
+	{
+		let mut transaction: Self::Transaction = Default::default();
+		let mut child_roots = Vec::new();
+		for (storage_key, child_delta) in child_deltas {
+			let (child_root, is_empty, child_transaction) = self.child_storage_root(&storage_key, child_delta);
+			transaction.consolidate(child_transaction);
+			child_roots.push((storage_key, if is_empty { None } else { Some(child_root.to_vec()) }));
+		}
+
+		let (root, top_transaction) = self.storage_root(delta.into_iter().chain(child_roots));
+		transaction.consolidate(top_transaction);
+		(root, transaction)
+	}
+}
+
+/// Transactions which can be merged together.
+pub trait Consolidate {
+	/// Merge `other` into this transaction.
+	fn consolidate(&mut self, other: Self);
+}
+
+impl Consolidate for () {
+	fn consolidate(&mut self, _other: Self) {}
+}
+
+impl<T> Consolidate for Vec<T> {
+	fn consolidate(&mut self, mut other: Self) {
+		self.append(&mut other);
+	}
+}
+
+impl Consolidate for MemoryDB {
+	fn consolidate(&mut self, mut other: Self) {
+		for (key, (value, rc)) in other.drain() {
+			if rc > 0 {
+				for _ in 0..rc {
+					self.emplace(key, value.clone());
+				}
+			} else {
+				for _ in rc..0 {
+					self.remove(&key);
+				}
+			}
+		}
+	}
 }
 
 /// Error impossible.
@@ -59,10 +147,11 @@ impl error::Error for Void {
 }
 
 /// In-memory backend. Fully recomputes tries on each commit but useful for
-/// tests.
+/// tests. Entries of the main trie are kept under `None`, those of child tries
+/// under their storage key.
 #[derive(Clone, PartialEq, Eq)]
 pub struct InMemory {
-	inner: Arc<HashMap<Vec<u8>, Vec<u8>>>,
+	inner: Arc<HashMap<Option<Vec<u8>>, HashMap<Vec<u8>, Vec<u8>>>>,
 }
 
 impl Default for InMemory {
@@ -77,19 +166,64 @@ impl InMemory {
 	/// Copy the state, with applied updates
 	pub fn update(&self, changes: <Self as Backend>::Transaction) -> Self {
 		let mut inner: HashMap<_, _> = (&*self.inner).clone();
-		for (key, val) in changes {
+		for (storage_key, key, val) in changes {
 			match val {
-				Some(v) => { inner.insert(key, v); },
-				None => { inner.remove(&key); },
+				Some(v) => { inner.entry(storage_key).or_insert_with(HashMap::new).insert(key, v); },
+				None => { inner.entry(storage_key).or_insert_with(HashMap::new).remove(&key); },
 			}
 		}
+		inner.retain(|storage_key, entries| storage_key.is_none() || !entries.is_empty());
 
 		inner.into()
 	}
+
+	fn entries(&self, storage_key: Option<&[u8]>) -> Option<&HashMap<Vec<u8>, Vec<u8>>> {
+		self.inner.get(&storage_key.map(|k| k.to_vec()))
+	}
+
+	/// Root of a trie made of the given entries with `delta` applied, along with whether
+	/// that trie is empty and the transaction for the entries of the trie at `storage_key`.
+	fn root_with_delta<I>(&self, storage_key: Option<&[u8]>, delta: I) -> ([u8; 32], bool, <Self as Backend>::Transaction)
+		where I: IntoIterator<Item=(Vec<u8>, Option<Vec<u8>>)>
+	{
+		let existing_pairs = self.entries(storage_key).into_iter()
+			.flat_map(|entries| entries.iter().map(|(k, v)| (k.clone(), Some(v.clone()))));
+
+		let transaction: Vec<_> = delta.into_iter().collect();
+		let pairs: Vec<_> = existing_pairs.chain(transaction.iter().cloned())
+			.collect::<HashMap<_, _>>()
+			.into_iter()
+			.filter_map(|(k, maybe_val)| maybe_val.map(|val| (k, val)))
+			.collect();
+		let is_empty = pairs.is_empty();
+		let root = ::triehash::trie_root(pairs).0;
+
+		let storage_key = storage_key.map(|k| k.to_vec());
+		let transaction = transaction.into_iter().map(|(k, v)| (storage_key.clone(), k, v)).collect();
+		(root, is_empty, transaction)
+	}
+
+	fn keys_with_prefix(&self, storage_key: Option<&[u8]>, prefix: &[u8]) -> Vec<Vec<u8>> {
+		let mut keys: Vec<_> = self.entries(storage_key).into_iter()
+			.flat_map(|entries| entries.keys())
+			.filter(|k| k.starts_with(prefix))
+			.cloned()
+			.collect();
+		keys.sort();
+		keys
+	}
 }
 
 impl From<HashMap<Vec<u8>, Vec<u8>>> for InMemory {
 	fn from(inner: HashMap<Vec<u8>, Vec<u8>>) -> Self {
+		let mut tries = HashMap::new();
+		tries.insert(None, inner);
+		tries.into()
+	}
+}
+
+impl From<HashMap<Option<Vec<u8>>, HashMap<Vec<u8>, Vec<u8>>>> for InMemory {
+	fn from(inner: HashMap<Option<Vec<u8>>, HashMap<Vec<u8>, Vec<u8>>>) -> Self {
 		InMemory {
 			inner: Arc::new(inner),
 		}
@@ -98,29 +232,78 @@ impl From<HashMap<Vec<u8>, Vec<u8>>> for InMemory {
 
 impl Backend for InMemory {
 	type Error = Void;
-	type Transaction = Vec<(Vec<u8>, Option<Vec<u8>>)>;
+	type Transaction = Vec<(Option<Vec<u8>>, Vec<u8>, Option<Vec<u8>>)>;
 
 	fn storage(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
-		Ok(self.inner.get(key).map(Clone::clone))
+		Ok(self.entries(None).and_then(|entries| entries.get(key)).cloned())
+	}
+
+	fn child_storage(&self, storage_key: &[u8], key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+		Ok(self.entries(Some(storage_key)).and_then(|entries| entries.get(key)).cloned())
 	}
 
 	fn storage_root<I>(&self, delta: I) -> ([u8; 32], Self::Transaction)
 		where I: IntoIterator<Item=(Vec<u8>, Option<Vec<u8>>)>
 	{
-		let existing_pairs = self.inner.iter().map(|(k, v)| (k.clone(), Some(v.clone())));
-
-		let transaction: Vec<_> = delta.into_iter().collect();
-		let root = ::triehash::trie_root(existing_pairs.chain(transaction.iter().cloned())
-			.collect::<HashMap<_, _>>()
-			.into_iter()
-			.filter_map(|(k, maybe_val)| maybe_val.map(|val| (k, val)))
-		).0;
-
+		let (root, _, transaction) = self.root_with_delta(None, delta);
 		(root, transaction)
 	}
 
+	fn child_storage_root<I>(&self, storage_key: &[u8], delta: I) -> ([u8; 32], bool, Self::Transaction)
+		where I: IntoIterator<Item=(Vec<u8>, Option<Vec<u8>>)>
+	{
+		self.root_with_delta(Some(storage_key), delta)
+	}
+
 	fn pairs(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
-		self.inner.iter().map(|(k, v)| (k.clone(), v.clone())).collect()
+		self.entries(None).into_iter()
+			.flat_map(|entries| entries.iter().map(|(k, v)| (k.clone(), v.clone())))
+			.collect()
+	}
+
+	fn keys(&self, prefix: &[u8]) -> Result<Vec<Vec<u8>>, Self::Error> {
+		Ok(self.keys_with_prefix(None, prefix))
+	}
+
+	fn child_keys(&self, storage_key: &[u8], prefix: &[u8]) -> Result<Vec<Vec<u8>>, Self::Error> {
+		Ok(self.keys_with_prefix(Some(storage_key), prefix))
+	}
+
+	fn try_into_trie_backend(self) -> Option<TrieBackend> {
+		let mut mdb = MemoryDB::default();
+		let mut child_roots = Vec::new();
+		for (storage_key, entries) in self.inner.iter() {
+			if let Some(ref storage_key) = *storage_key {
+				let root = insert_into_memory_db(&mut mdb, entries.iter())?;
+				child_roots.push((storage_key.clone(), root.to_vec()));
+			}
+		}
+
+		let top = self.entries(None).into_iter()
+			.flat_map(|entries| entries.iter().map(|(k, v)| (k.clone(), v.clone())))
+			.filter(|&(ref k, _)| !child_roots.iter().any(|&(ref storage_key, _)| storage_key == k))
+			.chain(child_roots)
+			.collect::<Vec<_>>();
+		let root = insert_into_memory_db(&mut mdb, top.iter().map(|&(ref k, ref v)| (k, v)))?;
+
+		Some(TrieBackend::with_memorydb(mdb, root))
 	}
 }
 
+/// Insert the given entries into a new trie in `mdb`, returning its root.
+pub(crate) fn insert_into_memory_db<'a, I>(mdb: &mut MemoryDB, entries: I) -> Option<TrieH256>
+	where I: IntoIterator<Item=(&'a Vec<u8>, &'a Vec<u8>)>
+{
+	let mut root = TrieH256::default();
+	{
+		let mut trie = TrieDBMut::new(mdb, &mut root);
+		for (key, value) in entries {
+			if let Err(e) = trie.insert(key, value) {
+				warn!("Failed to write to trie: {}", e);
+				return None;
+			}
+		}
+	}
+
+	Some(root)
+}
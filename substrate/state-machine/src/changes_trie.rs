@@ -0,0 +1,217 @@
+// Copyright 2017 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Changes trie: commits to the keys of the main trie changed by a block.
+//!
+//! Every changed key maps to the encoded, sorted indices of the extrinsics which changed
+//! it. Keys changed only outside of extrinsics (e.g. while initialising the block) map to
+//! an empty list. Changes to child tries are not included.
+
+use std::sync::Arc;
+use codec::Slicable;
+use ethereum_types::H256 as TrieH256;
+use hashdb::{DBValue, HashDB};
+use memorydb::MemoryDB;
+use parking_lot::RwLock;
+use primitives::storage::well_known_keys::{CHANGES_TRIE_CONFIG, EXTRINSIC_INDEX};
+use backend::{Backend, Consolidate, insert_into_memory_db};
+use proving_backend::{ProvingBackend, create_proof_check_backend};
+use trie_backend::{Storage, TrieBackend};
+use {Error, OverlayedChanges};
+
+/// Transaction which inserts the nodes of a changes trie into storage.
+pub type ChangesTrieTransaction = MemoryDB;
+
+/// Changes trie nodes kept in memory.
+#[derive(Default)]
+pub struct InMemoryChangesTrieStorage {
+	nodes: RwLock<MemoryDB>,
+}
+
+impl InMemoryChangesTrieStorage {
+	/// Insert the nodes of a changes trie.
+	pub fn insert(&self, transaction: ChangesTrieTransaction) {
+		self.nodes.write().consolidate(transaction);
+	}
+}
+
+impl Storage for InMemoryChangesTrieStorage {
+	fn get(&self, key: &TrieH256) -> Result<Option<DBValue>, String> {
+		Ok(self.nodes.read().get(key))
+	}
+}
+
+/// Whether a changes trie is built for the changes made on top of the given state.
+pub(crate) fn is_enabled<B: Backend>(backend: &B, overlay: &OverlayedChanges) -> Result<bool, B::Error> {
+	match overlay.storage(CHANGES_TRIE_CONFIG) {
+		Some(value) => Ok(value.is_some()),
+		None => backend.storage(CHANGES_TRIE_CONFIG).map(|value| value.is_some()),
+	}
+}
+
+/// Compute the root of the changes trie for the changes in the overlay, along with the
+/// transaction which inserts the trie nodes into storage. Returns `None` if changes
+/// tries are not enabled in the given state.
+pub fn changes_trie_root<B: Backend>(
+	backend: &B,
+	overlay: &OverlayedChanges,
+) -> Result<Option<([u8; 32], ChangesTrieTransaction)>, Box<Error>> {
+	if !is_enabled(backend, overlay).map_err(|e| Box::new(e) as Box<Error>)? {
+		return Ok(None);
+	}
+
+	let entries = overlay.extrinsics_changes().into_iter()
+		.filter(|&(ref key, _)| &key[..] != EXTRINSIC_INDEX)
+		.map(|(key, extrinsics)| (key, extrinsics.into_iter().collect::<Vec<u32>>().encode()))
+		.collect::<Vec<_>>();
+
+	let mut mdb = MemoryDB::default();
+	let root = insert_into_memory_db(&mut mdb, entries.iter().map(|&(ref k, ref v)| (k, v)))
+		.ok_or_else(|| Box::new("Failed to build changes trie".to_owned()) as Box<Error>)?;
+	Ok(Some((root.0, mdb)))
+}
+
+/// Read the indices of the extrinsics which changed `key` from the changes trie with the
+/// given root, along with the proof of the read. The indices are `None` if the key was
+/// not changed.
+pub fn prove_key_changes(
+	storage: Arc<Storage>,
+	root: [u8; 32],
+	key: &[u8],
+) -> Result<(Option<Vec<u32>>, Vec<Vec<u8>>), Box<Error>> {
+	let backend = ProvingBackend::new(TrieBackend::with_storage(storage, root.into()));
+	let extrinsics = read_key_changes(&backend, key)?;
+	Ok((extrinsics, backend.extract_proof()))
+}
+
+/// Check a proof produced by `prove_key_changes` against the changes trie root, returning
+/// the indices of the extrinsics which changed `key`.
+///
+/// The proof may include nodes of other tries, so the proofs of a key in several blocks
+/// can be checked as one.
+pub fn check_key_changes_proof(
+	root: [u8; 32],
+	proof: Vec<Vec<u8>>,
+	key: &[u8],
+) -> Result<Option<Vec<u32>>, Box<Error>> {
+	let backend = create_proof_check_backend(root.into(), proof)?;
+	read_key_changes(&backend, key)
+}
+
+fn read_key_changes<B: Backend>(backend: &B, key: &[u8]) -> Result<Option<Vec<u32>>, Box<Error>> {
+	match backend.storage(key).map_err(|e| Box::new(e) as Box<Error>)? {
+		Some(value) => Vec::<u32>::decode(&mut &value[..])
+			.map(Some)
+			.ok_or_else(|| Box::new("Invalid changes trie entry".to_owned()) as Box<Error>),
+		None => Ok(None),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+	use backend::InMemory;
+	use ext::Ext;
+	use Externalities;
+	use super::*;
+
+	fn enabled_backend() -> InMemory {
+		let mut storage = HashMap::new();
+		storage.insert(CHANGES_TRIE_CONFIG.to_vec(), vec![1]);
+		storage.insert(b"doe".to_vec(), b"reindeer".to_vec());
+		InMemory::from(storage)
+	}
+
+	fn set_in_extrinsic<B: Backend>(ext: &mut Ext<B>, index: u32, key: &[u8], value: &[u8]) {
+		ext.set_storage(EXTRINSIC_INDEX.to_vec(), index.encode());
+		ext.set_storage(key.to_vec(), value.to_vec());
+	}
+
+	#[test]
+	fn no_changes_trie_when_disabled() {
+		let backend = InMemory::default();
+		let mut overlay = OverlayedChanges::default();
+		let root = {
+			let mut ext = Ext::new(&mut overlay, &backend);
+			ext.set_storage(b"dog".to_vec(), b"puppy".to_vec());
+			ext.storage_changes_root()
+		};
+		assert_eq!(root, None);
+		assert!(changes_trie_root(&backend, &overlay).unwrap().is_none());
+	}
+
+	#[test]
+	fn changes_are_attributed_to_extrinsics() {
+		let backend = enabled_backend();
+		let mut overlay = OverlayedChanges::default();
+		let root = {
+			let mut ext = Ext::new(&mut overlay, &backend);
+			ext.set_storage(b"block".to_vec(), vec![1]);
+			set_in_extrinsic(&mut ext, 0, b"dog", b"puppy");
+			set_in_extrinsic(&mut ext, 1, b"doe", b"fawn");
+			set_in_extrinsic(&mut ext, 2, b"dog", b"dog");
+			ext.clear_storage(EXTRINSIC_INDEX);
+			ext.storage_changes_root()
+		};
+		overlay.commit_prospective();
+
+		let expected = ::triehash::trie_root(vec![
+			(b"block".to_vec(), Vec::<u32>::new().encode()),
+			(b"doe".to_vec(), vec![1u32].encode()),
+			(b"dog".to_vec(), vec![0u32, 2].encode()),
+		]).0;
+		assert_eq!(root, Some(expected));
+		assert_eq!(changes_trie_root(&backend, &overlay).unwrap().unwrap().0, expected);
+	}
+
+	#[test]
+	fn rolled_back_changes_are_not_attributed() {
+		let backend = enabled_backend();
+		let mut overlay = OverlayedChanges::default();
+		let mut ext = Ext::new(&mut overlay, &backend);
+		ext.set_storage(EXTRINSIC_INDEX.to_vec(), 0u32.encode());
+		ext.start_transaction();
+		ext.set_storage(b"dog".to_vec(), b"puppy".to_vec());
+		assert!(ext.rollback_transaction());
+		ext.clear_storage(EXTRINSIC_INDEX);
+
+		assert_eq!(ext.storage_changes_root(), Some(::triehash::trie_root(Vec::<(Vec<u8>, Vec<u8>)>::new()).0));
+	}
+
+	#[test]
+	fn key_changes_proof_is_generated_and_checked() {
+		let backend = enabled_backend();
+		let mut overlay = OverlayedChanges::default();
+		{
+			let mut ext = Ext::new(&mut overlay, &backend);
+			set_in_extrinsic(&mut ext, 0, b"dog", b"puppy");
+			set_in_extrinsic(&mut ext, 3, b"dog", b"dog");
+		}
+		let (root, mdb) = changes_trie_root(&backend, &overlay).unwrap().unwrap();
+		let storage = Arc::new(InMemoryChangesTrieStorage::default());
+		storage.insert(mdb);
+
+		let (extrinsics, proof) = prove_key_changes(storage.clone(), root, b"dog").unwrap();
+		assert_eq!(extrinsics, Some(vec![0, 3]));
+		assert_eq!(check_key_changes_proof(root, proof, b"dog").unwrap(), Some(vec![0, 3]));
+
+		let (extrinsics, proof) = prove_key_changes(storage, root, b"doe").unwrap();
+		assert_eq!(extrinsics, None);
+		assert_eq!(check_key_changes_proof(root, proof, b"doe").unwrap(), None);
+
+		assert!(check_key_changes_proof(root, vec![], b"dog").is_err());
+	}
+}
@@ -18,6 +18,9 @@
 
 use std::{error, fmt};
 use backend::Backend;
+use changes_trie;
+use codec::Slicable;
+use primitives::storage::well_known_keys::{EXTRINSIC_INDEX, is_child_storage_key};
 use {Externalities, OverlayedChanges};
 
 /// Errors that can occur when interacting with the externalities.
@@ -57,6 +60,9 @@ pub struct Ext<'a, B: 'a + Backend> {
 	backend: &'a B,
 	// The transaction necessary to commit to the backend.
 	transaction: Option<(B::Transaction, [u8; 32])>,
+	// Whether changes are attributed to extrinsics for the changes trie. Read lazily, on
+	// the first change.
+	changes_trie_enabled: Option<bool>,
 }
 
 impl<'a, B: 'a + Backend> Ext<'a, B> {
@@ -66,9 +72,26 @@ impl<'a, B: 'a + Backend> Ext<'a, B> {
 			overlay,
 			backend,
 			transaction: None,
+			changes_trie_enabled: None,
 		}
 	}
 
+	/// The index of the extrinsic to attribute changes to, if changes tries are enabled
+	/// and an extrinsic is being applied.
+	fn extrinsic_index(&mut self) -> Option<u32> {
+		if self.changes_trie_enabled.is_none() {
+			let enabled = changes_trie::is_enabled(self.backend, &*self.overlay)
+				.expect("Externalities not allowed to fail within runtime");
+			self.changes_trie_enabled = Some(enabled);
+		}
+
+		if self.changes_trie_enabled != Some(true) {
+			return None;
+		}
+
+		self.storage(EXTRINSIC_INDEX).and_then(|index| u32::decode(&mut &index[..]))
+	}
+
 	/// Get the transaction necessary to update the backend.
 	pub fn transaction(mut self) -> B::Transaction {
 		let _ = self.storage_root();
@@ -83,8 +106,7 @@ impl<'a, B: 'a + Backend> Ext<'a, B> {
 
 		self.backend.pairs().iter()
 			.map(|&(ref k, ref v)| (k.to_vec(), Some(v.to_vec())))
-			.chain(self.overlay.committed.clone().into_iter())
-			.chain(self.overlay.prospective.clone().into_iter())
+			.chain(self.overlay.top_changes().into_iter())
 			.collect::<HashMap<_, _>>()
 			.into_iter()
 			.filter_map(|(k, maybe_val)| maybe_val.map(|val| (k, val)))
@@ -101,10 +123,71 @@ impl<'a, B: 'a> Externalities for Ext<'a, B>
 	}
 
 	fn place_storage(&mut self, key: Vec<u8>, value: Option<Vec<u8>>) {
+		if is_child_storage_key(&key) {
+			warn!("Refuse to directly set child storage key");
+			return;
+		}
+
 		self.transaction = None; // wipe out the transaction since root will no longer be the same.
+		if let Some(extrinsic_index) = self.extrinsic_index() {
+			self.overlay.note_extrinsic(&key, extrinsic_index);
+		}
 		self.overlay.set_storage(key, value);
 	}
 
+	fn clear_prefix(&mut self, prefix: &[u8]) {
+		self.transaction = None; // wipe out the transaction since root will no longer be the same.
+
+		let backend_keys = self.backend.keys(prefix).expect("Externalities not allowed to fail within runtime");
+		let overlay_keys: Vec<_> = self.overlay.layers().into_iter()
+			.flat_map(|changes| changes.top.keys())
+			.filter(|key| key.starts_with(prefix))
+			.cloned()
+			.collect();
+
+		let extrinsic_index = self.extrinsic_index();
+		for key in backend_keys.into_iter().chain(overlay_keys) {
+			if let Some(extrinsic_index) = extrinsic_index {
+				self.overlay.note_extrinsic(&key, extrinsic_index);
+			}
+			self.overlay.set_storage(key, None);
+		}
+	}
+
+	fn child_storage(&self, storage_key: &[u8], key: &[u8]) -> Option<Vec<u8>> {
+		self.overlay.child_storage(storage_key, key).map(|x| x.map(|x| x.to_vec())).unwrap_or_else(||
+			self.backend.child_storage(storage_key, key).expect("Externalities not allowed to fail within runtime"))
+	}
+
+	fn place_child_storage(&mut self, storage_key: Vec<u8>, key: Vec<u8>, value: Option<Vec<u8>>) -> bool {
+		if !is_child_storage_key(&storage_key) {
+			return false;
+		}
+
+		self.transaction = None; // wipe out the transaction since root will no longer be the same.
+		self.overlay.set_child_storage(storage_key, key, value);
+		true
+	}
+
+	fn kill_child_storage(&mut self, storage_key: &[u8]) {
+		if !is_child_storage_key(storage_key) {
+			return;
+		}
+
+		self.transaction = None; // wipe out the transaction since root will no longer be the same.
+
+		let backend_keys = self.backend.child_keys(storage_key, &[]).expect("Externalities not allowed to fail within runtime");
+		let overlay_keys: Vec<_> = self.overlay.layers().into_iter()
+			.filter_map(|changes| changes.children.get(storage_key))
+			.flat_map(|child| child.keys())
+			.cloned()
+			.collect();
+
+		for key in backend_keys.into_iter().chain(overlay_keys) {
+			self.overlay.set_child_storage(storage_key.to_vec(), key, None);
+		}
+	}
+
 	fn chain_id(&self) -> u64 {
 		42
 	}
@@ -115,12 +198,39 @@ impl<'a, B: 'a> Externalities for Ext<'a, B>
 		}
 
 		// compute and memoize
-		let delta = self.overlay.committed.iter()
-			.chain(self.overlay.prospective.iter())
-			.map(|(k, v)| (k.clone(), v.clone()));
+		let delta = self.overlay.top_changes();
+		let child_deltas = self.overlay.child_changes();
 
-		let (root, transaction) = self.backend.storage_root(delta);
+		let (root, transaction) = self.backend.full_storage_root(delta, child_deltas);
 		self.transaction = Some((transaction, root));
 		root
 	}
+
+	fn child_storage_root(&mut self, storage_key: &[u8]) -> Option<[u8; 32]> {
+		if !is_child_storage_key(storage_key) {
+			return None;
+		}
+
+		let delta = self.overlay.child_changes().remove(storage_key).unwrap_or_default();
+		Some(self.backend.child_storage_root(storage_key, delta).0)
+	}
+
+	fn storage_changes_root(&mut self) -> Option<[u8; 32]> {
+		changes_trie::changes_trie_root(self.backend, &*self.overlay)
+			.expect("Externalities not allowed to fail within runtime")
+			.map(|(root, _)| root)
+	}
+
+	fn start_transaction(&mut self) {
+		self.overlay.start_transaction();
+	}
+
+	fn commit_transaction(&mut self) -> bool {
+		self.overlay.commit_transaction()
+	}
+
+	fn rollback_transaction(&mut self) -> bool {
+		self.transaction = None; // wipe out the transaction since root will no longer be the same.
+		self.overlay.rollback_transaction()
+	}
 }
@@ -22,27 +22,81 @@
 extern crate hex_literal;
 extern crate triehash;
 extern crate byteorder;
-
-use std::collections::HashMap;
+extern crate ethereum_types;
+extern crate hashdb;
+extern crate memorydb;
+extern crate parking_lot;
+extern crate patricia_trie;
+extern crate substrate_codec as codec;
+extern crate substrate_primitives as primitives;
+
+#[macro_use]
+extern crate log;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::collections::hash_map::Drain;
 use std::fmt;
 
 pub mod backend;
+mod changes_trie;
 mod ext;
 mod testing;
+mod proving_backend;
+mod trie_backend;
 
 pub use testing::TestExternalities;
 pub use ext::Ext;
 pub use backend::Backend;
+pub use changes_trie::{ChangesTrieTransaction, InMemoryChangesTrieStorage, changes_trie_root,
+	prove_key_changes, check_key_changes_proof};
+pub use proving_backend::{ProvingBackend, create_proof_check_backend};
+pub use trie_backend::{TrieBackend, TrieBackendStorage, Storage};
+
+/// Pending changes to the main trie and to child tries.
+#[derive(Debug, Default, Clone, PartialEq)]
+struct OverlayedChangeSet {
+	/// Changes to the main trie.
+	top: HashMap<Vec<u8>, Option<Vec<u8>>>,
+	/// Changes to child tries, by child storage key.
+	children: HashMap<Vec<u8>, HashMap<Vec<u8>, Option<Vec<u8>>>>,
+	/// Indices of the extrinsics which changed each key of the main trie. Only tracked
+	/// while a changes trie is being built.
+	extrinsics: HashMap<Vec<u8>, BTreeSet<u32>>,
+}
+
+impl OverlayedChangeSet {
+	fn is_empty(&self) -> bool {
+		self.top.is_empty() && self.children.is_empty()
+	}
+
+	fn clear(&mut self) {
+		self.top.clear();
+		self.children.clear();
+		self.extrinsics.clear();
+	}
+
+	/// Apply the changes of `other` on top of these.
+	fn extend(&mut self, other: OverlayedChangeSet) {
+		self.top.extend(other.top);
+		for (storage_key, changes) in other.children {
+			self.children.entry(storage_key).or_insert_with(HashMap::new).extend(changes);
+		}
+		for (key, extrinsics) in other.extrinsics {
+			self.extrinsics.entry(key).or_insert_with(BTreeSet::new).extend(extrinsics);
+		}
+	}
+}
 
 /// The overlayed changes to state to be queried on top of the backend.
 ///
 /// A transaction shares all prospective changes within an inner overlay
-/// that can be cleared.
+/// that can be cleared. Within it, any number of nested storage transactions
+/// may be started, each of which can be committed into its parent or rolled back.
 #[derive(Debug, Default, Clone)]
 pub struct OverlayedChanges {
-	prospective: HashMap<Vec<u8>, Option<Vec<u8>>>,
-	committed: HashMap<Vec<u8>, Option<Vec<u8>>>,
+	prospective: OverlayedChangeSet,
+	committed: OverlayedChangeSet,
+	transactions: Vec<OverlayedChangeSet>,
 }
 
 impl OverlayedChanges {
@@ -50,32 +104,145 @@ impl OverlayedChanges {
 	/// to the backend); Some(None) if the key has been deleted. Some(Some(...)) for a key whose
 	/// value has been set.
 	pub fn storage(&self, key: &[u8]) -> Option<Option<&[u8]>> {
-		self.prospective.get(key)
-			.or_else(|| self.committed.get(key))
+		self.layers().into_iter().rev()
+			.filter_map(|changes| changes.top.get(key))
+			.next()
+			.map(|x| x.as_ref().map(AsRef::as_ref))
+	}
+
+	/// Like `storage`, but for an entry of the child trie addressed by `storage_key`.
+	pub fn child_storage(&self, storage_key: &[u8], key: &[u8]) -> Option<Option<&[u8]>> {
+		self.layers().into_iter().rev()
+			.filter_map(|changes| changes.children.get(storage_key).and_then(|child| child.get(key)))
+			.next()
 			.map(|x| x.as_ref().map(AsRef::as_ref))
 	}
 
 	fn set_storage(&mut self, key: Vec<u8>, val: Option<Vec<u8>>) {
-		self.prospective.insert(key, val);
+		self.current_mut().top.insert(key, val);
+	}
+
+	/// Attribute the pending change of `key` to the extrinsic with the given index.
+	fn note_extrinsic(&mut self, key: &[u8], extrinsic_index: u32) {
+		self.current_mut().extrinsics.entry(key.to_vec()).or_insert_with(BTreeSet::new).insert(extrinsic_index);
+	}
+
+	fn set_child_storage(&mut self, storage_key: Vec<u8>, key: Vec<u8>, val: Option<Vec<u8>>) {
+		self.current_mut().children.entry(storage_key).or_insert_with(HashMap::new).insert(key, val);
+	}
+
+	/// Start a new nested storage transaction. All changes made until it is committed or
+	/// rolled back are kept apart from the prospective changes.
+	pub fn start_transaction(&mut self) {
+		self.transactions.push(OverlayedChangeSet::default());
+	}
+
+	/// Commit the innermost storage transaction into its parent. Returns `false` if there
+	/// is no open transaction.
+	pub fn commit_transaction(&mut self) -> bool {
+		match self.transactions.pop() {
+			Some(changes) => {
+				self.current_mut().extend(changes);
+				true
+			}
+			None => false,
+		}
+	}
+
+	/// Discard all changes made in the innermost storage transaction. Returns `false` if
+	/// there is no open transaction.
+	pub fn rollback_transaction(&mut self) -> bool {
+		self.transactions.pop().is_some()
 	}
 
-	/// Discard prospective changes to state.
+	/// Discard prospective changes to state, including those of any open transactions.
 	pub fn discard_prospective(&mut self) {
 		self.prospective.clear();
+		self.transactions.clear();
 	}
 
 	/// Commit prospective changes to state.
+	///
+	/// Panics if a storage transaction is still open.
 	pub fn commit_prospective(&mut self) {
+		assert!(self.transactions.is_empty(), "prospective changes committed with {} open storage transactions", self.transactions.len());
+
 		if self.committed.is_empty() {
 			::std::mem::swap(&mut self.prospective, &mut self.committed);
 		} else {
-			self.committed.extend(self.prospective.drain());
+			let prospective = ::std::mem::replace(&mut self.prospective, Default::default());
+			self.committed.extend(prospective);
 		}
 	}
 
-	/// Drain prospective changes to an iterator.
+	/// Drain committed changes to the main trie to an iterator.
 	pub fn drain(&mut self) -> Drain<Vec<u8>, Option<Vec<u8>>> {
-		self.committed.drain()
+		self.committed.top.drain()
+	}
+
+	/// The layer new changes are written to.
+	fn current_mut(&mut self) -> &mut OverlayedChangeSet {
+		self.transactions.last_mut().unwrap_or(&mut self.prospective)
+	}
+
+	/// All layers of pending changes, from the oldest to the most recent.
+	fn layers(&self) -> Vec<&OverlayedChangeSet> {
+		let mut layers = vec![&self.committed, &self.prospective];
+		layers.extend(self.transactions.iter());
+		layers
+	}
+
+	/// Pending changes to the main trie, with later layers applied on top.
+	fn top_changes(&self) -> HashMap<Vec<u8>, Option<Vec<u8>>> {
+		let mut changes = HashMap::new();
+		for layer in self.layers() {
+			changes.extend(layer.top.iter().map(|(k, v)| (k.clone(), v.clone())));
+		}
+		changes
+	}
+
+	/// Keys of the main trie with pending changes, each with the indices of the extrinsics
+	/// which changed it.
+	fn extrinsics_changes(&self) -> BTreeMap<Vec<u8>, BTreeSet<u32>> {
+		let mut changes = BTreeMap::new();
+		for layer in self.layers() {
+			for key in layer.top.keys() {
+				changes.entry(key.clone()).or_insert_with(BTreeSet::new);
+			}
+			for (key, extrinsics) in &layer.extrinsics {
+				changes.entry(key.clone()).or_insert_with(BTreeSet::new).extend(extrinsics.iter().cloned());
+			}
+		}
+		changes
+	}
+
+	/// Pending changes to each changed child trie, with later layers applied on top.
+	fn child_changes(&self) -> HashMap<Vec<u8>, HashMap<Vec<u8>, Option<Vec<u8>>>> {
+		let mut changes: HashMap<Vec<u8>, HashMap<_, _>> = HashMap::new();
+		for layer in self.layers() {
+			for (storage_key, child) in &layer.children {
+				changes.entry(storage_key.clone()).or_insert_with(HashMap::new)
+					.extend(child.iter().map(|(k, v)| (k.clone(), v.clone())));
+			}
+		}
+		changes
+	}
+
+	/// Prospective changes with those of any open transactions applied on top. Changes to
+	/// child tries are keyed by the child storage key followed by the key in the child trie.
+	fn flattened_prospective(&self) -> HashMap<Vec<u8>, Option<Vec<u8>>> {
+		let mut changes = HashMap::new();
+		for layer in ::std::iter::once(&self.prospective).chain(self.transactions.iter()) {
+			changes.extend(layer.top.iter().map(|(k, v)| (k.clone(), v.clone())));
+			for (storage_key, child) in &layer.children {
+				changes.extend(child.iter().map(|(k, v)| {
+					let mut key = storage_key.clone();
+					key.extend_from_slice(k);
+					(key, v.clone())
+				}));
+			}
+		}
+		changes
 	}
 }
 
@@ -93,11 +260,52 @@ impl<E> Error for E where E: 'static + fmt::Debug + fmt::Display + Send {}
 #[derive(Debug, Eq, PartialEq)]
 pub enum ExecutionError {
 	/// The entry `:code` doesn't exist in storage so there's no way we can execute anything.
-	CodeEntryDoesNotExist
+	CodeEntryDoesNotExist,
+	/// The state backend is not trie-based, so no proof can be generated from it.
+	UnableToGenerateProof,
+	/// The given proof doesn't contain the trie node of the state root.
+	InvalidProof,
+	/// Native and wasm execution of the same call disagreed on the output or on the storage
+	/// changes made.
+	ConsensusFailure {
+		/// The runtime method that was called.
+		method: String,
+		/// Description of the outcome of the native execution.
+		native: String,
+		/// Description of the outcome of the wasm execution.
+		wasm: String,
+		/// Storage keys whose pending values differ between the two executions, in order.
+		diverging_keys: Vec<Vec<u8>>,
+	},
+	/// The runtime call returned without closing the storage transactions it started.
+	UnclosedTransaction {
+		/// The runtime method that was called.
+		method: String,
+	},
 }
 
 impl fmt::Display for ExecutionError {
-	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "Externalities Error") }
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			ExecutionError::CodeEntryDoesNotExist => write!(f, "Externalities Error"),
+			ExecutionError::UnableToGenerateProof => write!(f, "Unable to generate proof"),
+			ExecutionError::InvalidProof => write!(f, "Invalid proof"),
+			ExecutionError::ConsensusFailure { ref method, ref native, ref wasm, ref diverging_keys } => {
+				write!(f, "Native and wasm execution of `{}` diverged: native {}, wasm {}", method, native, wasm)?;
+				if !diverging_keys.is_empty() {
+					let keys = diverging_keys.iter().map(|k| format!("0x{}", to_hex(k))).collect::<Vec<_>>();
+					write!(f, "; diverging storage keys: [{}]", keys.join(", "))?;
+				}
+				Ok(())
+			}
+			ExecutionError::UnclosedTransaction { ref method } =>
+				write!(f, "Runtime call `{}` returned with an open storage transaction", method),
+		}
+	}
+}
+
+fn to_hex(data: &[u8]) -> String {
+	data.iter().map(|b| format!("{:02x}", b)).collect()
 }
 
 /// Externalities: pinned to specific active address.
@@ -115,14 +323,59 @@ pub trait Externalities {
 		self.place_storage(key.to_vec(), None);
 	}
 
+	/// Clear all storage entries whose keys start with the given prefix (effective immediately).
+	fn clear_prefix(&mut self, prefix: &[u8]);
+
 	/// Set or clear a storage entry (`key`) of current contract being called (effective immediately).
 	fn place_storage(&mut self, key: Vec<u8>, value: Option<Vec<u8>>);
 
+	/// Read entry `key` of the child trie addressed by `storage_key`.
+	fn child_storage(&self, storage_key: &[u8], key: &[u8]) -> Option<Vec<u8>>;
+
+	/// Set entry `key` of the child trie addressed by `storage_key` (effective immediately).
+	/// Returns `false` if `storage_key` is not a valid child storage key.
+	fn set_child_storage(&mut self, storage_key: Vec<u8>, key: Vec<u8>, value: Vec<u8>) -> bool {
+		self.place_child_storage(storage_key, key, Some(value))
+	}
+
+	/// Clear entry `key` of the child trie addressed by `storage_key` (effective immediately).
+	/// Returns `false` if `storage_key` is not a valid child storage key.
+	fn clear_child_storage(&mut self, storage_key: &[u8], key: &[u8]) -> bool {
+		self.place_child_storage(storage_key.to_vec(), key.to_vec(), None)
+	}
+
+	/// Set or clear entry `key` of the child trie addressed by `storage_key` (effective
+	/// immediately). Returns `false` if `storage_key` is not a valid child storage key.
+	fn place_child_storage(&mut self, storage_key: Vec<u8>, key: Vec<u8>, value: Option<Vec<u8>>) -> bool;
+
+	/// Clear all entries of the child trie addressed by `storage_key` (effective immediately).
+	fn kill_child_storage(&mut self, storage_key: &[u8]);
+
 	/// Get the identity of the chain.
 	fn chain_id(&self) -> u64;
 
-	/// Get the trie root of the current storage map.
+	/// Get the trie root of the current storage map, with the roots of all changed child
+	/// tries committed into it.
 	fn storage_root(&mut self) -> [u8; 32];
+
+	/// Get the trie root of the child trie addressed by `storage_key`. Returns `None` if
+	/// `storage_key` is not a valid child storage key.
+	fn child_storage_root(&mut self, storage_key: &[u8]) -> Option<[u8; 32]>;
+
+	/// Get the root of the changes trie of the changes made so far, or `None` if changes
+	/// tries are not enabled.
+	fn storage_changes_root(&mut self) -> Option<[u8; 32]>;
+
+	/// Start a new nested storage transaction.
+	fn start_transaction(&mut self);
+
+	/// Commit the innermost storage transaction into its parent. Returns `false` if there
+	/// is no open transaction.
+	fn commit_transaction(&mut self) -> bool;
+
+	/// Discard the changes of the innermost storage transaction. Returns `false` if there
+	/// is no open transaction.
+	fn rollback_transaction(&mut self) -> bool;
 }
 
 /// Code execution engine.
@@ -130,14 +383,30 @@ pub trait CodeExecutor: Sized + Send + Sync {
 	/// Externalities error type.
 	type Error: Error;
 
-	/// Call a given method in the runtime.
+	/// Call a given method in the runtime. If `use_native` is false, the given `code` is
+	/// always executed. Returns the result of the call along with whether native code
+	/// was actually used.
 	fn call<E: Externalities>(
 		&self,
 		ext: &mut E,
 		code: &[u8],
 		method: &str,
 		data: &[u8],
-	) -> Result<Vec<u8>, Self::Error>;
+		use_native: bool,
+	) -> (Result<Vec<u8>, Self::Error>, bool);
+}
+
+/// Strategy for executing a call into the runtime.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ExecutionStrategy {
+	/// Execute with the native equivalent if it is compatible with the given wasm module,
+	/// otherwise fall back to the wasm.
+	NativeWhenPossible,
+	/// Always execute the wasm module.
+	AlwaysWasm,
+	/// Execute with both the native equivalent (if compatible) and the wasm module, failing
+	/// if their results or storage changes differ. The wasm result is the one used.
+	Both,
 }
 
 /// Execute a call using the given state backend, overlayed changes, and call executor.
@@ -154,25 +423,46 @@ pub fn execute<B: backend::Backend, Exec: CodeExecutor>(
 	exec: &Exec,
 	method: &str,
 	call_data: &[u8],
+	strategy: ExecutionStrategy,
 ) -> Result<(Vec<u8>, B::Transaction), Box<Error>>
 {
-
-	let result = {
-		let mut externalities = ext::Ext::new(overlay, backend);
-		// make a copy.
-		let code = externalities.storage(b":code")
-			.ok_or(Box::new(ExecutionError::CodeEntryDoesNotExist) as Box<Error>)?
-			.to_vec();
-
-		exec.call(
-			&mut externalities,
-			&code,
-			method,
-			call_data,
-		).map(move |out| (out, externalities.transaction()))
+	// make a copy.
+	let code = ext::Ext::new(overlay, backend).storage(b":code")
+		.ok_or(Box::new(ExecutionError::CodeEntryDoesNotExist) as Box<Error>)?
+		.to_vec();
+
+	let result = match strategy {
+		ExecutionStrategy::NativeWhenPossible =>
+			execute_using(backend, overlay, exec, &code, method, call_data, true).0,
+		ExecutionStrategy::AlwaysWasm =>
+			execute_using(backend, overlay, exec, &code, method, call_data, false).0,
+		ExecutionStrategy::Both => {
+			let mut native_overlay = overlay.clone();
+			let (native_result, was_native) =
+				execute_using(backend, &mut native_overlay, exec, &code, method, call_data, true);
+
+			if !was_native {
+				// the executor fell back to wasm, so there is nothing to compare against.
+				*overlay = native_overlay;
+				native_result
+			} else {
+				let (wasm_result, _) =
+					execute_using(backend, overlay, exec, &code, method, call_data, false);
+
+				if let Some(failure) = check_consensus(method, &native_result, &native_overlay, &wasm_result, overlay) {
+					overlay.discard_prospective();
+					return Err(Box::new(failure));
+				}
+				wasm_result
+			}
+		}
 	};
 
 	match result {
+		Ok(_) if !overlay.transactions.is_empty() => {
+			overlay.discard_prospective();
+			Err(Box::new(ExecutionError::UnclosedTransaction { method: method.into() }))
+		}
 		Ok(x) => {
 			overlay.commit_prospective();
 			Ok(x)
@@ -184,12 +474,229 @@ pub fn execute<B: backend::Backend, Exec: CodeExecutor>(
 	}
 }
 
+/// Execute a call using the given state backend, overlayed changes, and call executor,
+/// recording all trie nodes read during the execution.
+///
+/// Returns the result of the call, the proof which allows re-executing it with
+/// `execution_proof_check`, and the transaction. Fails if the backend is not trie-based.
+pub fn prove_execution<B: backend::Backend, Exec: CodeExecutor>(
+	backend: B,
+	overlay: &mut OverlayedChanges,
+	exec: &Exec,
+	method: &str,
+	call_data: &[u8],
+) -> Result<(Vec<u8>, Vec<Vec<u8>>, memorydb::MemoryDB), Box<Error>>
+{
+	let trie_backend = backend.try_into_trie_backend()
+		.ok_or_else(|| Box::new(ExecutionError::UnableToGenerateProof) as Box<Error>)?;
+	let proving_backend = ProvingBackend::new(trie_backend);
+	let (result, transaction) = execute(
+		&proving_backend,
+		overlay,
+		exec,
+		method,
+		call_data,
+		ExecutionStrategy::NativeWhenPossible,
+	)?;
+	let proof = proving_backend.extract_proof();
+	Ok((result, proof, transaction))
+}
+
+/// Re-execute a call on top of the state described by the given proof nodes and root,
+/// as produced by `prove_execution`.
+pub fn execution_proof_check<Exec: CodeExecutor>(
+	root: [u8; 32],
+	proof: Vec<Vec<u8>>,
+	overlay: &mut OverlayedChanges,
+	exec: &Exec,
+	method: &str,
+	call_data: &[u8],
+) -> Result<(Vec<u8>, memorydb::MemoryDB), Box<Error>>
+{
+	let backend = create_proof_check_backend(root.into(), proof)?;
+	execute(&backend, overlay, exec, method, call_data, ExecutionStrategy::NativeWhenPossible)
+}
+
+fn execute_using<B: backend::Backend, Exec: CodeExecutor>(
+	backend: &B,
+	overlay: &mut OverlayedChanges,
+	exec: &Exec,
+	code: &[u8],
+	method: &str,
+	call_data: &[u8],
+	use_native: bool,
+) -> (Result<(Vec<u8>, B::Transaction), Exec::Error>, bool) {
+	let mut externalities = ext::Ext::new(overlay, backend);
+	let (result, was_native) = exec.call(&mut externalities, code, method, call_data, use_native);
+	(result.map(move |out| (out, externalities.transaction())), was_native)
+}
+
+/// Compare the outcomes of a native and a wasm execution of the same call, returning
+/// a description of the divergence if there is one.
+fn check_consensus<T, E: Error>(
+	method: &str,
+	native_result: &Result<(Vec<u8>, T), E>,
+	native_overlay: &OverlayedChanges,
+	wasm_result: &Result<(Vec<u8>, T), E>,
+	wasm_overlay: &OverlayedChanges,
+) -> Option<ExecutionError> {
+	let describe = |result: &Result<(Vec<u8>, T), E>| match *result {
+		Ok((ref out, _)) => format!("returned 0x{}", to_hex(out)),
+		Err(ref e) => format!("failed with: {}", e),
+	};
+
+	let diverging_keys = match (native_result, wasm_result) {
+		(&Ok(_), &Ok(_)) => diverging_keys(
+			&native_overlay.flattened_prospective(),
+			&wasm_overlay.flattened_prospective(),
+		),
+		_ => Vec::new(),
+	};
+
+	let agree = match (native_result, wasm_result) {
+		(&Ok((ref native_out, _)), &Ok((ref wasm_out, _))) => native_out == wasm_out && diverging_keys.is_empty(),
+		(&Err(_), &Err(_)) => true,
+		_ => false,
+	};
+
+	if agree {
+		None
+	} else {
+		Some(ExecutionError::ConsensusFailure {
+			method: method.to_owned(),
+			native: describe(native_result),
+			wasm: describe(wasm_result),
+			diverging_keys,
+		})
+	}
+}
+
+/// Keys which are set to different values (or set in only one) of the two change sets, sorted.
+fn diverging_keys(a: &HashMap<Vec<u8>, Option<Vec<u8>>>, b: &HashMap<Vec<u8>, Option<Vec<u8>>>) -> Vec<Vec<u8>> {
+	let mut keys = a.iter()
+		.filter(|&(key, value)| b.get(key) != Some(value))
+		.map(|(key, _)| key.clone())
+		.chain(b.keys().filter(|key| !a.contains_key(*key)).cloned())
+		.collect::<Vec<_>>();
+	keys.sort();
+	keys
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
 	use super::backend::InMemory;
 	use super::ext::Ext;
 
+	macro_rules! map {
+		($( $name:expr => $value:expr ),*) => (
+			vec![ $( ( $name, $value ) ),* ].into_iter().collect()
+		)
+	}
+
+	struct DummyCodeExecutor {
+		native_available: bool,
+		native_succeeds: bool,
+		native_value: u8,
+	}
+
+	impl CodeExecutor for DummyCodeExecutor {
+		type Error = u8;
+
+		fn call<E: Externalities>(
+			&self,
+			ext: &mut E,
+			_code: &[u8],
+			_method: &str,
+			_data: &[u8],
+			use_native: bool,
+		) -> (Result<Vec<u8>, Self::Error>, bool) {
+			let using_native = use_native && self.native_available;
+			let (value, succeeds) = if using_native {
+				(self.native_value, self.native_succeeds)
+			} else {
+				(42, true)
+			};
+
+			ext.set_storage(b"value".to_vec(), vec![value]);
+			let result = if succeeds { Ok(vec![value]) } else { Err(0) };
+			(result, using_native)
+		}
+	}
+
+	fn code_backend() -> InMemory {
+		let initial: HashMap<_, _> = map![
+			b":code".to_vec() => b"dummy".to_vec()
+		];
+		InMemory::from(initial)
+	}
+
+	#[test]
+	fn execute_works_with_all_strategies() {
+		let executor = DummyCodeExecutor { native_available: true, native_succeeds: true, native_value: 42 };
+		for &strategy in &[ExecutionStrategy::NativeWhenPossible, ExecutionStrategy::AlwaysWasm, ExecutionStrategy::Both] {
+			let mut overlay = OverlayedChanges::default();
+			let (output, _) = execute(&code_backend(), &mut overlay, &executor, "test", &[], strategy).unwrap();
+			assert_eq!(output, vec![42]);
+			assert_eq!(overlay.storage(b"value"), Some(Some(&[42][..])));
+		}
+	}
+
+	#[test]
+	fn both_strategy_reports_diverging_storage() {
+		let executor = DummyCodeExecutor { native_available: true, native_succeeds: true, native_value: 69 };
+		let mut overlay = OverlayedChanges::default();
+
+		let err = execute(&code_backend(), &mut overlay, &executor, "test", &[], ExecutionStrategy::Both).unwrap_err();
+		let message = err.to_string();
+		assert!(message.contains("native returned 0x45"));
+		assert!(message.contains("wasm returned 0x2a"));
+		assert!(message.contains("diverging storage keys: [0x76616c7565]"));
+		assert!(overlay.storage(b"value").is_none());
+	}
+
+	#[test]
+	fn both_strategy_reports_diverging_failure() {
+		let executor = DummyCodeExecutor { native_available: true, native_succeeds: false, native_value: 42 };
+		let mut overlay = OverlayedChanges::default();
+
+		let err = execute(&code_backend(), &mut overlay, &executor, "test", &[], ExecutionStrategy::Both).unwrap_err();
+		assert!(err.to_string().contains("native failed with: 0"));
+	}
+
+	#[test]
+	fn both_strategy_falls_back_to_wasm_when_native_is_not_available() {
+		let executor = DummyCodeExecutor { native_available: false, native_succeeds: true, native_value: 69 };
+		let mut overlay = OverlayedChanges::default();
+
+		let (output, _) = execute(&code_backend(), &mut overlay, &executor, "test", &[], ExecutionStrategy::Both).unwrap();
+		assert_eq!(output, vec![42]);
+	}
+
+	#[test]
+	fn prove_execution_and_proof_check_works() {
+		let executor = DummyCodeExecutor { native_available: true, native_succeeds: true, native_value: 42 };
+
+		let remote_backend = trie_backend::tests::test_trie();
+		let remote_root = remote_backend.storage_root(::std::iter::empty()).0;
+		let (remote_result, remote_proof, _) = prove_execution(remote_backend,
+			&mut Default::default(), &executor, "test", &[]).unwrap();
+
+		let (local_result, _) = execution_proof_check(remote_root, remote_proof,
+			&mut Default::default(), &executor, "test", &[]).unwrap();
+
+		assert_eq!(remote_result, vec![42]);
+		assert_eq!(local_result, vec![42]);
+	}
+
+	#[test]
+	fn proof_check_fails_without_root_node() {
+		let executor = DummyCodeExecutor { native_available: true, native_succeeds: true, native_value: 42 };
+
+		let err = execution_proof_check([1; 32], Vec::new(), &mut Default::default(), &executor, "test", &[]).unwrap_err();
+		assert_eq!(err.to_string(), "Invalid proof");
+	}
+
 	#[test]
 	fn overlayed_storage_works() {
 		let mut overlayed = OverlayedChanges::default();
@@ -218,10 +725,64 @@ mod tests {
 		assert!(overlayed.storage(&key).unwrap().is_none());
 	}
 
-	macro_rules! map {
-		($( $name:expr => $value:expr ),*) => (
-			vec![ $( ( $name, $value ) ),* ].into_iter().collect()
-		)
+	#[test]
+	fn overlayed_nested_transactions_work() {
+		let mut overlayed = OverlayedChanges::default();
+
+		let key = vec![42, 69, 169, 142];
+		overlayed.set_storage(key.clone(), Some(vec![1]));
+
+		overlayed.start_transaction();
+		overlayed.set_storage(key.clone(), Some(vec![2]));
+		assert_eq!(overlayed.storage(&key).unwrap(), Some(&[2][..]));
+
+		overlayed.start_transaction();
+		overlayed.set_storage(key.clone(), None);
+		assert!(overlayed.storage(&key).unwrap().is_none());
+
+		assert!(overlayed.rollback_transaction());
+		assert_eq!(overlayed.storage(&key).unwrap(), Some(&[2][..]));
+
+		assert!(overlayed.commit_transaction());
+		assert_eq!(overlayed.storage(&key).unwrap(), Some(&[2][..]));
+		assert!(!overlayed.commit_transaction());
+		assert!(!overlayed.rollback_transaction());
+
+		overlayed.start_transaction();
+		overlayed.set_storage(key.clone(), Some(vec![3]));
+		assert!(overlayed.commit_transaction());
+		overlayed.commit_prospective();
+		assert_eq!(overlayed.committed.top, map![key.clone() => Some(vec![3])]);
+		assert!(!overlayed.rollback_transaction());
+	}
+
+	#[test]
+	#[should_panic]
+	fn committing_prospective_with_open_transaction_panics() {
+		let mut overlayed = OverlayedChanges::default();
+		overlayed.start_transaction();
+		overlayed.set_storage(vec![42], Some(vec![1]));
+		overlayed.commit_prospective();
+	}
+
+	#[test]
+	fn rolled_back_transaction_is_excluded_from_storage_root() {
+		let initial: HashMap<_, _> = map![
+			b"doe".to_vec() => b"reindeer".to_vec(),
+			b"dog".to_vec() => b"puppy".to_vec()
+		];
+		let backend = InMemory::from(initial);
+		let mut overlay = OverlayedChanges::default();
+		let mut ext = Ext::new(&mut overlay, &backend);
+		let root = ext.storage_root();
+
+		ext.start_transaction();
+		ext.set_storage(b"dogglesworth".to_vec(), b"cat".to_vec());
+		assert!(ext.storage_root() != root);
+
+		assert!(ext.rollback_transaction());
+		assert_eq!(ext.storage_root(), root);
+		assert_eq!(ext.storage(b"dogglesworth"), None);
 	}
 
 	#[test]
@@ -234,18 +795,131 @@ mod tests {
 		];
 		let backend = InMemory::from(initial);
 		let mut overlay = OverlayedChanges {
-			committed: map![
-				b"dog".to_vec() => Some(b"puppy".to_vec()),
-				b"dogglesworth".to_vec() => Some(b"catYYY".to_vec()),
-				b"doug".to_vec() => Some(vec![])
-			],
-			prospective: map![
-				b"dogglesworth".to_vec() => Some(b"cat".to_vec()),
-				b"doug".to_vec() => None
-			],
+			committed: OverlayedChangeSet {
+				top: map![
+					b"dog".to_vec() => Some(b"puppy".to_vec()),
+					b"dogglesworth".to_vec() => Some(b"catYYY".to_vec()),
+					b"doug".to_vec() => Some(vec![])
+				],
+				..Default::default()
+			},
+			prospective: OverlayedChangeSet {
+				top: map![
+					b"dogglesworth".to_vec() => Some(b"cat".to_vec()),
+					b"doug".to_vec() => None
+				],
+				..Default::default()
+			},
+			..Default::default()
 		};
 		let mut ext = Ext::new(&mut overlay, &backend);
 		const ROOT: [u8; 32] = hex!("8aad789dff2f538bca5d8ea56e8abe10f4c7ba3a5dea95fea4cd6e7c3a1168d3");
 		assert_eq!(ext.storage_root(), ROOT);
 	}
+
+	#[test]
+	fn clear_prefix_in_ext_works() {
+		let initial: HashMap<_, _> = map![
+			b"aaa".to_vec() => b"0".to_vec(),
+			b"abb".to_vec() => b"1".to_vec(),
+			b"abc".to_vec() => b"2".to_vec(),
+			b"bbb".to_vec() => b"3".to_vec()
+		];
+		let backend = InMemory::from(initial);
+		let mut overlay = OverlayedChanges {
+			committed: OverlayedChangeSet {
+				top: map![
+					b"aba".to_vec() => Some(b"1312".to_vec()),
+					b"bab".to_vec() => Some(b"228".to_vec())
+				],
+				..Default::default()
+			},
+			prospective: OverlayedChangeSet {
+				top: map![
+					b"abd".to_vec() => Some(b"69".to_vec()),
+					b"bbd".to_vec() => Some(b"42".to_vec())
+				],
+				..Default::default()
+			},
+			..Default::default()
+		};
+
+		{
+			let mut ext = Ext::new(&mut overlay, &backend);
+			ext.clear_prefix(b"ab");
+		}
+		overlay.commit_prospective();
+
+		assert_eq!(
+			overlay.committed.top,
+			map![
+				b"abb".to_vec() => None,
+				b"abc".to_vec() => None,
+				b"aba".to_vec() => None,
+				b"abd".to_vec() => None,
+				b"bab".to_vec() => Some(b"228".to_vec()),
+				b"bbd".to_vec() => Some(b"42".to_vec())
+			]
+		);
+	}
+
+	#[test]
+	fn child_storage_in_ext_works() {
+		let child = b":child_storage:test".to_vec();
+		let initial: HashMap<Option<Vec<u8>>, HashMap<_, _>> = map![
+			None => map![b"doe".to_vec() => b"reindeer".to_vec()],
+			Some(child.clone()) => map![b"aaa".to_vec() => b"0".to_vec()]
+		];
+		let backend = InMemory::from(initial);
+		let mut overlay = OverlayedChanges::default();
+		let mut ext = Ext::new(&mut overlay, &backend);
+
+		assert!(!ext.set_child_storage(b"test".to_vec(), b"abc".to_vec(), b"1".to_vec()));
+		assert!(ext.set_child_storage(child.clone(), b"abc".to_vec(), b"1".to_vec()));
+		assert_eq!(ext.child_storage(&child, b"aaa"), Some(b"0".to_vec()));
+		assert_eq!(ext.child_storage(&child, b"abc"), Some(b"1".to_vec()));
+		assert_eq!(ext.storage(b"abc"), None);
+
+		let child_root = ::triehash::trie_root(vec![
+			(b"aaa".to_vec(), b"0".to_vec()),
+			(b"abc".to_vec(), b"1".to_vec()),
+		]).0;
+		assert_eq!(ext.child_storage_root(&child), Some(child_root));
+		assert_eq!(ext.child_storage_root(b"test"), None);
+
+		ext.kill_child_storage(&child);
+		assert_eq!(ext.child_storage(&child, b"aaa"), None);
+		assert_eq!(ext.child_storage(&child, b"abc"), None);
+	}
+
+	#[test]
+	fn child_storage_root_is_committed_into_main_trie() {
+		let child = b":child_storage:test".to_vec();
+		let initial: HashMap<_, _> = map![b"doe".to_vec() => b"reindeer".to_vec()];
+		let backend = InMemory::from(initial);
+		let mut overlay = OverlayedChanges::default();
+		let (root, transaction) = {
+			let mut ext = Ext::new(&mut overlay, &backend);
+			ext.set_child_storage(child.clone(), b"abc".to_vec(), b"1".to_vec());
+			(ext.storage_root(), ext.transaction())
+		};
+
+		let child_root = ::triehash::trie_root(vec![(b"abc".to_vec(), b"1".to_vec())]).0;
+		let expected_root = ::triehash::trie_root(vec![
+			(b"doe".to_vec(), b"reindeer".to_vec()),
+			(child.clone(), child_root.to_vec()),
+		]).0;
+		assert_eq!(root, expected_root);
+
+		// the updated state yields the same root through the trie backend.
+		let trie = backend.update(transaction).try_into_trie_backend().unwrap();
+		assert_eq!(trie.root().0, expected_root);
+		assert_eq!(trie.child_storage(&child, b"abc").unwrap(), Some(b"1".to_vec()));
+
+		// killing the only child entry removes the child root from the main trie.
+		let mut overlay = OverlayedChanges::default();
+		let mut ext = Ext::new(&mut overlay, &trie);
+		ext.kill_child_storage(&child);
+		assert_eq!(ext.storage_root(), ::triehash::trie_root(vec![(b"doe".to_vec(), b"reindeer".to_vec())]).0);
+	}
 }
@@ -0,0 +1,147 @@
+// Copyright 2017 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Proving state machine backend.
+
+use std::sync::Arc;
+use ethereum_types::H256 as TrieH256;
+use hashdb::HashDB;
+use memorydb::MemoryDB;
+use backend::Backend;
+use trie_backend::{TrieBackend, ProofRecorder};
+use {Error, ExecutionError};
+
+/// Patricia trie-based backend which also records all trie nodes read from the backing
+/// storage. Clones share the same record.
+#[derive(Clone)]
+pub struct ProvingBackend {
+	backend: TrieBackend,
+	proof_recorder: Arc<ProofRecorder>,
+}
+
+impl ProvingBackend {
+	/// Create a new proving backend on top of the given trie backend.
+	pub fn new(backend: TrieBackend) -> Self {
+		ProvingBackend {
+			backend,
+			proof_recorder: Default::default(),
+		}
+	}
+
+	/// Consume the backend, extracting all recorded trie nodes.
+	pub fn extract_proof(self) -> Vec<Vec<u8>> {
+		let mut recorded = self.proof_recorder.lock();
+		let proof = recorded.drain().map(|(_, node)| node.to_vec()).collect();
+		proof
+	}
+}
+
+impl Backend for ProvingBackend {
+	type Error = String;
+	type Transaction = MemoryDB;
+
+	fn storage(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+		self.backend.storage_recorded(key, Some(&*self.proof_recorder))
+	}
+
+	fn child_storage(&self, storage_key: &[u8], key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+		self.backend.child_storage_recorded(storage_key, key, Some(&*self.proof_recorder))
+	}
+
+	fn pairs(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+		self.backend.pairs_recorded(Some(&*self.proof_recorder))
+	}
+
+	fn keys(&self, prefix: &[u8]) -> Result<Vec<Vec<u8>>, Self::Error> {
+		self.backend.keys_recorded(prefix, Some(&*self.proof_recorder))
+	}
+
+	fn keys_paged(&self, prefix: &[u8], start_key: Option<&[u8]>, count: usize) -> Result<Vec<Vec<u8>>, Self::Error> {
+		self.backend.keys_paged_recorded(prefix, start_key, count, Some(&*self.proof_recorder))
+	}
+
+	fn child_keys(&self, storage_key: &[u8], prefix: &[u8]) -> Result<Vec<Vec<u8>>, Self::Error> {
+		self.backend.child_keys_recorded(storage_key, prefix, Some(&*self.proof_recorder))
+	}
+
+	fn storage_root<I>(&self, delta: I) -> ([u8; 32], MemoryDB)
+		where I: IntoIterator<Item=(Vec<u8>, Option<Vec<u8>>)>
+	{
+		self.backend.storage_root_recorded(delta, Some(&*self.proof_recorder))
+	}
+
+	fn child_storage_root<I>(&self, storage_key: &[u8], delta: I) -> ([u8; 32], bool, MemoryDB)
+		where I: IntoIterator<Item=(Vec<u8>, Option<Vec<u8>>)>
+	{
+		self.backend.child_storage_root_recorded(storage_key, delta, Some(&*self.proof_recorder))
+	}
+
+	fn try_into_trie_backend(self) -> Option<TrieBackend> {
+		None
+	}
+}
+
+/// Create a backend which runs on top of the given proof nodes only. Fails if the proof
+/// does not include the root node.
+pub fn create_proof_check_backend(root: TrieH256, proof: Vec<Vec<u8>>) -> Result<TrieBackend, Box<Error>> {
+	let mut db = MemoryDB::default();
+	for node in proof {
+		db.insert(&node);
+	}
+
+	if !db.contains(&root) {
+		return Err(Box::new(ExecutionError::InvalidProof) as Box<Error>);
+	}
+
+	Ok(TrieBackend::with_memorydb(db, root))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use trie_backend::tests::test_trie;
+
+	#[test]
+	fn proof_is_empty_until_value_is_read() {
+		assert!(ProvingBackend::new(test_trie()).extract_proof().is_empty());
+	}
+
+	#[test]
+	fn proof_is_non_empty_after_value_is_read() {
+		let backend = ProvingBackend::new(test_trie());
+		assert_eq!(backend.storage(b"key").unwrap(), Some(b"value".to_vec()));
+		assert!(!backend.extract_proof().is_empty());
+	}
+
+	#[test]
+	fn proof_is_invalid_when_does_not_contain_root() {
+		assert!(create_proof_check_backend(1.into(), vec![]).is_err());
+	}
+
+	#[test]
+	fn proof_recorded_and_checked() {
+		let trie_backend = test_trie();
+		let root = trie_backend.root().clone();
+		let proving_backend = ProvingBackend::new(trie_backend);
+		assert_eq!(proving_backend.storage(b"value1").unwrap(), Some(vec![42]));
+		assert_eq!(proving_backend.storage(b"non-existing-key").unwrap(), None);
+		let proof = proving_backend.extract_proof();
+
+		let proof_check = create_proof_check_backend(root, proof).unwrap();
+		assert_eq!(proof_check.storage(b"value1").unwrap(), Some(vec![42]));
+		assert_eq!(proof_check.storage(b"non-existing-key").unwrap(), None);
+	}
+}
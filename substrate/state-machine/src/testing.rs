@@ -16,29 +16,176 @@
 
 //! Test implementation for Externalities.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::collections::hash_map::IntoIter;
+use std::iter::FromIterator;
+use std::ops::{Deref, DerefMut};
+use codec::Slicable;
+use primitives::storage::well_known_keys::is_child_storage_key;
 use super::Externalities;
 use triehash::trie_root;
 
-/// Simple HashMap based Externalities impl.
-pub type TestExternalities = HashMap<Vec<u8>, Vec<u8>>;
+/// Simple HashMap based Externalities impl. Derefs to the underlying map.
+///
+/// Storage transactions are tracked by snapshotting the whole map on a stack kept
+/// alongside it.
+///
+/// A child trie is kept in the map at its child storage key, as the encoded list of its
+/// entries. Its root is substituted in when computing the storage root.
+#[derive(Debug, Default, Clone)]
+pub struct TestExternalities {
+	storage: HashMap<Vec<u8>, Vec<u8>>,
+	transaction_snapshots: Vec<HashMap<Vec<u8>, Vec<u8>>>,
+}
+
+impl TestExternalities {
+	/// Create a new instance with empty storage.
+	pub fn new() -> Self {
+		Self::default()
+	}
+}
+
+impl PartialEq for TestExternalities {
+	fn eq(&self, other: &TestExternalities) -> bool {
+		self.storage == other.storage
+	}
+}
+
+impl Deref for TestExternalities {
+	type Target = HashMap<Vec<u8>, Vec<u8>>;
+
+	fn deref(&self) -> &Self::Target {
+		&self.storage
+	}
+}
+
+impl DerefMut for TestExternalities {
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		&mut self.storage
+	}
+}
+
+impl From<HashMap<Vec<u8>, Vec<u8>>> for TestExternalities {
+	fn from(storage: HashMap<Vec<u8>, Vec<u8>>) -> Self {
+		TestExternalities {
+			storage,
+			transaction_snapshots: Vec::new(),
+		}
+	}
+}
+
+impl FromIterator<(Vec<u8>, Vec<u8>)> for TestExternalities {
+	fn from_iter<I: IntoIterator<Item=(Vec<u8>, Vec<u8>)>>(iter: I) -> Self {
+		HashMap::from_iter(iter).into()
+	}
+}
+
+impl IntoIterator for TestExternalities {
+	type Item = (Vec<u8>, Vec<u8>);
+	type IntoIter = IntoIter<Vec<u8>, Vec<u8>>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.storage.into_iter()
+	}
+}
+
+fn child_entries(ext: &HashMap<Vec<u8>, Vec<u8>>, storage_key: &[u8]) -> BTreeMap<Vec<u8>, Vec<u8>> {
+	ext.get(storage_key)
+		.and_then(|encoded| Vec::<(Vec<u8>, Vec<u8>)>::decode(&mut &encoded[..]))
+		.map(|entries| entries.into_iter().collect())
+		.unwrap_or_default()
+}
+
+fn set_child_entries(ext: &mut HashMap<Vec<u8>, Vec<u8>>, storage_key: Vec<u8>, entries: BTreeMap<Vec<u8>, Vec<u8>>) {
+	if entries.is_empty() {
+		ext.remove(&storage_key);
+	} else {
+		ext.insert(storage_key, entries.into_iter().collect::<Vec<_>>().encode());
+	}
+}
 
 impl Externalities for TestExternalities {
 	fn storage(&self, key: &[u8]) -> Option<Vec<u8>> {
-		self.get(key).map(|x| x.to_vec())
+		self.storage.get(key).map(|x| x.to_vec())
 	}
 
 	fn place_storage(&mut self, key: Vec<u8>, maybe_value: Option<Vec<u8>>) {
 		match maybe_value {
-			Some(value) => { self.insert(key, value); }
-			None => { self.remove(&key); }
+			Some(value) => { self.storage.insert(key, value); }
+			None => { self.storage.remove(&key); }
+		}
+	}
+
+	fn clear_prefix(&mut self, prefix: &[u8]) {
+		self.storage.retain(|key, _| !key.starts_with(prefix));
+	}
+
+	fn child_storage(&self, storage_key: &[u8], key: &[u8]) -> Option<Vec<u8>> {
+		if !is_child_storage_key(storage_key) {
+			return None;
+		}
+		child_entries(&self.storage, storage_key).remove(key)
+	}
+
+	fn place_child_storage(&mut self, storage_key: Vec<u8>, key: Vec<u8>, value: Option<Vec<u8>>) -> bool {
+		if !is_child_storage_key(&storage_key) {
+			return false;
+		}
+
+		let mut entries = child_entries(&self.storage, &storage_key);
+		match value {
+			Some(value) => { entries.insert(key, value); }
+			None => { entries.remove(&key); }
+		}
+		set_child_entries(&mut self.storage, storage_key, entries);
+		true
+	}
+
+	fn kill_child_storage(&mut self, storage_key: &[u8]) {
+		if is_child_storage_key(storage_key) {
+			self.storage.remove(storage_key);
 		}
 	}
 
 	fn chain_id(&self) -> u64 { 42 }
 
 	fn storage_root(&mut self) -> [u8; 32] {
-		trie_root(self.clone()).0
+		let top = self.storage.iter().map(|(key, value)| if is_child_storage_key(key) {
+			(key.clone(), trie_root(child_entries(&self.storage, key)).0.to_vec())
+		} else {
+			(key.clone(), value.clone())
+		}).collect::<Vec<_>>();
+		trie_root(top).0
+	}
+
+	fn child_storage_root(&mut self, storage_key: &[u8]) -> Option<[u8; 32]> {
+		if !is_child_storage_key(storage_key) {
+			return None;
+		}
+		Some(trie_root(child_entries(&self.storage, storage_key)).0)
+	}
+
+	fn storage_changes_root(&mut self) -> Option<[u8; 32]> {
+		// changes are not attributed to extrinsics here.
+		None
+	}
+
+	fn start_transaction(&mut self) {
+		self.transaction_snapshots.push(self.storage.clone());
+	}
+
+	fn commit_transaction(&mut self) -> bool {
+		self.transaction_snapshots.pop().is_some()
+	}
+
+	fn rollback_transaction(&mut self) -> bool {
+		match self.transaction_snapshots.pop() {
+			Some(snapshot) => {
+				self.storage = snapshot;
+				true
+			}
+			None => false,
+		}
 	}
 }
 
@@ -55,4 +202,75 @@ mod tests {
 		const ROOT: [u8; 32] = hex!("8aad789dff2f538bca5d8ea56e8abe10f4c7ba3a5dea95fea4cd6e7c3a1168d3");
 		assert_eq!(ext.storage_root(), ROOT);
 	}
+
+	#[test]
+	fn clear_prefix_should_work() {
+		let mut ext = TestExternalities::new();
+		ext.set_storage(b"doe".to_vec(), b"reindeer".to_vec());
+		ext.set_storage(b"dog".to_vec(), b"puppy".to_vec());
+		ext.set_storage(b"dogglesworth".to_vec(), b"cat".to_vec());
+
+		ext.clear_prefix(b"dog");
+
+		assert_eq!(ext.storage(b"doe"), Some(b"reindeer".to_vec()));
+		assert_eq!(ext.storage(b"dog"), None);
+		assert_eq!(ext.storage(b"dogglesworth"), None);
+	}
+
+	#[test]
+	fn nested_transactions_should_work() {
+		let mut ext = TestExternalities::new();
+		ext.set_storage(b"doe".to_vec(), b"reindeer".to_vec());
+
+		ext.start_transaction();
+		ext.set_storage(b"dog".to_vec(), b"puppy".to_vec());
+
+		ext.start_transaction();
+		ext.clear_storage(b"doe");
+		assert!(ext.rollback_transaction());
+
+		assert!(ext.commit_transaction());
+		assert!(!ext.commit_transaction());
+
+		assert_eq!(ext.storage(b"doe"), Some(b"reindeer".to_vec()));
+		assert_eq!(ext.storage(b"dog"), Some(b"puppy".to_vec()));
+	}
+
+	#[test]
+	fn transactions_are_kept_per_instance() {
+		let mut ext = TestExternalities::new();
+		let mut other = TestExternalities::new();
+
+		ext.start_transaction();
+		assert!(!other.commit_transaction());
+		other.set_storage(b"dog".to_vec(), b"puppy".to_vec());
+		assert!(ext.rollback_transaction());
+
+		assert_eq!(ext.storage(b"dog"), None);
+		assert_eq!(other.storage(b"dog"), Some(b"puppy".to_vec()));
+	}
+
+	#[test]
+	fn child_storage_should_work() {
+		let child = b":child_storage:dog".to_vec();
+		let mut ext = TestExternalities::new();
+		ext.set_storage(b"doe".to_vec(), b"reindeer".to_vec());
+		let root_without_child = ext.storage_root();
+
+		assert!(!ext.set_child_storage(b"dog".to_vec(), b"name".to_vec(), b"puppy".to_vec()));
+		assert!(ext.set_child_storage(child.clone(), b"name".to_vec(), b"puppy".to_vec()));
+		assert_eq!(ext.child_storage(&child, b"name"), Some(b"puppy".to_vec()));
+		assert_eq!(ext.storage(b"name"), None);
+
+		let child_root = trie_root(vec![(b"name".to_vec(), b"puppy".to_vec())]).0;
+		assert_eq!(ext.child_storage_root(&child), Some(child_root));
+		assert_eq!(
+			ext.storage_root(),
+			trie_root(vec![(b"doe".to_vec(), b"reindeer".to_vec()), (child.clone(), child_root.to_vec())]).0,
+		);
+
+		ext.kill_child_storage(&child);
+		assert_eq!(ext.child_storage(&child, b"name"), None);
+		assert_eq!(ext.storage_root(), root_without_child);
+	}
 }
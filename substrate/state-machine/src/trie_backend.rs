@@ -0,0 +1,442 @@
+// Copyright 2017 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Trie-based state machine backend.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use ethereum_types::H256 as TrieH256;
+use hashdb::{DBValue, HashDB};
+use memorydb::MemoryDB;
+use parking_lot::Mutex;
+use patricia_trie::{TrieDB, TrieDBMut, TrieError, Trie, TrieMut};
+use backend::Backend;
+
+/// Storage of trie nodes, keyed by their hash.
+pub trait Storage: Send + Sync {
+	/// Get a trie node.
+	fn get(&self, key: &TrieH256) -> Result<Option<DBValue>, String>;
+}
+
+/// Trie nodes read from the backing storage, recorded while generating a proof.
+pub(crate) type ProofRecorder = Mutex<HashMap<TrieH256, DBValue>>;
+
+/// Where a trie backend reads its nodes from.
+#[derive(Clone)]
+pub enum TrieBackendStorage {
+	/// Nodes are read from a key-value storage.
+	Storage(Arc<Storage>),
+	/// Nodes are read from an in-memory set, e.g. the nodes of an execution proof.
+	MemoryDb(MemoryDB),
+}
+
+impl TrieBackendStorage {
+	fn get(&self, key: &TrieH256) -> Option<DBValue> {
+		match *self {
+			TrieBackendStorage::Storage(ref db) => match db.get(key) {
+				Ok(x) => x,
+				Err(e) => {
+					warn!("Failed to read from DB: {}", e);
+					None
+				}
+			},
+			TrieBackendStorage::MemoryDb(ref db) => db.get(key),
+		}
+	}
+}
+
+/// Patricia trie-based backend. Transaction type is an overlay of changes to commit.
+#[derive(Clone)]
+pub struct TrieBackend {
+	storage: TrieBackendStorage,
+	root: TrieH256,
+}
+
+impl TrieBackend {
+	/// Create a new trie-based backend reading its nodes from the given storage.
+	pub fn with_storage(db: Arc<Storage>, root: TrieH256) -> Self {
+		TrieBackend {
+			storage: TrieBackendStorage::Storage(db),
+			root,
+		}
+	}
+
+	/// Create a new trie-based backend reading its nodes from the given in-memory set.
+	pub fn with_memorydb(db: MemoryDB, root: TrieH256) -> Self {
+		TrieBackend {
+			storage: TrieBackendStorage::MemoryDb(db),
+			root,
+		}
+	}
+
+	/// Get the root of the storage trie.
+	pub fn root(&self) -> &TrieH256 {
+		&self.root
+	}
+
+	/// Read a storage entry, recording every node read from the backing storage if
+	/// `recorder` is given.
+	pub(crate) fn storage_recorded(&self, key: &[u8], recorder: Option<&ProofRecorder>) -> Result<Option<Vec<u8>>, String> {
+		self.storage_at(&self.root, key, recorder)
+	}
+
+	/// Read an entry of the child trie addressed by `storage_key`, recording every node
+	/// read from the backing storage if `recorder` is given.
+	pub(crate) fn child_storage_recorded(&self, storage_key: &[u8], key: &[u8], recorder: Option<&ProofRecorder>) -> Result<Option<Vec<u8>>, String> {
+		match self.child_root(storage_key, recorder)? {
+			Some(root) => self.storage_at(&root, key, recorder),
+			None => Ok(None),
+		}
+	}
+
+	/// Get all key/value pairs, recording every node read from the backing storage if
+	/// `recorder` is given.
+	pub(crate) fn pairs_recorded(&self, recorder: Option<&ProofRecorder>) -> Vec<(Vec<u8>, Vec<u8>)> {
+		let mut read_overlay = MemoryDB::default();
+		let eph = Ephemeral::new(&self.storage, &mut read_overlay, recorder);
+
+		let collect_all = || -> Result<_, Box<TrieError>> {
+			let trie = TrieDB::new(&eph, &self.root)?;
+			let mut v = Vec::new();
+			for x in trie.iter()? {
+				let (key, value) = x?;
+				v.push((key.to_vec(), value.to_vec()));
+			}
+
+			Ok(v)
+		};
+
+		match collect_all() {
+			Ok(v) => v,
+			Err(e) => {
+				debug!("Error extracting trie values: {}", e);
+				Vec::new()
+			}
+		}
+	}
+
+	/// Get all keys starting with the given prefix, recording every node read from the
+	/// backing storage if `recorder` is given.
+	pub(crate) fn keys_recorded(&self, prefix: &[u8], recorder: Option<&ProofRecorder>) -> Result<Vec<Vec<u8>>, String> {
+		self.keys_at(&self.root, prefix, None, usize::max_value(), recorder)
+	}
+
+	/// Get at most `count` keys starting with the given prefix and following `start_key`,
+	/// recording every node read from the backing storage if `recorder` is given.
+	pub(crate) fn keys_paged_recorded(&self, prefix: &[u8], start_key: Option<&[u8]>, count: usize, recorder: Option<&ProofRecorder>) -> Result<Vec<Vec<u8>>, String> {
+		self.keys_at(&self.root, prefix, start_key, count, recorder)
+	}
+
+	/// Get all keys of the child trie addressed by `storage_key` starting with the given
+	/// prefix, recording every node read from the backing storage if `recorder` is given.
+	pub(crate) fn child_keys_recorded(&self, storage_key: &[u8], prefix: &[u8], recorder: Option<&ProofRecorder>) -> Result<Vec<Vec<u8>>, String> {
+		match self.child_root(storage_key, recorder)? {
+			Some(root) => self.keys_at(&root, prefix, None, usize::max_value(), recorder),
+			None => Ok(Vec::new()),
+		}
+	}
+
+	/// Calculate the storage root with the given delta applied, recording every node read
+	/// from the backing storage if `recorder` is given.
+	pub(crate) fn storage_root_recorded<I>(&self, delta: I, recorder: Option<&ProofRecorder>) -> ([u8; 32], MemoryDB)
+		where I: IntoIterator<Item=(Vec<u8>, Option<Vec<u8>>)>
+	{
+		let (root, transaction) = self.root_with_delta(Some(self.root), delta, recorder);
+		(root.0.into(), transaction)
+	}
+
+	/// Calculate the root of the child trie addressed by `storage_key` with the given delta
+	/// applied, and whether that trie is empty, recording every node read from the backing
+	/// storage if `recorder` is given.
+	pub(crate) fn child_storage_root_recorded<I>(&self, storage_key: &[u8], delta: I, recorder: Option<&ProofRecorder>) -> ([u8; 32], bool, MemoryDB)
+		where I: IntoIterator<Item=(Vec<u8>, Option<Vec<u8>>)>
+	{
+		let existing_root = match self.child_root(storage_key, recorder) {
+			Ok(root) => root,
+			Err(e) => {
+				warn!("Failed to read child trie root: {}", e);
+				None
+			}
+		};
+
+		let (root, transaction) = self.root_with_delta(existing_root, delta, recorder);
+		(root.0.into(), root == empty_trie_root(), transaction)
+	}
+
+	/// The root of the child trie addressed by `storage_key`, if it exists.
+	fn child_root(&self, storage_key: &[u8], recorder: Option<&ProofRecorder>) -> Result<Option<TrieH256>, String> {
+		match self.storage_recorded(storage_key, recorder)? {
+			Some(ref root) if root.len() == 32 => Ok(Some(TrieH256::from_slice(root))),
+			Some(_) => Err(format!("Invalid child trie root at {:?}", storage_key)),
+			None => Ok(None),
+		}
+	}
+
+	fn storage_at(&self, root: &TrieH256, key: &[u8], recorder: Option<&ProofRecorder>) -> Result<Option<Vec<u8>>, String> {
+		let mut read_overlay = MemoryDB::default();
+		let eph = Ephemeral::new(&self.storage, &mut read_overlay, recorder);
+
+		let map_e = |e: Box<TrieError>| format!("Trie lookup error: {}", e);
+
+		TrieDB::new(&eph, root).map_err(map_e)?
+			.get(key).map(|x| x.map(|val| val.to_vec())).map_err(map_e)
+	}
+
+	// keys starting with `prefix` and greater than `start_key`, up to `count` of them.
+	fn keys_at(&self, root: &TrieH256, prefix: &[u8], start_key: Option<&[u8]>, count: usize, recorder: Option<&ProofRecorder>) -> Result<Vec<Vec<u8>>, String> {
+		use patricia_trie::TrieIterator;
+
+		let mut read_overlay = MemoryDB::default();
+		let eph = Ephemeral::new(&self.storage, &mut read_overlay, recorder);
+
+		let collect_keys = || -> Result<_, Box<TrieError>> {
+			let trie = TrieDB::new(&eph, root)?;
+			let mut iter = trie.iter()?;
+			// keys before the prefix can be skipped over.
+			let start_key = match start_key {
+				Some(start) if start >= prefix => Some(start),
+				_ => None,
+			};
+			iter.seek(start_key.unwrap_or(prefix))?;
+
+			let mut v = Vec::new();
+			for x in iter {
+				if v.len() >= count {
+					break;
+				}
+				let (key, _) = x?;
+				if !key.starts_with(prefix) {
+					break;
+				}
+				if start_key.map_or(false, |start| &key[..] == start) {
+					continue;
+				}
+				v.push(key.to_vec());
+			}
+
+			Ok(v)
+		};
+
+		collect_keys().map_err(|e| format!("Trie lookup error: {}", e))
+	}
+
+	/// Apply the delta to the trie with the given root, or to a new empty trie.
+	fn root_with_delta<I>(&self, root: Option<TrieH256>, delta: I, recorder: Option<&ProofRecorder>) -> (TrieH256, MemoryDB)
+		where I: IntoIterator<Item=(Vec<u8>, Option<Vec<u8>>)>
+	{
+		let mut write_overlay = MemoryDB::default();
+		let mut new_root = root.unwrap_or_default();
+		{
+			let mut eph = Ephemeral::new(&self.storage, &mut write_overlay, recorder);
+
+			let mut trie = match root {
+				Some(_) => TrieDBMut::from_existing(&mut eph, &mut new_root).expect("prior state root to exist"), // TODO: handle gracefully
+				None => TrieDBMut::new(&mut eph, &mut new_root),
+			};
+			for (key, change) in delta {
+				let result = match change {
+					Some(val) => trie.insert(&key, &val),
+					None => trie.remove(&key), // TODO: archive mode
+				};
+
+				if let Err(e) = result {
+					warn!("Failed to write to trie: {}", e);
+				}
+			}
+		}
+
+		(new_root, write_overlay)
+	}
+}
+
+impl Backend for TrieBackend {
+	type Error = String;
+	type Transaction = MemoryDB;
+
+	fn storage(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+		self.storage_recorded(key, None)
+	}
+
+	fn child_storage(&self, storage_key: &[u8], key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+		self.child_storage_recorded(storage_key, key, None)
+	}
+
+	fn pairs(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+		self.pairs_recorded(None)
+	}
+
+	fn keys(&self, prefix: &[u8]) -> Result<Vec<Vec<u8>>, Self::Error> {
+		self.keys_recorded(prefix, None)
+	}
+
+	fn keys_paged(&self, prefix: &[u8], start_key: Option<&[u8]>, count: usize) -> Result<Vec<Vec<u8>>, Self::Error> {
+		self.keys_paged_recorded(prefix, start_key, count, None)
+	}
+
+	fn child_keys(&self, storage_key: &[u8], prefix: &[u8]) -> Result<Vec<Vec<u8>>, Self::Error> {
+		self.child_keys_recorded(storage_key, prefix, None)
+	}
+
+	fn storage_root<I>(&self, delta: I) -> ([u8; 32], MemoryDB)
+		where I: IntoIterator<Item=(Vec<u8>, Option<Vec<u8>>)>
+	{
+		self.storage_root_recorded(delta, None)
+	}
+
+	fn child_storage_root<I>(&self, storage_key: &[u8], delta: I) -> ([u8; 32], bool, MemoryDB)
+		where I: IntoIterator<Item=(Vec<u8>, Option<Vec<u8>>)>
+	{
+		self.child_storage_root_recorded(storage_key, delta, None)
+	}
+
+	fn try_into_trie_backend(self) -> Option<TrieBackend> {
+		Some(self)
+	}
+}
+
+/// Root of the empty trie.
+fn empty_trie_root() -> TrieH256 {
+	let mut root = TrieH256::default();
+	let mut db = MemoryDB::default();
+	TrieDBMut::new(&mut db, &mut root);
+	root
+}
+
+/// Trie nodes of the backing storage with an overlay of changes on top.
+struct Ephemeral<'a> {
+	storage: &'a TrieBackendStorage,
+	overlay: &'a mut MemoryDB,
+	recorder: Option<&'a ProofRecorder>,
+}
+
+impl<'a> Ephemeral<'a> {
+	fn new(storage: &'a TrieBackendStorage, overlay: &'a mut MemoryDB, recorder: Option<&'a ProofRecorder>) -> Self {
+		Ephemeral {
+			storage,
+			overlay,
+			recorder,
+		}
+	}
+}
+
+impl<'a> HashDB for Ephemeral<'a> {
+	fn keys(&self) -> HashMap<TrieH256, i32> {
+		self.overlay.keys() // TODO: iterate backing
+	}
+
+	fn get(&self, key: &TrieH256) -> Option<DBValue> {
+		match self.overlay.raw(key) {
+			Some((val, i)) => {
+				if i <= 0 {
+					None
+				} else {
+					Some(val)
+				}
+			}
+			None => {
+				let val = self.storage.get(key);
+				if let (Some(recorder), Some(val)) = (self.recorder, val.as_ref()) {
+					recorder.lock().insert(key.clone(), val.clone());
+				}
+				val
+			}
+		}
+	}
+
+	fn contains(&self, key: &TrieH256) -> bool {
+		self.get(key).is_some()
+	}
+
+	fn insert(&mut self, value: &[u8]) -> TrieH256 {
+		self.overlay.insert(value)
+	}
+
+	fn emplace(&mut self, key: TrieH256, value: DBValue) {
+		self.overlay.emplace(key, value)
+	}
+
+	fn remove(&mut self, key: &TrieH256) {
+		self.overlay.remove(key)
+	}
+}
+
+#[cfg(test)]
+pub mod tests {
+	use super::*;
+
+	pub fn test_trie() -> TrieBackend {
+		let mut root = TrieH256::default();
+		let mut mdb = MemoryDB::default();
+		{
+			let mut trie = TrieDBMut::new(&mut mdb, &mut root);
+			trie.insert(b"key", b"value").unwrap();
+			trie.insert(b"value1", &[42]).unwrap();
+			trie.insert(b"value2", &[24]).unwrap();
+			trie.insert(b":code", b"return 42").unwrap();
+			for i in 128u8..255u8 {
+				trie.insert(&[i], &[i]).unwrap();
+			}
+		}
+		TrieBackend::with_memorydb(mdb, root)
+	}
+
+	#[test]
+	fn read_from_storage_returns_some() {
+		assert_eq!(test_trie().storage(b"key").unwrap(), Some(b"value".to_vec()));
+	}
+
+	#[test]
+	fn read_from_storage_returns_none() {
+		assert_eq!(test_trie().storage(b"non-existing-key").unwrap(), None);
+	}
+
+	#[test]
+	fn pairs_are_not_empty_on_non_empty_storage() {
+		assert!(!test_trie().pairs().is_empty());
+	}
+
+	#[test]
+	fn keys_with_prefix_are_enumerated_in_order() {
+		assert_eq!(test_trie().keys(b"value").unwrap(), vec![b"value1".to_vec(), b"value2".to_vec()]);
+	}
+
+	#[test]
+	fn keys_are_paged() {
+		let trie = test_trie();
+		assert_eq!(trie.keys_paged(b"", None, 2).unwrap(), vec![b":code".to_vec(), b"key".to_vec()]);
+		assert_eq!(trie.keys_paged(b"", Some(b"key"), 2).unwrap(), vec![b"value1".to_vec(), b"value2".to_vec()]);
+		assert_eq!(trie.keys_paged(b"value", Some(b"value1"), 10).unwrap(), vec![b"value2".to_vec()]);
+		assert_eq!(trie.keys_paged(b"value", Some(b"a"), 1).unwrap(), vec![b"value1".to_vec()]);
+		assert!(trie.keys_paged(b"value", Some(b"value2"), 10).unwrap().is_empty());
+	}
+
+	#[test]
+	fn storage_root_is_non_default() {
+		assert!(test_trie().storage_root(::std::iter::empty()).0 != [0; 32]);
+	}
+
+	#[test]
+	fn storage_root_transaction_is_empty() {
+		assert!(test_trie().storage_root(::std::iter::empty()).1.drain().is_empty());
+	}
+
+	#[test]
+	fn storage_root_transaction_is_non_empty() {
+		let (new_root, mut tx) = test_trie().storage_root(vec![(b"new-key".to_vec(), Some(b"new-value".to_vec()))]);
+		assert!(!tx.drain().is_empty());
+		assert!(new_root != test_trie().storage_root(::std::iter::empty()).0);
+	}
+}
@@ -20,14 +20,11 @@ use client::{self, Client};
 use keyring::Keyring;
 use runtime::genesismap::{GenesisConfig, additional_storage_with_genesis};
 use runtime;
+use state_machine;
 use bft;
-use {Backend, Executor, NativeExecutor};
 
 /// Extension trait for a test client.
 pub trait TestClient {
-	/// Crates new client instance for tests.
-	fn new_for_tests() -> Self;
-
 	/// Justify and import block to the chain.
 	fn justify_and_import(&self, origin: client::BlockOrigin, block: runtime::Block) -> client::error::Result<()>;
 
@@ -35,11 +32,11 @@ pub trait TestClient {
 	fn genesis_hash(&self) -> runtime::Hash;
 }
 
-impl TestClient for Client<Backend, Executor, runtime::Block> {
-	fn new_for_tests() -> Self {
-		client::new_in_mem(NativeExecutor::new(), GenesisBuilder).unwrap()
-	}
-
+impl<B, E> TestClient for Client<B, E, runtime::Block> where
+	B: client::backend::Backend<runtime::Block>,
+	E: client::CallExecutor<runtime::Block>,
+	client::error::Error: From<<<B as client::backend::Backend<runtime::Block>>::State as state_machine::backend::Backend>::Error>,
+{
 	fn justify_and_import(&self, origin: client::BlockOrigin, block: runtime::Block) -> client::error::Result<()> {
 		let justification = fake_justify(&block.header);
 		let justified = self.check_justification(block.header, justification)?;
@@ -93,7 +90,7 @@ fn genesis_config() -> GenesisConfig {
 	], 1000)
 }
 
-struct GenesisBuilder;
+pub struct GenesisBuilder;
 
 impl client::GenesisBuilder<runtime::Block> for GenesisBuilder {
 	fn build(self) -> (runtime::Header, Vec<(Vec<u8>, Vec<u8>)>) {
@@ -24,6 +24,7 @@ extern crate substrate_keyring as keyring;
 extern crate substrate_primitives as primitives;
 extern crate substrate_runtime_support as runtime_support;
 extern crate substrate_runtime_primitives as runtime_primitives;
+extern crate substrate_state_machine as state_machine;
 #[macro_use] extern crate substrate_executor as executor;
 
 pub extern crate substrate_test_runtime as runtime;
@@ -31,13 +32,15 @@ pub extern crate substrate_client as client;
 
 mod client_ext;
 
+use std::sync::Arc;
+
 pub use client_ext::TestClient;
 
 mod native_executor {
 	#![allow(missing_docs)]
 	use super::runtime;
 
-	native_executor_instance!(pub NativeExecutor, runtime::api::dispatch, include_bytes!("../../test-runtime/wasm/target/wasm32-unknown-unknown/release/substrate_test_runtime.compact.wasm"));
+	native_executor_instance!(pub NativeExecutor, runtime::api::dispatch, runtime::VERSION, include_bytes!("../../test-runtime/wasm/target/wasm32-unknown-unknown/release/substrate_test_runtime.compact.wasm"));
 }
 
 /// Native executor used for tests.
@@ -51,5 +54,17 @@ pub type Executor = client::LocalCallExecutor<Backend, executor::NativeExecutor<
 
 /// Creates new client instance used for tests.
 pub fn new() -> client::Client<Backend, Executor, runtime::Block> {
-	TestClient::new_for_tests()
+	new_with_backend(Arc::new(Backend::new()))
+}
+
+/// Creates new client instance used for tests, keeping the chain in the given backend.
+pub fn new_with_backend<B>(
+	backend: Arc<B>,
+) -> client::Client<B, client::LocalCallExecutor<B, executor::NativeExecutor<NativeExecutor>>, runtime::Block> where
+	B: client::backend::LocalBackend<runtime::Block>,
+	client::error::Error: From<<<B as client::backend::Backend<runtime::Block>>::State as state_machine::backend::Backend>::Error>,
+{
+	let executor = client::LocalCallExecutor::new(backend.clone(), NativeExecutor::new());
+	client::Client::new(backend, executor, client_ext::GenesisBuilder, Default::default())
+		.expect("genesis is written to an empty backend; qed")
 }
@@ -20,6 +20,7 @@
 
 extern crate substrate_runtime_std as rstd;
 extern crate substrate_codec as codec;
+#[macro_use]
 extern crate substrate_runtime_primitives as runtime_primitives;
 
 #[cfg(feature = "std")]
@@ -53,8 +54,18 @@ use codec::Slicable;
 
 use runtime_primitives::traits::{Checkable, BlakeTwo256};
 use runtime_primitives::Ed25519Signature;
+use runtime_primitives::version::RuntimeVersion;
 pub use primitives::hash::H256;
 
+/// Test runtime version.
+pub const VERSION: RuntimeVersion = RuntimeVersion {
+	spec_name: ver_str!("test"),
+	spec_version: 1,
+	impl_name: ver_str!("parity-test"),
+	impl_version: 1,
+	apis: ver_apis!(("core", 1)),
+};
+
 /// Calls in transactions.
 #[derive(Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
@@ -144,6 +155,7 @@ pub mod api {
 	use system;
 
 	impl_stubs!(
+		version => |()| super::VERSION,
 		authorities => |()| system::authorities(),
 		initialise_block => |header| system::initialise_block(header),
 		execute_block => |block| system::execute_block(block),